@@ -13,7 +13,7 @@ use tokio::{
 };
 use tracing::{error, info, warn};
 use twilight_cache_inmemory::InMemoryCache;
-use twilight_gateway::{cluster::Events, Cluster, Event, Intents};
+use twilight_gateway::{cluster::Events, Cluster, Event};
 use twilight_model::{
     gateway::event::DispatchEvent,
     id::{marker::GuildMarker, Id},
@@ -24,16 +24,8 @@ pub async fn run_broker(
     discord_state: Arc<InMemoryCache>,
     stores: Arc<dyn ConfigStore>,
     ready: Arc<AtomicBool>,
-) -> Result<BrokerHandle, Box<dyn std::error::Error>> {
-    let intents = Intents::GUILD_MESSAGES
-        | Intents::MESSAGE_CONTENT
-        | Intents::GUILDS
-        | Intents::GUILD_MEMBERS
-        | Intents::GUILD_MODERATION
-        | Intents::GUILD_INVITES
-        | Intents::GUILD_VOICE_STATES
-        | Intents::GUILD_MESSAGES
-        | Intents::GUILD_MESSAGE_REACTIONS;
+) -> Result<(BrokerHandle, Arc<Cluster>), Box<dyn std::error::Error>> {
+    let intents = common::gateway_intents();
 
     let (cluster, events) = Cluster::new(token, intents).await?;
     let cluster = Arc::new(cluster);
@@ -46,7 +38,7 @@ pub async fn run_broker(
     let (cmd_tx, cmd_rx) = mpsc::unbounded_channel();
 
     let mut discord_manager = Broker {
-        _cluster: cluster,
+        _cluster: cluster.clone(),
         discord_state,
         events,
         cmd_rx,
@@ -59,7 +51,7 @@ pub async fn run_broker(
 
     tokio::spawn(async move { discord_manager.run().await });
 
-    Ok(cmd_tx)
+    Ok((cmd_tx, cluster))
 }
 
 pub type BrokerHandle = mpsc::UnboundedSender<BrokerCommand>;
@@ -252,6 +244,7 @@ impl Broker {
                 }
             }
             Event::ThreadDelete(v) => v.guild_id,
+            Event::CommandPermissionsUpdate(v) => v.guild_id,
             _ => return,
         };
 
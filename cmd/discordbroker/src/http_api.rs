@@ -12,7 +12,13 @@ use axum::{
 use dbrokerapi::state_client::ConnectedGuildsResponse;
 use tracing::info;
 use twilight_cache_inmemory::{model::CachedGuild, InMemoryCache};
-use twilight_model::{channel::Channel, guild::Role, id::Id};
+use twilight_gateway::Cluster;
+use twilight_model::{
+    channel::Channel,
+    guild::Role,
+    id::{marker::GuildMarker, Id},
+    user::User,
+};
 
 #[derive(Clone)]
 struct ReadyTracker {
@@ -22,6 +28,7 @@ struct ReadyTracker {
 pub async fn run_http_server(
     conf: crate::BrokerConfig,
     discord_state: Arc<InMemoryCache>,
+    cluster: Arc<Cluster>,
     ready: Arc<AtomicBool>,
 ) {
     let app = Router::new()
@@ -34,8 +41,14 @@ pub async fn run_http_server(
             get(handle_get_channel),
         )
         .route("/guilds/:guild_id", get(handle_get_guild))
+        .route(
+            "/guilds/:guild_id/gateway_latency",
+            get(handle_get_gateway_latency),
+        )
+        .route("/users/:user_id", get(handle_get_user))
         .route("/connected_guilds", get(handle_get_connected_guilds))
         .layer(Extension(discord_state))
+        .layer(Extension(cluster))
         .layer(Extension(ReadyTracker { ready }))
         .layer(axum_metrics_layer::MetricsLayer {
             name: "bl.broker.http_api_hits_total",
@@ -66,6 +79,32 @@ async fn handle_get_guild(
     Ok((StatusCode::NOT_FOUND, Json(None)))
 }
 
+/// Returns the most recent heartbeat latency in milliseconds of the shard the guild is on
+async fn handle_get_gateway_latency(
+    Path(guild_id_u): Path<u64>,
+    Extension(cluster): Extension<Arc<Cluster>>,
+) -> Result<(StatusCode, Json<Option<u64>>), String> {
+    let guild_id: Id<GuildMarker> =
+        Id::new_checked(guild_id_u).ok_or_else(|| String::from("bad guild_id"))?;
+
+    let shard_total = match cluster.shards().next() {
+        Some(shard) => shard.config().shard()[1],
+        None => return Ok((StatusCode::NOT_FOUND, Json(None))),
+    };
+
+    let shard_id = (guild_id.get() >> 22) % shard_total;
+    let latency = cluster
+        .shard(shard_id)
+        .and_then(|shard| shard.info().ok())
+        .and_then(|info| info.latency().recent().back().copied())
+        .map(|latency| latency.as_millis() as u64);
+
+    match latency {
+        Some(latency) => Ok((StatusCode::OK, Json(Some(latency)))),
+        None => Ok((StatusCode::NOT_FOUND, Json(None))),
+    }
+}
+
 async fn handle_get_channel(
     Path((guild_id_u, channel_id_u)): Path<(u64, u64)>,
     Extension(discord_state): Extension<Arc<InMemoryCache>>,
@@ -136,6 +175,19 @@ async fn handle_get_roles(
     Ok((StatusCode::NOT_FOUND, Json(None)))
 }
 
+async fn handle_get_user(
+    Path(user_id_u): Path<u64>,
+    Extension(discord_state): Extension<Arc<InMemoryCache>>,
+) -> Result<(StatusCode, Json<Option<User>>), String> {
+    let user_id = Id::new_checked(user_id_u).ok_or_else(|| String::from("bad user_id"))?;
+
+    if let Some(u) = discord_state.user(user_id) {
+        return Ok((StatusCode::OK, Json(Some(u.value().clone()))));
+    }
+
+    Ok((StatusCode::NOT_FOUND, Json(None)))
+}
+
 async fn handle_get_connected_guilds(
     Extension(ready_tracker): Extension<ReadyTracker>,
     Extension(discord_state): Extension<Arc<InMemoryCache>>,
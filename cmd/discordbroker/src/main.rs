@@ -33,7 +33,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     );
 
     let ready = Arc::new(AtomicBool::new(false));
-    let handle = run_broker(
+    let (handle, cluster) = run_broker(
         config.common.discord_token.clone(),
         discord_state.clone(),
         postgres_store,
@@ -45,7 +45,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         config.broker_rpc_listen_addr.clone(),
         handle,
     ));
-    run_http_server(config, discord_state, ready.clone()).await;
+    run_http_server(config, discord_state, cluster, ready.clone()).await;
 
     Ok(())
 }
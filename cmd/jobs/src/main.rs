@@ -3,9 +3,7 @@ use std::sync::Arc;
 use clap::{Args, Parser, Subcommand};
 use common::DiscordConfig;
 use dbrokerapi::state_client::ConnectedGuildsResponse;
-use stores::{
-    bucketstore::BucketStore, config::ConfigStore, postgres::Postgres, timers::TimerStore,
-};
+use stores::{config::ConfigStore, postgres::Postgres};
 use tracing::{info, warn};
 use twilight_http::error::ErrorType;
 
@@ -29,6 +27,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         Command::DeleteLeftGuilds(opts) => {
             delete_left_guilds(config.clone(), opts.clone(), db).await
         }
+        Command::ExpireLapsedPremiumSlots => expire_lapsed_premium_slots(db).await,
     }
 }
 
@@ -52,6 +51,7 @@ struct Config {
 enum Command {
     ScanForLeftGuilds,
     DeleteLeftGuilds(DeleteSettings),
+    ExpireLapsedPremiumSlots,
 }
 #[derive(Clone, Args)]
 struct DeleteSettings {
@@ -80,6 +80,7 @@ async fn scan_for_left_guilds(
     let left_guilds = db.get_joined_guilds_not_in(&connected_guilds).await?;
     info!("left guilds: {}, {:?}", left_guilds.len(), left_guilds);
 
+    let mut confirmed_left = Vec::new();
     for guild in left_guilds {
         match discord_config.client.guild(guild.id).await {
             Ok(_) => {
@@ -94,16 +95,20 @@ async fn scan_for_left_guilds(
                     status,
                 } if status.get() == 403 => {
                     info!("marking guild as left: {}", guild.id);
-                    db.set_guild_left_status(guild.id, true).await?;
+                    confirmed_left.push(guild.id);
                 }
                 _ => {
-                    // another error occured
+                    // another error occured, still mark the ones we confirmed so far
+                    db.mark_guilds_left(&confirmed_left, true).await?;
                     return Err(Box::new(e));
                 }
             },
         }
     }
 
+    let marked = db.mark_guilds_left(&confirmed_left, true).await?;
+    info!("marked {} guilds as left", marked);
+
     Ok(())
 }
 
@@ -125,10 +130,31 @@ async fn delete_left_guilds(
     for g in guilds {
         info!("deleting {}", g.id);
 
-        db.delete_guild_bucket_store_data(g.id).await?;
-        db.delete_guild_timer_data(g.id).await?;
-        db.delete_guild_config_data(g.id).await?;
+        match db.purge_left_guild_data(g.id).await? {
+            Some(purge) => {
+                for (table, count) in &purge.tables {
+                    info!(guild_id = %g.id, table, count, "purged rows");
+                }
+                info!(guild_id = %g.id, total = purge.total(), "purged guild data");
+            }
+            None => info!(guild_id = %g.id, "guild is no longer marked as left, skipping"),
+        }
+    }
+
+    Ok(())
+}
+
+async fn expire_lapsed_premium_slots(db: Postgres) -> Result<(), Box<dyn std::error::Error>> {
+    info!("Expiring lapsed premium slots");
+
+    let expired = db.expire_lapsed_premium_slots().await?;
+    for slot in &expired {
+        info!(
+            "expired premium slot {}, expired at: {}, user: {:?}",
+            slot.id, slot.expires_at, slot.user_id
+        );
     }
 
+    info!("expired {} premium slots", expired.len());
     Ok(())
 }
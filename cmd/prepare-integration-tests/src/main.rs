@@ -18,6 +18,9 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
 
     let compiled_filter_regex = config.filter.map(|v| regex::Regex::new(&v).unwrap());
 
+    // create all the scripts at once so a failure doesn't leave the guild with only some of them
+    let mut tx = config_store.transaction().await?;
+
     let dir = std::fs::read_dir(config.scripts_path)?;
     for entry in dir {
         let unwrapped = entry.unwrap();
@@ -38,22 +41,23 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
 
         let contents = std::fs::read_to_string(unwrapped.path())?;
 
-        config_store
-            .create_script(
-                guild_id,
-                CreateScript {
-                    enabled: true,
-                    name: name_with_suffix.strip_suffix(".ts").unwrap().to_string(),
-                    original_source: contents,
-                    plugin_auto_update: None,
-                    plugin_id: None,
-                },
-            )
-            .await?;
+        tx.create_script(
+            guild_id,
+            CreateScript {
+                enabled: true,
+                name: name_with_suffix.strip_suffix(".ts").unwrap().to_string(),
+                original_source: contents,
+                plugin_auto_update: None,
+                plugin_id: None,
+            },
+        )
+        .await?;
 
         info!("added scrpt {}", name_with_suffix);
     }
 
+    tx.commit().await?;
+
     Ok(())
 }
 
@@ -120,6 +120,14 @@ pub fn discord_event_to_dispatch(evt: DispatchEvent) -> Option<DiscordDispatchEv
             data: serde_json::to_value(runtime_models::discord::events::EventThreadDelete::from(r))
                 .unwrap(),
         }),
+        DispatchEvent::CommandPermissionsUpdate(p) => Some(DiscordDispatchEvent {
+            name: "APPLICATION_COMMAND_PERMISSIONS_UPDATE",
+            guild_id: p.guild_id,
+            data: serde_json::to_value(
+                runtime_models::discord::command_permissions::CommandPermissions::from(p.0),
+            )
+            .unwrap(),
+        }),
         DispatchEvent::InteractionCreate(interaction) => {
             let guild_id = interaction.guild_id.unwrap();
 
@@ -1,4 +1,7 @@
-use std::sync::{Arc, RwLock};
+use std::{
+    sync::{Arc, RwLock},
+    time::{Duration, Instant},
+};
 
 use crate::{
     command_manager,
@@ -10,8 +13,11 @@ use common::DiscordConfig;
 use dbrokerapi::broker_scheduler_rpc::GuildEvent;
 use guild_logger::GuildLogger;
 use stores::config::PremiumSlotTier;
-use tokio::sync::mpsc;
-use tracing::{info, instrument};
+use tokio::{
+    sync::{mpsc, oneshot},
+    task::JoinHandle,
+};
+use tracing::{error, info, instrument, warn};
 use twilight_model::{
     gateway::event::DispatchEvent,
     id::{marker::GuildMarker, Id},
@@ -24,6 +30,33 @@ pub enum GuildCommand {
     Shutdown,
 }
 
+/// Identifies this scheduler node when claiming guilds, so multiple schedulers never run the same guild
+#[derive(Clone)]
+pub struct LeaseConfig {
+    pub node_id: String,
+    pub duration: Duration,
+}
+
+impl LeaseConfig {
+    // renew well before it expires so a slow query or two doesn't lose us the guild
+    fn renew_interval(&self) -> Duration {
+        self.duration / 3
+    }
+}
+
+/// This node's lease on a guild, shared with the scheduled task manager so tasks are only claimed
+/// while it's held
+#[derive(Clone, Copy)]
+pub enum LeaseState {
+    /// Leases are disabled, the guild is always run
+    Disabled,
+    Held {
+        generation: u64,
+        expires_at: Instant,
+    },
+    Lost,
+}
+
 #[derive(Clone, Copy)]
 pub enum PremiumTierState {
     Fetched(Option<PremiumSlotTier>),
@@ -52,6 +85,11 @@ pub struct GuildHandler {
 
     premium_tier: Arc<RwLock<PremiumTierState>>,
 
+    lease: Option<LeaseConfig>,
+    lease_state: Arc<RwLock<LeaseState>>,
+    // renewed from its own task so a slow vm action can't hold up the renewal
+    lease_renewal: Option<(JoinHandle<()>, oneshot::Receiver<()>)>,
+
     _id_gen: u64,
 
     scripts_session: VmSession,
@@ -65,6 +103,7 @@ impl GuildHandler {
         worker_pool: crate::vmworkerpool::VmWorkerPool,
         cmd_manager_handle: crate::command_manager::Handle,
         discord_config: Arc<DiscordConfig>,
+        lease: Option<LeaseConfig>,
     ) -> GuildHandle {
         let (cmd_tx, cmd_rx) = mpsc::unbounded_channel();
         let (evt_tx, evt_rx) = mpsc::unbounded_channel();
@@ -76,6 +115,11 @@ impl GuildHandler {
         };
 
         let premium_tier = Arc::new(RwLock::new(PremiumTierState::Unknown));
+        let lease_state = Arc::new(RwLock::new(if lease.is_some() {
+            LeaseState::Lost
+        } else {
+            LeaseState::Disabled
+        }));
 
         let worker = GuildHandler {
             stores: stores.clone(),
@@ -89,6 +133,10 @@ impl GuildHandler {
             _id_gen: 1,
             premium_tier: premium_tier.clone(),
 
+            lease,
+            lease_state: lease_state.clone(),
+            lease_renewal: None,
+
             _cmd_manager_handle: cmd_manager_handle.clone(),
             scripts_session: VmSession::new(
                 stores,
@@ -98,6 +146,7 @@ impl GuildHandler {
                 cmd_manager_handle,
                 discord_config,
                 premium_tier,
+                lease_state,
             ),
         };
 
@@ -108,6 +157,12 @@ impl GuildHandler {
 
     #[instrument(skip(self), fields(guild_id = self.guild_id.get()))]
     async fn run(mut self) {
+        if !self.claim_lease().await {
+            info!("guild is held by another scheduler node, not starting it");
+            let _ = self.scheduler_tx.send(VmSessionEvent::LeaseHeldElsewhere);
+            return;
+        }
+
         self.fetch_premium_tier().await;
         self.scripts_session.start().await;
 
@@ -120,6 +175,11 @@ impl GuildHandler {
                 NextGuildAction::GuildCommand(cmd) => {
                     self.handle_guild_command(cmd).await;
                 }
+                NextGuildAction::LeaseLost => {
+                    warn!("lost the lease on the guild, shutting down");
+                    let _ = self.scheduler_tx.send(VmSessionEvent::LeaseHeldElsewhere);
+                    break;
+                }
                 NextGuildAction::VmAction(action) => {
                     if let Some(evt) = self.scripts_session.handle_action(action).await {
                         match evt {
@@ -131,6 +191,9 @@ impl GuildHandler {
                                 let _ = self.scheduler_tx.send(evt);
                                 break;
                             }
+                            crate::vm_session::VmSessionEvent::LeaseHeldElsewhere => {
+                                unreachable!("only sent by the guild handler")
+                            }
                         }
                     }
                 }
@@ -139,6 +202,7 @@ impl GuildHandler {
 
         info!("shutting down guild handler");
         self.scripts_session.shutdown().await;
+        self.release_lease().await;
     }
 
     async fn next_event(&mut self) -> Option<NextGuildAction> {
@@ -151,6 +215,81 @@ impl GuildHandler {
             next_guild_evt = self.guild_rx.recv() => {
                 next_guild_evt.map(NextGuildAction::GuildCommand)
             }
+            _ = Self::lease_lost(&mut self.lease_renewal) => {
+                Some(NextGuildAction::LeaseLost)
+            }
+        }
+    }
+
+    async fn lease_lost(renewal: &mut Option<(JoinHandle<()>, oneshot::Receiver<()>)>) {
+        match renewal {
+            Some((_, lost_rx)) => {
+                let _ = lost_rx.await;
+            }
+            None => std::future::pending().await,
+        }
+    }
+
+    /// Claims the lease on the guild and starts renewing it, returns false if we don't hold it
+    ///
+    /// Always true if leases are disabled.
+    async fn claim_lease(&mut self) -> bool {
+        let lease = if let Some(lease) = &self.lease {
+            lease.clone()
+        } else {
+            return true;
+        };
+
+        let requested_at = Instant::now();
+        let generation = match self
+            .stores
+            .claim_guild(self.guild_id, &lease.node_id, lease.duration)
+            .await
+        {
+            Ok(Some(generation)) => generation,
+            Ok(None) => return false,
+            Err(err) => {
+                // we can't know if someone else holds it so don't run it
+                error!(%err, "failed claiming guild lease");
+                return false;
+            }
+        };
+
+        *self.lease_state.write().unwrap() = LeaseState::Held {
+            generation,
+            expires_at: requested_at + lease.duration,
+        };
+
+        let (lost_tx, lost_rx) = oneshot::channel();
+        let task = tokio::spawn(renew_lease(
+            self.stores.clone(),
+            self.guild_id,
+            lease,
+            self.lease_state.clone(),
+            lost_tx,
+        ));
+        self.lease_renewal = Some((task, lost_rx));
+
+        true
+    }
+
+    async fn release_lease(&mut self) {
+        if let Some((task, _)) = self.lease_renewal.take() {
+            task.abort();
+        }
+
+        let held = matches!(*self.lease_state.read().unwrap(), LeaseState::Held { .. });
+        *self.lease_state.write().unwrap() = LeaseState::Lost;
+
+        if let (Some(lease), true) = (&self.lease, held) {
+            if let Err(err) = self
+                .stores
+                .release_guild(self.guild_id, &lease.node_id)
+                .await
+            {
+                // it will expire on its own eventually
+                error!(%err, "failed releasing guild lease");
+            }
         }
     }
 
@@ -185,16 +324,7 @@ impl GuildHandler {
             return;
         };
 
-        let mut highest_tier = Option::<PremiumSlotTier>::None;
-        for slot in slots {
-            if let Some(current_highest) = highest_tier {
-                if slot.tier.is_higher_than(current_highest) {
-                    highest_tier = Some(slot.tier);
-                }
-            } else {
-                highest_tier = Some(slot.tier);
-            }
-        }
+        let highest_tier = PremiumSlotTier::highest_of(&slots);
 
         let mut w = self.premium_tier.write().unwrap();
         *w = PremiumTierState::Fetched(highest_tier);
@@ -213,9 +343,56 @@ impl GuildHandler {
     }
 }
 
+/// Renews the lease until it's lost, which is signaled through `lost_tx`
+async fn renew_lease(
+    stores: Arc<dyn Store>,
+    guild_id: Id<GuildMarker>,
+    lease: LeaseConfig,
+    state: Arc<RwLock<LeaseState>>,
+    lost_tx: oneshot::Sender<()>,
+) {
+    loop {
+        tokio::time::sleep(lease.renew_interval()).await;
+
+        let (held_generation, expires_at) = match *state.read().unwrap() {
+            LeaseState::Held {
+                generation,
+                expires_at,
+            } => (generation, expires_at),
+            _ => break,
+        };
+
+        let requested_at = Instant::now();
+        match stores
+            .claim_guild(guild_id, &lease.node_id, lease.duration)
+            .await
+        {
+            // a new generation means it expired and someone else might have had it in between
+            Ok(Some(generation)) if generation == held_generation => {
+                *state.write().unwrap() = LeaseState::Held {
+                    generation,
+                    expires_at: requested_at + lease.duration,
+                };
+            }
+            Ok(_) => break,
+            Err(err) => {
+                error!(%err, "failed renewing guild lease");
+                // keep going on transient errors as long as the lease we have is still valid
+                if Instant::now() >= expires_at {
+                    break;
+                }
+            }
+        }
+    }
+
+    *state.write().unwrap() = LeaseState::Lost;
+    let _ = lost_tx.send(());
+}
+
 enum NextGuildAction {
     VmAction(crate::vm_session::NextAction),
     GuildCommand(GuildCommand),
+    LeaseLost,
 }
 
 pub struct GuildHandle {
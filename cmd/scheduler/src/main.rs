@@ -18,6 +18,7 @@ mod guild_handler;
 mod integration_testing;
 mod interval_timer_manager;
 mod rpc_server;
+mod sandbox;
 mod scheduled_task_manager;
 mod scheduler;
 mod vm_session;
@@ -40,8 +41,11 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
             .unwrap(),
     );
 
-    let guild_log_sub_backend =
-        Arc::new(guild_logger::guild_subscriber_backend::GuildSubscriberBackend::default());
+    let guild_log_sub_backend = Arc::new(
+        guild_logger::guild_subscriber_backend::GuildSubscriberBackend::new(
+            config.guild_log_history_size,
+        ),
+    );
 
     let (logger, ig_testing_tracker) = {
         let mut builder = guild_logger::GuildLoggerBuilder::new()
@@ -64,13 +68,6 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
 
     let (scheduler_tx, scheduler_rx) = mpsc::unbounded_channel();
 
-    let bot_rpc_server = rpc_server::Server::new(
-        guild_log_sub_backend,
-        scheduler_tx.clone(),
-        config.common.bot_rpc_listen_addr,
-    );
-    tokio::spawn(bot_rpc_server.run());
-
     let (manager, cmd_man_handle) = command_manager::create_manager_pair(
         postgres_store.clone(),
         discord_config.clone(),
@@ -83,6 +80,15 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         cmd: config.vmworker_bin_path,
     });
     worker_listener::listen_for_workers("/tmp/botloader_scheduler_workers", worker_pool.clone());
+
+    let bot_rpc_server = rpc_server::Server::new(
+        guild_log_sub_backend,
+        scheduler_tx.clone(),
+        worker_pool.clone(),
+        config.common.bot_rpc_listen_addr,
+    );
+    tokio::spawn(bot_rpc_server.run());
+
     tokio::time::sleep(Duration::from_secs(1)).await;
     info!(
         "spawning {},{},{}, free, lite, premium workers",
@@ -105,6 +111,10 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         cmd_man_handle,
         worker_pool,
         discord_config.clone(),
+        config.node_id.map(|node_id| guild_handler::LeaseConfig {
+            node_id,
+            duration: Duration::from_secs(config.guild_lease_secs),
+        }),
     );
     let task = tokio::spawn(scheduler.run());
 
@@ -175,4 +185,20 @@ pub struct SchedulerConfig {
     pub(crate) num_workers_lite: u16,
     #[clap(long, env = "BL_SCHEDULER_NUM_WORKERS_PREMIUM", default_value = "0")]
     pub(crate) num_workers_premium: u16,
+
+    /// number of recent guild log entries to keep per watched guild, sent to new log subscribers
+    #[clap(
+        long,
+        env = "BL_SCHEDULER_GUILD_LOG_HISTORY_SIZE",
+        default_value = "50"
+    )]
+    pub(crate) guild_log_history_size: usize,
+
+    /// unique id of this scheduler node, when set guilds are leased so that only one node runs a guild at a time
+    #[clap(long, env = "BL_SCHEDULER_NODE_ID")]
+    pub(crate) node_id: Option<String>,
+
+    /// how long a guild lease lasts without being renewed, after which another node can claim the guild
+    #[clap(long, env = "BL_SCHEDULER_GUILD_LEASE_SECS", default_value = "60")]
+    pub(crate) guild_lease_secs: u64,
 }
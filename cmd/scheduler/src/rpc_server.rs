@@ -8,24 +8,31 @@ use tonic::{Response, Status};
 use botrpc::proto;
 use twilight_model::id::Id;
 
-use crate::scheduler::SchedulerCommand;
+use crate::{
+    sandbox::{self, SandboxRun},
+    scheduler::SchedulerCommand,
+    vmworkerpool::VmWorkerPool,
+};
 
 pub struct Server {
     addr: String,
     log_subscriber: Arc<GuildSubscriberBackend>,
     scheduler_tx: UnboundedSender<SchedulerCommand>,
+    worker_pool: VmWorkerPool,
 }
 
 impl Server {
     pub fn new(
         log_subscriber: Arc<GuildSubscriberBackend>,
         scheduler_tx: UnboundedSender<SchedulerCommand>,
+        worker_pool: VmWorkerPool,
         addr: String,
     ) -> Self {
         Self {
             log_subscriber,
             addr,
             scheduler_tx,
+            worker_pool,
         }
     }
 
@@ -78,8 +85,12 @@ impl proto::bot_service_server::BotService for Server {
     ) -> Result<Response<Self::StreamGuildLogsStream>, Status> {
         let guild_id = Id::new(request.into_inner().guild_id);
 
-        let mut rx = self.log_subscriber.subscribe(guild_id);
+        let (recent, mut rx) = self.log_subscriber.subscribe_with_history(guild_id);
         let out = async_stream::try_stream! {
+            for entry in recent {
+                yield proto::GuildLogItem::from(entry);
+            }
+
             while let Ok(next) = rx.recv().await{
                 yield proto::GuildLogItem::from(next);
             }
@@ -87,4 +98,35 @@ impl proto::bot_service_server::BotService for Server {
 
         Ok(Response::new(Box::pin(out)))
     }
+
+    async fn run_sandbox(
+        &self,
+        request: tonic::Request<proto::SandboxRunRequest>,
+    ) -> Result<Response<proto::SandboxRunResponse>, Status> {
+        let req = request.into_inner();
+        let premium_tier = req.premium_tier();
+        let event = serde_json::from_str(&req.event_json)
+            .map_err(|err| Status::invalid_argument(format!("invalid event json: {err}")))?;
+
+        let result = sandbox::run_sandbox(
+            &self.worker_pool,
+            SandboxRun {
+                guild_id: Id::new(req.guild_id),
+                premium_tier: premium_tier.into(),
+                source: req.source,
+                event_name: req.event_name,
+                event,
+            },
+        )
+        .await;
+
+        Ok(Response::new(proto::SandboxRunResponse {
+            logs: result.logs.into_iter().map(Into::into).collect(),
+            timed_out: result.timed_out,
+            shutdown_reason: result
+                .shutdown_reason
+                .map(|reason| format!("{reason:?}"))
+                .unwrap_or_default(),
+        }))
+    }
 }
@@ -0,0 +1,127 @@
+use std::time::Duration;
+
+use guild_logger::LogEntry;
+use scheduler_worker_rpc::{
+    CreateScriptsVmReq, SchedulerMessage, ShutdownReason, VmDispatchEvent, WorkerMessage,
+};
+use stores::config::{PremiumSlotTier, Script, ScriptContributes};
+use tracing::{info, warn};
+use twilight_model::id::{marker::GuildMarker, Id};
+
+use crate::vmworkerpool::{VmWorkerPool, WorkerHandle};
+
+/// Max time a test run can take, including starting the vm
+pub const SANDBOX_RUN_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Name of the script in sandbox runs, shown in the log entries
+const SANDBOX_SCRIPT_NAME: &str = "sandbox";
+
+pub struct SandboxRun {
+    pub guild_id: Id<GuildMarker>,
+    pub premium_tier: Option<PremiumSlotTier>,
+    pub source: String,
+    pub event_name: String,
+    pub event: serde_json::Value,
+}
+
+#[derive(Default)]
+pub struct SandboxRunResult {
+    pub logs: Vec<LogEntry>,
+    pub timed_out: bool,
+    /// Set if the vm was forcibly shut down, e.g. it ran out of memory
+    pub shutdown_reason: Option<ShutdownReason>,
+}
+
+/// Runs the provided source against a single event in a sandboxed vm on its own worker
+///
+/// Log entries are collected and returned instead of being sent to the guild's log subscribers.
+pub async fn run_sandbox(pool: &VmWorkerPool, run: SandboxRun) -> SandboxRunResult {
+    let mut worker = pool.req_sandbox_worker(run.premium_tier).await;
+    info!(guild_id = %run.guild_id, "starting sandbox run");
+
+    let mut result = SandboxRunResult::default();
+    let broken = match tokio::time::timeout(
+        SANDBOX_RUN_TIMEOUT,
+        drive_sandbox_run(&mut worker, run, &mut result),
+    )
+    .await
+    {
+        Ok(Ok(())) => false,
+        Ok(Err(())) => true,
+        Err(_) => {
+            result.timed_out = true;
+            true
+        }
+    };
+
+    if broken {
+        warn!(timed_out = result.timed_out, "sandbox run did not complete");
+    }
+
+    // a worker stuck in a test run is replaced rather than waited on
+    pool.return_worker(worker, broken);
+    result
+}
+
+async fn drive_sandbox_run(
+    worker: &mut WorkerHandle,
+    run: SandboxRun,
+    result: &mut SandboxRunResult,
+) -> Result<(), ()> {
+    let create_seq = 1;
+    let dispatch_seq = 2;
+
+    worker
+        .tx
+        .send(SchedulerMessage::CreateSandboxVm(CreateScriptsVmReq {
+            seq: create_seq,
+            premium_tier: run.premium_tier,
+            guild_id: run.guild_id,
+            scripts: vec![Script {
+                id: 0,
+                name: SANDBOX_SCRIPT_NAME.to_string(),
+                original_source: run.source,
+                enabled: true,
+                contributes: ScriptContributes {
+                    commands: Vec::new(),
+                    interval_timers: Vec::new(),
+                    api_version: None,
+                },
+                plugin_id: None,
+                plugin_auto_update: None,
+                min_log_level: Default::default(),
+            }],
+            changed_script: None,
+        }))
+        .map_err(|_| ())?;
+
+    worker
+        .tx
+        .send(SchedulerMessage::Dispatch(VmDispatchEvent {
+            name: run.event_name,
+            seq: dispatch_seq,
+            value: run.event,
+        }))
+        .map_err(|_| ())?;
+
+    loop {
+        match worker.rx.recv().await.ok_or(())? {
+            WorkerMessage::Ack(seq) if seq == dispatch_seq => {
+                // the handler has run, let the vm finish up any pending work
+                worker.tx.send(SchedulerMessage::Complete).map_err(|_| ())?;
+            }
+            WorkerMessage::Shutdown(reason) => {
+                result.shutdown_reason = Some(reason);
+            }
+            WorkerMessage::NonePending => return Ok(()),
+            WorkerMessage::GuildLog(entry) => result.logs.push(entry),
+            WorkerMessage::Ack(_)
+            | WorkerMessage::ScriptStarted(_)
+            | WorkerMessage::ScriptsInit
+            | WorkerMessage::TaskScheduled
+            | WorkerMessage::TaskHandled { .. }
+            | WorkerMessage::Hello(_)
+            | WorkerMessage::Metric(..) => {}
+        }
+    }
+}
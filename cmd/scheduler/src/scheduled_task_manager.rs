@@ -1,32 +1,57 @@
-use std::{ops::Add, sync::Arc};
+use std::{
+    collections::HashMap,
+    ops::Add,
+    sync::{Arc, RwLock},
+    time::Instant,
+};
 
 use chrono::{DateTime, Utc};
 use runtime_models::internal::script::ScriptMeta;
-use stores::timers::ScheduledTask;
+use stores::{
+    config::PremiumSlotTier,
+    timers::{ScheduledTask, TaskExecution, TaskOutcome, TimerStore},
+};
 use tracing::{error, info};
 use twilight_model::id::{marker::GuildMarker, Id};
 
-use crate::scheduler;
+use crate::guild_handler::LeaseState;
 
+/// Keeps track of when the next scheduled task is due and which ones have been dispatched
+///
+/// Only tasks for namespaces registered by the running scripts are dispatched, so tasks that
+/// are due while the vm restarts are kept until a handler for them is running again. Tasks are
+/// deleted when the worker acks them, which happens as the vm starts handling them, so a handler
+/// that gets interrupted is not retried: delivery is at most once. The execution is recorded
+/// once the handler reports whether it succeeded.
 pub struct Manager {
-    storage: Arc<dyn scheduler::Store>,
+    storage: Arc<dyn TimerStore>,
     guild_id: Id<GuildMarker>,
 
     // outer option: none if not fetched, some if fetched
     // inner: none if no tasks remaining
     next_task_time: Option<Option<DateTime<Utc>>>,
     pending: Vec<u64>,
-    task_names: Vec<String>,
+    // acked tasks whose handler hasn't reported back yet
+    running: HashMap<u64, FiredTask>,
+    // the task names each script has handlers for
+    task_names: HashMap<u64, Vec<String>>,
+    lease: Arc<RwLock<LeaseState>>,
 }
 
 impl Manager {
-    pub fn new(guild_id: Id<GuildMarker>, storage: Arc<dyn scheduler::Store>) -> Self {
+    pub fn new(
+        guild_id: Id<GuildMarker>,
+        storage: Arc<dyn TimerStore>,
+        lease: Arc<RwLock<LeaseState>>,
+    ) -> Self {
         Self {
+            lease,
             storage,
             guild_id,
             next_task_time: None,
             pending: Vec::new(),
-            task_names: Vec::new(),
+            running: HashMap::new(),
+            task_names: HashMap::new(),
         }
     }
 
@@ -38,7 +63,7 @@ impl Manager {
         // fetch
         match self
             .storage
-            .get_next_task_time(self.guild_id, &self.pending, &self.task_names)
+            .get_next_task_time(self.guild_id, &self.pending, &self.all_task_names())
             .await
         {
             Ok(v) => {
@@ -66,10 +91,29 @@ impl Manager {
     }
 
     pub async fn start_triggered_tasks(&mut self) -> Vec<ScheduledTask> {
+        // the store checks the lease generation as well, in case it expired since we last renewed it
+        let lease_generation = match *self.lease.read().unwrap() {
+            LeaseState::Disabled => None,
+            LeaseState::Held {
+                generation,
+                expires_at,
+            } if expires_at > Instant::now() => Some(generation),
+            _ => {
+                info!("not holding the guild lease, not starting tasks");
+                return Vec::new();
+            }
+        };
+
         // trigger some tasks
         match self
             .storage
-            .get_triggered_tasks(self.guild_id, Utc::now(), &self.pending, &self.task_names)
+            .get_triggered_tasks(
+                self.guild_id,
+                Utc::now(),
+                &self.pending,
+                &self.all_task_names(),
+                lease_generation,
+            )
             .await
         {
             Ok(v) => {
@@ -87,7 +131,8 @@ impl Manager {
         }
     }
 
-    pub async fn ack_triggered_task(&mut self, id: u64) {
+    pub async fn ack_triggered_task(&mut self, task: FiredTask) {
+        let id = task.id;
         if let Some(index) =
             self.pending
                 .iter()
@@ -99,13 +144,94 @@ impl Manager {
 
         loop {
             match self.storage.del_task_by_id(self.guild_id, id).await {
-                Ok(_) => return,
+                Ok(_) => break,
                 Err(err) => {
                     error!(%err, "failed deleting task");
                     tokio::time::sleep(std::time::Duration::from_secs(5)).await;
                 }
             }
         }
+
+        self.running.insert(task.id, task);
+    }
+
+    /// Records the result reported by the handler of an acked task
+    pub async fn task_handled(
+        &mut self,
+        task_id: u64,
+        error: Option<String>,
+        premium_tier: Option<PremiumSlotTier>,
+    ) {
+        let task = if let Some(task) = self.running.remove(&task_id) {
+            task
+        } else {
+            return;
+        };
+
+        let outcome = if error.is_some() {
+            TaskOutcome::Failed
+        } else {
+            TaskOutcome::Succeeded
+        };
+        self.record_execution(task, outcome, error, premium_tier)
+            .await;
+    }
+
+    /// Records the acked tasks whose handler never reported back as failed
+    pub async fn running_tasks_stopped(
+        &mut self,
+        reason: &str,
+        premium_tier: Option<PremiumSlotTier>,
+    ) {
+        for (_, task) in std::mem::take(&mut self.running) {
+            self.record_execution(
+                task,
+                TaskOutcome::Failed,
+                Some(reason.to_string()),
+                premium_tier,
+            )
+            .await;
+        }
+    }
+
+    /// Records a dispatched task that was never acked, it's dispatched again later
+    pub async fn task_interrupted(
+        &self,
+        task: FiredTask,
+        reason: String,
+        premium_tier: Option<PremiumSlotTier>,
+    ) {
+        self.record_execution(task, TaskOutcome::Interrupted, Some(reason), premium_tier)
+            .await;
+    }
+
+    // the history is only informational, so failing to record it is not retried
+    async fn record_execution(
+        &self,
+        task: FiredTask,
+        outcome: TaskOutcome,
+        error: Option<String>,
+        premium_tier: Option<PremiumSlotTier>,
+    ) {
+        let execution = TaskExecution {
+            task_id: task.id,
+            name: task.name,
+            fired_at: task.fired_at,
+            outcome,
+            error,
+        };
+
+        if let Err(err) = self
+            .storage
+            .record_task_execution(
+                self.guild_id,
+                execution,
+                task_history_retention(premium_tier),
+            )
+            .await
+        {
+            error!(%err, "failed recording task execution");
+        }
     }
 
     // pub async fn failed_ack_pending(&mut self, id: u64) {
@@ -134,16 +260,417 @@ impl Manager {
         self.task_names.clear();
     }
 
+    /// Forgets the task names of a single script, for when a new version of it is loaded in
+    /// place and registers its handlers again
+    pub fn clear_script_task_names(&mut self, script_id: u64) {
+        self.task_names.remove(&script_id);
+        self.clear_next();
+    }
+
     pub fn script_started(&mut self, meta: &ScriptMeta) {
-        for name in &meta.task_names {
-            if self.task_names.contains(name) {
-                continue;
+        self.task_names
+            .insert(meta.script_id.0, meta.task_names.clone());
+
+        self.clear_next();
+    }
+
+    fn all_task_names(&self) -> Vec<String> {
+        let mut names = Vec::new();
+        for name in self.task_names.values().flatten() {
+            if !names.contains(name) {
+                names.push(name.clone());
             }
-            self.task_names.push(name.clone());
         }
 
-        self.clear_next();
+        names
     }
 }
 
 pub type NextAction = crate::guild_handler::NextTimerAction;
+
+/// A task that was dispatched to the vm and is waiting to be acked
+pub struct FiredTask {
+    pub id: u64,
+    pub name: String,
+    pub fired_at: DateTime<Utc>,
+}
+
+/// Max number of task executions kept in the history per guild
+pub fn task_history_retention(premium_tier: Option<PremiumSlotTier>) -> u64 {
+    match premium_tier {
+        None => 100,
+        Some(PremiumSlotTier::Lite) => 500,
+        Some(PremiumSlotTier::Premium) => 1000,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{collections::HashMap, sync::Mutex};
+
+    use runtime_models::util::NotBigU64;
+    use stores::timers::{
+        IntervalTimer, TaskNamespaceSummary, TaskTag, TimerStoreError, TimerStoreResult,
+    };
+
+    use super::*;
+
+    // keeps the tasks in memory, only what the manager uses is implemented
+    #[derive(Default)]
+    struct MemoryTaskStore {
+        tasks: Mutex<Vec<ScheduledTask>>,
+        executions: Mutex<Vec<TaskExecution>>,
+    }
+
+    impl MemoryTaskStore {
+        fn pending_tasks(&self, ignore_ids: &[u64], names: &[String]) -> Vec<ScheduledTask> {
+            self.tasks
+                .lock()
+                .unwrap()
+                .iter()
+                .filter(|v| !ignore_ids.contains(&v.id) && names.contains(&v.name))
+                .cloned()
+                .collect()
+        }
+    }
+
+    #[async_trait::async_trait]
+    impl TimerStore for MemoryTaskStore {
+        async fn get_all_interval_timers(
+            &self,
+            _guild_id: Id<GuildMarker>,
+        ) -> TimerStoreResult<Vec<IntervalTimer>> {
+            unimplemented!()
+        }
+
+        async fn update_interval_timer(
+            &self,
+            _guild_id: Id<GuildMarker>,
+            _timer: IntervalTimer,
+        ) -> TimerStoreResult<IntervalTimer> {
+            unimplemented!()
+        }
+
+        async fn del_interval_timer(
+            &self,
+            _guild_id: Id<GuildMarker>,
+            _script_id: u64,
+            _timer_name: String,
+        ) -> TimerStoreResult<bool> {
+            unimplemented!()
+        }
+
+        async fn create_task(
+            &self,
+            _guild_id: Id<GuildMarker>,
+            name: String,
+            unique_key: Option<String>,
+            tags: HashMap<String, String>,
+            data: serde_json::Value,
+            at: DateTime<Utc>,
+        ) -> TimerStoreResult<ScheduledTask> {
+            let mut tasks = self.tasks.lock().unwrap();
+            let task = ScheduledTask {
+                id: tasks.iter().map(|v| v.id).max().unwrap_or_default() + 1,
+                name,
+                unique_key,
+                tags,
+                data,
+                execute_at: at,
+            };
+            tasks.push(task.clone());
+            Ok(task)
+        }
+
+        async fn get_task_by_id(
+            &self,
+            _guild_id: Id<GuildMarker>,
+            id: u64,
+        ) -> TimerStoreResult<Option<ScheduledTask>> {
+            Ok(self
+                .tasks
+                .lock()
+                .unwrap()
+                .iter()
+                .find(|v| v.id == id)
+                .cloned())
+        }
+
+        async fn get_task_by_key(
+            &self,
+            _guild_id: Id<GuildMarker>,
+            _name: String,
+            _key: String,
+        ) -> TimerStoreResult<Option<ScheduledTask>> {
+            unimplemented!()
+        }
+
+        async fn task_exists_by_key(
+            &self,
+            _guild_id: Id<GuildMarker>,
+            _name: String,
+            _key: String,
+        ) -> TimerStoreResult<bool> {
+            unimplemented!()
+        }
+
+        async fn get_tasks(
+            &self,
+            _guild_id: Id<GuildMarker>,
+            _name: Option<String>,
+            _tag: Option<TaskTag>,
+            _id_after: u64,
+            _limit: usize,
+        ) -> TimerStoreResult<Vec<ScheduledTask>> {
+            unimplemented!()
+        }
+
+        async fn del_task_by_id(
+            &self,
+            _guild_id: Id<GuildMarker>,
+            id: u64,
+        ) -> TimerStoreResult<u64> {
+            let mut tasks = self.tasks.lock().unwrap();
+            let before = tasks.len();
+            tasks.retain(|v| v.id != id);
+            Ok((before - tasks.len()) as u64)
+        }
+
+        async fn del_task_by_key(
+            &self,
+            _guild_id: Id<GuildMarker>,
+            _name: String,
+            _key: String,
+        ) -> TimerStoreResult<u64> {
+            unimplemented!()
+        }
+
+        async fn del_all_tasks(
+            &self,
+            _guild_id: Id<GuildMarker>,
+            _name: Option<String>,
+        ) -> TimerStoreResult<u64> {
+            unimplemented!()
+        }
+
+        async fn del_tasks_by_tag(
+            &self,
+            _guild_id: Id<GuildMarker>,
+            _tag: TaskTag,
+        ) -> TimerStoreResult<u64> {
+            unimplemented!()
+        }
+
+        async fn get_task_count(&self, _guild_id: Id<GuildMarker>) -> TimerStoreResult<u64> {
+            Ok(self.tasks.lock().unwrap().len() as u64)
+        }
+
+        async fn get_task_namespace_summary(
+            &self,
+            _guild_id: Id<GuildMarker>,
+            _limit: usize,
+        ) -> TimerStoreResult<Vec<TaskNamespaceSummary>> {
+            unimplemented!()
+        }
+
+        async fn get_next_task_time(
+            &self,
+            _guild_id: Id<GuildMarker>,
+            ignore_ids: &[u64],
+            names: &[String],
+        ) -> TimerStoreResult<Option<DateTime<Utc>>> {
+            Ok(self
+                .pending_tasks(ignore_ids, names)
+                .iter()
+                .map(|v| v.execute_at)
+                .min())
+        }
+
+        async fn get_triggered_tasks(
+            &self,
+            _guild_id: Id<GuildMarker>,
+            t: DateTime<Utc>,
+            ignore_ids: &[u64],
+            names: &[String],
+            lease_generation: Option<u64>,
+        ) -> TimerStoreResult<Vec<ScheduledTask>> {
+            if lease_generation.is_some() {
+                return Err(TimerStoreError::Other(
+                    "leases are not supported by this store".into(),
+                ));
+            }
+
+            Ok(self
+                .pending_tasks(ignore_ids, names)
+                .into_iter()
+                .filter(|v| v.execute_at <= t)
+                .collect())
+        }
+
+        async fn record_task_execution(
+            &self,
+            _guild_id: Id<GuildMarker>,
+            execution: TaskExecution,
+            _keep: u64,
+        ) -> TimerStoreResult<()> {
+            self.executions.lock().unwrap().push(execution);
+            Ok(())
+        }
+
+        async fn get_task_executions(
+            &self,
+            _guild_id: Id<GuildMarker>,
+            _name: String,
+            _limit: usize,
+        ) -> TimerStoreResult<Vec<TaskExecution>> {
+            unimplemented!()
+        }
+
+        async fn delete_guild_timer_data(
+            &self,
+            _guild_id: Id<GuildMarker>,
+        ) -> TimerStoreResult<()> {
+            unimplemented!()
+        }
+    }
+
+    fn script_meta(task_names: &[&str]) -> ScriptMeta {
+        ScriptMeta {
+            description: String::new(),
+            script_id: NotBigU64(1),
+            commands: Vec::new(),
+            command_groups: Vec::new(),
+            interval_timers: Vec::new(),
+            task_names: task_names.iter().map(|v| v.to_string()).collect(),
+            event_names: Vec::new(),
+            api_version: None,
+        }
+    }
+
+    fn fired(task: &ScheduledTask) -> FiredTask {
+        FiredTask {
+            id: task.id,
+            name: task.name.clone(),
+            fired_at: Utc::now(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_task_scheduled_before_restart_fires_once() {
+        let guild_id = Id::new(1);
+        let store = Arc::new(MemoryTaskStore::default());
+        let mut manager = Manager::new(
+            guild_id,
+            store.clone(),
+            Arc::new(RwLock::new(LeaseState::Disabled)),
+        );
+        manager.script_started(&script_meta(&["reminder"]));
+
+        // scheduled right before the vm gets restarted, and already due by the time it's back
+        let task = store
+            .create_task(
+                guild_id,
+                "reminder".to_string(),
+                None,
+                HashMap::new(),
+                serde_json::Value::Null,
+                Utc::now() - chrono::Duration::seconds(1),
+            )
+            .await
+            .unwrap();
+
+        // what the session does as it restarts the vm with new scripts
+        manager.clear_task_names();
+        manager.clear_next();
+
+        // nothing is dispatched before the handler is registered again
+        manager.init_next_task_time().await;
+        assert!(matches!(manager.next_action(), NextAction::None));
+        assert!(manager.start_triggered_tasks().await.is_empty());
+
+        manager.script_started(&script_meta(&["reminder"]));
+        manager.init_next_task_time().await;
+        assert!(matches!(manager.next_action(), NextAction::Run));
+
+        let triggered = manager.start_triggered_tasks().await;
+        assert_eq!(
+            triggered.iter().map(|v| v.id).collect::<Vec<_>>(),
+            vec![task.id]
+        );
+
+        // not dispatched again while waiting for the ack
+        manager.init_next_task_time().await;
+        assert!(matches!(manager.next_action(), NextAction::None));
+        assert!(manager.start_triggered_tasks().await.is_empty());
+
+        manager.ack_triggered_task(fired(&task)).await;
+        assert_eq!(store.get_task_count(guild_id).await.unwrap(), 0);
+        assert!(manager.start_triggered_tasks().await.is_empty());
+
+        // recorded once the handler is done
+        assert!(store.executions.lock().unwrap().is_empty());
+        manager.task_handled(task.id, None, None).await;
+
+        let executions = store.executions.lock().unwrap();
+        assert_eq!(executions.len(), 1);
+        assert_eq!(executions[0].outcome, TaskOutcome::Succeeded);
+    }
+
+    #[tokio::test]
+    async fn test_task_not_acked_before_restart_is_dispatched_again() {
+        let guild_id = Id::new(1);
+        let store = Arc::new(MemoryTaskStore::default());
+        let mut manager = Manager::new(
+            guild_id,
+            store.clone(),
+            Arc::new(RwLock::new(LeaseState::Disabled)),
+        );
+        manager.script_started(&script_meta(&["reminder"]));
+
+        let task = store
+            .create_task(
+                guild_id,
+                "reminder".to_string(),
+                None,
+                HashMap::new(),
+                serde_json::Value::Null,
+                Utc::now() - chrono::Duration::seconds(1),
+            )
+            .await
+            .unwrap();
+        assert_eq!(manager.start_triggered_tasks().await.len(), 1);
+
+        // the worker died before acking it, what the session does when resetting the vm
+        manager
+            .task_interrupted(fired(&task), "worker died".to_string(), None)
+            .await;
+        manager.clear_pending();
+        manager.clear_task_names();
+        manager.clear_next();
+
+        manager.script_started(&script_meta(&["reminder"]));
+        let triggered = manager.start_triggered_tasks().await;
+        assert_eq!(
+            triggered.iter().map(|v| v.id).collect::<Vec<_>>(),
+            vec![task.id]
+        );
+
+        manager.ack_triggered_task(fired(&task)).await;
+        assert_eq!(store.get_task_count(guild_id).await.unwrap(), 0);
+        manager
+            .task_handled(task.id, Some("Error: oops".to_string()), None)
+            .await;
+
+        let outcomes = store
+            .executions
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|v| v.outcome)
+            .collect::<Vec<_>>();
+        assert_eq!(
+            outcomes,
+            vec![TaskOutcome::Interrupted, TaskOutcome::Failed]
+        );
+    }
+}
@@ -8,7 +8,7 @@ use std::{
 
 use crate::{
     command_manager,
-    guild_handler::{GuildCommand, GuildHandle, GuildHandler},
+    guild_handler::{GuildCommand, GuildHandle, GuildHandler, LeaseConfig},
     vm_session::VmSessionEvent,
 };
 use common::DiscordConfig;
@@ -42,6 +42,7 @@ pub struct Scheduler {
     cmd_manager_handle: command_manager::Handle,
     worker_pool: crate::vmworkerpool::VmWorkerPool,
     discord_config: Arc<DiscordConfig>,
+    lease: Option<LeaseConfig>,
 
     suspended_guilds: HashMap<Id<GuildMarker>, GuildSuspension>,
 }
@@ -54,6 +55,7 @@ impl Scheduler {
         cmd_manager_handle: command_manager::Handle,
         worker_pool: crate::vmworkerpool::VmWorkerPool,
         discord_config: Arc<DiscordConfig>,
+        lease: Option<LeaseConfig>,
     ) -> Self {
         Self {
             stores,
@@ -61,6 +63,7 @@ impl Scheduler {
             cmd_manager_handle,
             worker_pool,
             discord_config,
+            lease,
 
             guilds: HashMap::new(),
             cmd_rx: scheduler_rx,
@@ -181,6 +184,10 @@ impl Scheduler {
                     SuspensionReason::ExcessInvalidDiscordRequests,
                 );
             }
+            VmSessionEvent::LeaseHeldElsewhere => {
+                // the node holding it handles its events, so drop them here until we try claiming it again
+                self.mark_guild_as_suspended(guild_id, SuspensionReason::LeaseHeldElsewhere);
+            }
         }
     }
 
@@ -306,6 +313,7 @@ impl Scheduler {
                 self.worker_pool.clone(),
                 self.cmd_manager_handle.clone(),
                 self.discord_config.clone(),
+                self.lease.clone(),
             );
             e.insert(handle);
             return self.guilds.get(&guild_id).unwrap();
@@ -378,6 +386,7 @@ impl<T: stores::config::ConfigStore + stores::timers::TimerStore> Store for T {}
 enum SuspensionReason {
     ExcessCpu,
     ExcessInvalidDiscordRequests,
+    LeaseHeldElsewhere,
 }
 
 impl SuspensionReason {
@@ -385,6 +394,7 @@ impl SuspensionReason {
         match self {
             Self::ExcessCpu => Duration::from_secs(15),
             Self::ExcessInvalidDiscordRequests => Duration::from_secs(60 * 10),
+            Self::LeaseHeldElsewhere => Duration::from_secs(30),
         }
     }
 }
@@ -6,8 +6,9 @@ use std::{
 
 use crate::{
     command_manager,
-    guild_handler::PremiumTierState,
-    interval_timer_manager, scheduled_task_manager,
+    guild_handler::{LeaseState, PremiumTierState},
+    interval_timer_manager,
+    scheduled_task_manager::{self, FiredTask},
     scheduler::Store,
     vmworkerpool::{WorkerHandle, WorkerRetrieved},
 };
@@ -55,11 +56,12 @@ impl VmSession {
         cmd_manager_handle: crate::command_manager::Handle,
         discord_config: Arc<DiscordConfig>,
         premium_tier: Arc<RwLock<PremiumTierState>>,
+        lease_state: Arc<RwLock<LeaseState>>,
     ) -> VmSession {
         let interval_timer_man =
             crate::interval_timer_manager::Manager::new(guild_id, stores.clone());
 
-        let tasks_man = scheduled_task_manager::Manager::new(guild_id, stores.clone());
+        let tasks_man = scheduled_task_manager::Manager::new(guild_id, stores.clone(), lease_state);
 
         VmSession {
             stores,
@@ -88,7 +90,7 @@ impl VmSession {
 
     pub async fn start(&mut self) {
         self.try_retry_load_guild_scripts().await;
-        self.load_contribs().await;
+        self.load_contribs(None).await;
     }
 
     #[instrument(skip(self, action), fields(guild_id = self.guild_id.get()))]
@@ -101,7 +103,8 @@ impl VmSession {
                 ));
 
                 self.reset_contribs();
-                self.pending_acks.clear();
+                self.interrupt_pending_acks(format!("vm was shut down: {reason:?}"))
+                    .await;
 
                 match reason {
                     scheduler_worker_rpc::ShutdownReason::TooManyInvalidRequests => {
@@ -167,7 +170,9 @@ impl VmSession {
         *r
     }
 
-    pub async fn load_contribs(&mut self) {
+    /// Sends the current scripts to the worker, `changed_script` is set when only that script
+    /// changed so the worker can reload it without restarting the others
+    pub async fn load_contribs(&mut self, changed_script: Option<u64>) {
         info!("loading contribs");
 
         let evt_id = self.gen_id();
@@ -179,10 +184,23 @@ impl VmSession {
                     guild_id: self.guild_id,
                     premium_tier: self.get_premium_tier().option(),
                     scripts: self.scripts.clone(),
+                    changed_script,
                 }))
                 .is_err()
             {
                 self.broken_worker().await;
+            } else if let Some(script_id) = changed_script {
+                // the other scripts keep running with their handlers registered, if the worker
+                // restarts the vm instead they all register them again as they start
+                self.scheduled_tasks_man.clear_script_task_names(script_id);
+            } else {
+                // the vm gets restarted with the new scripts, only dispatch tasks to handlers that
+                // are registered again once they start, otherwise tasks for a removed handler would
+                // get acked and deleted without ever running.
+                //
+                // pending tasks are kept as their acks from the old vm are still on the way
+                self.scheduled_tasks_man.clear_task_names();
+                self.scheduled_tasks_man.clear_next();
             }
         } else {
             self.reset_contribs();
@@ -282,8 +300,8 @@ impl VmSession {
                             let _ = resp.send(());
                         }
                         PendingAck::Dispatch(_) => {}
-                        PendingAck::ScheduledTask(t_id) => {
-                            self.scheduled_tasks_man.ack_triggered_task(t_id).await;
+                        PendingAck::ScheduledTask(task) => {
+                            self.scheduled_tasks_man.ack_triggered_task(task).await;
                         }
                         PendingAck::IntervalTimer(name) => {
                             self.interval_timers_man.timer_ack(name).await;
@@ -295,7 +313,16 @@ impl VmSession {
                 self.script_loaded(start).await;
             }
             WorkerMessage::ScriptsInit => todo!(),
+            WorkerMessage::TaskHandled { task_id, error } => {
+                self.task_handled(task_id, error).await;
+            }
             WorkerMessage::NonePending => {
+                // the vm is idle, so a task handler that hasn't reported back never ran
+                let premium_tier = self.get_premium_tier().option();
+                self.scheduled_tasks_man
+                    .running_tasks_stopped("no handler finished running the task", premium_tier)
+                    .await;
+
                 if self.pending_acks.is_empty() {
                     if let Some(current) = self.current_worker.take() {
                         // return worker
@@ -307,7 +334,12 @@ impl VmSession {
                 self.scheduled_tasks_man.clear_next();
             }
             WorkerMessage::GuildLog(entry) => {
-                self.logger.log(entry);
+                // every entry from a script is held to the floor configured for it
+                let below_floor = log_entry_script(&self.scripts, &entry)
+                    .map_or(false, |script| entry.level.is_below(script.min_log_level));
+                if !below_floor {
+                    self.logger.log(entry);
+                }
             }
             WorkerMessage::Hello(_) => {
                 // handled when connection is established, not applicable here
@@ -357,19 +389,26 @@ impl VmSession {
     }
 
     pub async fn reload_guild_scripts(&mut self) {
+        let old_scripts = self.scripts.clone();
         self.try_retry_load_guild_scripts().await;
-        self.load_contribs().await;
+
+        let changed_script = single_changed_script(&old_scripts, &self.scripts);
+        self.load_contribs(changed_script).await;
     }
 
     async fn dispatch_scheduled_task(&mut self, task: ScheduledTask) {
         info!("dispatching scheduled task");
-        let task_id = task.id;
+        let fired = FiredTask {
+            id: task.id,
+            name: task.name.clone(),
+            fired_at: chrono::Utc::now(),
+        };
         let evt = runtime_models::internal::tasks::ScheduledTask::from(task);
         let serialized = serde_json::to_value(&evt).unwrap();
         self.dispatch_worker_evt(
             "BOTLOADER_SCHEDULED_TASK_FIRED".to_string(),
             serialized,
-            PendingAck::ScheduledTask(task_id),
+            PendingAck::ScheduledTask(fired),
         )
         .await;
     }
@@ -436,7 +475,8 @@ impl VmSession {
 
                 #[allow(clippy::collapsible_if)]
                 if self.should_send_scripts(wr) {
-                    self.pending_acks.clear();
+                    self.interrupt_pending_acks("vm was restarted".to_string())
+                        .await;
                     self.reset_contribs();
 
                     if worker
@@ -446,6 +486,7 @@ impl VmSession {
                             guild_id: self.guild_id,
                             premium_tier: self.get_premium_tier().option(),
                             scripts: self.scripts.clone(),
+                            changed_script: None,
                         }))
                         .is_err()
                     {
@@ -471,7 +512,49 @@ impl VmSession {
 
             self.worker_pool.return_worker(worker, true);
             self.reset_contribs();
-            self.pending_acks.clear();
+            self.interrupt_pending_acks("worker stopped responding".to_string())
+                .await;
+        }
+    }
+
+    // the result can arrive before the ack as they're sent separately by the worker
+    async fn task_handled(&mut self, task_id: u64, error: Option<String>) {
+        let ack_id = self.pending_acks.iter().find_map(|(k, v)| match v {
+            PendingAck::ScheduledTask(task) if task.id == task_id => Some(*k),
+            _ => None,
+        });
+
+        if let Some(PendingAck::ScheduledTask(task)) =
+            ack_id.and_then(|id| self.pending_acks.remove(&id))
+        {
+            self.scheduled_tasks_man.ack_triggered_task(task).await;
+        }
+
+        let premium_tier = self.get_premium_tier().option();
+        self.scheduled_tasks_man
+            .task_handled(task_id, error, premium_tier)
+            .await;
+    }
+
+    // drops the pending acks, recording the scheduled tasks among them as interrupted and the
+    // ones whose handler was still running as failed
+    async fn interrupt_pending_acks(&mut self, reason: String) {
+        let premium_tier = self.get_premium_tier().option();
+        self.scheduled_tasks_man
+            .running_tasks_stopped(&reason, premium_tier)
+            .await;
+
+        let acks = self
+            .pending_acks
+            .drain()
+            .map(|(_, v)| v)
+            .collect::<Vec<_>>();
+        for ack in acks {
+            if let PendingAck::ScheduledTask(task) = ack {
+                self.scheduled_tasks_man
+                    .task_interrupted(task, reason.clone(), premium_tier)
+                    .await;
+            }
         }
     }
 
@@ -548,6 +631,7 @@ impl VmSession {
                 ScriptContributes {
                     commands: twilight_commands,
                     interval_timers: interval_contribs,
+                    api_version: evt.api_version,
                 },
             )
             .await
@@ -557,6 +641,54 @@ impl VmSession {
     }
 }
 
+/// Finds the script a log entry was logged from by the file in its script context
+///
+/// Depending on where the entry came from that's either the compiled module, the original source
+/// or the id of the script.
+fn log_entry_script<'a>(scripts: &'a [Script], entry: &LogEntry) -> Option<&'a Script> {
+    let filename = &entry.script_context.as_ref()?.filename;
+    if let Ok(script_id) = filename.parse::<u64>() {
+        return scripts.iter().find(|v| v.id == script_id);
+    }
+
+    let path = filename.strip_prefix("file:///").unwrap_or(filename);
+    // scripts reloaded in place are loaded with a query to get a fresh module
+    let path = path.split_once('?').map(|(path, _)| path).unwrap_or(path);
+    let name = path.strip_prefix("guild_scripts/")?;
+    let name = name
+        .strip_suffix(".ts")
+        .or_else(|| name.strip_suffix(".js"))?;
+
+    scripts.iter().find(|v| v.name == name)
+}
+
+// the id of the only script that changed if the set of scripts is otherwise the same, the
+// contributes are left out as they're updated by the scheduler itself as the scripts start
+fn single_changed_script(old: &[Script], new: &[Script]) -> Option<u64> {
+    if old.len() != new.len() {
+        return None;
+    }
+
+    let mut changed = None;
+    for new_script in new {
+        let old_script = old.iter().find(|v| v.id == new_script.id)?;
+        if old_script.name == new_script.name
+            && old_script.original_source == new_script.original_source
+            && old_script.plugin_id == new_script.plugin_id
+            && old_script.min_log_level == new_script.min_log_level
+        {
+            continue;
+        }
+
+        if changed.is_some() {
+            return None;
+        }
+        changed = Some(new_script.id);
+    }
+
+    changed
+}
+
 pub enum NextAction {
     WorkerMessage(Option<WorkerMessage>),
     CheckScheduledTasks,
@@ -566,10 +698,12 @@ pub enum NextAction {
 pub enum VmSessionEvent {
     TooManyInvalidRequests,
     ForciblyShutdown,
+    /// Another scheduler node holds the lease on the guild
+    LeaseHeldElsewhere,
 }
 
 pub enum PendingAck {
     Dispatch(Option<oneshot::Sender<()>>),
-    ScheduledTask(u64),
+    ScheduledTask(FiredTask),
     IntervalTimer(String),
 }
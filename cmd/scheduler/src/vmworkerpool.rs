@@ -57,7 +57,7 @@ impl VmWorkerPool {
         guild_id: Id<GuildMarker>,
         premium_tier: Option<PremiumSlotTier>,
     ) -> (WorkerHandle, WorkerRetrieved) {
-        let mut worker = self.inner_get_worker(guild_id, premium_tier).await;
+        let mut worker = self.inner_get_worker(Some(guild_id), premium_tier).await;
         let wr = if matches!(worker.last_active_guild, Some(g) if g == guild_id) {
             WorkerRetrieved::SameGuild
         } else {
@@ -68,9 +68,20 @@ impl VmWorkerPool {
         (worker, wr)
     }
 
+    /// Claims a worker for a sandboxed test run
+    ///
+    /// The worker is not marked as active for any guild, so the next session claiming it always
+    /// starts a new vm with the guild's real scripts
+    pub async fn req_sandbox_worker(&self, premium_tier: Option<PremiumSlotTier>) -> WorkerHandle {
+        let mut worker = self.inner_get_worker(None, premium_tier).await;
+        worker.last_active_guild = None;
+        worker.claimed_at = Instant::now();
+        worker
+    }
+
     async fn inner_get_worker(
         &self,
-        guild_id: Id<GuildMarker>,
+        guild_id: Option<Id<GuildMarker>>,
         premium_tier: Option<PremiumSlotTier>,
     ) -> WorkerHandle {
         let rx = {
@@ -78,26 +89,28 @@ impl VmWorkerPool {
 
             let priority_index = premium_tier_index(premium_tier);
 
-            // try to find one with identical guild id, avoids us having to reload all scripts
-            let mut i = priority_index;
-            loop {
-                let pool = &mut w.pools[i];
-                let pref_worker = pool
-                    .iter()
-                    .enumerate()
-                    .find(|(_, v)| matches!(v.last_active_guild, Some(g) if g == guild_id))
-                    .map(|(i, _)| i);
+            if let Some(guild_id) = guild_id {
+                // try to find one with identical guild id, avoids us having to reload all scripts
+                let mut i = priority_index;
+                loop {
+                    let pool = &mut w.pools[i];
+                    let pref_worker = pool
+                        .iter()
+                        .enumerate()
+                        .find(|(_, v)| matches!(v.last_active_guild, Some(g) if g == guild_id))
+                        .map(|(i, _)| i);
+
+                    if let Some(pref_worker) = pref_worker {
+                        metrics::decrement_gauge!("bl.scheduler.workerpool_available_workers", 1.0);
+                        return pool.remove(pref_worker);
+                    }
 
-                if let Some(pref_worker) = pref_worker {
-                    metrics::decrement_gauge!("bl.scheduler.workerpool_available_workers", 1.0);
-                    return pool.remove(pref_worker);
-                }
+                    if i == 0 {
+                        break;
+                    }
 
-                if i == 0 {
-                    break;
+                    i -= 1;
                 }
-
-                i -= 1;
             }
 
             // take the least recently used worker
@@ -1,15 +1,32 @@
-use std::sync::{Arc, RwLock};
+use std::{
+    collections::{HashMap, HashSet},
+    sync::{Arc, RwLock},
+    time::Duration,
+};
 
 use clap::Parser;
 use common::DiscordConfig;
 use guild_logger::GuildLogger;
-use runtime::{CreateRuntimeContext, RuntimeEvent};
+use runtime::{
+    jsmodules::{BuiltinModuleAllowlist, BuiltinModuleGate},
+    CreateRuntimeContext, RuntimeEvent,
+};
 use scheduler_worker_rpc::{CreateScriptsVmReq, SchedulerMessage, ShutdownReason, WorkerMessage};
-use stores::{config::PremiumSlotTier, postgres::Postgres};
-use tokio::sync::mpsc;
+use stores::{
+    config::{ConfigStore, PremiumSlotTier, Script},
+    postgres::Postgres,
+};
+use tokio::{sync::mpsc, time::Instant};
 use tracing::{error, info};
 use twilight_model::id::{marker::GuildMarker, Id};
-use vm::vm::{CreateRt, GuildVmEvent, Vm, VmCommand, VmContext, VmEvent, VmRole};
+use vm::vm::{
+    CreateRt, FailedScripts, GuildVmEvent, RestartInfo, RestartReason, Vm, VmCommand, VmContext,
+    VmEvent, VmRole,
+};
+use vm::{
+    cpu_budget::CpuBudgetConfig, dispatch_dedup::DispatchDedupConfig,
+    error_breaker::ErrorBreakerConfig,
+};
 use vmthread::{VmThreadCommand, VmThreadFuture, VmThreadHandle};
 
 mod metrics_forwarder;
@@ -18,6 +35,10 @@ mod metrics_forwarder;
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
     common::common_init(None);
     let config = WorkerConfig::parse();
+    let guild_http_proxies = parse_guild_http_proxies(&config.guild_http_proxies)?;
+    let builtin_module_allowlist =
+        parse_builtin_module_allowlist(&config.gated_modules, &config.module_feature_flags)?;
+    let guild_feature_flags = parse_guild_feature_flags(&config.guild_feature_flags)?;
     let discord_config = common::fetch_discord_config(config.common.discord_token.clone())
         .await
         .expect("failed fetching discord config");
@@ -61,7 +82,28 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         logger,
         discord_config,
         config.common.user_script_http_proxy.clone(),
+        guild_http_proxies,
+        builtin_module_allowlist,
+        guild_feature_flags,
         broker_client,
+        Duration::from_secs(config.hibernate_after_secs),
+        !config.disable_source_maps,
+        config.shared_ratelimits,
+        Duration::from_secs(config.failed_script_retry_grace_secs),
+        ErrorBreakerConfig {
+            threshold: config.error_breaker_threshold,
+            window: Duration::from_secs(config.error_breaker_window_secs),
+            cooldown: Duration::from_secs(config.error_breaker_cooldown_secs),
+        },
+        DispatchDedupConfig {
+            window: Duration::from_secs(config.dispatch_dedup_window_secs),
+            max_events: config.dispatch_dedup_max_events,
+        },
+        CpuBudgetConfig {
+            free: Duration::from_millis(config.cpu_budget_ms),
+            lite: Duration::from_millis(config.cpu_budget_lite_ms),
+            premium: Duration::from_millis(config.cpu_budget_premium_ms),
+        },
     );
 
     worker.run().await;
@@ -84,6 +126,180 @@ pub struct WorkerConfig {
 
     #[clap(long, env = "BL_WORKER_ID")]
     pub(crate) worker_id: u64,
+
+    /// shut down the vm after it has been idle for this long, it's recreated when new events arrive
+    #[clap(long, env = "BL_VMWORKER_HIBERNATE_AFTER_SECS", default_value = "300")]
+    pub(crate) hibernate_after_secs: u64,
+
+    /// don't map script errors back to the typescript source, errors will contain the raw stack trace
+    /// but the source maps, which are often larger than the scripts themselves, are not kept in memory
+    #[clap(long, env = "BL_VMWORKER_DISABLE_SOURCE_MAPS")]
+    pub(crate) disable_source_maps: bool,
+
+    /// enforce script rate limits across all of a guild's vm's using the database,
+    /// also makes them persist across vm restarts
+    #[clap(long, env = "BL_VMWORKER_SHARED_RATELIMITS")]
+    pub(crate) shared_ratelimits: bool,
+
+    /// scripts that failed to load are skipped on vm restarts for this long, unless their source changes
+    #[clap(
+        long,
+        env = "BL_VMWORKER_FAILED_SCRIPT_RETRY_GRACE_SECS",
+        default_value = "60"
+    )]
+    pub(crate) failed_script_retry_grace_secs: u64,
+
+    /// disable the event handlers of a script after this many errors within the error breaker window, 0 to never disable them
+    #[clap(
+        long,
+        env = "BL_VMWORKER_ERROR_BREAKER_THRESHOLD",
+        default_value = "20"
+    )]
+    pub(crate) error_breaker_threshold: u32,
+
+    /// the window in seconds that errors are counted over for the error breaker threshold
+    #[clap(
+        long,
+        env = "BL_VMWORKER_ERROR_BREAKER_WINDOW_SECS",
+        default_value = "60"
+    )]
+    pub(crate) error_breaker_window_secs: u64,
+
+    /// how long the event handlers of a script stay disabled after it hit the error breaker threshold
+    #[clap(
+        long,
+        env = "BL_VMWORKER_ERROR_BREAKER_COOLDOWN_SECS",
+        default_value = "300"
+    )]
+    pub(crate) error_breaker_cooldown_secs: u64,
+
+    /// how long dispatched events are remembered to skip ones the gateway delivers again, 0 to disable
+    #[clap(
+        long,
+        env = "BL_VMWORKER_DISPATCH_DEDUP_WINDOW_SECS",
+        default_value = "300"
+    )]
+    pub(crate) dispatch_dedup_window_secs: u64,
+
+    /// max number of dispatched events remembered per vm for skipping duplicates
+    #[clap(
+        long,
+        env = "BL_VMWORKER_DISPATCH_DEDUP_MAX_EVENTS",
+        default_value = "1000"
+    )]
+    pub(crate) dispatch_dedup_max_events: usize,
+
+    /// how long in milliseconds a single event dispatch or script load can run before the vm is
+    /// shut down, for guilds without premium, 0 to disable
+    #[clap(long, env = "BL_VMWORKER_CPU_BUDGET_MS", default_value = "2000")]
+    pub(crate) cpu_budget_ms: u64,
+
+    /// same as cpu_budget_ms, for guilds with lite premium
+    #[clap(long, env = "BL_VMWORKER_CPU_BUDGET_LITE_MS", default_value = "4000")]
+    pub(crate) cpu_budget_lite_ms: u64,
+
+    /// same as cpu_budget_ms, for guilds with premium
+    #[clap(
+        long,
+        env = "BL_VMWORKER_CPU_BUDGET_PREMIUM_MS",
+        default_value = "8000"
+    )]
+    pub(crate) cpu_budget_premium_ms: u64,
+
+    /// comma separated list of guild_id=proxy_addr pairs, overriding the user script http proxy
+    /// for those guilds
+    #[clap(long, env = "BL_VMWORKER_GUILD_HTTP_PROXIES", value_delimiter = ',')]
+    pub(crate) guild_http_proxies: Vec<String>,
+
+    /// comma separated list of module=gate pairs restricting which builtin modules scripts can import,
+    /// the gate is either a premium tier (lite, premium) or flag:<name>
+    #[clap(long, env = "BL_VMWORKER_GATED_MODULES", value_delimiter = ',')]
+    pub(crate) gated_modules: Vec<String>,
+
+    /// comma separated list of enabled feature flags for flag gated builtin modules
+    #[clap(long, env = "BL_VMWORKER_MODULE_FEATURE_FLAGS", value_delimiter = ',')]
+    pub(crate) module_feature_flags: Vec<String>,
+
+    /// comma separated list of guild_id=flag pairs, enabling feature flags for those guilds
+    /// in addition to the module feature flags
+    #[clap(long, env = "BL_VMWORKER_GUILD_FEATURE_FLAGS", value_delimiter = ',')]
+    pub(crate) guild_feature_flags: Vec<String>,
+}
+
+// the proxy addresses are checked here as the vm's http client is only built once a guild's vm
+// is created
+fn parse_guild_http_proxies(raw: &[String]) -> anyhow::Result<HashMap<Id<GuildMarker>, String>> {
+    raw.iter()
+        .map(|entry| {
+            let (guild_id, proxy_addr) = entry.split_once('=').ok_or_else(|| {
+                anyhow::anyhow!(
+                    "guild http proxy entry {entry:?} should be in the form of guild_id=proxy_addr"
+                )
+            })?;
+
+            let guild_id = guild_id
+                .trim()
+                .parse()
+                .map_err(|err| anyhow::anyhow!("invalid guild id in {entry:?}: {err}"))?;
+
+            let proxy_addr = proxy_addr.trim().to_string();
+            runtime::validate_http_proxy_addr(&proxy_addr)
+                .map_err(|err| anyhow::anyhow!("invalid proxy address in {entry:?}: {err}"))?;
+
+            Ok((guild_id, proxy_addr))
+        })
+        .collect()
+}
+
+fn parse_builtin_module_allowlist(
+    gated_modules: &[String],
+    feature_flags: &[String],
+) -> anyhow::Result<BuiltinModuleAllowlist> {
+    let gates = gated_modules
+        .iter()
+        .map(|entry| {
+            let (module_name, gate) = entry.split_once('=').ok_or_else(|| {
+                anyhow::anyhow!("gated module entry {entry:?} should be in the form of module=gate")
+            })?;
+
+            let gate: BuiltinModuleGate = gate
+                .trim()
+                .parse()
+                .map_err(|err| anyhow::anyhow!("invalid gate in {entry:?}: {err}"))?;
+
+            Ok((module_name.trim().to_string(), gate))
+        })
+        .collect::<anyhow::Result<HashMap<_, _>>>()?;
+
+    Ok(BuiltinModuleAllowlist {
+        gates,
+        enabled_feature_flags: feature_flags.iter().map(|v| v.trim().to_string()).collect(),
+    })
+}
+
+fn parse_guild_feature_flags(
+    raw: &[String],
+) -> anyhow::Result<HashMap<Id<GuildMarker>, HashSet<String>>> {
+    let mut result: HashMap<Id<GuildMarker>, HashSet<String>> = HashMap::new();
+    for entry in raw {
+        let (guild_id, flag) = entry.split_once('=').ok_or_else(|| {
+            anyhow::anyhow!(
+                "guild feature flag entry {entry:?} should be in the form of guild_id=flag"
+            )
+        })?;
+
+        let guild_id = guild_id
+            .trim()
+            .parse()
+            .map_err(|err| anyhow::anyhow!("invalid guild id in {entry:?}: {err}"))?;
+
+        result
+            .entry(guild_id)
+            .or_default()
+            .insert(flag.trim().to_string());
+    }
+
+    Ok(result)
 }
 
 struct WorkerState {
@@ -91,6 +307,16 @@ struct WorkerState {
     vm_thread: VmThreadHandle<Vm>,
     scripts_vm: mpsc::UnboundedSender<VmCommand>,
     evt_rx: mpsc::UnboundedReceiver<(Id<GuildMarker>, VmRole, VmEvent)>,
+    scripts: Vec<Script>,
+    idle_since: Option<Instant>,
+    // test run vm, never hibernated or reused for the guild's real scripts
+    sandbox: bool,
+}
+
+// a vm that was shut down after being idle, it's recreated when the next event arrives
+struct HibernatedVm {
+    guild_id: Id<GuildMarker>,
+    scripts: Vec<Script>,
 }
 
 struct Worker {
@@ -102,14 +328,30 @@ struct Worker {
     guild_logger: guild_logger::GuildLogger,
     discord_config: Arc<DiscordConfig>,
     user_http_proxy: Option<String>,
+    guild_http_proxies: HashMap<Id<GuildMarker>, String>,
+    builtin_modules: BuiltinModuleAllowlist,
+    guild_feature_flags: HashMap<Id<GuildMarker>, HashSet<String>>,
     broker_client: dbrokerapi::state_client::Client,
 
     premium_tier: Arc<RwLock<Option<PremiumSlotTier>>>,
     stores: Arc<Postgres>,
     current_state: Option<WorkerState>,
+    hibernated: Option<HibernatedVm>,
+    hibernate_after: Duration,
+    source_maps: bool,
+    shared_ratelimits: bool,
+    failed_script_retry_grace: Duration,
+    error_breaker: ErrorBreakerConfig,
+    dispatch_dedup: DispatchDedupConfig,
+    cpu_budget: CpuBudgetConfig,
+    // why the last vm was shut down, passed on to the next vm if it's for the same guild
+    last_restart: Option<(Id<GuildMarker>, RestartInfo)>,
+    // the scripts that failed to load, shared by the vms started for the same guild
+    failed_scripts: Option<(Id<GuildMarker>, FailedScripts)>,
 }
 
 impl Worker {
+    #[allow(clippy::too_many_arguments)]
     fn new(
         scheduler_rx: mpsc::UnboundedReceiver<SchedulerMessage>,
         scheduler_tx: mpsc::UnboundedSender<WorkerMessage>,
@@ -117,7 +359,17 @@ impl Worker {
         guild_logger: GuildLogger,
         discord_config: Arc<DiscordConfig>,
         user_http_proxy: Option<String>,
+        guild_http_proxies: HashMap<Id<GuildMarker>, String>,
+        builtin_modules: BuiltinModuleAllowlist,
+        guild_feature_flags: HashMap<Id<GuildMarker>, HashSet<String>>,
         broker_client: dbrokerapi::state_client::Client,
+        hibernate_after: Duration,
+        source_maps: bool,
+        shared_ratelimits: bool,
+        failed_script_retry_grace: Duration,
+        error_breaker: ErrorBreakerConfig,
+        dispatch_dedup: DispatchDedupConfig,
+        cpu_budget: CpuBudgetConfig,
     ) -> Self {
         let (runtime_evt_tx, runtime_evt_rx) = mpsc::unbounded_channel();
 
@@ -130,8 +382,21 @@ impl Worker {
             guild_logger,
             discord_config,
             user_http_proxy,
+            guild_http_proxies,
+            builtin_modules,
+            guild_feature_flags,
             broker_client,
             current_state: None,
+            hibernated: None,
+            hibernate_after,
+            source_maps,
+            shared_ratelimits,
+            failed_script_retry_grace,
+            error_breaker,
+            dispatch_dedup,
+            cpu_budget,
+            last_restart: None,
+            failed_scripts: None,
             premium_tier: Arc::new(RwLock::new(None)),
         }
     }
@@ -139,6 +404,11 @@ impl Worker {
     async fn run(mut self) {
         loop {
             let res = if let Some(current) = &mut self.current_state {
+                let hibernate_at = current
+                    .idle_since
+                    .filter(|_| !current.sandbox)
+                    .map(|v| v + self.hibernate_after);
+
                 tokio::select! {
                     scheduler_cmd = self.scheduler_rx.recv() => {
                         if let Some(cmd) = scheduler_cmd{
@@ -164,6 +434,10 @@ impl Worker {
                              self.write_message(WorkerMessage::Shutdown(ShutdownReason::Other)).await.map(|_| ContinueState::Continue)
                         }
                     }
+                    _ = tokio::time::sleep_until(hibernate_at.unwrap_or_else(Instant::now)), if hibernate_at.is_some() => {
+                        self.hibernate_current_vm().await;
+                        Ok(ContinueState::Continue)
+                    }
                 }
             } else {
                 tokio::select! {
@@ -203,6 +477,11 @@ impl Worker {
         match cmd {
             SchedulerMessage::Dispatch(evt) => {
                 info!("worker is dispatching {}", evt.name);
+                if self.current_state.is_none() {
+                    self.rehydrate_vm().await;
+                }
+
+                // commands are queued up in the channel while the vm is being created
                 if let Some(current) = &self.current_state {
                     let _ = current
                         .scripts_vm
@@ -213,6 +492,7 @@ impl Worker {
             }
             SchedulerMessage::Shutdown => Ok(ContinueState::Stop),
             SchedulerMessage::CreateScriptsVm(data) => self.handle_create_scripts_vm(data).await,
+            SchedulerMessage::CreateSandboxVm(data) => self.handle_create_sandbox_vm(data).await,
             SchedulerMessage::Complete => {
                 // complete the vm
                 if let Some(current) = &self.current_state {
@@ -220,6 +500,9 @@ impl Worker {
                         .vm_thread
                         .send_cmd
                         .send(vmthread::VmThreadCommand::Shutdown);
+                } else if self.hibernated.take().is_some() {
+                    // nothing running, we're already complete
+                    self.write_message(WorkerMessage::NonePending).await?;
                 }
                 Ok(ContinueState::Continue)
             }
@@ -229,11 +512,19 @@ impl Worker {
     async fn handle_runtime_evt(&mut self, evt: RuntimeEvent) -> anyhow::Result<ContinueState> {
         match evt {
             RuntimeEvent::ScriptStarted(sm) => {
+                self.record_script_validation(sm.script_id.0, None);
                 self.write_message(WorkerMessage::ScriptStarted(sm)).await?;
             }
+            RuntimeEvent::ScriptValidationFailed { script_id, reason } => {
+                self.record_script_validation(script_id, Some(reason));
+            }
             RuntimeEvent::NewTaskScheduled => {
                 self.write_message(WorkerMessage::TaskScheduled).await?;
             }
+            RuntimeEvent::TaskHandled { task_id, error } => {
+                self.write_message(WorkerMessage::TaskHandled { task_id, error })
+                    .await?;
+            }
             RuntimeEvent::InvalidRequestsExceeded => {
                 self.write_message(WorkerMessage::Shutdown(
                     ShutdownReason::TooManyInvalidRequests,
@@ -243,6 +534,43 @@ impl Worker {
         }
         Ok(ContinueState::Continue)
     }
+
+    /// Persists the result of validating a script's meta on load, so broken scripts can be
+    /// shown in the dashboard
+    fn record_script_validation(&self, script_id: u64, failure: Option<String>) {
+        let current = if let Some(current) = &self.current_state {
+            current
+        } else {
+            return;
+        };
+
+        if current.sandbox {
+            return;
+        }
+
+        // don't hold up the event loop on the database
+        let guild_id = current.guild_id;
+        let stores = self.stores.clone();
+        tokio::spawn(async move {
+            let res = match failure {
+                Some(reason) => {
+                    stores
+                        .set_script_validation_failure(guild_id, script_id, reason)
+                        .await
+                }
+                None => {
+                    stores
+                        .clear_script_validation_failure(guild_id, script_id)
+                        .await
+                }
+            };
+
+            if let Err(err) = res {
+                error!(%err, "failed storing script validation result");
+            }
+        });
+    }
+
     async fn handle_vm_evt(
         &mut self,
         (guild_id, _vm_role, evt): GuildVmEvent,
@@ -263,6 +591,19 @@ impl Worker {
         match evt {
             VmEvent::Shutdown(reason) => {
                 info!("vm shut down: {:?}", reason);
+                let restart_reason = match reason {
+                    vmthread::ShutdownReason::OutOfMemory => RestartReason::OutOfMemory,
+                    // both mean a script was stuck, the next vm doesn't need to tell them apart
+                    vmthread::ShutdownReason::Runaway | vmthread::ShutdownReason::Timeout => {
+                        RestartReason::Runaway
+                    }
+                    vmthread::ShutdownReason::Unknown
+                    | vmthread::ShutdownReason::ThreadTermination => RestartReason::Other,
+                };
+                if !self.current_state.as_ref().map_or(false, |c| c.sandbox) {
+                    self.last_restart = Some((guild_id, RestartInfo::now(restart_reason)));
+                }
+
                 // shut down the vm thread
                 self.wait_shutdown_current_vm().await;
 
@@ -279,6 +620,10 @@ impl Worker {
                         self.write_message(WorkerMessage::Shutdown(ShutdownReason::Runaway))
                             .await?
                     }
+                    vmthread::ShutdownReason::Timeout => {
+                        self.write_message(WorkerMessage::Shutdown(ShutdownReason::Timeout))
+                            .await?
+                    }
                     vmthread::ShutdownReason::Unknown
                     | vmthread::ShutdownReason::ThreadTermination => {
                         self.write_message(WorkerMessage::Shutdown(ShutdownReason::Other))
@@ -295,6 +640,27 @@ impl Worker {
                 }
                 self.write_message(WorkerMessage::NonePending).await?
             }
+            VmEvent::Idle => {
+                if let Some(current) = &mut self.current_state {
+                    current.idle_since = Some(Instant::now());
+                }
+            }
+            VmEvent::Busy => {
+                if let Some(current) = &mut self.current_state {
+                    current.idle_since = None;
+                }
+            }
+            VmEvent::ScriptStats(_) if self.current_state.as_ref().map_or(false, |c| c.sandbox) => {
+            }
+            VmEvent::ScriptStats(stats) => {
+                // don't hold up the event loop on the database
+                let stores = self.stores.clone();
+                tokio::spawn(async move {
+                    if let Err(err) = stores.add_script_runtime_stats(guild_id, &stats).await {
+                        error!(%err, "failed storing script runtime stats");
+                    }
+                });
+            }
         }
         Ok(ContinueState::Continue)
     }
@@ -303,8 +669,11 @@ impl Worker {
         &mut self,
         req: CreateScriptsVmReq,
     ) -> anyhow::Result<ContinueState> {
+        // we're given a fresh set of scripts, no need to rehydrate the old vm
+        self.hibernated = None;
+
         if let Some(current) = &self.current_state {
-            if current.guild_id != req.guild_id {
+            if current.guild_id != req.guild_id || current.sandbox {
                 self.wait_shutdown_current_vm().await;
             }
         };
@@ -315,29 +684,104 @@ impl Worker {
             *w = req.premium_tier;
         }
 
-        if let Some(current) = &self.current_state {
-            // we were already running a vm for this guild, issue a restart command with the new scripts instead
-            let _ = current.scripts_vm.send(VmCommand::Restart(req.scripts));
+        if let Some(current) = &mut self.current_state {
+            // we were already running a vm for this guild, reload the changed script in place if
+            // it's the only change and issue a restart command with the new scripts otherwise
+            let same_scripts = current.scripts.len() == req.scripts.len()
+                && current
+                    .scripts
+                    .iter()
+                    .all(|old| req.scripts.iter().any(|new| new.id == old.id));
+            let changed_script = req
+                .changed_script
+                .filter(|_| same_scripts)
+                .and_then(|id| req.scripts.iter().find(|v| v.id == id).cloned());
+
+            current.scripts = req.scripts.clone();
+            let cmd = if let Some(script) = changed_script {
+                VmCommand::UpdateScript(script)
+            } else {
+                VmCommand::Restart(req.scripts)
+            };
+            let _ = current.scripts_vm.send(cmd);
             self.write_message(WorkerMessage::Ack(req.seq)).await?;
             return Ok(ContinueState::Continue);
         }
 
+        self.start_vm(req.guild_id, req.scripts, false);
+
+        self.write_message(WorkerMessage::Ack(req.seq)).await?;
+        Ok(ContinueState::Continue)
+    }
+
+    async fn handle_create_sandbox_vm(
+        &mut self,
+        req: CreateScriptsVmReq,
+    ) -> anyhow::Result<ContinueState> {
+        // the sandbox always gets a fresh vm, and the guild's real vm is started from scratch
+        // the next time this worker is claimed
+        self.hibernated = None;
+        self.wait_shutdown_current_vm().await;
+
+        {
+            let mut w = self.premium_tier.write().unwrap();
+            *w = req.premium_tier;
+        }
+
+        self.start_vm(req.guild_id, req.scripts, true);
+
+        self.write_message(WorkerMessage::Ack(req.seq)).await?;
+        Ok(ContinueState::Continue)
+    }
+
+    fn start_vm(&mut self, guild_id: Id<GuildMarker>, scripts: Vec<Script>, sandbox: bool) {
         let vmthread = VmThreadFuture::create();
         let (vm_cmd_tx, vm_cmd_rx) = mpsc::unbounded_channel();
         let (vm_evt_tx, vm_evt_rx) = mpsc::unbounded_channel();
 
+        // the guild's own flags also unlock flag gated builtin modules
+        let mut builtin_modules = self.builtin_modules.clone();
+        if let Some(flags) = self.guild_feature_flags.get(&guild_id) {
+            builtin_modules
+                .enabled_feature_flags
+                .extend(flags.iter().cloned());
+        }
+        let feature_flags = Arc::new(builtin_modules.enabled_feature_flags.clone());
+
+        // sandbox vms run scripts that aren't saved yet, so they don't share the failures
+        let failed_scripts = match &self.failed_scripts {
+            Some((last_guild_id, failed)) if !sandbox && *last_guild_id == guild_id => {
+                failed.clone()
+            }
+            _ => {
+                let failed = FailedScripts::default();
+                if !sandbox {
+                    self.failed_scripts = Some((guild_id, failed.clone()));
+                }
+                failed
+            }
+        };
+
         let rt_ctx = CreateRuntimeContext {
             bot_state: self.broker_client.clone(),
             discord_config: self.discord_config.clone(),
-            guild_id: req.guild_id,
+            guild_id,
             role: VmRole::Main,
             guild_logger: self.guild_logger.clone(),
             script_http_client_proxy: self.user_http_proxy.clone(),
+            guild_http_client_proxy: self.guild_http_proxies.get(&guild_id).cloned(),
             premium_tier: self.premium_tier.clone(),
+            feature_flags,
 
             bucket_store: self.stores.clone(),
             config_store: self.stores.clone(),
             timer_store: self.stores.clone(),
+            ratelimit_store: if self.shared_ratelimits {
+                Some(self.stores.clone())
+            } else {
+                None
+            },
+            sandbox,
 
             event_tx: self.runtime_evt_tx.clone(),
         };
@@ -348,28 +792,81 @@ impl Worker {
                 guild_logger: self.guild_logger.clone(),
                 rx: vm_cmd_rx,
                 tx: vm_evt_tx,
-                load_scripts: req.scripts,
+                load_scripts: scripts.clone(),
 
                 ctx: VmContext {
                     // bot_state: self.inner.shared_state.bot_context.state.clone(),
                     // dapi: self.inner.shared_state.bot_context.http.clone(),
-                    guild_id: req.guild_id,
+                    guild_id,
                     role: VmRole::Main,
                 },
                 extension_factory: Box::new(move || runtime::create_extensions(rt_ctx.clone())),
                 extension_modules: runtime::jsmodules::create_module_map(),
+                extension_module_access: builtin_modules
+                    .into_access_check(self.premium_tier.clone()),
+                source_maps: self.source_maps,
+                failed_script_retry_grace: self.failed_script_retry_grace,
+                failed_scripts,
+                error_breaker: self.error_breaker,
+                dispatch_dedup: self.dispatch_dedup,
+                cpu_budget: self.cpu_budget,
+                premium_tier: self.premium_tier.clone(),
+                last_restart: self
+                    .last_restart
+                    .as_ref()
+                    .filter(|(last_guild_id, _)| !sandbox && *last_guild_id == guild_id)
+                    .map(|(_, info)| info.clone()),
             }))
             .map_err(|_| unreachable!());
 
         self.current_state = Some(WorkerState {
-            guild_id: req.guild_id,
+            guild_id,
             scripts_vm: vm_cmd_tx,
             evt_rx: vm_evt_rx,
             vm_thread: vmthread,
+            scripts,
+            idle_since: None,
+            sandbox,
         });
+    }
 
-        self.write_message(WorkerMessage::Ack(req.seq)).await?;
-        Ok(ContinueState::Continue)
+    async fn hibernate_current_vm(&mut self) {
+        if let Some(current) = &self.current_state {
+            info!(guild_id = current.guild_id.get(), "hibernating idle vm");
+
+            let hibernated = HibernatedVm {
+                guild_id: current.guild_id,
+                scripts: current.scripts.clone(),
+            };
+
+            self.wait_shutdown_current_vm().await;
+            self.hibernated = Some(hibernated);
+        }
+    }
+
+    async fn rehydrate_vm(&mut self) {
+        let hibernated = if let Some(hibernated) = self.hibernated.take() {
+            hibernated
+        } else {
+            return;
+        };
+
+        info!(guild_id = hibernated.guild_id.get(), "rehydrating vm");
+        self.last_restart = Some((
+            hibernated.guild_id,
+            RestartInfo::now(RestartReason::Hibernation),
+        ));
+
+        // the scripts could have changed while we were hibernated
+        let scripts = match self.stores.list_scripts(hibernated.guild_id).await {
+            Ok(scripts) => scripts.into_iter().filter(|v| v.enabled).collect(),
+            Err(err) => {
+                error!(%err, "failed fetching guild scripts, using the ones from before hibernation");
+                hibernated.scripts
+            }
+        };
+
+        self.start_vm(hibernated.guild_id, scripts, false);
     }
 
     async fn write_message(&mut self, v: WorkerMessage) -> anyhow::Result<()> {
@@ -34,6 +34,36 @@ pub enum ApiErrorResponse {
 
     #[error("you have created too many plugins")]
     UserPluginLimitReached,
+
+    #[error("Script does not exist")]
+    ScriptNotFound,
+
+    #[error("a script with that name already exists")]
+    ScriptNameTaken,
+
+    #[error("script source is too large ({size} bytes), the limit is {limit} bytes")]
+    ScriptTooLarge { size: usize, limit: usize },
+
+    #[error("script failed to compile: {0}")]
+    ScriptCompileFailed(String),
+
+    #[error("bundle is too large ({size} bytes), the limit is {limit} bytes")]
+    BundleTooLarge { size: usize, limit: usize },
+
+    #[error("{0}")]
+    InvalidBundle(String),
+
+    #[error("Premium slot does not exist")]
+    PremiumSlotNotFound,
+
+    #[error("a test run was started recently, try again in a few seconds")]
+    SandboxRateLimited,
+
+    #[error("too many test runs in progress, try again later")]
+    SandboxBusy,
+
+    #[error("storage would use {size} bytes, the limit is {limit} bytes")]
+    StorageLimitExceeded { size: u64, limit: u64 },
 }
 
 impl ApiErrorResponse {
@@ -52,6 +82,16 @@ impl ApiErrorResponse {
             Self::NoAccessToPlugin => (StatusCode::FORBIDDEN, 7, self.to_string()),
             Self::UserPluginLimitReached => (StatusCode::BAD_REQUEST, 8, self.to_string()),
             Self::PluginNotFound => (StatusCode::BAD_REQUEST, 9, self.to_string()),
+            Self::ScriptNotFound => (StatusCode::BAD_REQUEST, 10, self.to_string()),
+            Self::ScriptNameTaken => (StatusCode::BAD_REQUEST, 11, self.to_string()),
+            Self::ScriptTooLarge { .. } => (StatusCode::PAYLOAD_TOO_LARGE, 12, self.to_string()),
+            Self::ScriptCompileFailed(_) => (StatusCode::BAD_REQUEST, 13, self.to_string()),
+            Self::BundleTooLarge { .. } => (StatusCode::PAYLOAD_TOO_LARGE, 14, self.to_string()),
+            Self::InvalidBundle(_) => (StatusCode::BAD_REQUEST, 15, self.to_string()),
+            Self::PremiumSlotNotFound => (StatusCode::BAD_REQUEST, 16, self.to_string()),
+            Self::SandboxRateLimited => (StatusCode::TOO_MANY_REQUESTS, 17, self.to_string()),
+            Self::SandboxBusy => (StatusCode::SERVICE_UNAVAILABLE, 18, self.to_string()),
+            Self::StorageLimitExceeded { .. } => (StatusCode::BAD_REQUEST, 19, self.to_string()),
         }
     }
 }
@@ -2,7 +2,7 @@ use std::sync::Arc;
 
 use axum::{
     error_handling::HandleErrorLayer,
-    extract::Extension,
+    extract::{DefaultBodyLimit, Extension},
     response::IntoResponse,
     routing::{delete, get, patch, post, put},
     BoxError, Router,
@@ -96,6 +96,7 @@ async fn main() {
         .layer(Extension(session_store.clone()))
         .layer(Extension(client_cache))
         .layer(Extension(news_handle))
+        .layer(Extension(routes::vm::SandboxLimiter::new()))
         .layer(session_layer)
         .layer(CorsLayer {
             run_config: conf.clone(),
@@ -119,14 +120,20 @@ async fn main() {
             "/reload_vm",
             post(routes::vm::reload_guild_vm::<CurrentSessionStore>),
         )
+        .route("/sandbox_run", post(routes::vm::sandbox_run))
         .route(
             "/settings",
-            get(routes::guilds::get_guild_settings::<CurrentSessionStore>),
+            get(routes::guilds::get_guild_settings::<CurrentSessionStore>)
+                .patch(routes::guilds::update_guild_settings::<CurrentConfigStore>),
         )
         .route(
             "/premium_slots",
             get(routes::guilds::get_guild_premium_slots::<CurrentConfigStore>),
         )
+        .route(
+            "/limits",
+            get(routes::guilds::get_guild_limits::<CurrentConfigStore>),
+        )
         .route(
             "/scripts",
             get(routes::scripts::get_all_guild_scripts).put(routes::scripts::create_guild_script),
@@ -136,7 +143,34 @@ async fn main() {
             patch(routes::scripts::update_guild_script)
                 .delete(routes::scripts::delete_guild_script),
         )
+        .route(
+            "/scripts/:script_id/rename",
+            post(routes::scripts::rename_guild_script),
+        )
+        .route(
+            "/scripts/:script_id/min_log_level",
+            put(routes::scripts::set_guild_script_min_log_level),
+        )
+        .route(
+            "/scripts/:script_id/stats",
+            get(routes::scripts::get_guild_script_stats),
+        )
+        .route(
+            "/failing_scripts",
+            get(routes::scripts::get_guild_failing_scripts),
+        )
+        .route("/export", get(routes::bundle::export_guild_bundle))
+        .route(
+            "/import",
+            post(routes::bundle::import_guild_bundle)
+                .layer(DefaultBodyLimit::max(stores::bundle::MAX_BUNDLE_SIZE_BYTES)),
+        )
         .route("/add_plugin", post(routes::plugins::guild_add_plugin))
+        .route(
+            "/plugins/:plugin_id/config",
+            get(routes::plugins::get_guild_plugin_config)
+                .put(routes::plugins::set_guild_plugin_config_value),
+        )
         .layer(auth_guild_mw_stack);
 
     let authorized_api_routes =
@@ -158,6 +192,13 @@ async fn main() {
                     >,
                 ),
             )
+            .route(
+                "/premium_slots/:slot_id/attachable_guilds",
+                get(routes::premium::list_premium_slot_attachable_guilds::<
+                    CurrentSessionStore,
+                    CurrentConfigStore,
+                >),
+            )
             .route(
                 "/premium_slots",
                 get(routes::premium::list_user_premium_slots::<
@@ -193,6 +234,11 @@ async fn main() {
                 put(routes::plugins::update_plugin_dev_source)
                     .layer(axum::middleware::from_fn(plugin_middleware)),
             )
+            .route(
+                "/plugins/:plugin_id/publish_status",
+                get(routes::plugins::get_plugin_publish_status)
+                    .layer(axum::middleware::from_fn(plugin_middleware)),
+            )
             .route(
                 "/plugins/:plugin_id/publish_script_version",
                 post(routes::plugins::publish_plugin_version)
@@ -0,0 +1,174 @@
+use axum::{
+    body::Bytes,
+    extract::{Extension, Query},
+    http::header,
+    response::IntoResponse,
+    Json,
+};
+use serde::Deserialize;
+use stores::{
+    bucketstore::BucketStore,
+    bundle::{BundleError, ExportOptions},
+    config::{ConfigStore, ConfigStoreError, PremiumSlotTier},
+};
+use tracing::{error, info};
+use twilight_model::user::CurrentUserGuild;
+use validation::{
+    validate,
+    web::{script_source_max_len, storage_total_bytes_max},
+};
+
+use crate::{
+    errors::ApiErrorResponse,
+    middlewares::{plugins::fetch_plugin, LoggedInSession},
+    ApiResult, CurrentConfigStore, CurrentSessionStore,
+};
+
+#[derive(Deserialize)]
+pub struct ExportQuery {
+    #[serde(default)]
+    include_storage: bool,
+    /// Comma separated list of buckets to leave out
+    #[serde(default)]
+    exclude_buckets: Option<String>,
+}
+
+pub async fn export_guild_bundle(
+    Extension(config_store): Extension<CurrentConfigStore>,
+    Extension(current_guild): Extension<CurrentUserGuild>,
+    Query(query): Query<ExportQuery>,
+) -> ApiResult<impl IntoResponse> {
+    let options = ExportOptions {
+        include_storage: query.include_storage,
+        exclude_buckets: query
+            .exclude_buckets
+            .map(|buckets| {
+                buckets
+                    .split(',')
+                    .map(|bucket| bucket.trim().to_string())
+                    .filter(|bucket| !bucket.is_empty())
+                    .collect()
+            })
+            .unwrap_or_default(),
+    };
+
+    let bundle = stores::bundle::export_guild_bundle(
+        &config_store,
+        Some(&config_store),
+        current_guild.id,
+        &options,
+    )
+    .await
+    .map_err(bundle_error_response)?;
+
+    let disposition = format!(
+        "attachment; filename=\"botloader-{}.json\"",
+        current_guild.id
+    );
+
+    Ok((
+        [
+            (header::CONTENT_TYPE, "application/json".to_string()),
+            (header::CONTENT_DISPOSITION, disposition),
+        ],
+        bundle,
+    ))
+}
+
+/// Imports a bundle through the same checks as creating the scripts, adding the plugins and
+/// storing the values one by one would go through
+pub async fn import_guild_bundle(
+    Extension(config_store): Extension<CurrentConfigStore>,
+    Extension(session): Extension<LoggedInSession<CurrentSessionStore>>,
+    Extension(current_guild): Extension<CurrentUserGuild>,
+    Extension(bot_rpc): Extension<botrpc::Client>,
+    body: Bytes,
+) -> ApiResult<impl IntoResponse> {
+    let bundle = stores::bundle::parse_guild_bundle(&body).map_err(bundle_error_response)?;
+
+    if let Err(verr) = validate(&bundle) {
+        return Err(ApiErrorResponse::ValidationFailed(verr));
+    }
+
+    let slots = config_store
+        .get_guild_premium_slots(current_guild.id)
+        .await
+        .map_err(|err| {
+            error!(%err, "failed fetching guild premium slots");
+            ApiErrorResponse::InternalError
+        })?;
+    let premium_tier = PremiumSlotTier::highest_of(&slots);
+
+    let source_limit = script_source_max_len(premium_tier);
+    for script in &bundle.scripts {
+        if script.original_source.len() > source_limit {
+            return Err(ApiErrorResponse::ScriptTooLarge {
+                size: script.original_source.len(),
+                limit: source_limit,
+            });
+        }
+
+        if let Some(plugin_id) = script.plugin_id {
+            let plugin = fetch_plugin(&config_store, plugin_id).await?;
+            if !plugin.is_public && plugin.author_id != session.session.user.id {
+                return Err(ApiErrorResponse::NoAccessToPlugin);
+            }
+        }
+    }
+
+    if bundle.storage.is_some() {
+        let used = config_store
+            .guild_storage_usage_bytes(current_guild.id)
+            .await
+            .map_err(|err| {
+                error!(%err, "failed fetching guild storage usage");
+                ApiErrorResponse::InternalError
+            })?;
+
+        let size = used + bundle.storage_size_bytes();
+        let limit = storage_total_bytes_max(premium_tier);
+        if size > limit {
+            return Err(ApiErrorResponse::StorageLimitExceeded { size, limit });
+        }
+    }
+
+    let summary = stores::bundle::import_guild_bundle(
+        &config_store,
+        Some(&config_store),
+        current_guild.id,
+        bundle,
+    )
+    .await
+    .map_err(bundle_error_response)?;
+
+    info!(
+        guild_id = %current_guild.id,
+        scripts = summary.scripts,
+        storage_entries = summary.storage_entries,
+        "imported guild bundle"
+    );
+
+    bot_rpc
+        .restart_guild_vm(current_guild.id)
+        .await
+        .map_err(|err| {
+            error!(%err, "failed reloading guild vm");
+            ApiErrorResponse::InternalError
+        })?;
+
+    Ok(Json(summary))
+}
+
+fn bundle_error_response(err: BundleError) -> ApiErrorResponse {
+    match err {
+        BundleError::TooLarge { size, limit } => ApiErrorResponse::BundleTooLarge { size, limit },
+        BundleError::UnsupportedVersion(_) | BundleError::Invalid(_) => {
+            ApiErrorResponse::InvalidBundle(err.to_string())
+        }
+        BundleError::Config(ConfigStoreError::ScriptNameTaken) => ApiErrorResponse::ScriptNameTaken,
+        _ => {
+            error!(%err, "failed handling guild bundle");
+            ApiErrorResponse::InternalError
+        }
+    }
+}
@@ -14,8 +14,9 @@ use twilight_model::{
 
 use crate::{errors::ApiErrorResponse, middlewares::LoggedInSession, ApiResult};
 
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 use tracing::error;
+use validation::{validate, web::check_timezone, ValidationContext, Validator};
 
 #[derive(Serialize)]
 pub struct GuildList {
@@ -80,6 +81,84 @@ pub async fn get_guild_settings<CT: ConfigStore + 'static>(
     Ok(Json(settings))
 }
 
+#[derive(Debug, Deserialize)]
+pub struct UpdateGuildSettingsData {
+    /// IANA timezone name, null resets it to UTC
+    pub timezone: Option<String>,
+}
+
+impl Validator for UpdateGuildSettingsData {
+    fn validate(&self, ctx: &mut ValidationContext) {
+        if let Some(timezone) = &self.timezone {
+            check_timezone(ctx, "timezone", timezone);
+        }
+    }
+}
+
+pub async fn update_guild_settings<CT: ConfigStore + 'static>(
+    Extension(config_store): Extension<CT>,
+    Extension(current_guild): Extension<CurrentUserGuild>,
+    Extension(bot_rpc): Extension<botrpc::Client>,
+    Json(payload): Json<UpdateGuildSettingsData>,
+) -> ApiResult<impl IntoResponse> {
+    if let Err(verr) = validate(&payload) {
+        return Err(ApiErrorResponse::ValidationFailed(verr));
+    }
+
+    let mut settings = config_store
+        .get_guild_meta_config_or_default(current_guild.id)
+        .await
+        .map_err(|err| {
+            error!(%err, "failed fetching guild config");
+            ApiErrorResponse::InternalError
+        })?;
+
+    settings.timezone = payload.timezone;
+    let settings = config_store
+        .update_guild_meta_config(&settings)
+        .await
+        .map_err(|err| {
+            error!(%err, "failed updating guild config");
+            ApiErrorResponse::InternalError
+        })?;
+
+    // the vm caches the timezone, restart it so the change is picked up
+    bot_rpc
+        .restart_guild_vm(current_guild.id)
+        .await
+        .map_err(|err| {
+            error!(%err, "failed reloading guild vm");
+            ApiErrorResponse::InternalError
+        })?;
+
+    Ok(Json(settings))
+}
+
+#[derive(Debug, Serialize)]
+pub struct GuildLimits {
+    pub premium_tier: Option<PremiumSlotTier>,
+    pub script_source_max_len: usize,
+}
+
+pub async fn get_guild_limits<CT: ConfigStore + 'static>(
+    Extension(config_store): Extension<CT>,
+    Extension(current_guild): Extension<CurrentUserGuild>,
+) -> ApiResult<Json<GuildLimits>> {
+    let slots = config_store
+        .get_guild_premium_slots(current_guild.id)
+        .await
+        .map_err(|err| {
+            error!(%err, "failed fetching guild premium slots");
+            ApiErrorResponse::InternalError
+        })?;
+
+    let premium_tier = PremiumSlotTier::highest_of(&slots);
+    Ok(Json(GuildLimits {
+        premium_tier,
+        script_source_max_len: validation::web::script_source_max_len(premium_tier),
+    }))
+}
+
 pub async fn get_guild_premium_slots<CT: ConfigStore + 'static>(
     Extension(config_store): Extension<CT>,
     Extension(current_guild): Extension<CurrentUserGuild>,
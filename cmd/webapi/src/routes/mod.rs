@@ -1,4 +1,5 @@
 pub mod auth;
+pub mod bundle;
 pub mod errortest;
 pub mod general;
 pub mod guilds;
@@ -1,10 +1,14 @@
-use axum::{response::IntoResponse, Extension, Json};
+use axum::{extract::Path, response::IntoResponse, Extension, Json};
 use common::plugin::Plugin;
 use serde::Deserialize;
-use stores::config::{ConfigStore, CreatePlugin, UpdatePluginMeta};
+use stores::config::{ConfigStore, CreatePlugin, PremiumSlotTier, UpdatePluginMeta};
 use tracing::error;
 use twilight_model::user::CurrentUserGuild;
-use validation::{validate, ValidationContext, Validator};
+use validation::{
+    validate,
+    web::{check_plugin_config_key, check_plugin_config_value, script_source_max_len},
+    ValidationContext, Validator,
+};
 
 use crate::{
     errors::ApiErrorResponse,
@@ -145,12 +149,6 @@ pub struct UpdatePluginDevSourceRequest {
     new_source: String,
 }
 
-impl Validator for UpdatePluginDevSourceRequest {
-    fn validate(&self, ctx: &mut ValidationContext) {
-        validation::web::check_script_source(ctx, "new_source", &self.new_source);
-    }
-}
-
 // update plugin dev source
 pub async fn update_plugin_dev_source(
     Extension(config_store): Extension<CurrentConfigStore>,
@@ -158,14 +156,12 @@ pub async fn update_plugin_dev_source(
     Extension(plugin): Extension<Plugin>,
     Json(body): Json<UpdatePluginDevSourceRequest>,
 ) -> ApiResult<impl IntoResponse> {
-    if let Err(err) = validate(&body) {
-        return Err(ApiErrorResponse::ValidationFailed(err));
-    }
-
     if plugin.author_id != session.session.user.id {
         return Err(ApiErrorResponse::NoAccessToPlugin);
     }
 
+    check_plugin_source_size(&config_store, &session, &body.new_source).await?;
+
     let plugin = config_store
         .update_script_plugin_dev_version(plugin.id, body.new_source)
         .await
@@ -177,18 +173,63 @@ pub async fn update_plugin_dev_source(
     Ok(Json(plugin))
 }
 
+// plugins aren't tied to a guild, so the author's premium tier decides the max source size
+async fn check_plugin_source_size(
+    config_store: &CurrentConfigStore,
+    session: &LoggedInSession<CurrentSessionStore>,
+    source: &str,
+) -> ApiResult<()> {
+    // no need to look up the tier if it's below the lowest limit
+    if source.len() <= script_source_max_len(None) {
+        return Ok(());
+    }
+
+    let slots = config_store
+        .get_user_premium_slots(session.session.user.id)
+        .await
+        .map_err(|err| {
+            error!(%err, "failed fetching user premium slots");
+            ApiErrorResponse::InternalError
+        })?;
+
+    let limit = script_source_max_len(PremiumSlotTier::highest_of(&slots));
+    if source.len() > limit {
+        return Err(ApiErrorResponse::ScriptTooLarge {
+            size: source.len(),
+            limit,
+        });
+    }
+
+    Ok(())
+}
+
+// get whether the plugin has unpublished changes
+pub async fn get_plugin_publish_status(
+    Extension(config_store): Extension<CurrentConfigStore>,
+    Extension(session): Extension<LoggedInSession<CurrentSessionStore>>,
+    Extension(plugin): Extension<Plugin>,
+) -> ApiResult<impl IntoResponse> {
+    if plugin.author_id != session.session.user.id {
+        return Err(ApiErrorResponse::NoAccessToPlugin);
+    }
+
+    let status = config_store
+        .get_plugin_publish_status(plugin.id)
+        .await
+        .map_err(|err| {
+            error!(?err, "failed fetching plugin publish status");
+            ApiErrorResponse::InternalError
+        })?;
+
+    Ok(Json(status))
+}
+
 // publish plugin version
 #[derive(Deserialize)]
 pub struct PublishPluginVersionData {
     new_source: String,
 }
 
-impl Validator for PublishPluginVersionData {
-    fn validate(&self, ctx: &mut ValidationContext) {
-        validation::web::check_script_source(ctx, "new_source", &self.new_source);
-    }
-}
-
 pub async fn publish_plugin_version(
     Extension(config_store): Extension<CurrentConfigStore>,
     Extension(session): Extension<LoggedInSession<CurrentSessionStore>>,
@@ -196,14 +237,39 @@ pub async fn publish_plugin_version(
     Extension(bot_rpc): Extension<botrpc::Client>,
     Json(body): Json<PublishPluginVersionData>,
 ) -> ApiResult<impl IntoResponse> {
-    if let Err(err) = validate(&body) {
-        return Err(ApiErrorResponse::ValidationFailed(err));
-    }
-
     if plugin.author_id != session.session.user.id {
         return Err(ApiErrorResponse::NoAccessToPlugin);
     }
 
+    check_plugin_source_size(&config_store, &session, &body.new_source).await?;
+
+    // a broken version would break every guild that auto updates at once, so make sure it at least compiles
+    //
+    // the script meta (commands, timers etc) is only known once the script runs, that's still
+    // validated when the guild vms start it
+    // compiling can take a while for large sources, so it's kept off the async runtime
+    let source = body.new_source.clone();
+    let compiled = tokio::task::spawn_blocking(move || tscompiler::compile_typescript(&source))
+        .await
+        .map_err(|err| {
+            error!(%err, plugin_id = plugin.id, "failed compiling plugin source");
+            ApiErrorResponse::InternalError
+        })?;
+
+    match compiled {
+        Ok(_) => {}
+        Err(tscompiler::CompileError::Rejected(diagnostics)) => {
+            return Err(ApiErrorResponse::ScriptCompileFailed(diagnostics));
+        }
+        Err(err @ tscompiler::CompileError::TooLarge { .. }) => {
+            return Err(ApiErrorResponse::ScriptCompileFailed(err.to_string()));
+        }
+        Err(err @ tscompiler::CompileError::Internal(_)) => {
+            error!(%err, plugin_id = plugin.id, "failed compiling plugin source");
+            return Err(ApiErrorResponse::InternalError);
+        }
+    }
+
     let guilds = config_store
         .publish_script_plugin_version(plugin.id, body.new_source)
         .await
@@ -260,3 +326,113 @@ pub async fn guild_add_plugin(
 
     Ok(Json(script))
 }
+
+#[derive(Deserialize)]
+pub struct GuildPluginPathParams {
+    plugin_id: u64,
+}
+
+async fn check_guild_has_plugin(
+    config_store: &CurrentConfigStore,
+    current_guild: &CurrentUserGuild,
+    plugin_id: u64,
+) -> ApiResult<()> {
+    let scripts = config_store
+        .list_scripts(current_guild.id)
+        .await
+        .map_err(|err| {
+            error!(%err, "failed fetching guild scripts");
+            ApiErrorResponse::InternalError
+        })?;
+
+    if scripts.iter().any(|v| v.plugin_id == Some(plugin_id)) {
+        Ok(())
+    } else {
+        Err(ApiErrorResponse::PluginNotFound)
+    }
+}
+
+pub async fn get_guild_plugin_config(
+    Extension(config_store): Extension<CurrentConfigStore>,
+    Extension(current_guild): Extension<CurrentUserGuild>,
+    Path(GuildPluginPathParams { plugin_id }): Path<GuildPluginPathParams>,
+) -> ApiResult<impl IntoResponse> {
+    check_guild_has_plugin(&config_store, &current_guild, plugin_id).await?;
+
+    let entries = config_store
+        .get_guild_plugin_config(current_guild.id, plugin_id)
+        .await
+        .map_err(|err| {
+            error!(?err, "failed fetching plugin config");
+            ApiErrorResponse::InternalError
+        })?;
+
+    Ok(Json(entries))
+}
+
+#[derive(Deserialize)]
+pub struct SetPluginConfigValueData {
+    key: String,
+    value: Option<String>,
+}
+
+impl Validator for SetPluginConfigValueData {
+    fn validate(&self, ctx: &mut ValidationContext) {
+        check_plugin_config_key(ctx, &self.key);
+
+        if let Some(value) = &self.value {
+            check_plugin_config_value(ctx, value);
+        }
+    }
+}
+
+// sets a config value, or deletes it if value is null
+pub async fn set_guild_plugin_config_value(
+    Extension(config_store): Extension<CurrentConfigStore>,
+    Extension(current_guild): Extension<CurrentUserGuild>,
+    Extension(bot_rpc): Extension<botrpc::Client>,
+    Path(GuildPluginPathParams { plugin_id }): Path<GuildPluginPathParams>,
+    Json(body): Json<SetPluginConfigValueData>,
+) -> ApiResult<impl IntoResponse> {
+    if let Err(err) = validate(&body) {
+        return Err(ApiErrorResponse::ValidationFailed(err));
+    }
+
+    check_guild_has_plugin(&config_store, &current_guild, plugin_id).await?;
+
+    let res = if let Some(value) = body.value {
+        config_store
+            .set_guild_plugin_config_value(current_guild.id, plugin_id, body.key, value)
+            .await
+            .map(|_| ())
+    } else {
+        config_store
+            .del_guild_plugin_config_value(current_guild.id, plugin_id, body.key)
+            .await
+            .map(|_| ())
+    };
+
+    res.map_err(|err| {
+        error!(?err, "failed updating plugin config");
+        ApiErrorResponse::InternalError
+    })?;
+
+    // the vm caches the config, restart it so the changes are picked up
+    bot_rpc
+        .restart_guild_vm(current_guild.id)
+        .await
+        .map_err(|err| {
+            error!(%err, "failed reloading guild vm");
+            ApiErrorResponse::InternalError
+        })?;
+
+    let entries = config_store
+        .get_guild_plugin_config(current_guild.id, plugin_id)
+        .await
+        .map_err(|err| {
+            error!(?err, "failed fetching plugin config");
+            ApiErrorResponse::InternalError
+        })?;
+
+    Ok(Json(entries))
+}
@@ -3,10 +3,13 @@ use axum::{
     Json,
 };
 use stores::{
-    config::{ConfigStore, PremiumSlot},
+    config::{AttachableGuild, ConfigStore, PremiumSlot},
     web::SessionStore,
 };
-use twilight_model::id::{marker::GuildMarker, Id};
+use twilight_model::{
+    guild::Permissions,
+    id::{marker::GuildMarker, Id},
+};
 
 use crate::{errors::ApiErrorResponse, middlewares::LoggedInSession, ApiResult};
 
@@ -53,6 +56,57 @@ pub async fn update_premium_slot_guild<ST: SessionStore + 'static, CT: ConfigSto
     Ok(Json(res))
 }
 
+/// Lists the guilds the slot could be attached to, for the guild picker when attaching a slot
+pub async fn list_premium_slot_attachable_guilds<
+    ST: SessionStore + 'static,
+    CT: ConfigStore + 'static,
+>(
+    Extension(session): Extension<LoggedInSession<ST>>,
+    Extension(config_store): Extension<CT>,
+    Path(UpdateSlotPathParams { slot_id }): Path<UpdateSlotPathParams>,
+) -> ApiResult<Json<Vec<AttachableGuild>>> {
+    let slots = config_store
+        .get_user_premium_slots(session.session.user.id)
+        .await
+        .map_err(|err| {
+            error!(%err, "failed fetching user premium slots");
+            ApiErrorResponse::InternalError
+        })?;
+
+    let slot = slots
+        .into_iter()
+        .find(|slot| slot.id == slot_id)
+        .ok_or(ApiErrorResponse::PremiumSlotNotFound)?;
+
+    let user_guilds = session
+        .api_client
+        .current_user_guilds()
+        .await
+        .map_err(|err| {
+            error!(%err, "failed fetching user guilds");
+            ApiErrorResponse::InternalError
+        })?;
+
+    let admin_guild_ids = user_guilds
+        .iter()
+        .filter(|g| {
+            g.permissions
+                .intersects(Permissions::ADMINISTRATOR | Permissions::MANAGE_GUILD)
+        })
+        .map(|g| g.id)
+        .collect::<Vec<_>>();
+
+    let guilds = config_store
+        .get_attachable_guilds(session.session.user.id, &admin_guild_ids, slot.tier)
+        .await
+        .map_err(|err| {
+            error!(%err, "failed fetching attachable guilds");
+            ApiErrorResponse::InternalError
+        })?;
+
+    Ok(Json(guilds))
+}
+
 #[derive(Deserialize)]
 pub struct UpdateSlotGuildBody {
     guild_id: Option<Id<GuildMarker>>,
@@ -4,10 +4,20 @@ use axum::{
     Json,
 };
 use serde::Deserialize;
-use stores::config::{ConfigStore, CreateScript, UpdateScript};
+use stores::config::{
+    ConfigStore, ConfigStoreError, CreateScript, PremiumSlotTier, ScriptLogLevel,
+    ScriptRuntimeStats, UpdateScript,
+};
 use tracing::error;
-use twilight_model::user::CurrentUserGuild;
-use validation::validate;
+use twilight_model::{
+    id::{marker::GuildMarker, Id},
+    user::CurrentUserGuild,
+};
+use validation::{
+    validate,
+    web::{check_script_name, script_source_max_len},
+    ValidationContext, Validator,
+};
 
 use crate::{errors::ApiErrorResponse, ApiResult, CurrentConfigStore};
 
@@ -43,6 +53,8 @@ pub async fn create_guild_script(
     Extension(current_guild): Extension<CurrentUserGuild>,
     Json(payload): Json<CreateRequestData>,
 ) -> ApiResult<impl IntoResponse> {
+    check_script_source_size(&config_store, current_guild.id, &payload.original_source).await?;
+
     let cs = CreateScript {
         enabled: payload.enabled,
         original_source: payload.original_source,
@@ -82,6 +94,10 @@ pub async fn update_guild_script(
     Path(GuildScriptPathParams { script_id }): Path<GuildScriptPathParams>,
     Json(payload): Json<UpdateRequestData>,
 ) -> ApiResult<impl IntoResponse> {
+    if let Some(source) = &payload.original_source {
+        check_script_source_size(&config_store, current_guild.id, source).await?;
+    }
+
     let sc = UpdateScript {
         id: script_id,
         enabled: payload.enabled,
@@ -105,6 +121,160 @@ pub async fn update_guild_script(
     Ok(Json(script))
 }
 
+// rejects sources above the guild's tier limit before they get any further (validation, compiler, db)
+async fn check_script_source_size(
+    config_store: &CurrentConfigStore,
+    guild_id: Id<GuildMarker>,
+    source: &str,
+) -> ApiResult<()> {
+    // no need to look up the tier if it's below the lowest limit
+    if source.len() <= script_source_max_len(None) {
+        return Ok(());
+    }
+
+    let slots = config_store
+        .get_guild_premium_slots(guild_id)
+        .await
+        .map_err(|err| {
+            error!(%err, "failed fetching guild premium slots");
+            ApiErrorResponse::InternalError
+        })?;
+
+    let limit = script_source_max_len(PremiumSlotTier::highest_of(&slots));
+    if source.len() > limit {
+        return Err(ApiErrorResponse::ScriptTooLarge {
+            size: source.len(),
+            limit,
+        });
+    }
+
+    Ok(())
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct RenameRequestData {
+    pub name: String,
+}
+
+impl Validator for RenameRequestData {
+    fn validate(&self, ctx: &mut ValidationContext) {
+        check_script_name(ctx, &self.name);
+    }
+}
+
+pub async fn rename_guild_script(
+    Extension(config_store): Extension<CurrentConfigStore>,
+    Extension(current_guild): Extension<CurrentUserGuild>,
+    Extension(bot_rpc): Extension<botrpc::Client>,
+    Path(GuildScriptPathParams { script_id }): Path<GuildScriptPathParams>,
+    Json(payload): Json<RenameRequestData>,
+) -> ApiResult<impl IntoResponse> {
+    if let Err(verr) = validate(&payload) {
+        return Err(ApiErrorResponse::ValidationFailed(verr));
+    }
+
+    let script = config_store
+        .rename_script(current_guild.id, script_id, payload.name)
+        .await
+        .map_err(|err| match err {
+            ConfigStoreError::ScriptNotFound => ApiErrorResponse::ScriptNotFound,
+            ConfigStoreError::ScriptNameTaken => ApiErrorResponse::ScriptNameTaken,
+            _ => {
+                error!(%err, "failed renaming guild script");
+                ApiErrorResponse::InternalError
+            }
+        })?;
+
+    bot_rpc
+        .restart_guild_vm(current_guild.id)
+        .await
+        .map_err(|err| {
+            error!(%err, "failed reloading guild vm");
+            ApiErrorResponse::InternalError
+        })?;
+
+    Ok(Json(script))
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct SetMinLogLevelRequestData {
+    pub min_log_level: ScriptLogLevel,
+}
+
+pub async fn set_guild_script_min_log_level(
+    Extension(config_store): Extension<CurrentConfigStore>,
+    Extension(current_guild): Extension<CurrentUserGuild>,
+    Extension(bot_rpc): Extension<botrpc::Client>,
+    Path(GuildScriptPathParams { script_id }): Path<GuildScriptPathParams>,
+    Json(payload): Json<SetMinLogLevelRequestData>,
+) -> ApiResult<impl IntoResponse> {
+    let script = config_store
+        .set_script_min_log_level(current_guild.id, script_id, payload.min_log_level)
+        .await
+        .map_err(|err| match err {
+            ConfigStoreError::ScriptNotFound => ApiErrorResponse::ScriptNotFound,
+            _ => {
+                error!(%err, "failed setting guild script min log level");
+                ApiErrorResponse::InternalError
+            }
+        })?;
+
+    bot_rpc
+        .restart_guild_vm(current_guild.id)
+        .await
+        .map_err(|err| {
+            error!(%err, "failed reloading guild vm");
+            ApiErrorResponse::InternalError
+        })?;
+
+    Ok(Json(script))
+}
+
+pub async fn get_guild_script_stats(
+    Extension(config_store): Extension<CurrentConfigStore>,
+    Extension(current_guild): Extension<CurrentUserGuild>,
+    Path(GuildScriptPathParams { script_id }): Path<GuildScriptPathParams>,
+) -> ApiResult<impl IntoResponse> {
+    config_store
+        .get_script_by_id(current_guild.id, script_id)
+        .await
+        .map_err(|err| match err {
+            ConfigStoreError::ScriptNotFound => ApiErrorResponse::ScriptNotFound,
+            _ => {
+                error!(%err, "failed fetching guild script");
+                ApiErrorResponse::InternalError
+            }
+        })?;
+
+    // scripts that haven't run since the stats were introduced have no row yet
+    let stats = config_store
+        .get_script_stats(current_guild.id, script_id)
+        .await
+        .map_err(|err| {
+            error!(%err, "failed fetching guild script stats");
+            ApiErrorResponse::InternalError
+        })?
+        .unwrap_or_else(|| ScriptRuntimeStats::new(script_id));
+
+    Ok(Json(stats))
+}
+
+/// Scripts that failed to load the last time the guild's vm was started
+pub async fn get_guild_failing_scripts(
+    Extension(config_store): Extension<CurrentConfigStore>,
+    Extension(current_guild): Extension<CurrentUserGuild>,
+) -> ApiResult<impl IntoResponse> {
+    let failures = config_store
+        .get_failing_scripts(current_guild.id)
+        .await
+        .map_err(|err| {
+            error!(%err, "failed fetching failing guild scripts");
+            ApiErrorResponse::InternalError
+        })?;
+
+    Ok(Json(failures))
+}
+
 pub async fn delete_guild_script(
     Extension(config_store): Extension<CurrentConfigStore>,
     Extension(current_guild): Extension<CurrentUserGuild>,
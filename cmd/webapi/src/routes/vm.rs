@@ -1,9 +1,25 @@
-use axum::{extract::Extension, response::IntoResponse};
-use stores::web::SessionStore;
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use axum::{extract::Extension, response::IntoResponse, Json};
+use guild_logger::LogEntry;
+use serde::{Deserialize, Serialize};
+use stores::{
+    config::{ConfigStore, PremiumSlotTier},
+    web::SessionStore,
+};
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
 use tracing::error;
-use twilight_model::user::CurrentUserGuild;
+use twilight_model::{
+    id::{marker::GuildMarker, Id},
+    user::CurrentUserGuild,
+};
+use validation::web::script_source_max_len;
 
-use crate::{errors::ApiErrorResponse, util::EmptyResponse, ApiResult};
+use crate::{errors::ApiErrorResponse, util::EmptyResponse, ApiResult, CurrentConfigStore};
 
 pub async fn reload_guild_vm<ST: SessionStore + Clone + Send + Sync + 'static>(
     Extension(bot_rpc): Extension<botrpc::Client>,
@@ -19,3 +35,116 @@ pub async fn reload_guild_vm<ST: SessionStore + Clone + Send + Sync + 'static>(
 
     Ok(EmptyResponse)
 }
+
+#[derive(Deserialize)]
+pub struct SandboxRunRequest {
+    source: String,
+    event_name: String,
+    event: serde_json::Value,
+}
+
+/// Handlers don't return anything, the logs (with source mapped errors) are the result of the run
+#[derive(Serialize)]
+pub struct SandboxRunResponse {
+    logs: Vec<LogEntry>,
+    timed_out: bool,
+    shutdown_reason: Option<String>,
+}
+
+/// Min time between test runs of a guild, the same as the max time a run can take so a guild
+/// has at most one run going at a time
+const SANDBOX_RUN_INTERVAL: Duration = Duration::from_secs(10);
+
+/// Max number of test runs in progress across all guilds, each of them holds a vm worker
+const MAX_CONCURRENT_SANDBOX_RUNS: usize = 4;
+
+/// Limits how often guilds can start test runs and how many can run at once
+#[derive(Clone)]
+pub struct SandboxLimiter {
+    running: Arc<Semaphore>,
+    last_runs: Arc<Mutex<HashMap<Id<GuildMarker>, Instant>>>,
+}
+
+impl SandboxLimiter {
+    pub fn new() -> Self {
+        Self {
+            running: Arc::new(Semaphore::new(MAX_CONCURRENT_SANDBOX_RUNS)),
+            last_runs: Default::default(),
+        }
+    }
+
+    /// The returned permit has to be held for the duration of the run
+    fn try_start(&self, guild_id: Id<GuildMarker>) -> ApiResult<OwnedSemaphorePermit> {
+        let mut last_runs = self.last_runs.lock().unwrap();
+
+        let now = Instant::now();
+        last_runs.retain(|_, started| now.duration_since(*started) < SANDBOX_RUN_INTERVAL);
+        if last_runs.contains_key(&guild_id) {
+            return Err(ApiErrorResponse::SandboxRateLimited);
+        }
+
+        let permit = self
+            .running
+            .clone()
+            .try_acquire_owned()
+            .map_err(|_| ApiErrorResponse::SandboxBusy)?;
+
+        last_runs.insert(guild_id, now);
+        Ok(permit)
+    }
+}
+
+impl Default for SandboxLimiter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Runs the provided source against a sample event in a sandboxed vm, where everything that
+/// would affect the guild (discord, storage, http etc.) throws
+pub async fn sandbox_run(
+    Extension(bot_rpc): Extension<botrpc::Client>,
+    Extension(config_store): Extension<CurrentConfigStore>,
+    Extension(limiter): Extension<SandboxLimiter>,
+    Extension(current_guild): Extension<CurrentUserGuild>,
+    Json(body): Json<SandboxRunRequest>,
+) -> ApiResult<impl IntoResponse> {
+    let _permit = limiter.try_start(current_guild.id)?;
+
+    let slots = config_store
+        .get_guild_premium_slots(current_guild.id)
+        .await
+        .map_err(|err| {
+            error!(%err, "failed fetching guild premium slots");
+            ApiErrorResponse::InternalError
+        })?;
+
+    let premium_tier = PremiumSlotTier::highest_of(&slots);
+    let limit = script_source_max_len(premium_tier);
+    if body.source.len() > limit {
+        return Err(ApiErrorResponse::ScriptTooLarge {
+            size: body.source.len(),
+            limit,
+        });
+    }
+
+    let result = bot_rpc
+        .run_sandbox(
+            current_guild.id,
+            premium_tier,
+            body.source,
+            body.event_name,
+            body.event.to_string(),
+        )
+        .await
+        .map_err(|err| {
+            error!(%err, "failed running sandbox");
+            ApiErrorResponse::InternalError
+        })?;
+
+    Ok(Json(SandboxRunResponse {
+        logs: result.logs,
+        timed_out: result.timed_out,
+        shutdown_reason: result.shutdown_reason,
+    }))
+}
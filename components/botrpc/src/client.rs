@@ -1,5 +1,6 @@
 use futures::{Stream, StreamExt};
 use guild_logger::LogEntry;
+use stores::config::PremiumSlotTier;
 use twilight_model::id::{marker::GuildMarker, Id};
 
 use crate::proto;
@@ -49,4 +50,44 @@ impl Client {
 
         Ok(stream.map(|item| item.map(Into::into)))
     }
+
+    /// Runs the source against a single event in a sandboxed vm, see [SandboxRunResult]
+    pub async fn run_sandbox(
+        &self,
+        guild_id: Id<GuildMarker>,
+        premium_tier: Option<PremiumSlotTier>,
+        source: String,
+        event_name: String,
+        event_json: String,
+    ) -> Result<SandboxRunResult, tonic::Status> {
+        let mut conn = self.get_conn();
+
+        let resp = conn
+            .run_sandbox(proto::SandboxRunRequest {
+                guild_id: guild_id.get(),
+                premium_tier: proto::PremiumTier::from(premium_tier) as i32,
+                source,
+                event_name,
+                event_json,
+            })
+            .await?
+            .into_inner();
+
+        Ok(SandboxRunResult {
+            logs: resp.logs.into_iter().map(Into::into).collect(),
+            timed_out: resp.timed_out,
+            shutdown_reason: Some(resp.shutdown_reason).filter(|v| !v.is_empty()),
+        })
+    }
+}
+
+/// The outcome of a sandbox run
+///
+/// Event handlers don't return anything, so the log entries (including errors, which have
+/// already been source mapped) are the result of the run.
+pub struct SandboxRunResult {
+    pub logs: Vec<LogEntry>,
+    pub timed_out: bool,
+    /// Set if the vm was forcibly shut down, e.g. it ran out of memory
+    pub shutdown_reason: Option<String>,
 }
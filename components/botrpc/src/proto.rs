@@ -1,3 +1,4 @@
+use stores::config::PremiumSlotTier;
 use twilight_model::id::Id;
 
 tonic::include_proto!("botrpc");
@@ -87,3 +88,23 @@ impl From<LogLevel> for guild_logger::LogLevel {
         }
     }
 }
+
+impl From<Option<PremiumSlotTier>> for PremiumTier {
+    fn from(tier: Option<PremiumSlotTier>) -> Self {
+        match tier {
+            None => Self::None,
+            Some(PremiumSlotTier::Lite) => Self::Lite,
+            Some(PremiumSlotTier::Premium) => Self::Premium,
+        }
+    }
+}
+
+impl From<PremiumTier> for Option<PremiumSlotTier> {
+    fn from(tier: PremiumTier) -> Self {
+        match tier {
+            PremiumTier::None => None,
+            PremiumTier::Lite => Some(PremiumSlotTier::Lite),
+            PremiumTier::Premium => Some(PremiumSlotTier::Premium),
+        }
+    }
+}
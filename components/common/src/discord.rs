@@ -3,7 +3,8 @@ use std::sync::Arc;
 use tracing::info;
 
 use twilight_model::{
-    oauth::Application,
+    gateway::Intents,
+    oauth::{Application, ApplicationFlags},
     user::{CurrentUser, User},
 };
 
@@ -60,4 +61,41 @@ impl DiscordConfig {
     pub fn interaction_client(&self) -> twilight_http::client::InteractionClient<'_> {
         self.client.interaction(self.application.id)
     }
+
+    /// Returns true if the application has been granted the provided privileged intent
+    /// (either the verified or the limited version of it)
+    pub fn has_privileged_intent(&self, intent: Intents) -> bool {
+        let flags = self.application.flags.unwrap_or(ApplicationFlags::empty());
+
+        let required = match intent {
+            Intents::MESSAGE_CONTENT => {
+                ApplicationFlags::GATEWAY_MESSAGE_CONTENT
+                    | ApplicationFlags::GATEWAY_MESSAGE_CONTENT_LIMITED
+            }
+            Intents::GUILD_MEMBERS => {
+                ApplicationFlags::GATEWAY_GUILD_MEMBERS
+                    | ApplicationFlags::GATEWAY_GUILD_MEMBERS_LIMITED
+            }
+            Intents::GUILD_PRESENCES => {
+                ApplicationFlags::GATEWAY_PRESENCE | ApplicationFlags::GATEWAY_PRESENCE_LIMITED
+            }
+            // not a privileged intent
+            _ => return true,
+        };
+
+        flags.intersects(required)
+    }
+}
+
+/// The intents the broker connects to the gateway with
+pub fn gateway_intents() -> Intents {
+    Intents::GUILD_MESSAGES
+        | Intents::MESSAGE_CONTENT
+        | Intents::GUILDS
+        | Intents::GUILD_MEMBERS
+        | Intents::GUILD_MODERATION
+        | Intents::GUILD_INVITES
+        | Intents::GUILD_VOICE_STATES
+        | Intents::GUILD_MESSAGES
+        | Intents::GUILD_MESSAGE_REACTIONS
 }
@@ -2,9 +2,9 @@ use reqwest::StatusCode;
 use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
 
-use twilight_model::id::marker::{ChannelMarker, GuildMarker, RoleMarker};
+use twilight_model::id::marker::{ChannelMarker, GuildMarker, RoleMarker, UserMarker};
 use twilight_model::id::Id;
-use twilight_model::{channel::Channel, guild::Role};
+use twilight_model::{channel::Channel, guild::Role, user::User};
 
 #[derive(Clone)]
 pub struct Client {
@@ -86,6 +86,22 @@ impl Client {
             .map(|v| v.unwrap_or_default())
     }
 
+    pub async fn get_user(&self, user_id: Id<UserMarker>) -> ApiResult<Option<User>> {
+        self.get(format!("{}/users/{}", self.server_addr, user_id))
+            .await
+    }
+
+    /// Returns the heartbeat latency in milliseconds of the shard the guild is on, None if the
+    /// shard has not received a heartbeat yet
+    pub async fn get_gateway_latency(&self, guild_id: Id<GuildMarker>) -> ApiResult<Option<u64>> {
+        self.get(format!(
+            "{}/guilds/{}/gateway_latency",
+            self.server_addr, guild_id
+        ))
+        .await
+        .map(Option::flatten)
+    }
+
     pub async fn get_connected_guilds(&self) -> ApiResult<ConnectedGuildsResponse> {
         self.get(format!("{}/connected_guilds", self.server_addr))
             .await
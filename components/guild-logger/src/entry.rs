@@ -1,6 +1,7 @@
 use std::fmt::Display;
 
 use serde::{Deserialize, Serialize};
+use stores::config::ScriptLogLevel;
 use twilight_model::id::{marker::GuildMarker, Id};
 
 #[derive(Clone, Serialize, Deserialize)]
@@ -121,6 +122,29 @@ pub enum LogLevel {
     ConsoleLog,
 }
 
+impl LogLevel {
+    /// Returns true if this level is less severe than the provided floor
+    pub fn is_below(&self, floor: ScriptLogLevel) -> bool {
+        match floor {
+            // there's no guild wide floor yet, so everything is logged
+            ScriptLogLevel::Inherit => false,
+            floor => ScriptLogLevel::from(self) < floor,
+        }
+    }
+}
+
+impl From<&LogLevel> for ScriptLogLevel {
+    fn from(v: &LogLevel) -> Self {
+        match v {
+            LogLevel::Critical => Self::Critical,
+            LogLevel::Error => Self::Error,
+            LogLevel::Warn => Self::Warn,
+            LogLevel::Info => Self::Info,
+            LogLevel::ConsoleLog => Self::ConsoleLog,
+        }
+    }
+}
+
 impl Display for LogLevel {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
@@ -1,4 +1,7 @@
-use std::{collections::HashMap, sync::RwLock};
+use std::{
+    collections::{HashMap, VecDeque},
+    sync::{Mutex, RwLock},
+};
 
 use crate::LogEntry;
 use tokio::sync::broadcast::{self, Receiver};
@@ -7,11 +10,29 @@ use twilight_model::id::{marker::GuildMarker, Id};
 #[derive(Default)]
 pub struct GuildSubscriberBackend {
     subscriptions: RwLock<HashMap<Id<GuildMarker>, broadcast::Sender<LogEntry>>>,
+
+    // the most recent entries per guild, this lives outside the vm's so that
+    // subscribers can catch up on what happened while a vm restarted
+    //
+    // only kept for guilds that have subscribers, it's dropped together with the subscription
+    history: Mutex<HashMap<Id<GuildMarker>, VecDeque<LogEntry>>>,
+    history_size: usize,
 }
 
 impl GuildSubscriberBackend {
+    /// Creates a backend that keeps the last `history_size` entries per guild around
+    pub fn new(history_size: usize) -> Self {
+        Self {
+            history_size,
+            ..Default::default()
+        }
+    }
+
     pub fn subscribe(&self, guild_id: Id<GuildMarker>) -> Receiver<LogEntry> {
         let mut subs = self.subscriptions.write().unwrap();
+        // clean up the guilds nobody is subscribed to anymore
+        subs.retain(|_, sender| sender.receiver_count() > 0);
+
         if let Some(entry) = subs.get(&guild_id) {
             entry.subscribe()
         } else {
@@ -20,21 +41,42 @@ impl GuildSubscriberBackend {
             receiver
         }
     }
-}
-#[async_trait::async_trait]
-impl crate::GuildLoggerBackend for GuildSubscriberBackend {
-    async fn handle_entry(&self, entry: LogEntry) {
+
+    /// Same as [Self::subscribe] but also returns the most recent entries,
+    /// oldest first, without any gaps or duplicates between them and the receiver
+    pub fn subscribe_with_history(
+        &self,
+        guild_id: Id<GuildMarker>,
+    ) -> (Vec<LogEntry>, Receiver<LogEntry>) {
+        // hold the history lock while subscribing so no entries can be sent in between
+        let mut history = self.history.lock().unwrap();
+        let receiver = self.subscribe(guild_id);
+
+        let subs = self.subscriptions.read().unwrap();
+        history.retain(|guild_id, _| subs.contains_key(guild_id));
+        drop(subs);
+
+        let recent = history
+            .get(&guild_id)
+            .map(|entries| entries.iter().cloned().collect())
+            .unwrap_or_default();
+
+        (recent, receiver)
+    }
+
+    /// Returns false if the guild has no subscribers
+    fn send(&self, entry: LogEntry) -> bool {
         let guild_id = entry.guild_id;
         {
             // fast path with read lock
             let read = self.subscriptions.read().unwrap();
             let sender = match read.get(&guild_id) {
                 Some(v) => v,
-                None => return,
+                None => return false,
             };
 
             if sender.send(entry.clone()).is_ok() {
-                return;
+                return true;
             }
         };
 
@@ -43,16 +85,41 @@ impl crate::GuildLoggerBackend for GuildSubscriberBackend {
         let mut write = self.subscriptions.write().unwrap();
         let sender = match write.get(&guild_id) {
             Some(v) => v,
-            None => return,
+            None => return false,
         };
 
         // try sending it again, things might have changed while we upgraded
         // the lock
         if sender.send(entry).is_ok() {
-            return;
+            return true;
         }
 
         // we need to remove the subscriptions, it has no more receivers
         write.remove_entry(&guild_id);
+        false
+    }
+}
+
+#[async_trait::async_trait]
+impl crate::GuildLoggerBackend for GuildSubscriberBackend {
+    async fn handle_entry(&self, entry: LogEntry) {
+        if self.history_size == 0 {
+            self.send(entry);
+            return;
+        }
+
+        let mut history = self.history.lock().unwrap();
+        let guild_id = entry.guild_id;
+        if !self.send(entry.clone()) {
+            // the last subscriber left
+            history.remove(&guild_id);
+            return;
+        }
+
+        let guild_history = history.entry(guild_id).or_default();
+        if guild_history.len() >= self.history_size {
+            guild_history.pop_front();
+        }
+        guild_history.push_back(entry);
     }
 }
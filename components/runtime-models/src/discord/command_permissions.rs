@@ -0,0 +1,62 @@
+use serde::{Deserialize, Serialize};
+use ts_rs::TS;
+use twilight_model::application::command::permissions::{
+    CommandPermission as TwilightCommandPermission, CommandPermissionType, GuildCommandPermissions,
+};
+
+/// The overrides configured in the server settings for who can use a command and where
+#[derive(Clone, Debug, Deserialize, Serialize, TS)]
+#[ts(export)]
+#[serde(rename_all = "camelCase")]
+#[ts(export_to = "bindings/discord/CommandPermissions.ts")]
+pub struct CommandPermissions {
+    pub command_id: String,
+    pub permissions: Vec<CommandPermission>,
+}
+
+impl From<GuildCommandPermissions> for CommandPermissions {
+    fn from(v: GuildCommandPermissions) -> Self {
+        Self {
+            command_id: v.id.to_string(),
+            permissions: v.permissions.into_iter().map(Into::into).collect(),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize, TS)]
+#[ts(export)]
+#[serde(rename_all = "camelCase")]
+#[ts(export_to = "bindings/discord/CommandPermission.ts")]
+pub struct CommandPermission {
+    pub kind: CommandPermissionKind,
+    /// Id of the channel, role or user, the id of the server minus 1 means all channels
+    /// and the id of the server itself is the @everyone role
+    pub id: String,
+    /// Whether the channel, role or user is allowed to use the command
+    pub allowed: bool,
+}
+
+impl From<TwilightCommandPermission> for CommandPermission {
+    fn from(v: TwilightCommandPermission) -> Self {
+        let (kind, id) = match v.id {
+            CommandPermissionType::Channel(id) => (CommandPermissionKind::Channel, id.to_string()),
+            CommandPermissionType::Role(id) => (CommandPermissionKind::Role, id.to_string()),
+            CommandPermissionType::User(id) => (CommandPermissionKind::User, id.to_string()),
+        };
+
+        Self {
+            kind,
+            id,
+            allowed: v.permission,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, Serialize, TS)]
+#[ts(export)]
+#[ts(export_to = "bindings/discord/CommandPermissionKind.ts")]
+pub enum CommandPermissionKind {
+    Channel,
+    Role,
+    User,
+}
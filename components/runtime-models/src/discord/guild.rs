@@ -2,6 +2,7 @@ use std::borrow::Cow;
 
 use dbrokerapi::models::BrokerGuild;
 use serde::{Deserialize, Serialize};
+use stores::config::JoinedGuild;
 use ts_rs::TS;
 use twilight_cache_inmemory::model::CachedGuild;
 use twilight_model::guild::{
@@ -97,6 +98,42 @@ impl From<&CachedGuild> for Guild {
     }
 }
 
+/// What's stored about a guild the bot has joined, for when it's not in the bot's state
+#[derive(Clone, Debug, Serialize, TS)]
+#[ts(export)]
+#[serde(rename_all = "camelCase")]
+#[ts(export_to = "bindings/discord/StoredGuild.ts")]
+pub struct StoredGuild {
+    pub(crate) id: String,
+    pub(crate) name: String,
+    pub(crate) icon: Option<String>,
+    pub(crate) owner_id: String,
+}
+
+impl From<JoinedGuild> for StoredGuild {
+    fn from(v: JoinedGuild) -> Self {
+        Self {
+            id: v.id.to_string(),
+            name: v.name,
+            icon: if v.icon.is_empty() {
+                None
+            } else {
+                Some(v.icon)
+            },
+            owner_id: v.owner_id.to_string(),
+        }
+    }
+}
+
+/// The guild from the bot's state, or what's stored about it if it's not in there
+#[derive(Clone, Debug, Serialize, TS)]
+#[serde(tag = "kind")]
+#[ts(export, export_to = "bindings/discord/GuildResult.ts")]
+pub enum GuildResult {
+    Full(Box<Guild>),
+    Stored(StoredGuild),
+}
+
 impl From<BrokerGuild> for Guild {
     fn from(v: BrokerGuild) -> Self {
         Self {
@@ -140,6 +177,45 @@ impl From<BrokerGuild> for Guild {
     }
 }
 
+/// The guild's discord server boost status, not to be confused with botloader premium
+#[derive(Clone, Debug, Serialize, TS)]
+#[ts(export)]
+#[serde(rename_all = "camelCase")]
+#[ts(export_to = "bindings/discord/GuildBoostInfo.ts")]
+pub struct GuildBoostInfo {
+    pub premium_tier: PremiumTier,
+    pub boost_count: NotBigU64,
+}
+
+impl Default for GuildBoostInfo {
+    fn default() -> Self {
+        Self {
+            premium_tier: PremiumTier::None,
+            boost_count: NotBigU64(0),
+        }
+    }
+}
+
+impl From<&Guild> for GuildBoostInfo {
+    fn from(v: &Guild) -> Self {
+        Self {
+            premium_tier: v.premium_tier,
+            boost_count: v.premium_subscription_count,
+        }
+    }
+}
+
+/// Number of members in the guild, both are discord's estimates as botloader doesn't keep track
+/// of presences
+#[derive(Clone, Debug, Serialize, TS)]
+#[ts(export)]
+#[serde(rename_all = "camelCase")]
+#[ts(export_to = "bindings/discord/MemberCounts.ts")]
+pub struct MemberCounts {
+    pub total: NotBigU64,
+    pub online: Option<NotBigU64>,
+}
+
 #[derive(Clone, Copy, Debug, Serialize, Deserialize, TS)]
 #[ts(export)]
 #[ts(export_to = "bindings/discord/DefaultMessageNotificationLevel.ts")]
@@ -1,4 +1,4 @@
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 use ts_rs::TS;
 
 use crate::{
@@ -119,3 +119,33 @@ impl From<twilight_model::gateway::payload::incoming::MessageUpdate> for EventMe
         }
     }
 }
+
+#[derive(Clone, Debug, Deserialize, TS)]
+#[ts(export)]
+#[ts(export_to = "bindings/internal/EventFilter.ts")]
+#[serde(rename_all = "camelCase")]
+pub struct EventFilter {
+    pub event: String,
+    #[serde(default)]
+    #[ts(optional)]
+    pub channel_id: Option<String>,
+    #[serde(default)]
+    #[ts(optional)]
+    pub user_id: Option<String>,
+    /// Only match interactions with a custom id starting with this
+    #[serde(default)]
+    #[ts(optional)]
+    pub custom_id_prefix: Option<String>,
+}
+
+#[derive(Clone, Debug, Serialize, TS)]
+#[ts(export)]
+#[ts(export_to = "bindings/internal/AwaitAnyResult.ts")]
+#[serde(rename_all = "camelCase")]
+pub struct AwaitAnyResult {
+    /// Index of the filter that matched
+    pub index: u32,
+    pub name: String,
+    #[ts(type = "any")]
+    pub event: serde_json::Value,
+}
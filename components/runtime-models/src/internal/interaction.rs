@@ -68,6 +68,7 @@ impl TryFrom<twilight_model::application::interaction::Interaction> for Interact
                     id: v.id.to_string(),
                     member: Member::from_partial(v.member.unwrap()),
                     token: v.token,
+                    command_id: data.id.to_string(),
                     data_map: data.resolved.map(Into::into).unwrap_or_default(),
 
                     kind: data.kind.into(),
@@ -161,6 +162,8 @@ pub struct CommandInteraction {
     pub member: Member,
     pub token: String,
 
+    /// Id of the top level command, subcommands and groups share it
+    pub command_id: String,
     pub name: String,
     pub parent_name: Option<String>,
     pub parent_parent_name: Option<String>,
@@ -413,3 +416,16 @@ impl From<TwilightModalInteractionDataComponent> for ModalInteractionDataCompone
         }
     }
 }
+
+/// A command registered on discord for the guild
+#[derive(Clone, Debug, Serialize, TS)]
+#[ts(export)]
+#[serde(rename_all = "camelCase")]
+#[ts(export_to = "bindings/internal/RegisteredCommand.ts")]
+pub struct RegisteredCommand {
+    pub id: String,
+    pub name: String,
+    pub kind: CommandType,
+    /// Whether a script currently running claims this command
+    pub claimed: bool,
+}
@@ -4,7 +4,7 @@ use ts_rs::TS;
 use crate::{
     discord::{
         channel::{ChannelType, ThreadMetadata},
-        component::Component,
+        component::{ActionRow, Component, TextInput},
         message::MessageFlags,
     },
     util::NotBigU64,
@@ -166,9 +166,9 @@ impl From<InteractionCallbackData> for TwilightCallbackData {
 )]
 #[serde(rename_all = "camelCase")]
 pub struct ModalCallbackData {
-    title: String,
-    custom_id: String,
-    components: Vec<Component>,
+    pub title: String,
+    pub custom_id: String,
+    pub components: Vec<Component>,
 }
 
 impl From<ModalCallbackData> for TwilightCallbackData {
@@ -181,3 +181,32 @@ impl From<ModalCallbackData> for TwilightCallbackData {
         }
     }
 }
+
+/// A modal made up of text inputs, each input is put in its own action row
+#[derive(Clone, Debug, Deserialize, TS)]
+#[ts(export)]
+#[ts(export_to = "bindings/internal/ShowModal.ts")]
+#[serde(rename_all = "camelCase")]
+pub struct OpShowModal {
+    pub title: String,
+    pub custom_id: String,
+    pub inputs: Vec<TextInput>,
+}
+
+impl From<OpShowModal> for ModalCallbackData {
+    fn from(v: OpShowModal) -> Self {
+        Self {
+            title: v.title,
+            custom_id: v.custom_id,
+            components: v
+                .inputs
+                .into_iter()
+                .map(|input| {
+                    Component::ActionRow(ActionRow {
+                        components: vec![Component::TextInput(input)],
+                    })
+                })
+                .collect(),
+        }
+    }
+}
@@ -72,6 +72,30 @@ impl From<twilight_model::guild::Member> for Member {
     }
 }
 
+#[derive(Clone, Debug, Serialize, TS)]
+#[ts(export, rename = "IMemberDetail")]
+#[ts(export_to = "bindings/internal/MemberDetail.ts")]
+#[serde(rename_all = "camelCase")]
+pub struct MemberDetail {
+    pub joined_at: NotBigU64,
+    pub roles: Vec<String>,
+    pub nick: Option<String>,
+    pub premium_since: Option<NotBigU64>,
+}
+
+impl From<twilight_model::guild::Member> for MemberDetail {
+    fn from(v: twilight_model::guild::Member) -> Self {
+        Self {
+            joined_at: NotBigU64(v.joined_at.as_micros() as u64 / 1000),
+            roles: v.roles.iter().map(ToString::to_string).collect(),
+            nick: v.nick,
+            premium_since: v
+                .premium_since
+                .map(|v| NotBigU64(v.as_micros() as u64 / 1000)),
+        }
+    }
+}
+
 impl Member {
     pub fn from_cache(user: User, member: twilight_cache_inmemory::model::CachedMember) -> Self {
         Self {
@@ -48,6 +48,26 @@ pub struct OpCreateChannelMessage {
     pub fields: OpCreateMessageFields,
 }
 
+#[derive(Clone, Debug, Serialize, Deserialize, TS)]
+#[ts(export)]
+#[ts(export_to = "bindings/internal/BroadcastMessage.ts")]
+#[serde(rename_all = "camelCase")]
+pub struct OpBroadcastMessage {
+    pub channel_ids: Vec<String>,
+    pub fields: OpCreateMessageFields,
+}
+
+/// The outcome of sending a broadcast message to a single channel, either `message` or `error` is set
+#[derive(Clone, Debug, Serialize, TS)]
+#[ts(export)]
+#[ts(export_to = "bindings/internal/BroadcastMessageResult.ts")]
+#[serde(rename_all = "camelCase")]
+pub struct BroadcastMessageResult {
+    pub channel_id: String,
+    pub message: Option<Message>,
+    pub error: Option<String>,
+}
+
 #[derive(Clone, Debug, Serialize, Deserialize, TS)]
 #[ts(export)]
 #[ts(export_to = "bindings/internal/EditChannelMessage.ts")]
@@ -58,6 +78,18 @@ pub struct OpEditChannelMessage {
     pub fields: OpCreateMessageFields,
 }
 
+/// The outcome of a single edit in a batch edit, either `message` or `error` is set
+#[derive(Clone, Debug, Serialize, TS)]
+#[ts(export)]
+#[ts(export_to = "bindings/internal/EditMessageResult.ts")]
+#[serde(rename_all = "camelCase")]
+pub struct EditMessageResult {
+    pub channel_id: String,
+    pub message_id: String,
+    pub message: Option<Message>,
+    pub error: Option<String>,
+}
+
 #[derive(Clone, Debug, Serialize, Deserialize, TS)]
 #[ts(export)]
 #[ts(export_to = "bindings/internal/CreateFollowUpMessage.ts")]
@@ -249,3 +281,39 @@ impl From<twilight_model::channel::message::Mention> for UserMention {
         }
     }
 }
+
+#[derive(Clone, Debug, Deserialize, TS)]
+#[ts(export)]
+#[ts(export_to = "bindings/internal/CreatePoll.ts")]
+#[serde(rename_all = "camelCase")]
+pub struct OpCreatePoll {
+    pub question: String,
+    pub answers: Vec<String>,
+    /// How long the poll is open for, in hours
+    pub duration_hours: u32,
+    #[serde(default)]
+    #[ts(optional)]
+    pub allow_multiselect: Option<bool>,
+}
+
+#[derive(Clone, Debug, Serialize, TS)]
+#[ts(export)]
+#[ts(export_to = "bindings/internal/PollResults.ts")]
+#[serde(rename_all = "camelCase")]
+pub struct PollResults {
+    pub question: String,
+    pub answers: Vec<PollAnswerResult>,
+    /// Whether the poll has ended and the counts are final
+    pub is_finalized: bool,
+    pub expires_at: Option<String>,
+}
+
+#[derive(Clone, Debug, Serialize, TS)]
+#[ts(export)]
+#[ts(export_to = "bindings/internal/PollAnswerResult.ts")]
+#[serde(rename_all = "camelCase")]
+pub struct PollAnswerResult {
+    pub answer_id: u32,
+    pub text: String,
+    pub votes: u32,
+}
@@ -1,4 +1,4 @@
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 use ts_rs::TS;
 
 use crate::discord::message::SendEmoji;
@@ -27,3 +27,13 @@ pub struct GetReactionsFields {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub limit: Option<u32>,
 }
+
+#[derive(Clone, Debug, Serialize, TS)]
+#[ts(export)]
+#[ts(export_to = "bindings/internal/GatewayIntents.ts")]
+#[serde(rename_all = "camelCase")]
+pub struct GatewayIntents {
+    pub message_content: bool,
+    pub guild_members: bool,
+    pub presences: bool,
+}
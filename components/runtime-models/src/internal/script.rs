@@ -17,6 +17,58 @@ pub struct ScriptMeta {
     pub command_groups: Vec<CommandGroup>,
     pub interval_timers: Vec<IntervalTimer>,
     pub task_names: Vec<String>,
+    /// Names of the events the script has registered handlers for
+    #[serde(default)]
+    pub event_names: Vec<String>,
+    /// The runtime API version the script was written against, if it pinned one
+    #[serde(default)]
+    #[ts(optional)]
+    pub api_version: Option<u32>,
+}
+
+#[derive(Clone, Debug, Serialize, TS)]
+#[ts(export)]
+#[serde(rename_all = "camelCase")]
+#[ts(export_to = "bindings/internal/EventHandlerRegistration.ts")]
+pub struct EventHandlerRegistration {
+    pub event_name: String,
+    #[ts(type = "number")]
+    pub script_id: NotBigU64,
+}
+
+#[derive(Clone, Debug, Serialize, TS)]
+#[ts(export)]
+#[serde(rename_all = "camelCase")]
+#[ts(export_to = "bindings/internal/RestartInfo.ts")]
+pub struct RestartInfo {
+    pub reason: RestartReason,
+    /// Unix timestamp in milliseconds of when the restart happened
+    #[ts(type = "number")]
+    pub at: NotBigU64,
+}
+
+#[derive(Clone, Debug, Serialize, TS)]
+#[ts(export)]
+#[serde(rename_all = "camelCase")]
+#[ts(export_to = "bindings/internal/LocalTime.ts")]
+pub struct LocalTime {
+    /// RFC 3339 timestamp including the offset, e.g. 2023-03-11T09:00:00+01:00
+    pub iso: String,
+    /// IANA name of the timezone, "UTC" if the server has none configured
+    pub timezone: String,
+    /// Offset from UTC in minutes, including daylight saving time if it's in effect
+    pub offset_minutes: i32,
+}
+
+#[derive(Clone, Copy, Debug, Serialize, TS)]
+#[ts(export)]
+#[ts(export_to = "bindings/internal/RestartReason.ts")]
+pub enum RestartReason {
+    ScriptsChanged,
+    OutOfMemory,
+    Runaway,
+    Hibernation,
+    Other,
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize, TS)]
@@ -37,6 +89,82 @@ pub enum IntervalType {
     Cron(String),
 }
 
+impl From<stores::timers::IntervalType> for IntervalType {
+    fn from(v: stores::timers::IntervalType) -> Self {
+        match v {
+            stores::timers::IntervalType::Minutes(m) => Self::Minutes(NotBigU64(m)),
+            stores::timers::IntervalType::Cron(c) => Self::Cron(c),
+        }
+    }
+}
+
+/// What a script has registered the last time it ran, as stored in the config store
+#[derive(Clone, Debug, Serialize, TS)]
+#[ts(export)]
+#[serde(rename_all = "camelCase")]
+#[ts(export_to = "bindings/internal/ScriptContributes.ts")]
+pub struct ScriptContributes {
+    pub commands: Vec<ContributedCommand>,
+    pub interval_timers: Vec<IntervalTimer>,
+    #[ts(optional)]
+    pub api_version: Option<u32>,
+}
+
+impl From<stores::config::ScriptContributes> for ScriptContributes {
+    fn from(v: stores::config::ScriptContributes) -> Self {
+        Self {
+            commands: v.commands.into_iter().map(Into::into).collect(),
+            interval_timers: v
+                .interval_timers
+                .into_iter()
+                .map(|timer| IntervalTimer {
+                    name: timer.name,
+                    interval: timer.interval.into(),
+                })
+                .collect(),
+            api_version: v.api_version,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize, TS)]
+#[ts(export)]
+#[serde(rename_all = "camelCase")]
+#[ts(export_to = "bindings/internal/ContributedCommand.ts")]
+pub struct ContributedCommand {
+    pub name: String,
+    pub description: String,
+    pub kind: CommandType,
+    /// Full names of the sub commands, e.g. "settings show" for a command in a group
+    pub sub_commands: Vec<String>,
+}
+
+impl From<twilight_model::application::command::Command> for ContributedCommand {
+    fn from(v: twilight_model::application::command::Command) -> Self {
+        use twilight_model::application::command::CommandOptionType;
+
+        let mut sub_commands = Vec::new();
+        for opt in &v.options {
+            match opt.kind {
+                CommandOptionType::SubCommand => sub_commands.push(opt.name.clone()),
+                CommandOptionType::SubCommandGroup => {
+                    for sub in opt.options.iter().flatten() {
+                        sub_commands.push(format!("{} {}", opt.name, sub.name));
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        Self {
+            name: v.name,
+            description: v.description,
+            kind: v.kind.into(),
+            sub_commands,
+        }
+    }
+}
+
 #[derive(Clone, Debug, Deserialize, Serialize, TS)]
 #[ts(export)]
 #[serde(rename_all = "camelCase")]
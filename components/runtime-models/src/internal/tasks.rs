@@ -1,3 +1,5 @@
+use std::collections::HashMap;
+
 use crate::util::NotBigU64;
 use serde::{Deserialize, Serialize};
 use ts_rs::TS;
@@ -13,11 +15,51 @@ pub struct CreateScheduledTask {
     #[ts(optional)]
     pub unique_key: Option<String>,
 
+    #[serde(default)]
+    #[ts(optional)]
+    pub tags: Option<HashMap<String, String>>,
+
     #[ts(type = "any")]
     pub data: serde_json::Value,
     pub execute_at: NotBigU64,
 }
 
+#[derive(Clone, Debug, Deserialize, TS)]
+#[ts(export)]
+#[ts(export_to = "bindings/internal/RunBackgroundTask.ts")]
+#[serde(rename_all = "camelCase")]
+pub struct RunBackgroundTask {
+    pub handler_name: String,
+
+    #[ts(type = "any")]
+    pub data: serde_json::Value,
+
+    /// Depth of the background task this is started from, 0 if it's not started from one
+    pub depth: u32,
+
+    #[serde(default)]
+    #[ts(optional)]
+    pub result_handler: Option<String>,
+}
+
+/// The data of the tasks created for background work
+#[derive(Clone, Debug, Serialize, TS)]
+#[ts(export)]
+#[ts(export_to = "bindings/internal/BackgroundTaskData.ts")]
+#[serde(rename_all = "camelCase")]
+pub struct BackgroundTaskData {
+    #[ts(type = "unknown")]
+    pub data: serde_json::Value,
+
+    /// 1 for tasks started outside of background handlers, one more for each started from
+    /// within one or as its result handler
+    pub depth: u32,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[ts(optional)]
+    pub result_handler: Option<String>,
+}
+
 #[derive(Clone, Debug, Serialize, TS)]
 #[ts(export)]
 #[ts(export_to = "bindings/internal/ScheduledTask.ts")]
@@ -27,7 +69,11 @@ pub struct ScheduledTask {
     pub namespace: String,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub key: Option<String>,
+    pub tags: HashMap<String, String>,
     pub execute_at: NotBigU64,
+    /// Milliseconds until the task is executed according to the server's clock, negative if overdue
+    #[ts(type = "number")]
+    pub remaining_ms: i64,
 
     #[ts(type = "unknown")]
     pub data: serde_json::Value,
@@ -39,8 +85,77 @@ impl From<stores::timers::ScheduledTask> for ScheduledTask {
             id: NotBigU64(v.id),
             namespace: v.name,
             key: v.unique_key,
+            tags: v.tags,
             execute_at: NotBigU64(v.execute_at.timestamp_millis() as u64),
+            remaining_ms: (v.execute_at - chrono::Utc::now()).num_milliseconds(),
             data: v.data,
         }
     }
 }
+
+#[derive(Clone, Debug, Serialize, TS)]
+#[ts(export)]
+#[ts(export_to = "bindings/internal/TaskNamespaceSummary.ts")]
+#[serde(rename_all = "camelCase")]
+pub struct TaskNamespaceSummary {
+    pub namespace: String,
+    pub count: NotBigU64,
+    pub next_execute_at: NotBigU64,
+}
+
+impl From<stores::timers::TaskNamespaceSummary> for TaskNamespaceSummary {
+    fn from(v: stores::timers::TaskNamespaceSummary) -> Self {
+        Self {
+            namespace: v.name,
+            count: NotBigU64(v.count),
+            next_execute_at: NotBigU64(v.next_execute_at.timestamp_millis() as u64),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize, TS)]
+#[ts(export)]
+#[ts(export_to = "bindings/internal/TaskExecution.ts")]
+#[serde(rename_all = "camelCase")]
+pub struct TaskExecution {
+    pub task_id: NotBigU64,
+    pub namespace: String,
+    pub fired_at: NotBigU64,
+    pub outcome: TaskOutcome,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl From<stores::timers::TaskExecution> for TaskExecution {
+    fn from(v: stores::timers::TaskExecution) -> Self {
+        Self {
+            task_id: NotBigU64(v.task_id),
+            namespace: v.name,
+            fired_at: NotBigU64(v.fired_at.timestamp_millis() as u64),
+            outcome: v.outcome.into(),
+            error: v.error,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Serialize, TS)]
+#[ts(export)]
+#[ts(export_to = "bindings/internal/TaskOutcome.ts")]
+pub enum TaskOutcome {
+    /// The task handler finished
+    Succeeded,
+    /// The task handler threw or the vm was shut down before it finished
+    Failed,
+    /// The vm was shut down before the task was delivered, it's delivered again later
+    Interrupted,
+}
+
+impl From<stores::timers::TaskOutcome> for TaskOutcome {
+    fn from(v: stores::timers::TaskOutcome) -> Self {
+        match v {
+            stores::timers::TaskOutcome::Succeeded => Self::Succeeded,
+            stores::timers::TaskOutcome::Failed => Self::Failed,
+            stores::timers::TaskOutcome::Interrupted => Self::Interrupted,
+        }
+    }
+}
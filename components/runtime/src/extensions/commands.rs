@@ -0,0 +1,296 @@
+use std::{cell::RefCell, collections::HashMap, rc::Rc};
+
+use deno_core::{op, Extension, OpState};
+use guild_logger::LogEntry;
+use rand::{rngs::OsRng, Rng};
+use runtime_models::internal::{
+    interaction::{CommandType, RegisteredCommand},
+    script::ScriptMeta,
+};
+use tracing::info;
+use twilight_model::application::command::{
+    Command as TwilightCommand, CommandType as TwilightCommandType,
+};
+use vm::{AnyError, ScriptsStateStoreHandle};
+
+use super::discord::handle_discord_error;
+use crate::{
+    get_rt_ctx,
+    limits::{count_dispatch_op, RateLimiters},
+};
+
+pub fn extension() -> Extension {
+    Extension::builder("bl_commands")
+        .ops(vec![
+            op_bl_list_registered_commands::decl(),
+            op_bl_prune_orphan_commands::decl(),
+            op_bl_restore_pruned_commands::decl(),
+        ])
+        .state(|state| {
+            state.put(ScriptCommandClaims::default());
+            state.put(PrunedCommands::default());
+            state.put(ScriptCommandTokens::default());
+            Ok(())
+        })
+        .build()
+}
+
+/// The top level commands claimed by the scripts running in this vm, keyed by script id
+#[derive(Default)]
+pub(crate) struct ScriptCommandClaims(HashMap<u64, Vec<(String, CommandType)>>);
+
+impl ScriptCommandClaims {
+    /// Records the commands from the meta of a started script
+    ///
+    /// Grouped commands are registered under their group as a chat command on discord
+    pub(crate) fn record(&mut self, meta: &ScriptMeta) {
+        let mut claims = meta
+            .command_groups
+            .iter()
+            .map(|group| (group.name.clone(), CommandType::Chat))
+            .collect::<Vec<_>>();
+
+        for cmd in &meta.commands {
+            match &cmd.group {
+                Some(group) => claims.push((group.clone(), CommandType::Chat)),
+                None => claims.push((cmd.name.clone(), cmd.kind)),
+            }
+        }
+
+        self.0.insert(meta.script_id.0, claims);
+    }
+
+    fn is_claimed(&self, cmd: &TwilightCommand) -> bool {
+        self.0
+            .values()
+            .flatten()
+            .any(|(name, kind)| *name == cmd.name && TwilightCommandType::from(*kind) == cmd.kind)
+    }
+}
+
+/// Tokens handed to scripts when they start, the command management ops take one of these
+/// instead of a script id so a script can't act on behalf of another one
+#[derive(Default)]
+pub(crate) struct ScriptCommandTokens(HashMap<u64, u64>);
+
+impl ScriptCommandTokens {
+    /// Issues a new token for the script, revoking the one from a previous version of it
+    pub(crate) fn issue(&mut self, script_id: u64) -> u64 {
+        self.0.retain(|_, id| *id != script_id);
+
+        // kept within the safe integer range so it survives as a js number
+        let token = OsRng.gen_range(0..(1 << 53));
+        self.0.insert(token, script_id);
+        token
+    }
+
+    fn script_id(&self, token: u64) -> Option<u64> {
+        self.0.get(&token).copied()
+    }
+}
+
+/// Commands removed by op_bl_prune_orphan_commands, kept so they can be restored until the vm
+/// is restarted
+#[derive(Default)]
+struct PrunedCommands(Vec<TwilightCommand>);
+
+fn to_registered_command(cmd: &TwilightCommand, claimed: bool) -> Option<RegisteredCommand> {
+    let kind = match cmd.kind {
+        TwilightCommandType::ChatInput => CommandType::Chat,
+        TwilightCommandType::User => CommandType::User,
+        TwilightCommandType::Message => CommandType::Message,
+        _ => return None,
+    };
+
+    Some(RegisteredCommand {
+        id: cmd.id?.to_string(),
+        name: cmd.name.clone(),
+        kind,
+        claimed,
+    })
+}
+
+/// Only the guild's own scripts are allowed to manage its commands, not plugins
+fn ensure_guild_script(state: &Rc<RefCell<OpState>>, token: u64) -> Result<(), AnyError> {
+    let state = state.borrow();
+    let script_id = state
+        .borrow::<ScriptCommandTokens>()
+        .script_id(token)
+        .ok_or_else(|| anyhow::anyhow!("unknown script"))?;
+
+    let script_store = state.borrow::<ScriptsStateStoreHandle>().borrow();
+    let script = script_store
+        .get_script(script_id)
+        .ok_or_else(|| anyhow::anyhow!("unknown script"))?;
+
+    if script.script.plugin_id.is_some() {
+        return Err(anyhow::anyhow!(
+            "plugins are not allowed to manage the server's commands"
+        ));
+    }
+
+    Ok(())
+}
+
+/// Commands are only orphans once every script had the chance to claim its commands, a script
+/// that failed to load or hasn't started yet would otherwise lose them
+fn ensure_all_scripts_started(state: &Rc<RefCell<OpState>>) -> Result<(), AnyError> {
+    let state = state.borrow();
+    let claims = state.borrow::<ScriptCommandClaims>();
+    let script_store = state.borrow::<ScriptsStateStoreHandle>().borrow();
+
+    if let Some(pending) = script_store
+        .scripts
+        .iter()
+        .find(|v| !claims.0.contains_key(&v.script.id))
+    {
+        return Err(anyhow::anyhow!(
+            "the script {} has not started, not pruning commands until all scripts are running",
+            pending.script.name
+        ));
+    }
+
+    Ok(())
+}
+
+async fn fetch_guild_commands(
+    state: &Rc<RefCell<OpState>>,
+) -> Result<Vec<TwilightCommand>, AnyError> {
+    RateLimiters::command_requests(state).await?;
+    let rt_ctx = get_rt_ctx(state);
+
+    let client = rt_ctx.discord_config.interaction_client();
+    Ok(client
+        .guild_commands(rt_ctx.guild_id)
+        .await
+        .map_err(|err| handle_discord_error(state, err))?
+        .models()
+        .await?)
+}
+
+#[op]
+pub async fn op_bl_list_registered_commands(
+    state: Rc<RefCell<OpState>>,
+) -> Result<Vec<RegisteredCommand>, AnyError> {
+    count_dispatch_op(&state)?;
+
+    let commands = fetch_guild_commands(&state).await?;
+
+    let state = state.borrow();
+    let claims = state.borrow::<ScriptCommandClaims>();
+    Ok(commands
+        .iter()
+        .filter_map(|cmd| to_registered_command(cmd, claims.is_claimed(cmd)))
+        .collect())
+}
+
+/// Returns the registered commands no running script claims, removing them if `confirm` is set
+#[op]
+pub async fn op_bl_prune_orphan_commands(
+    state: Rc<RefCell<OpState>>,
+    token: u64,
+    confirm: bool,
+) -> Result<Vec<RegisteredCommand>, AnyError> {
+    count_dispatch_op(&state)?;
+    ensure_guild_script(&state, token)?;
+    ensure_all_scripts_started(&state)?;
+
+    let orphans = {
+        let commands = fetch_guild_commands(&state).await?;
+        let state = state.borrow();
+        let claims = state.borrow::<ScriptCommandClaims>();
+        commands
+            .into_iter()
+            .filter(|cmd| !claims.is_claimed(cmd))
+            .collect::<Vec<_>>()
+    };
+
+    if !confirm {
+        return Ok(orphans
+            .iter()
+            .filter_map(|cmd| to_registered_command(cmd, false))
+            .collect());
+    }
+
+    let rt_ctx = get_rt_ctx(&state);
+    let client = rt_ctx.discord_config.interaction_client();
+
+    let mut pruned = Vec::new();
+    for cmd in orphans {
+        let command_id = if let Some(id) = cmd.id {
+            id
+        } else {
+            continue;
+        };
+
+        RateLimiters::command_requests(&state).await?;
+        client
+            .delete_guild_command(rt_ctx.guild_id, command_id)
+            .await
+            .map_err(|err| handle_discord_error(&state, err))?;
+
+        info!(guild_id = %rt_ctx.guild_id, "pruned orphan command {}", cmd.name);
+        rt_ctx.guild_logger.log(LogEntry::info(
+            rt_ctx.guild_id,
+            format!("removed the command {} as no script claims it", cmd.name),
+        ));
+
+        pruned.extend(to_registered_command(&cmd, false));
+        state
+            .borrow_mut()
+            .borrow_mut::<PrunedCommands>()
+            .0
+            .push(cmd);
+    }
+
+    Ok(pruned)
+}
+
+/// Registers the commands pruned since the vm was started again, returning all the registered
+/// commands afterwards
+#[op]
+pub async fn op_bl_restore_pruned_commands(
+    state: Rc<RefCell<OpState>>,
+    token: u64,
+) -> Result<Vec<RegisteredCommand>, AnyError> {
+    count_dispatch_op(&state)?;
+    ensure_guild_script(&state, token)?;
+
+    // only cleared once they've been registered again, so a failed restore can be retried
+    let pruned = state.borrow().borrow::<PrunedCommands>().0.clone();
+    if pruned.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let mut commands = fetch_guild_commands(&state).await?;
+    let num_restored = pruned.len();
+    commands.extend(
+        pruned
+            .into_iter()
+            .map(|cmd| TwilightCommand { id: None, ..cmd }),
+    );
+
+    let rt_ctx = get_rt_ctx(&state);
+    let client = rt_ctx.discord_config.interaction_client();
+    RateLimiters::command_requests(&state).await?;
+    let restored = client
+        .set_guild_commands(rt_ctx.guild_id, &commands)
+        .await
+        .map_err(|err| handle_discord_error(&state, err))?
+        .models()
+        .await?;
+
+    state.borrow_mut().borrow_mut::<PrunedCommands>().0.clear();
+
+    rt_ctx.guild_logger.log(LogEntry::info(
+        rt_ctx.guild_id,
+        format!("restored {num_restored} pruned command(s)"),
+    ));
+
+    let state = state.borrow();
+    let claims = state.borrow::<ScriptCommandClaims>();
+    Ok(restored
+        .iter()
+        .filter_map(|cmd| to_registered_command(cmd, claims.is_claimed(cmd)))
+        .collect())
+}
@@ -3,38 +3,43 @@ use deno_core::{error::custom_error, op, Extension, OpState};
 use futures::TryFutureExt;
 use runtime_models::{
     discord::{
-        channel::{PermissionOverwrite, PermissionOverwriteType},
-        guild::Guild,
+        channel::{ChannelType, PermissionOverwrite, PermissionOverwriteType},
+        command_permissions::CommandPermissions,
+        guild::{GuildBoostInfo, GuildResult, MemberCounts},
         message::SendEmoji,
         util::AuditLogExtras,
     },
     internal::{
         channel::{CreateChannel, EditChannel},
-        interactions::InteractionCallback,
-        member::{Ban, UpdateGuildMemberFields},
+        interactions::{InteractionCallback, InteractionResponse, ModalCallbackData, OpShowModal},
+        member::{Ban, MemberDetail, UpdateGuildMemberFields},
         messages::{
-            Message, OpCreateChannelMessage, OpCreateFollowUpMessage, OpDeleteMessage,
-            OpDeleteMessagesBulk, OpEditChannelMessage, OpGetMessages,
+            BroadcastMessageResult, EditMessageResult, Message, OpBroadcastMessage,
+            OpCreateChannelMessage, OpCreateFollowUpMessage, OpCreateMessageFields, OpCreatePoll,
+            OpDeleteMessage, OpDeleteMessagesBulk, OpEditChannelMessage, OpGetMessages,
+            PollAnswerResult, PollResults,
         },
         misc_op::{CreateBanFields, GetReactionsFields},
         user::User,
     },
+    util::NotBigU64,
 };
 use std::{
     borrow::Cow,
-    collections::VecDeque,
+    collections::{HashMap, VecDeque},
     str::FromStr,
     time::{Duration, Instant},
 };
 use std::{cell::RefCell, rc::Rc};
 use tracing::warn;
 use twilight_http::error::ErrorType;
-use twilight_http::request::AuditLogReason;
+use twilight_http::request::{AuditLogReason, Request};
+use twilight_http::routing::Route;
 use twilight_http::{
     api_error::{ApiError, GeneralApiError},
     response::StatusCode,
 };
-use twilight_model::id::marker::{GenericMarker, MessageMarker, RoleMarker};
+use twilight_model::id::marker::{CommandMarker, GenericMarker, MessageMarker, RoleMarker};
 use twilight_model::id::Id;
 use twilight_model::{
     guild::Permissions,
@@ -44,18 +49,28 @@ use vm::AnyError;
 use vmthread::ShutdownHandle;
 
 use super::{get_guild_channel, parse_get_guild_channel, parse_str_snowflake_id};
-use crate::{get_rt_ctx, RuntimeContext, RuntimeEvent};
+use crate::{
+    get_rt_ctx,
+    limits::{count_dispatch_op, RateLimiters},
+    RuntimeContext, RuntimeEvent,
+};
 
 pub fn extension() -> Extension {
     Extension::builder("bl_discord")
         .ops(vec![
             // guild
             op_discord_get_guild::decl(),
+            op_discord_get_boost_info::decl(),
+            op_discord_get_member_counts::decl(),
             // messages
             op_discord_get_message::decl(),
             op_discord_get_messages::decl(),
             op_discord_create_message::decl(),
+            op_discord_create_poll::decl(),
+            op_discord_get_poll_results::decl(),
+            op_discord_broadcast_message::decl(),
             op_discord_edit_message::decl(),
+            op_discord_edit_messages::decl(),
             op_discord_crosspost_message::decl(),
             op_discord_delete_message::decl(),
             op_discord_bulk_delete_messages::decl(),
@@ -69,27 +84,38 @@ pub fn extension() -> Extension {
             // roles
             op_discord_get_role::decl(),
             op_discord_get_roles::decl(),
+            op_discord_list_roles::decl(),
+            op_discord_bot_highest_role::decl(),
             // channels
             op_discord_get_channel::decl(),
             op_discord_get_channels::decl(),
+            op_discord_list_channels::decl(),
             op_discord_create_channel::decl(),
             op_discord_edit_channel::decl(),
             op_discord_delete_channel::decl(),
+            op_discord_set_slowmode::decl(),
             op_discord_update_channel_permission::decl(),
             op_discord_delete_channel_permission::decl(),
             // pins
             op_discord_get_channel_pins::decl(),
             op_discord_create_pin::decl(),
             op_discord_delete_pin::decl(),
+            // typing
+            op_discord_trigger_typing::decl(),
             // invites
             // members
             op_discord_remove_member::decl(),
             op_discord_get_members::decl(),
+            op_discord_get_member_detail::decl(),
+            op_discord_member_pending::decl(),
             op_discord_update_member::decl(),
             op_discord_add_member_role::decl(),
             op_discord_remove_member_role::decl(),
+            // users
+            op_discord_get_user::decl(),
             // interactions
             op_discord_interaction_callback::decl(),
+            op_discord_show_modal::decl(),
             op_discord_interaction_get_original_response::decl(),
             op_discord_interaction_edit_original_response::decl(),
             op_discord_interaction_delete_original::decl(),
@@ -104,10 +130,15 @@ pub fn extension() -> Extension {
             op_discord_delete_ban::decl(),
             // misc
             op_discord_get_member_permissions::decl(),
+            op_discord_get_command_permissions::decl(),
+            op_discord_update_command_permissions_cache::decl(),
         ])
         .state(move |state| {
             state.put(DiscordOpsState {
                 recent_bad_requests: VecDeque::new(),
+                cached_guild: None,
+                cached_member_counts: None,
+                cached_command_permissions: HashMap::new(),
             });
 
             Ok(())
@@ -117,8 +148,19 @@ pub fn extension() -> Extension {
 
 struct DiscordOpsState {
     recent_bad_requests: VecDeque<Instant>,
+    cached_guild: Option<(Instant, GuildResult)>,
+    cached_member_counts: Option<(Instant, MemberCounts)>,
+    // kept until the vm is restarted, the update event refreshes them
+    cached_command_permissions: HashMap<Id<CommandMarker>, CommandPermissions>,
 }
 
+// scripts tend to fetch the guild a lot at startup, so keep it around for a little while
+const GUILD_CACHE_TTL: Duration = Duration::from_secs(10);
+
+// discord only updates the approximate counts every few minutes, and they're typically polled
+// on an interval to update a channel name
+const MEMBER_COUNTS_CACHE_TTL: Duration = Duration::from_secs(60);
+
 impl DiscordOpsState {
     fn add_failed_req(&mut self) {
         self.recent_bad_requests.push_back(Instant::now());
@@ -179,6 +221,13 @@ pub fn error_from_code(resp_code: StatusCode, code: u64, message: &str) -> AnyEr
         404 => not_found_error(format!("{code}: {message}")),
         403 => custom_error("DiscordPermissionsError", format!("{code}: {message}")),
         400..=499 => match code {
+            // unknown emoji, for example when fetching reactions of an emoji that was deleted
+            10014 => not_found_error(format!("{code}: {message}")),
+            // invalid webhook token, interaction tokens are only valid for 15 minutes
+            50027 => custom_error(
+                "DiscordInteractionExpiredError",
+                format!("{code}: {message}"),
+            ),
             30001..=40000 => custom_error("DiscordLimitReachedError", format!("{code}: {message}")),
             _ => custom_error("DiscordGenericErrorResponse", format!("{code}: {message}")),
         },
@@ -199,23 +248,142 @@ pub fn error_from_code(resp_code: StatusCode, code: u64, message: &str) -> AnyEr
     }
 }
 
+/// Checks the message against discord's limits so we can give a more descriptive error
+/// than discord would
+fn validate_message_fields(fields: &OpCreateMessageFields) -> Result<(), AnyError> {
+    if let Err(verrs) = validation::validate(fields) {
+        let mut outbuf = String::from("invalid message:");
+        for verr in verrs {
+            outbuf.push_str(format!("\n{verr}").as_str());
+        }
+
+        return Err(anyhow!(outbuf));
+    }
+
+    Ok(())
+}
+
+// takes the modal as passed to the op so the errors point at the fields the script set
+fn validate_modal<T: validation::Validator>(modal: &T) -> Result<(), AnyError> {
+    if let Err(verrs) = validation::validate(modal) {
+        let mut outbuf = String::from("invalid modal:");
+        for verr in verrs {
+            outbuf.push_str(format!("\n{verr}").as_str());
+        }
+
+        return Err(anyhow!(outbuf));
+    }
+
+    Ok(())
+}
+
 pub fn not_found_error(message: impl Into<Cow<'static, str>>) -> AnyError {
     custom_error("DiscordNotFoundError", message)
 }
 
 #[op]
-pub async fn op_discord_get_guild(state: Rc<RefCell<OpState>>) -> Result<Guild, AnyError> {
+pub async fn op_discord_get_guild(state: Rc<RefCell<OpState>>) -> Result<GuildResult, AnyError> {
+    count_dispatch_op(&state)?;
+
+    fetch_guild(&state)
+        .await?
+        .ok_or_else(|| anyhow::anyhow!("guild not in state"))
+}
+
+#[op]
+pub async fn op_discord_get_boost_info(
+    state: Rc<RefCell<OpState>>,
+) -> Result<GuildBoostInfo, AnyError> {
+    count_dispatch_op(&state)?;
+
+    // default to no boosts if we don't know about the guild yet
+    match fetch_guild(&state).await? {
+        Some(GuildResult::Full(guild)) => Ok(GuildBoostInfo::from(guild.as_ref())),
+        _ => Ok(GuildBoostInfo::default()),
+    }
+}
+
+#[op]
+pub async fn op_discord_get_member_counts(
+    state: Rc<RefCell<OpState>>,
+) -> Result<MemberCounts, AnyError> {
+    count_dispatch_op(&state)?;
+
+    {
+        let rc = state.borrow();
+        let dstate = rc.borrow::<DiscordOpsState>();
+        if let Some((fetched_at, counts)) = &dstate.cached_member_counts {
+            if fetched_at.elapsed() < MEMBER_COUNTS_CACHE_TTL {
+                return Ok(counts.clone());
+            }
+        }
+    }
+
     let rt_ctx = get_rt_ctx(&state);
 
-    match rt_ctx
+    // the online count needs presences, which would need the privileged GUILD_PRESENCES
+    // intent, so both counts are the estimates discord includes with the guild
+    let guild = rt_ctx
+        .discord_config
+        .client
+        .guild(rt_ctx.guild_id)
+        .with_counts(true)
+        .await
+        .map_err(|err| handle_discord_error(&state, err))?
+        .model()
+        .await?;
+
+    let counts = MemberCounts {
+        total: NotBigU64(guild.approximate_member_count.unwrap_or_default()),
+        online: guild.approximate_presence_count.map(NotBigU64),
+    };
+
+    let mut rc = state.borrow_mut();
+    let dstate = rc.borrow_mut::<DiscordOpsState>();
+    dstate.cached_member_counts = Some((Instant::now(), counts.clone()));
+
+    Ok(counts)
+}
+
+async fn fetch_guild(state: &Rc<RefCell<OpState>>) -> Result<Option<GuildResult>, AnyError> {
+    {
+        let rc = state.borrow();
+        let dstate = rc.borrow::<DiscordOpsState>();
+        if let Some((fetched_at, guild)) = &dstate.cached_guild {
+            if fetched_at.elapsed() < GUILD_CACHE_TTL {
+                return Ok(Some(guild.clone()));
+            }
+        }
+    }
+
+    let rt_ctx = get_rt_ctx(state);
+
+    let guild = match rt_ctx
         .bot_state
         .get_guild(rt_ctx.guild_id)
         .map_err(|err| anyhow::anyhow!("error calling state api: {}", err))
         .await?
     {
-        Some(c) => Ok(c.into()),
-        None => Err(anyhow::anyhow!("guild not in state")),
-    }
+        Some(c) => GuildResult::Full(Box::new(c.into())),
+        None => {
+            // fall back to what we have stored about the guild
+            let joined = rt_ctx
+                .config_store
+                .get_joined_guilds(&[rt_ctx.guild_id])
+                .await?;
+
+            match joined.into_iter().next() {
+                Some(g) => GuildResult::Stored(g.into()),
+                None => return Ok(None),
+            }
+        }
+    };
+
+    let mut rc = state.borrow_mut();
+    let dstate = rc.borrow_mut::<DiscordOpsState>();
+    dstate.cached_guild = Some((Instant::now(), guild.clone()));
+
+    Ok(Some(guild))
 }
 
 // Messages
@@ -225,6 +393,8 @@ pub async fn op_discord_get_message(
     channel_id: Id<ChannelMarker>,
     message_id: Id<MessageMarker>,
 ) -> Result<Message, AnyError> {
+    count_dispatch_op(&state)?;
+
     let rt_ctx = get_rt_ctx(&state);
 
     let channel = get_guild_channel(&state, &rt_ctx, channel_id).await?;
@@ -245,6 +415,8 @@ pub async fn op_discord_get_messages(
     state: Rc<RefCell<OpState>>,
     args: OpGetMessages,
 ) -> Result<Vec<Message>, AnyError> {
+    count_dispatch_op(&state)?;
+
     let rt_ctx = get_rt_ctx(&state);
 
     let channel = parse_get_guild_channel(&state, &rt_ctx, &args.channel_id).await?;
@@ -294,20 +466,82 @@ pub async fn op_discord_create_message(
     state: Rc<RefCell<OpState>>,
     args: OpCreateChannelMessage,
 ) -> Result<Message, AnyError> {
+    count_dispatch_op(&state)?;
+
     let rt_ctx = get_rt_ctx(&state);
 
-    let channel = parse_get_guild_channel(&state, &rt_ctx, &args.channel_id).await?;
+    validate_message_fields(&args.fields)?;
 
-    let maybe_embeds = args
-        .fields
+    create_message(&state, &rt_ctx, &args.channel_id, args.fields).await
+}
+
+// max number of channels a single broadcast can target
+const BROADCAST_MAX_CHANNELS: usize = 50;
+
+#[op]
+pub async fn op_discord_broadcast_message(
+    state: Rc<RefCell<OpState>>,
+    args: OpBroadcastMessage,
+) -> Result<Vec<BroadcastMessageResult>, AnyError> {
+    count_dispatch_op(&state)?;
+
+    let rt_ctx = get_rt_ctx(&state);
+
+    if args.channel_ids.len() > BROADCAST_MAX_CHANNELS {
+        return Err(anyhow!(
+            "can't broadcast to more than {BROADCAST_MAX_CHANNELS} channels at a time"
+        ));
+    }
+
+    validate_message_fields(&args.fields)?;
+
+    // failing to send to one channel (missing permissions for example) shouldn't stop the rest
+    let mut results = Vec::with_capacity(args.channel_ids.len());
+    for channel_id in args.channel_ids {
+        RateLimiters::broadcast_messages(&state).await?;
+
+        let result = match create_message(&state, &rt_ctx, &channel_id, args.fields.clone()).await {
+            Ok(message) => BroadcastMessageResult {
+                channel_id,
+                message: Some(message),
+                error: None,
+            },
+            Err(err) => BroadcastMessageResult {
+                channel_id,
+                message: None,
+                error: Some(format_batch_error(&err)),
+            },
+        };
+
+        results.push(result);
+    }
+
+    Ok(results)
+}
+
+fn format_batch_error(err: &AnyError) -> String {
+    match deno_core::error::get_custom_error_class(err) {
+        Some(class) => format!("{class}: {err}"),
+        None => err.to_string(),
+    }
+}
+
+async fn create_message(
+    state: &Rc<RefCell<OpState>>,
+    rt_ctx: &RuntimeContext,
+    channel_id: &str,
+    fields: OpCreateMessageFields,
+) -> Result<Message, AnyError> {
+    let channel = parse_get_guild_channel(state, rt_ctx, channel_id).await?;
+
+    let maybe_embeds = fields
         .embeds
         .unwrap_or_default()
         .into_iter()
         .map(Into::into)
         .collect::<Vec<_>>();
 
-    let components = args
-        .fields
+    let components = fields
         .components
         .unwrap_or_default()
         .into_iter()
@@ -321,16 +555,162 @@ pub async fn op_discord_create_message(
         .embeds(&maybe_embeds)?
         .components(&components)?;
 
-    if let Some(content) = &args.fields.content {
+    if let Some(content) = &fields.content {
         mc = mc.content(content)?
     }
 
-    let mentions = args.fields.allowed_mentions.map(Into::into);
+    let mentions = fields.allowed_mentions.map(Into::into);
     if mentions.is_some() {
         mc = mc.allowed_mentions(mentions.as_ref());
     }
 
     Ok(mc
+        .await
+        .map_err(|err| handle_discord_error(state, err))?
+        .model()
+        .await?
+        .into())
+}
+
+const POLL_MAX_ANSWERS: usize = 10;
+const POLL_QUESTION_MAX_LEN: usize = 300;
+const POLL_ANSWER_MAX_LEN: usize = 55;
+/// Max poll duration discord allows, 32 days
+const POLL_MAX_DURATION_HOURS: u32 = 768;
+
+// twilight doesn't know about polls yet, so the requests are made with these instead of the
+// twilight models
+#[derive(serde::Serialize)]
+struct CreatePollMessage<'a> {
+    poll: CreatePollBody<'a>,
+}
+
+#[derive(serde::Serialize)]
+struct CreatePollBody<'a> {
+    question: CreatePollMedia<'a>,
+    answers: Vec<CreatePollAnswer<'a>>,
+    duration: u32,
+    allow_multiselect: bool,
+}
+
+#[derive(serde::Serialize)]
+struct CreatePollAnswer<'a> {
+    poll_media: CreatePollMedia<'a>,
+}
+
+#[derive(serde::Serialize)]
+struct CreatePollMedia<'a> {
+    text: &'a str,
+}
+
+#[derive(serde::Deserialize)]
+struct PollMessage {
+    poll: Option<Poll>,
+}
+
+#[derive(serde::Deserialize)]
+struct Poll {
+    question: PollMedia,
+    answers: Vec<PollAnswer>,
+    expiry: Option<String>,
+    results: Option<PollResultCounts>,
+}
+
+#[derive(serde::Deserialize)]
+struct PollAnswer {
+    answer_id: u32,
+    poll_media: PollMedia,
+}
+
+#[derive(serde::Deserialize)]
+struct PollMedia {
+    text: Option<String>,
+}
+
+#[derive(serde::Deserialize)]
+struct PollResultCounts {
+    is_finalized: bool,
+    answer_counts: Vec<PollAnswerCount>,
+}
+
+#[derive(serde::Deserialize)]
+struct PollAnswerCount {
+    id: u32,
+    count: u32,
+}
+
+fn validate_poll(args: &OpCreatePoll) -> Result<(), AnyError> {
+    if args.question.is_empty() || args.question.chars().count() > POLL_QUESTION_MAX_LEN {
+        return Err(anyhow!(
+            "poll question has to be between 1 and {POLL_QUESTION_MAX_LEN} characters"
+        ));
+    }
+
+    if args.answers.is_empty() || args.answers.len() > POLL_MAX_ANSWERS {
+        return Err(anyhow!(
+            "polls need between 1 and {POLL_MAX_ANSWERS} answers"
+        ));
+    }
+
+    if args
+        .answers
+        .iter()
+        .any(|answer| answer.is_empty() || answer.chars().count() > POLL_ANSWER_MAX_LEN)
+    {
+        return Err(anyhow!(
+            "poll answers have to be between 1 and {POLL_ANSWER_MAX_LEN} characters"
+        ));
+    }
+
+    if args.duration_hours < 1 || args.duration_hours > POLL_MAX_DURATION_HOURS {
+        return Err(anyhow!(
+            "poll duration has to be between 1 and {POLL_MAX_DURATION_HOURS} hours"
+        ));
+    }
+
+    Ok(())
+}
+
+#[op]
+pub async fn op_discord_create_poll(
+    state: Rc<RefCell<OpState>>,
+    channel_id: Id<ChannelMarker>,
+    args: OpCreatePoll,
+) -> Result<Message, AnyError> {
+    count_dispatch_op(&state)?;
+
+    validate_poll(&args)?;
+
+    let rt_ctx = get_rt_ctx(&state);
+    let channel = get_guild_channel(&state, &rt_ctx, channel_id).await?;
+
+    let body = CreatePollMessage {
+        poll: CreatePollBody {
+            question: CreatePollMedia {
+                text: &args.question,
+            },
+            answers: args
+                .answers
+                .iter()
+                .map(|answer| CreatePollAnswer {
+                    poll_media: CreatePollMedia { text: answer },
+                })
+                .collect(),
+            duration: args.duration_hours,
+            allow_multiselect: args.allow_multiselect.unwrap_or_default(),
+        },
+    };
+
+    let request = Request::builder(&Route::CreateMessage {
+        channel_id: channel.id.get(),
+    })
+    .json(&body)?
+    .build();
+
+    Ok(rt_ctx
+        .discord_config
+        .client
+        .request::<twilight_model::channel::Message>(request)
         .await
         .map_err(|err| handle_discord_error(&state, err))?
         .model()
@@ -338,14 +718,135 @@ pub async fn op_discord_create_message(
         .into())
 }
 
+#[op]
+pub async fn op_discord_get_poll_results(
+    state: Rc<RefCell<OpState>>,
+    channel_id: Id<ChannelMarker>,
+    message_id: Id<MessageMarker>,
+) -> Result<PollResults, AnyError> {
+    count_dispatch_op(&state)?;
+
+    let rt_ctx = get_rt_ctx(&state);
+    let channel = get_guild_channel(&state, &rt_ctx, channel_id).await?;
+
+    let request = Request::from_route(&Route::GetMessage {
+        channel_id: channel.id.get(),
+        message_id: message_id.get(),
+    });
+
+    let message = rt_ctx
+        .discord_config
+        .client
+        .request::<PollMessage>(request)
+        .await
+        .map_err(|err| handle_discord_error(&state, err))?
+        .model()
+        .await?;
+
+    let poll = if let Some(poll) = message.poll {
+        poll
+    } else {
+        return Err(not_found_error(format!(
+            "message `{message_id}` is not a poll"
+        )));
+    };
+
+    // discord leaves out the counts until someone has voted
+    let (is_finalized, counts) = match poll.results {
+        Some(results) => (results.is_finalized, results.answer_counts),
+        None => (false, Vec::new()),
+    };
+
+    Ok(PollResults {
+        question: poll.question.text.unwrap_or_default(),
+        answers: poll
+            .answers
+            .into_iter()
+            .map(|answer| PollAnswerResult {
+                answer_id: answer.answer_id,
+                text: answer.poll_media.text.unwrap_or_default(),
+                votes: counts
+                    .iter()
+                    .find(|count| count.id == answer.answer_id)
+                    .map(|count| count.count)
+                    .unwrap_or_default(),
+            })
+            .collect(),
+        is_finalized,
+        expires_at: poll.expiry,
+    })
+}
+
 #[op]
 pub async fn op_discord_edit_message(
     state: Rc<RefCell<OpState>>,
     args: OpEditChannelMessage,
 ) -> Result<Message, AnyError> {
+    count_dispatch_op(&state)?;
+
     let rt_ctx = get_rt_ctx(&state);
 
-    let channel = parse_get_guild_channel(&state, &rt_ctx, &args.channel_id).await?;
+    validate_message_fields(&args.fields)?;
+
+    edit_message(&state, &rt_ctx, args).await
+}
+
+// max number of messages a single batch edit can target
+const EDIT_MESSAGES_MAX: usize = 50;
+
+#[op]
+pub async fn op_discord_edit_messages(
+    state: Rc<RefCell<OpState>>,
+    edits: Vec<OpEditChannelMessage>,
+) -> Result<Vec<EditMessageResult>, AnyError> {
+    count_dispatch_op(&state)?;
+
+    let rt_ctx = get_rt_ctx(&state);
+
+    if edits.len() > EDIT_MESSAGES_MAX {
+        return Err(anyhow!(
+            "can't edit more than {EDIT_MESSAGES_MAX} messages at a time"
+        ));
+    }
+
+    for edit in &edits {
+        validate_message_fields(&edit.fields)?;
+    }
+
+    // failing to edit one message (deleted in the meantime for example) shouldn't stop the rest
+    let mut results = Vec::with_capacity(edits.len());
+    for edit in edits {
+        RateLimiters::batch_message_edits(&state).await?;
+
+        let channel_id = edit.channel_id.clone();
+        let message_id = edit.message_id.clone();
+        let result = match edit_message(&state, &rt_ctx, edit).await {
+            Ok(message) => EditMessageResult {
+                channel_id,
+                message_id,
+                message: Some(message),
+                error: None,
+            },
+            Err(err) => EditMessageResult {
+                channel_id,
+                message_id,
+                message: None,
+                error: Some(format_batch_error(&err)),
+            },
+        };
+
+        results.push(result);
+    }
+
+    Ok(results)
+}
+
+async fn edit_message(
+    state: &Rc<RefCell<OpState>>,
+    rt_ctx: &RuntimeContext,
+    args: OpEditChannelMessage,
+) -> Result<Message, AnyError> {
+    let channel = parse_get_guild_channel(state, rt_ctx, &args.channel_id).await?;
     let message_id = parse_str_snowflake_id(&args.message_id)?;
 
     let maybe_embeds = args
@@ -376,7 +877,7 @@ pub async fn op_discord_edit_message(
 
     Ok(mc
         .await
-        .map_err(|err| handle_discord_error(&state, err))?
+        .map_err(|err| handle_discord_error(state, err))?
         .model()
         .await?
         .into())
@@ -388,6 +889,8 @@ pub async fn op_discord_crosspost_message(
     channel_id: Id<ChannelMarker>,
     message_id: Id<MessageMarker>,
 ) -> Result<(), AnyError> {
+    count_dispatch_op(&state)?;
+
     let ctx = get_rt_ctx(&state);
     get_guild_channel(&state, &ctx, channel_id).await?;
 
@@ -405,8 +908,18 @@ pub async fn op_discord_interaction_callback(
     state: Rc<RefCell<OpState>>,
     args: InteractionCallback,
 ) -> Result<(), AnyError> {
+    count_dispatch_op(&state)?;
+
     let rt_ctx = get_rt_ctx(&state);
 
+    match &args.data {
+        InteractionResponse::ChannelMessageWithSource(data)
+        | InteractionResponse::DeferredChannelMessageWithSource(data)
+        | InteractionResponse::UpdateMessage(data) => validate_message_fields(&data.fields)?,
+        InteractionResponse::Modal(data) => validate_modal(data)?,
+        _ => {}
+    }
+
     let client = rt_ctx.discord_config.interaction_client();
     client
         .create_response(
@@ -420,11 +933,41 @@ pub async fn op_discord_interaction_callback(
     Ok(())
 }
 
+// the submission is dispatched as a modal submit interaction with the custom id of the modal
+#[op]
+pub async fn op_discord_show_modal(
+    state: Rc<RefCell<OpState>>,
+    interaction_id: String,
+    interaction_token: String,
+    modal: OpShowModal,
+) -> Result<(), AnyError> {
+    count_dispatch_op(&state)?;
+
+    let rt_ctx = get_rt_ctx(&state);
+
+    validate_modal(&modal)?;
+    let modal = ModalCallbackData::from(modal);
+
+    let client = rt_ctx.discord_config.interaction_client();
+    client
+        .create_response(
+            Id::from_str(&interaction_id)?,
+            &interaction_token,
+            &InteractionResponse::Modal(modal).into(),
+        )
+        .await
+        .map_err(|err| handle_discord_error(&state, err))?;
+
+    Ok(())
+}
+
 #[op]
 pub async fn op_discord_interaction_get_original_response(
     state: Rc<RefCell<OpState>>,
     token: String,
 ) -> Result<Message, AnyError> {
+    count_dispatch_op(&state)?;
+
     let rt_ctx = get_rt_ctx(&state);
 
     let client = rt_ctx.discord_config.interaction_client();
@@ -442,6 +985,8 @@ pub async fn op_discord_interaction_edit_original_response(
     state: Rc<RefCell<OpState>>,
     args: OpCreateFollowUpMessage,
 ) -> Result<Message, AnyError> {
+    count_dispatch_op(&state)?;
+
     let rt_ctx = get_rt_ctx(&state);
 
     let maybe_embeds = args
@@ -449,6 +994,8 @@ pub async fn op_discord_interaction_edit_original_response(
         .embeds
         .map(|inner| inner.into_iter().map(Into::into).collect::<Vec<_>>());
 
+    validate_message_fields(&args.fields)?;
+
     let components = args
         .fields
         .components
@@ -481,6 +1028,8 @@ pub async fn op_discord_interaction_delete_original(
     state: Rc<RefCell<OpState>>,
     token: String,
 ) -> Result<(), AnyError> {
+    count_dispatch_op(&state)?;
+
     let rt_ctx = get_rt_ctx(&state);
 
     let client = rt_ctx.discord_config.interaction_client();
@@ -494,6 +1043,8 @@ pub async fn op_discord_interaction_get_followup_message(
     token: String,
     id: Id<MessageMarker>,
 ) -> Result<Message, AnyError> {
+    count_dispatch_op(&state)?;
+
     let rt_ctx = get_rt_ctx(&state);
 
     let client = rt_ctx.discord_config.interaction_client();
@@ -511,6 +1062,8 @@ pub async fn op_discord_interaction_followup_message(
     state: Rc<RefCell<OpState>>,
     args: OpCreateFollowUpMessage,
 ) -> Result<Message, AnyError> {
+    count_dispatch_op(&state)?;
+
     let rt_ctx = get_rt_ctx(&state);
 
     let maybe_embeds = args
@@ -521,6 +1074,8 @@ pub async fn op_discord_interaction_followup_message(
         .map(Into::into)
         .collect::<Vec<_>>();
 
+    validate_message_fields(&args.fields)?;
+
     let components = args
         .fields
         .components
@@ -563,6 +1118,8 @@ pub async fn op_discord_interaction_edit_followup_message(
     message_id: Id<MessageMarker>,
     args: OpCreateFollowUpMessage,
 ) -> Result<(), AnyError> {
+    count_dispatch_op(&state)?;
+
     let rt_ctx = get_rt_ctx(&state);
 
     let maybe_embeds = args
@@ -570,6 +1127,8 @@ pub async fn op_discord_interaction_edit_followup_message(
         .embeds
         .map(|inner| inner.into_iter().map(Into::into).collect::<Vec<_>>());
 
+    validate_message_fields(&args.fields)?;
+
     let components = args
         .fields
         .components
@@ -600,6 +1159,8 @@ pub async fn op_discord_interaction_delete_followup_message(
     token: String,
     id: Id<MessageMarker>,
 ) -> Result<(), AnyError> {
+    count_dispatch_op(&state)?;
+
     let rt_ctx = get_rt_ctx(&state);
 
     let client = rt_ctx.discord_config.interaction_client();
@@ -615,6 +1176,8 @@ pub async fn op_discord_delete_message(
     state: Rc<RefCell<OpState>>,
     args: OpDeleteMessage,
 ) -> Result<(), AnyError> {
+    count_dispatch_op(&state)?;
+
     let rt_ctx = get_rt_ctx(&state);
 
     let channel = parse_get_guild_channel(&state, &rt_ctx, &args.channel_id).await?;
@@ -635,6 +1198,8 @@ pub async fn op_discord_bulk_delete_messages(
     state: Rc<RefCell<OpState>>,
     args: OpDeleteMessagesBulk,
 ) -> Result<(), AnyError> {
+    count_dispatch_op(&state)?;
+
     let rt_ctx = get_rt_ctx(&state);
 
     let channel = parse_get_guild_channel(&state, &rt_ctx, &args.channel_id).await?;
@@ -661,6 +1226,8 @@ pub async fn op_discord_get_role(
     state: Rc<RefCell<OpState>>,
     role_id: Id<RoleMarker>,
 ) -> Result<runtime_models::discord::role::Role, AnyError> {
+    count_dispatch_op(&state)?;
+
     let rt_ctx = get_rt_ctx(&state);
 
     match rt_ctx.bot_state.get_role(rt_ctx.guild_id, role_id).await? {
@@ -673,12 +1240,66 @@ pub async fn op_discord_get_role(
 pub async fn op_discord_get_roles(
     state: Rc<RefCell<OpState>>,
 ) -> Result<Vec<runtime_models::discord::role::Role>, AnyError> {
+    count_dispatch_op(&state)?;
+
     let rt_ctx = get_rt_ctx(&state);
 
     let roles = rt_ctx.bot_state.get_roles(rt_ctx.guild_id).await?;
     Ok(roles.into_iter().map(Into::into).collect())
 }
 
+#[op]
+pub async fn op_discord_list_roles(
+    state: Rc<RefCell<OpState>>,
+) -> Result<Vec<runtime_models::discord::role::Role>, AnyError> {
+    count_dispatch_op(&state)?;
+
+    let rt_ctx = get_rt_ctx(&state);
+
+    let mut roles = rt_ctx.bot_state.get_roles(rt_ctx.guild_id).await?;
+
+    // highest role first, same as discord displays them
+    roles.sort_by(|a, b| b.position.cmp(&a.position).then(a.id.cmp(&b.id)));
+
+    Ok(roles.into_iter().map(Into::into).collect())
+}
+
+#[op]
+pub async fn op_discord_bot_highest_role(
+    state: Rc<RefCell<OpState>>,
+) -> Result<runtime_models::discord::role::Role, AnyError> {
+    count_dispatch_op(&state)?;
+
+    let rt_ctx = get_rt_ctx(&state);
+
+    let guild_roles = rt_ctx.bot_state.get_roles(rt_ctx.guild_id).await?;
+    let highest = bot_highest_role(&state, &rt_ctx, guild_roles).await?;
+    Ok(highest.into())
+}
+
+async fn bot_highest_role(
+    state: &Rc<RefCell<OpState>>,
+    rt_ctx: &RuntimeContext,
+    guild_roles: Vec<twilight_model::guild::Role>,
+) -> Result<twilight_model::guild::Role, AnyError> {
+    let member = rt_ctx
+        .discord_config
+        .client
+        .guild_member(rt_ctx.guild_id, rt_ctx.discord_config.bot_user.id)
+        .await
+        .map_err(|err| handle_discord_error(state, err))?
+        .model()
+        .await?;
+
+    // the everyone role (same id as the guild) is the highest if the bot has no other roles
+    let everyone_role_id = rt_ctx.guild_id.cast::<RoleMarker>();
+    guild_roles
+        .into_iter()
+        .filter(|r| r.id == everyone_role_id || member.roles.contains(&r.id))
+        .max_by(|a, b| a.position.cmp(&b.position).then(b.id.cmp(&a.id)))
+        .ok_or_else(|| anyhow!("guild roles not in state"))
+}
+
 // Reactions
 #[op]
 pub async fn op_discord_create_reaction(
@@ -686,6 +1307,8 @@ pub async fn op_discord_create_reaction(
     (channel_id, message_id): (Id<ChannelMarker>, Id<MessageMarker>),
     emoji: SendEmoji,
 ) -> Result<(), AnyError> {
+    count_dispatch_op(&state)?;
+
     let rt_ctx = get_rt_ctx(&state);
 
     // ensure the provided channel is on the ctx guild
@@ -707,6 +1330,8 @@ pub async fn op_discord_delete_own_reaction(
     (channel_id, message_id): (Id<ChannelMarker>, Id<MessageMarker>),
     emoji: SendEmoji,
 ) -> Result<(), AnyError> {
+    count_dispatch_op(&state)?;
+
     let rt_ctx = get_rt_ctx(&state);
 
     // ensure the provided channel is on the ctx guild
@@ -728,6 +1353,8 @@ pub async fn op_discord_delete_user_reaction(
     (channel_id, message_id, user_id): (Id<ChannelMarker>, Id<MessageMarker>, Id<UserMarker>),
     emoji: SendEmoji,
 ) -> Result<(), AnyError> {
+    count_dispatch_op(&state)?;
+
     let rt_ctx = get_rt_ctx(&state);
 
     // ensure the provided channel is on the ctx guild
@@ -749,6 +1376,8 @@ pub async fn op_discord_get_reactions(
     (channel_id, message_id): (Id<ChannelMarker>, Id<MessageMarker>),
     fields: GetReactionsFields,
 ) -> Result<Vec<User>, AnyError> {
+    count_dispatch_op(&state)?;
+
     let rt_ctx = get_rt_ctx(&state);
 
     let _ = get_guild_channel(&state, &rt_ctx, channel_id).await?;
@@ -764,7 +1393,7 @@ pub async fn op_discord_get_reactions(
         req = req.after(parse_str_snowflake_id(after_str)?.cast())
     }
     if let Some(limit) = fields.limit {
-        req = req.limit(limit as u16)?;
+        req = req.limit(limit.clamp(1, 100) as u16)?;
     }
 
     Ok(req
@@ -782,6 +1411,8 @@ pub async fn op_discord_delete_all_reactions(
     state: Rc<RefCell<OpState>>,
     (channel_id, message_id): (Id<ChannelMarker>, Id<MessageMarker>),
 ) -> Result<(), AnyError> {
+    count_dispatch_op(&state)?;
+
     let rt_ctx = get_rt_ctx(&state);
 
     let _ = get_guild_channel(&state, &rt_ctx, channel_id).await?;
@@ -802,6 +1433,8 @@ pub async fn op_discord_delete_all_reactions_for_emoji(
     (channel_id, message_id): (Id<ChannelMarker>, Id<MessageMarker>),
     emoji: SendEmoji,
 ) -> Result<(), AnyError> {
+    count_dispatch_op(&state)?;
+
     let rt_ctx = get_rt_ctx(&state);
 
     let _ = get_guild_channel(&state, &rt_ctx, channel_id).await?;
@@ -822,6 +1455,8 @@ pub async fn op_discord_get_channel(
     state: Rc<RefCell<OpState>>,
     channel_id_str: String,
 ) -> Result<runtime_models::internal::channel::GuildChannel, AnyError> {
+    count_dispatch_op(&state)?;
+
     let rt_ctx = get_rt_ctx(&state);
 
     let channel = parse_get_guild_channel(&state, &rt_ctx, &channel_id_str).await?;
@@ -832,18 +1467,61 @@ pub async fn op_discord_get_channel(
 pub async fn op_discord_get_channels(
     state: Rc<RefCell<OpState>>,
 ) -> Result<Vec<runtime_models::internal::channel::GuildChannel>, AnyError> {
+    count_dispatch_op(&state)?;
+
     let rt_ctx = get_rt_ctx(&state);
 
     let channels = rt_ctx.bot_state.get_channels(rt_ctx.guild_id).await?;
     Ok(channels.into_iter().map(Into::into).collect())
 }
 
+#[op]
+pub async fn op_discord_list_channels(
+    state: Rc<RefCell<OpState>>,
+    type_filter: Option<ChannelType>,
+) -> Result<Vec<runtime_models::internal::channel::GuildChannel>, AnyError> {
+    count_dispatch_op(&state)?;
+
+    let rt_ctx = get_rt_ctx(&state);
+
+    let mut channels = rt_ctx.bot_state.get_channels(rt_ctx.guild_id).await?;
+
+    // sort them the same way discord displays them, channels without a category first
+    // then each category followed by the channels inside it
+    let category_positions = channels
+        .iter()
+        .filter(|v| v.kind == twilight_model::channel::ChannelType::GuildCategory)
+        .map(|v| (v.id, v.position.unwrap_or_default()))
+        .collect::<HashMap<_, _>>();
+
+    channels.sort_by_key(|v| {
+        let position = v.position.unwrap_or_default();
+        if v.kind == twilight_model::channel::ChannelType::GuildCategory {
+            (Some(position), false, 0, v.id)
+        } else {
+            let category_position = v
+                .parent_id
+                .and_then(|parent| category_positions.get(&parent).copied());
+            (category_position, true, position, v.id)
+        }
+    });
+
+    if let Some(filter) = type_filter {
+        let filter = twilight_model::channel::ChannelType::from(filter);
+        channels.retain(|v| v.kind == filter);
+    }
+
+    Ok(channels.into_iter().map(Into::into).collect())
+}
+
 #[op]
 pub async fn op_discord_edit_channel(
     state: Rc<RefCell<OpState>>,
     channel_id: Id<ChannelMarker>,
     params: EditChannel,
 ) -> Result<runtime_models::internal::channel::GuildChannel, AnyError> {
+    count_dispatch_op(&state)?;
+
     let rt_ctx = get_rt_ctx(&state);
 
     // ensure the channel exists on the guild
@@ -865,6 +1543,8 @@ pub async fn op_discord_create_channel(
     state: Rc<RefCell<OpState>>,
     params: CreateChannel,
 ) -> Result<runtime_models::internal::channel::GuildChannel, AnyError> {
+    count_dispatch_op(&state)?;
+
     let rt_ctx = get_rt_ctx(&state);
 
     let mut overwrites = Vec::new();
@@ -886,6 +1566,8 @@ pub async fn op_discord_delete_channel(
     state: Rc<RefCell<OpState>>,
     channel_id: Id<ChannelMarker>,
 ) -> Result<runtime_models::internal::channel::GuildChannel, AnyError> {
+    count_dispatch_op(&state)?;
+
     let rt_ctx = get_rt_ctx(&state);
 
     // ensure the channel exists on the guild
@@ -901,36 +1583,136 @@ pub async fn op_discord_delete_channel(
         .into())
 }
 
+/// Max slowmode discord allows, 6 hours
+const MAX_SLOWMODE_SECONDS: u16 = 21600;
+
+#[op]
+pub async fn op_discord_set_slowmode(
+    state: Rc<RefCell<OpState>>,
+    channel_id: Id<ChannelMarker>,
+    seconds: u32,
+) -> Result<runtime_models::internal::channel::GuildChannel, AnyError> {
+    count_dispatch_op(&state)?;
+
+    let seconds = match u16::try_from(seconds) {
+        Ok(seconds) if seconds <= MAX_SLOWMODE_SECONDS => seconds,
+        _ => {
+            return Err(anyhow!(
+                "slowmode has to be between 0 and {MAX_SLOWMODE_SECONDS} seconds"
+            ))
+        }
+    };
+
+    let rt_ctx = get_rt_ctx(&state);
+
+    // ensure the channel exists on the guild
+    get_guild_channel(&state, &rt_ctx, channel_id).await?;
+
+    let resp = rt_ctx
+        .discord_config
+        .client
+        .update_channel(channel_id)
+        .rate_limit_per_user(seconds)?
+        .await;
+
+    match resp {
+        Ok(resp) => Ok(resp.model().await?.into()),
+        Err(err) => {
+            let missing_perms = matches!(
+                err.kind(),
+                ErrorType::Response { status, .. } if status.get() == 403
+            );
+
+            let err = handle_discord_error(&state, err);
+            if missing_perms {
+                Err(custom_error(
+                    "DiscordPermissionsError",
+                    "the bot needs the Manage Channels permission in the channel to set its \
+                     slowmode",
+                ))
+            } else {
+                Err(err)
+            }
+        }
+    }
+}
+
 #[op]
 pub async fn op_discord_update_channel_permission(
     state: Rc<RefCell<OpState>>,
     channel_id: Id<ChannelMarker>,
     permission_overwrite: PermissionOverwrite,
 ) -> Result<(), AnyError> {
+    count_dispatch_op(&state)?;
+
     let rt_ctx = get_rt_ctx(&state);
 
+    let allow = parse_permission_bits("allow", &permission_overwrite.allow_raw)?;
+    let deny = parse_permission_bits("deny", &permission_overwrite.deny_raw)?;
+    if allow.intersects(deny) {
+        return Err(anyhow!(
+            "the same permissions can't be both allowed and denied: {}",
+            (allow & deny).bits()
+        ));
+    }
+
     // ensure the channel exists on the guild
     get_guild_channel(&state, &rt_ctx, channel_id).await?;
 
-    let conv = permission_overwrite
-        .try_into()
-        .map_err(|_| anyhow!("invalid id"))?;
+    let conv: twilight_model::http::permission_overwrite::PermissionOverwrite =
+        permission_overwrite
+            .try_into()
+            .map_err(|_| anyhow!("invalid id"))?;
 
     rt_ctx
         .discord_config
         .client
         .update_channel_permission(channel_id, &conv)
-        .await?;
+        .await
+        .map_err(|err| handle_permission_overwrite_error(&state, err))?;
 
     Ok(())
 }
 
+// bits this version doesn't know about yet are dropped rather than rejected
+fn parse_permission_bits(field: &str, raw: &str) -> Result<Permissions, AnyError> {
+    let bits: u64 = raw
+        .parse()
+        .map_err(|_| anyhow!("invalid {field} permissions: {raw}"))?;
+
+    Ok(Permissions::from_bits_truncate(bits))
+}
+
+// discord only responds with a plain 403, so point out the permission that is most likely missing
+fn handle_permission_overwrite_error(
+    state: &Rc<RefCell<OpState>>,
+    err: twilight_http::Error,
+) -> AnyError {
+    let missing_perms = matches!(
+        err.kind(),
+        ErrorType::Response { status, .. } if status.get() == 403
+    );
+
+    let err = handle_discord_error(state, err);
+    if missing_perms {
+        custom_error(
+            "DiscordPermissionsError",
+            "the bot needs the Manage Roles permission in the channel to change its permission \
+             overwrites, and can only allow or deny permissions it has itself",
+        )
+    } else {
+        err
+    }
+}
+
 #[op]
 pub async fn op_discord_delete_channel_permission(
     state: Rc<RefCell<OpState>>,
     channel_id: Id<ChannelMarker>,
     (kind, overwrite_id): (PermissionOverwriteType, Id<GenericMarker>),
 ) -> Result<(), AnyError> {
+    count_dispatch_op(&state)?;
+
     let rt_ctx = get_rt_ctx(&state);
 
     // ensure the channel exists on the guild
@@ -941,10 +1723,11 @@ pub async fn op_discord_delete_channel_permission(
         .client
         .delete_channel_permission(channel_id);
 
-    match kind {
-        PermissionOverwriteType::Member => req.member(overwrite_id.cast()).await?,
-        PermissionOverwriteType::Role => req.role(overwrite_id.cast()).await?,
+    let res = match kind {
+        PermissionOverwriteType::Member => req.member(overwrite_id.cast()).await,
+        PermissionOverwriteType::Role => req.role(overwrite_id.cast()).await,
     };
+    res.map_err(|err| handle_permission_overwrite_error(&state, err))?;
 
     Ok(())
 }
@@ -955,6 +1738,8 @@ pub async fn op_discord_get_channel_pins(
     state: Rc<RefCell<OpState>>,
     channel_id: Id<ChannelMarker>,
 ) -> Result<Vec<Message>, AnyError> {
+    count_dispatch_op(&state)?;
+
     let rt_ctx = get_rt_ctx(&state);
 
     // ensure the provided channel is on the guild
@@ -978,6 +1763,8 @@ pub async fn op_discord_create_pin(
     channel_id: Id<ChannelMarker>,
     message_id: Id<MessageMarker>,
 ) -> Result<(), AnyError> {
+    count_dispatch_op(&state)?;
+
     let rt_ctx = get_rt_ctx(&state);
 
     // ensure the provided channel is on the guild
@@ -999,6 +1786,8 @@ pub async fn op_discord_delete_pin(
     channel_id: Id<ChannelMarker>,
     message_id: Id<MessageMarker>,
 ) -> Result<(), AnyError> {
+    count_dispatch_op(&state)?;
+
     let rt_ctx = get_rt_ctx(&state);
 
     // ensure the provided channel is on the guild
@@ -1014,12 +1803,67 @@ pub async fn op_discord_delete_pin(
     Ok(())
 }
 
+#[op]
+pub async fn op_discord_trigger_typing(
+    state: Rc<RefCell<OpState>>,
+    channel_id: Id<ChannelMarker>,
+) -> Result<(), AnyError> {
+    count_dispatch_op(&state)?;
+
+    let rt_ctx = get_rt_ctx(&state);
+
+    // ensure the provided channel is on the guild
+    get_guild_channel(&state, &rt_ctx, channel_id).await?;
+
+    rt_ctx
+        .discord_config
+        .client
+        .create_typing_trigger(channel_id)
+        .await
+        .map_err(|err| handle_discord_error(&state, err))?;
+
+    Ok(())
+}
+
 // Members
+#[op]
+pub async fn op_discord_get_user(
+    state: Rc<RefCell<OpState>>,
+    user_id: String,
+) -> Result<User, AnyError> {
+    count_dispatch_op(&state)?;
+
+    let rt_ctx = get_rt_ctx(&state);
+
+    let user_id: Id<UserMarker> = if let Some(id) = user_id.parse().ok().and_then(Id::new_checked) {
+        id
+    } else {
+        return Err(not_found_error(format!("user `{user_id}` not found")));
+    };
+
+    if let Some(user) = rt_ctx.bot_state.get_user(user_id).await? {
+        return Ok(user.into());
+    }
+
+    // not in the cache, for example if they're not in any of the bot's guilds
+    Ok(rt_ctx
+        .discord_config
+        .client
+        .user(user_id)
+        .await
+        .map_err(|err| handle_discord_error(&state, err))?
+        .model()
+        .await?
+        .into())
+}
+
 #[op]
 pub async fn op_discord_get_members(
     state: Rc<RefCell<OpState>>,
     user_ids: Vec<String>,
 ) -> Result<Vec<Option<runtime_models::internal::member::Member>>, AnyError> {
+    count_dispatch_op(&state)?;
+
     let rt_ctx = get_rt_ctx(&state);
 
     if user_ids.len() > 100 {
@@ -1072,12 +1916,71 @@ pub async fn op_discord_get_members(
     Ok(res)
 }
 
+#[op]
+pub async fn op_discord_get_member_detail(
+    state: Rc<RefCell<OpState>>,
+    user_id: Id<UserMarker>,
+) -> Result<MemberDetail, AnyError> {
+    count_dispatch_op(&state)?;
+
+    let rt_ctx = get_rt_ctx(&state);
+
+    // unknown members are a 404, so this gives a not found error for users that aren't members
+    let member = rt_ctx
+        .discord_config
+        .client
+        .guild_member(rt_ctx.guild_id, user_id)
+        .await
+        .map_err(|err| handle_discord_error(&state, err))?
+        .model()
+        .await?;
+
+    Ok(member.into())
+}
+
+#[op]
+pub async fn op_discord_member_pending(
+    state: Rc<RefCell<OpState>>,
+    user_id: Id<UserMarker>,
+) -> Result<bool, AnyError> {
+    count_dispatch_op(&state)?;
+
+    let rt_ctx = get_rt_ctx(&state);
+
+    match rt_ctx
+        .discord_config
+        .client
+        .guild_member(rt_ctx.guild_id, user_id)
+        .await
+    {
+        Ok(resp) => Ok(resp.model().await?.pending),
+        Err(err) => {
+            if matches!(
+                err.kind(),
+                ErrorType::Response {
+                    // 10007 is unknown member
+                    error: ApiError::General(GeneralApiError { code: 10007, .. }),
+                    ..
+                },
+            ) {
+                return Err(not_found_error(format!(
+                    "user `{user_id}` is not a member of this server"
+                )));
+            }
+
+            Err(handle_discord_error(&state, err))
+        }
+    }
+}
+
 #[op]
 pub async fn op_discord_add_member_role(
     state: Rc<RefCell<OpState>>,
     user_id: Id<UserMarker>,
     role_id: Id<RoleMarker>,
 ) -> Result<(), AnyError> {
+    count_dispatch_op(&state)?;
+
     let rt_ctx = get_rt_ctx(&state);
 
     rt_ctx
@@ -1096,6 +1999,8 @@ pub async fn op_discord_remove_member_role(
     user_id: Id<UserMarker>,
     role_id: Id<RoleMarker>,
 ) -> Result<(), AnyError> {
+    count_dispatch_op(&state)?;
+
     let rt_ctx = get_rt_ctx(&state);
 
     rt_ctx
@@ -1114,6 +2019,8 @@ pub async fn op_discord_update_member(
     user_id: Id<UserMarker>,
     fields: UpdateGuildMemberFields,
 ) -> Result<runtime_models::internal::member::Member, AnyError> {
+    count_dispatch_op(&state)?;
+
     let rt_ctx = get_rt_ctx(&state);
     let mut builder = rt_ctx
         .discord_config
@@ -1162,6 +2069,8 @@ pub async fn op_discord_create_ban(
     user_id: Id<UserMarker>,
     extras: CreateBanFields,
 ) -> Result<(), AnyError> {
+    count_dispatch_op(&state)?;
+
     let rt_ctx = get_rt_ctx(&state);
 
     let mut req = rt_ctx
@@ -1187,6 +2096,8 @@ pub async fn op_discord_get_ban(
     state: Rc<RefCell<OpState>>,
     user_id: Id<UserMarker>,
 ) -> Result<Ban, AnyError> {
+    count_dispatch_op(&state)?;
+
     let rt_ctx = get_rt_ctx(&state);
 
     let result = rt_ctx
@@ -1203,6 +2114,8 @@ pub async fn op_discord_get_ban(
 
 #[op]
 pub async fn op_discord_get_bans(state: Rc<RefCell<OpState>>) -> Result<Vec<Ban>, AnyError> {
+    count_dispatch_op(&state)?;
+
     let rt_ctx = get_rt_ctx(&state);
 
     let result = rt_ctx
@@ -1223,6 +2136,8 @@ pub async fn op_discord_delete_ban(
     user_id: Id<UserMarker>,
     extras: AuditLogExtras,
 ) -> Result<(), AnyError> {
+    count_dispatch_op(&state)?;
+
     let rt_ctx = get_rt_ctx(&state);
 
     let mut req = rt_ctx
@@ -1246,6 +2161,8 @@ pub async fn op_discord_remove_member(
     user_id: Id<UserMarker>,
     extras: AuditLogExtras,
 ) -> Result<(), AnyError> {
+    count_dispatch_op(&state)?;
+
     let rt_ctx = get_rt_ctx(&state);
 
     let mut req = rt_ctx
@@ -1268,6 +2185,8 @@ pub async fn op_discord_get_member_permissions(
     user_id: Id<UserMarker>,
     (roles, channel_id): (Option<Vec<Id<RoleMarker>>>, Option<Id<ChannelMarker>>),
 ) -> Result<(String, Option<String>), AnyError> {
+    count_dispatch_op(&state)?;
+
     let rt_ctx = get_rt_ctx(&state);
 
     let member_roles = if let Some(roles) = roles {
@@ -1356,3 +2275,71 @@ pub async fn op_discord_get_member_permissions(
         channel_perms.map(|v| v.bits().to_string()),
     ))
 }
+
+#[op]
+pub async fn op_discord_get_command_permissions(
+    state: Rc<RefCell<OpState>>,
+    command_id: Id<CommandMarker>,
+) -> Result<CommandPermissions, AnyError> {
+    count_dispatch_op(&state)?;
+
+    {
+        let rc = state.borrow();
+        let dstate = rc.borrow::<DiscordOpsState>();
+        if let Some(permissions) = dstate.cached_command_permissions.get(&command_id) {
+            return Ok(permissions.clone());
+        }
+    }
+
+    let rt_ctx = get_rt_ctx(&state);
+
+    let permissions = match rt_ctx
+        .discord_config
+        .interaction_client()
+        .command_permissions(rt_ctx.guild_id, command_id)
+        .await
+    {
+        Ok(resp) => CommandPermissions::from(resp.model().await?),
+        Err(err) => {
+            if matches!(
+                err.kind(),
+                ErrorType::Response {
+                    // 10066 is unknown application command permissions, i.e. none are set up
+                    error: ApiError::General(GeneralApiError { code: 10066, .. }),
+                    ..
+                },
+            ) {
+                CommandPermissions {
+                    command_id: command_id.to_string(),
+                    permissions: Vec::new(),
+                }
+            } else {
+                return Err(handle_discord_error(&state, err));
+            }
+        }
+    };
+
+    let mut rc = state.borrow_mut();
+    let dstate = rc.borrow_mut::<DiscordOpsState>();
+    dstate
+        .cached_command_permissions
+        .insert(command_id, permissions.clone());
+
+    Ok(permissions)
+}
+
+/// Called for every APPLICATION_COMMAND_PERMISSIONS_UPDATE event to keep the cache fresh
+#[op]
+pub fn op_discord_update_command_permissions_cache(
+    state: &mut OpState,
+    permissions: CommandPermissions,
+) -> Result<(), AnyError> {
+    let command_id = permissions.command_id.parse()?;
+
+    let dstate = state.borrow_mut::<DiscordOpsState>();
+    dstate
+        .cached_command_permissions
+        .insert(command_id, permissions);
+
+    Ok(())
+}
@@ -0,0 +1,172 @@
+use std::{cell::RefCell, rc::Rc, time::Duration};
+
+use deno_core::{op, Extension, OpState};
+use runtime_models::internal::events::{AwaitAnyResult, EventFilter};
+use tokio::sync::oneshot;
+use vm::AnyError;
+
+/// Max number of filters in a single wait
+const MAX_FILTERS: usize = 10;
+
+/// Max number of waits in progress at the same time in a vm
+const MAX_WAITERS: usize = 50;
+
+/// Max time a wait can last, interaction tokens expire after 15 minutes so there's little point in going further
+const MAX_TIMEOUT_MS: u64 = 15 * 60 * 1000;
+
+pub fn extension() -> Extension {
+    Extension::builder("bl_events")
+        .ops(vec![
+            op_bl_await_any::decl(),
+            op_bl_resolve_event_waiters::decl(),
+        ])
+        .state(|state| {
+            state.put(EventWaiters::default());
+            Ok(())
+        })
+        .build()
+}
+
+#[derive(Default)]
+struct EventWaiters {
+    next_id: u64,
+    waiters: Vec<EventWaiter>,
+}
+
+struct EventWaiter {
+    id: u64,
+    filters: Vec<EventFilter>,
+    tx: oneshot::Sender<AwaitAnyResult>,
+}
+
+/// Removes the waiter when the wait ends for any reason, including the op future being dropped
+struct WaiterGuard {
+    state: Rc<RefCell<OpState>>,
+    id: u64,
+}
+
+impl Drop for WaiterGuard {
+    fn drop(&mut self) {
+        // the op state could be borrowed if we're dropped while the runtime is shutting down
+        if let Ok(mut state) = self.state.try_borrow_mut() {
+            let waiters = state.borrow_mut::<EventWaiters>();
+            waiters.waiters.retain(|w| w.id != self.id);
+        }
+    }
+}
+
+/// Waits for the first event matching any of the filters, returns None on timeout
+#[op]
+pub async fn op_bl_await_any(
+    state: Rc<RefCell<OpState>>,
+    filters: Vec<EventFilter>,
+    timeout_ms: u64,
+) -> Result<Option<AwaitAnyResult>, AnyError> {
+    if filters.is_empty() || filters.len() > MAX_FILTERS {
+        return Err(anyhow::anyhow!(
+            "need between 1 and {MAX_FILTERS} filters, got {}",
+            filters.len()
+        ));
+    }
+
+    if timeout_ms > MAX_TIMEOUT_MS {
+        return Err(anyhow::anyhow!(
+            "timeout too long, max is {MAX_TIMEOUT_MS} milliseconds"
+        ));
+    }
+
+    let (tx, rx) = oneshot::channel();
+    let guard = {
+        let mut op_state = state.borrow_mut();
+        let waiters = op_state.borrow_mut::<EventWaiters>();
+        if waiters.waiters.len() >= MAX_WAITERS {
+            return Err(anyhow::anyhow!(
+                "too many event waits in progress, max is {MAX_WAITERS}"
+            ));
+        }
+
+        let id = waiters.next_id;
+        waiters.next_id += 1;
+        waiters.waiters.push(EventWaiter { id, filters, tx });
+
+        WaiterGuard {
+            state: state.clone(),
+            id,
+        }
+    };
+
+    let result = tokio::time::timeout(Duration::from_millis(timeout_ms), rx).await;
+    drop(guard);
+
+    match result {
+        Ok(Ok(evt)) => Ok(Some(evt)),
+        // timed out, or the sender was dropped without an event which is treated the same
+        Ok(Err(_)) | Err(_) => Ok(None),
+    }
+}
+
+/// Resolves all the waiters with a filter matching this event, called for every dispatched event while waits are in progress
+#[op]
+pub fn op_bl_resolve_event_waiters(state: &mut OpState, name: String, data: serde_json::Value) {
+    let waiters = state.borrow_mut::<EventWaiters>();
+
+    let mut i = 0;
+    while i < waiters.waiters.len() {
+        let matched = waiters.waiters[i]
+            .filters
+            .iter()
+            .position(|filter| filter_matches(filter, &name, &data));
+
+        if let Some(index) = matched {
+            let waiter = waiters.waiters.swap_remove(i);
+            // the receiver is gone if the wait was cancelled in the meantime, nothing to do then
+            let _ = waiter.tx.send(AwaitAnyResult {
+                index: index as u32,
+                name: name.clone(),
+                event: data.clone(),
+            });
+        } else {
+            i += 1;
+        }
+    }
+}
+
+fn filter_matches(filter: &EventFilter, name: &str, data: &serde_json::Value) -> bool {
+    if filter.event != name {
+        return false;
+    }
+
+    if let Some(channel_id) = &filter.channel_id {
+        if data.get("channelId").and_then(|v| v.as_str()) != Some(channel_id.as_str()) {
+            return false;
+        }
+    }
+
+    if let Some(user_id) = &filter.user_id {
+        if event_user_id(data) != Some(user_id.as_str()) {
+            return false;
+        }
+    }
+
+    if let Some(prefix) = &filter.custom_id_prefix {
+        let custom_id = data.get("customId").and_then(|v| v.as_str());
+        if !matches!(custom_id, Some(custom_id) if custom_id.starts_with(prefix.as_str())) {
+            return false;
+        }
+    }
+
+    true
+}
+
+// the user that caused the event, the field differs between events
+fn event_user_id(data: &serde_json::Value) -> Option<&str> {
+    data.get("userId")
+        .or_else(|| data.get("author").and_then(|v| v.get("id")))
+        .or_else(|| {
+            data.get("member")
+                .and_then(|v| v.get("user"))
+                .and_then(|v| v.get("id"))
+        })
+        .or_else(|| data.get("user").and_then(|v| v.get("id")))
+        .and_then(|v| v.as_str())
+}
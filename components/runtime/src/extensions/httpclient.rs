@@ -10,14 +10,17 @@ use deno_core::{
 use futures::Stream;
 use reqwest::Body;
 use runtime_models::internal::httpclient::{ClientHttpRequest, ClientHttpResponse};
-use tokio::{io::AsyncReadExt, sync::mpsc};
+use tokio::{
+    io::AsyncReadExt,
+    sync::{mpsc, OwnedSemaphorePermit},
+};
 use tokio_stream::{wrappers::ReceiverStream, StreamExt};
 use tokio_util::io::StreamReader;
 use tracing::info;
 use url::Url;
 use vm::AnyError;
 
-use crate::limits::RateLimiters;
+use crate::limits::{self, ConcurrencyLimiter, RateLimiters};
 
 pub fn extension() -> Extension {
     Extension::builder("bl_http")
@@ -45,7 +48,10 @@ pub async fn op_bl_http_request_send(
     state_rc: Rc<RefCell<OpState>>,
     args: ClientHttpRequest,
 ) -> Result<ClientHttpResponse, AnyError> {
-    RateLimiters::user_http(&state_rc).await;
+    RateLimiters::user_http(&state_rc).await?;
+
+    // held until the response body resource is closed
+    let permit = request_limiter(&state_rc).acquire().await;
 
     // lookup the body stream resource
     let req_resource = if let Some(rid) = args.body_resource_id {
@@ -83,12 +89,28 @@ pub async fn op_bl_http_request_send(
         state_rc.borrow_mut().resource_table.close(rid).ok();
     }
 
-    handle_response(state_rc, res?)
+    handle_response(state_rc, res?, permit)
+}
+
+// created lazily since the limit depends on the premium tier in the runtime context
+struct HttpRequestLimiter(ConcurrencyLimiter);
+
+fn request_limiter(state_rc: &Rc<RefCell<OpState>>) -> ConcurrencyLimiter {
+    if let Some(limiter) = state_rc.borrow().try_borrow::<HttpRequestLimiter>() {
+        return limiter.0.clone();
+    }
+
+    let limiter = ConcurrencyLimiter::new(limits::user_http_concurrent_requests(state_rc) as usize);
+    state_rc
+        .borrow_mut()
+        .put(HttpRequestLimiter(limiter.clone()));
+    limiter
 }
 
 fn handle_response(
     state_rc: Rc<RefCell<OpState>>,
     resp: reqwest::Response,
+    permit: OwnedSemaphorePermit,
 ) -> Result<ClientHttpResponse, AnyError> {
     let mut resp_headers = HashMap::<String, String>::new();
     for (k, v) in resp.headers() {
@@ -108,6 +130,7 @@ fn handle_response(
         .add(RequestReponseBodyResource {
             body: AsyncRefCell::new(stream_reader),
             cancel: CancelHandle::default(),
+            _permit: permit,
         });
 
     tokio::task::spawn_local(async move {
@@ -164,6 +187,7 @@ type BytesStream = Pin<Box<dyn Stream<Item = Result<bytes::Bytes, std::io::Error
 struct RequestReponseBodyResource {
     body: AsyncRefCell<StreamReader<BytesStream, bytes::Bytes>>,
     cancel: CancelHandle,
+    _permit: OwnedSemaphorePermit,
 }
 
 impl Resource for RequestReponseBodyResource {
@@ -14,9 +14,12 @@ use crate::RuntimeContext;
 
 use self::discord::{handle_discord_error, not_found_error};
 
+pub mod commands;
 pub mod console;
 pub mod discord;
+pub mod events;
 pub mod httpclient;
+pub mod plugins;
 pub mod storage;
 pub mod tasks;
 
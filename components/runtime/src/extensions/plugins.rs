@@ -0,0 +1,80 @@
+use std::{cell::RefCell, collections::HashMap, rc::Rc};
+
+use deno_core::{op, Extension, OpState};
+use vm::{AnyError, ScriptsStateStoreHandle};
+
+use crate::get_rt_ctx;
+
+pub fn extension() -> Extension {
+    Extension::builder("bl_plugins")
+        .ops(vec![op_bl_get_plugin_config::decl()])
+        .state(|state| {
+            state.put(PluginConfigCache::default());
+            Ok(())
+        })
+        .build()
+}
+
+/// Plugin config values fetched so far in this vm, keyed by plugin id
+///
+/// This gets reset when the vm is restarted, which happens whenever the config is changed
+#[derive(Default)]
+struct PluginConfigCache {
+    plugins: HashMap<u64, Rc<HashMap<String, String>>>,
+}
+
+#[op]
+pub async fn op_bl_get_plugin_config(
+    state: Rc<RefCell<OpState>>,
+    script_id: u64,
+    key: String,
+) -> Result<Option<String>, AnyError> {
+    let plugin_id = {
+        let state = state.borrow();
+        let script_store = state.borrow::<ScriptsStateStoreHandle>().borrow();
+        let script = script_store
+            .get_script(script_id)
+            .ok_or_else(|| anyhow::anyhow!("unknown script"))?;
+
+        script
+            .script
+            .plugin_id
+            .ok_or_else(|| anyhow::anyhow!("this script is not a plugin"))?
+    };
+
+    let cached = {
+        let state = state.borrow();
+        state
+            .borrow::<PluginConfigCache>()
+            .plugins
+            .get(&plugin_id)
+            .cloned()
+    };
+
+    let config = if let Some(cached) = cached {
+        cached
+    } else {
+        let rt_ctx = get_rt_ctx(&state);
+        let entries = rt_ctx
+            .config_store
+            .get_guild_plugin_config(rt_ctx.guild_id, plugin_id)
+            .await?;
+
+        let config = Rc::new(
+            entries
+                .into_iter()
+                .map(|v| (v.key, v.value))
+                .collect::<HashMap<_, _>>(),
+        );
+
+        let mut state = state.borrow_mut();
+        state
+            .borrow_mut::<PluginConfigCache>()
+            .plugins
+            .insert(plugin_id, config.clone());
+
+        config
+    };
+
+    Ok(config.get(&key).cloned())
+}
@@ -8,7 +8,7 @@ use runtime_models::internal::storage::{
 };
 use tracing::{info, instrument};
 use twilight_model::id::{marker::GuildMarker, Id};
-use vm::AnyError;
+use vm::{AnyError, ScriptsStateStoreHandle};
 
 use crate::RuntimeContext;
 
@@ -25,6 +25,7 @@ pub fn extension() -> Extension {
             op_botloader_bucket_storage_count::decl(),
             op_botloader_bucket_storage_incr::decl(),
             op_botloader_bucket_storage_sorted_list::decl(),
+            op_bl_storage_namespace::decl(),
         ])
         .state(move |state| {
             state.put(StorageState {
@@ -43,17 +44,34 @@ struct StorageState {
     hit_limit: bool,
 }
 
+/// Returns a storage key prefix unique to the script, plugin scripts use the plugin id so
+/// the prefix stays the same across script updates
+#[op]
+pub fn op_bl_storage_namespace(state: &mut OpState, script_id: u64) -> Result<String, AnyError> {
+    let script_store = state.borrow::<ScriptsStateStoreHandle>().borrow();
+    let script = script_store
+        .get_script(script_id)
+        .ok_or_else(|| anyhow!("unknown script"))?;
+
+    Ok(match script.script.plugin_id {
+        Some(plugin_id) => format!("plugin-{plugin_id}:"),
+        None => format!("script-{script_id}:"),
+    })
+}
+
 #[op]
 pub async fn op_botloader_bucket_storage_set(
     state: Rc<RefCell<OpState>>,
     args: OpStorageBucketSetValue,
 ) -> Result<OpStorageBucketEntry, AnyError> {
+    crate::limits::count_dispatch_op(&state)?;
+
     let rt_ctx = {
         let state = state.borrow();
         state.borrow::<RuntimeContext>().clone()
     };
 
-    check_validate_value_len(&args.value)?;
+    check_validate_value_len(&args.value, crate::limits::storage_value_size(&state))?;
     check_validate_key_len(&args.key)?;
     check_validate_storage_usage(rt_ctx.guild_id, &rt_ctx, state.clone()).await?;
 
@@ -76,12 +94,14 @@ pub async fn op_botloader_bucket_storage_set_if(
     state: Rc<RefCell<OpState>>,
     args: OpStorageBucketSetIf,
 ) -> Result<Option<OpStorageBucketEntry>, AnyError> {
+    crate::limits::count_dispatch_op(&state)?;
+
     let rt_ctx = {
         let state = state.borrow();
         state.borrow::<RuntimeContext>().clone()
     };
 
-    check_validate_value_len(&args.value)?;
+    check_validate_value_len(&args.value, crate::limits::storage_value_size(&state))?;
     check_validate_key_len(&args.key)?;
     check_validate_storage_usage(rt_ctx.guild_id, &rt_ctx, state.clone()).await?;
 
@@ -105,6 +125,8 @@ pub async fn op_botloader_bucket_storage_get(
     state: Rc<RefCell<OpState>>,
     args: OpStorageBucketEntryId,
 ) -> Result<Option<OpStorageBucketEntry>, AnyError> {
+    crate::limits::count_dispatch_op(&state)?;
+
     let rt_ctx = {
         let state = state.borrow();
         state.borrow::<RuntimeContext>().clone()
@@ -123,6 +145,8 @@ pub async fn op_botloader_bucket_storage_del(
     state: Rc<RefCell<OpState>>,
     args: OpStorageBucketEntryId,
 ) -> Result<Option<OpStorageBucketEntry>, AnyError> {
+    crate::limits::count_dispatch_op(&state)?;
+
     let rt_ctx = {
         let state = state.borrow();
         state.borrow::<RuntimeContext>().clone()
@@ -150,6 +174,8 @@ pub async fn op_botloader_bucket_storage_del_many(
     bucket_name: String,
     key_pattern: String,
 ) -> Result<u64, AnyError> {
+    crate::limits::count_dispatch_op(&state)?;
+
     let rt_ctx = {
         let state = state.borrow();
         state.borrow::<RuntimeContext>().clone()
@@ -177,6 +203,8 @@ pub async fn op_botloader_bucket_storage_list(
     args: OpStorageBucketList,
     _: (),
 ) -> Result<Vec<OpStorageBucketEntry>, AnyError> {
+    crate::limits::count_dispatch_op(&state)?;
+
     let rt_ctx = {
         let state = state.borrow();
         state.borrow::<RuntimeContext>().clone()
@@ -212,6 +240,8 @@ pub async fn op_botloader_bucket_storage_count(
     bucket_name: String,
     key_pattern: String,
 ) -> Result<u64, AnyError> {
+    crate::limits::count_dispatch_op(&state)?;
+
     let rt_ctx = {
         let state = state.borrow();
         state.borrow::<RuntimeContext>().clone()
@@ -230,6 +260,8 @@ pub async fn op_botloader_bucket_storage_incr(
     state: Rc<RefCell<OpState>>,
     args: OpStorageBucketIncr,
 ) -> Result<OpStorageBucketEntry, AnyError> {
+    crate::limits::count_dispatch_op(&state)?;
+
     let rt_ctx = {
         let state = state.borrow();
         state.borrow::<RuntimeContext>().clone()
@@ -252,6 +284,8 @@ pub async fn op_botloader_bucket_storage_sorted_list(
     args: OpStorageBucketSortedList,
     _: (),
 ) -> Result<Vec<OpStorageBucketEntry>, AnyError> {
+    crate::limits::count_dispatch_op(&state)?;
+
     let rt_ctx = {
         let state = state.borrow();
         state.borrow::<RuntimeContext>().clone()
@@ -281,12 +315,14 @@ pub async fn op_botloader_bucket_storage_sorted_list(
     Ok(entries.into_iter().map(Into::into).collect())
 }
 
-fn check_validate_value_len(val: &OpStorageBucketValue) -> Result<(), AnyError> {
+fn check_validate_value_len(val: &OpStorageBucketValue, max_len: u64) -> Result<(), AnyError> {
     match val {
         OpStorageBucketValue::Json(json) => {
             let serialized = serde_json::to_string(json).unwrap();
-            if serialized.len() > 1_000_000 {
-                Err(anyhow::anyhow!("value too big, max value size is 1MB"))
+            if serialized.len() as u64 > max_len {
+                Err(anyhow::anyhow!(
+                    "value too big, max value size is {max_len} bytes"
+                ))
             } else {
                 Ok(())
             }
@@ -333,7 +369,7 @@ async fn check_validate_storage_usage(
 
     if do_check {
         info!("doing a storage check");
-        let limit = crate::limits::storage_total_size(&state_rc);
+        let limit = crate::limits::storage_total_bytes(&state_rc);
         let used_storage = ctx.bucket_store.guild_storage_usage_bytes(guild_id).await;
 
         let mut state = state_rc.borrow_mut();
@@ -1,43 +1,130 @@
-use std::{cell::RefCell, rc::Rc};
+use std::{cell::RefCell, collections::HashMap, rc::Rc};
 
 use chrono::TimeZone;
 use deno_core::{op, Extension, OpState};
-use runtime_models::internal::tasks::{CreateScheduledTask, ScheduledTask};
+use runtime_models::internal::tasks::{
+    BackgroundTaskData, CreateScheduledTask, RunBackgroundTask, ScheduledTask, TaskExecution,
+    TaskNamespaceSummary,
+};
+use stores::timers::TaskTag;
 use vm::AnyError;
 
-use crate::{get_rt_ctx, limits::RateLimiters, RuntimeEvent};
+use crate::{get_rt_ctx, limits::RateLimiters, RuntimeContext, RuntimeEvent};
 
 pub fn extension() -> Extension {
     Extension::builder("bl_tasks")
         .ops(vec![
             // botloader stuff
             op_bl_schedule_task::decl(),
+            op_bl_run_background::decl(),
             op_bl_del_task::decl(),
             op_bl_del_task_by_key::decl(),
             op_bl_del_all_tasks::decl(),
+            op_bl_del_tasks_by_tag::decl(),
             op_bl_get_task::decl(),
             op_bl_get_task_by_key::decl(),
+            op_bl_task_exists::decl(),
             op_bl_get_all_tasks::decl(),
+            op_bl_get_task_summary::decl(),
+            op_bl_get_task_history::decl(),
+            op_bl_task_handled::decl(),
         ])
         .build()
 }
 
+// the task is persisted before returning, the scheduler only considers tasks for namespaces
+// registered by the currently running scripts so it's picked up again after a restart
 #[op]
 async fn op_bl_schedule_task(
     state: Rc<RefCell<OpState>>,
     opts: CreateScheduledTask,
 ) -> Result<ScheduledTask, AnyError> {
-    let rt_ctx = get_rt_ctx(&state);
-    RateLimiters::task_ops(&state).await;
-
     let seconds = (opts.execute_at.0 as f64 / 1000f64).floor() as i64;
     let millis = opts.execute_at.0 as i64 - (seconds * 1000);
     let t = chrono::Utc
         .timestamp_opt(seconds, millis as u32 * 1_000_000)
         .unwrap();
 
-    let data_serialized = serde_json::to_string(&opts.data)?;
-    let limit_data_len = crate::limits::tasks_data_size(&state);
+    schedule_task(
+        &state,
+        opts.namespace,
+        opts.unique_key,
+        opts.tags.unwrap_or_default(),
+        opts.data,
+        t,
+    )
+    .await
+}
+
+/// Namespace prefix of the tasks created by op_bl_run_background, the handler name follows it
+const BACKGROUND_TASK_NAMESPACE_PREFIX: &str = "__bl_background:";
+
+/// How deep background tasks can be chained, counting the ones started from within background
+/// handlers and result handlers
+const MAX_BACKGROUND_TASK_DEPTH: u32 = 10;
+
+// schedules a task that's due right away, the script registers a handler for the namespace
+// so it's dispatched back to this guild's vm
+//
+// it goes through the same limits as normal tasks
+#[op]
+async fn op_bl_run_background(
+    state: Rc<RefCell<OpState>>,
+    opts: RunBackgroundTask,
+) -> Result<ScheduledTask, AnyError> {
+    validate_background_handler_name(&opts.handler_name)?;
+    if let Some(result_handler) = &opts.result_handler {
+        validate_background_handler_name(result_handler)?;
+    }
+
+    if opts.depth >= MAX_BACKGROUND_TASK_DEPTH {
+        return Err(anyhow::anyhow!(
+            "background tasks can only be chained {MAX_BACKGROUND_TASK_DEPTH} deep"
+        ));
+    }
+
+    let data = serde_json::to_value(BackgroundTaskData {
+        data: opts.data,
+        depth: opts.depth + 1,
+        result_handler: opts.result_handler,
+    })?;
+
+    schedule_task(
+        &state,
+        format!("{BACKGROUND_TASK_NAMESPACE_PREFIX}{}", opts.handler_name),
+        None,
+        HashMap::new(),
+        data,
+        chrono::Utc::now(),
+    )
+    .await
+}
+
+fn validate_background_handler_name(name: &str) -> Result<(), AnyError> {
+    if name.is_empty() || name.len() > 100 {
+        return Err(anyhow::anyhow!(
+            "background handler names need to be between 1 and 100 characters"
+        ));
+    }
+
+    Ok(())
+}
+
+async fn schedule_task(
+    state: &Rc<RefCell<OpState>>,
+    namespace: String,
+    unique_key: Option<String>,
+    tags: HashMap<String, String>,
+    data: serde_json::Value,
+    execute_at: chrono::DateTime<chrono::Utc>,
+) -> Result<ScheduledTask, AnyError> {
+    validate_task_tags(&tags)?;
+
+    let rt_ctx = get_rt_ctx(state);
+    RateLimiters::task_ops(state).await?;
+
+    let data_serialized = serde_json::to_string(&data)?;
+    let limit_data_len = crate::limits::tasks_data_size(state);
     if data_serialized.len() as u64 > limit_data_len {
         return Err(anyhow::anyhow!(
             "data cannot be over {limit_data_len}bytes on your guild's plan"
@@ -46,7 +133,7 @@ async fn op_bl_schedule_task(
 
     // TODO: make a more efficient check
     let current = rt_ctx.timer_store.get_task_count(rt_ctx.guild_id).await?;
-    let limit_num_tasks = crate::limits::tasks_scheduled_count(&state);
+    let limit_num_tasks = crate::limits::tasks_scheduled_count(state);
     if current > limit_num_tasks {
         return Err(anyhow::anyhow!(
             "max {limit_num_tasks} can be scheduled on this guild's plan"
@@ -57,10 +144,11 @@ async fn op_bl_schedule_task(
         .timer_store
         .create_task(
             rt_ctx.guild_id,
-            opts.namespace,
-            opts.unique_key,
-            opts.data,
-            t,
+            namespace,
+            unique_key,
+            tags,
+            data,
+            execute_at,
         )
         .await?
         .into();
@@ -70,10 +158,45 @@ async fn op_bl_schedule_task(
     Ok(res)
 }
 
+/// Max number of tags on a single task
+const TASK_MAX_TAGS: usize = 10;
+const TASK_TAG_KEY_MAX_LEN: usize = 32;
+const TASK_TAG_VALUE_MAX_LEN: usize = 100;
+
+fn validate_task_tags(tags: &HashMap<String, String>) -> Result<(), AnyError> {
+    if tags.len() > TASK_MAX_TAGS {
+        return Err(anyhow::anyhow!(
+            "tasks can have at most {TASK_MAX_TAGS} tags"
+        ));
+    }
+
+    for (key, value) in tags {
+        validate_task_tag(key, value)?;
+    }
+
+    Ok(())
+}
+
+fn validate_task_tag(key: &str, value: &str) -> Result<(), AnyError> {
+    if key.is_empty() || key.len() > TASK_TAG_KEY_MAX_LEN {
+        return Err(anyhow::anyhow!(
+            "task tag keys need to be between 1 and {TASK_TAG_KEY_MAX_LEN} characters"
+        ));
+    }
+
+    if value.len() > TASK_TAG_VALUE_MAX_LEN {
+        return Err(anyhow::anyhow!(
+            "task tag values can be at most {TASK_TAG_VALUE_MAX_LEN} characters"
+        ));
+    }
+
+    Ok(())
+}
+
 #[op]
 async fn op_bl_del_task(state: Rc<RefCell<OpState>>, task_id: u64) -> Result<bool, AnyError> {
     let rt_ctx = get_rt_ctx(&state);
-    RateLimiters::task_ops(&state).await;
+    RateLimiters::task_ops(&state).await?;
 
     let del = rt_ctx
         .timer_store
@@ -89,7 +212,7 @@ async fn op_bl_del_task_by_key(
     key: String,
 ) -> Result<bool, AnyError> {
     let rt_ctx = get_rt_ctx(&state);
-    RateLimiters::task_ops(&state).await;
+    RateLimiters::task_ops(&state).await?;
 
     let del = rt_ctx
         .timer_store
@@ -103,7 +226,7 @@ async fn op_bl_del_task_by_key(
 async fn op_bl_del_all_tasks(state: Rc<RefCell<OpState>>, name: String) -> Result<u64, AnyError> {
     let rt_ctx = get_rt_ctx(&state);
 
-    RateLimiters::task_ops(&state).await;
+    RateLimiters::task_ops(&state).await?;
 
     let del = rt_ctx
         .timer_store
@@ -112,6 +235,25 @@ async fn op_bl_del_all_tasks(state: Rc<RefCell<OpState>>, name: String) -> Resul
     Ok(del)
 }
 
+// deletes across all namespaces, unlike op_bl_del_all_tasks
+#[op]
+async fn op_bl_del_tasks_by_tag(
+    state: Rc<RefCell<OpState>>,
+    key: String,
+    value: String,
+) -> Result<u64, AnyError> {
+    validate_task_tag(&key, &value)?;
+
+    let rt_ctx = get_rt_ctx(&state);
+    RateLimiters::task_ops(&state).await?;
+
+    let del = rt_ctx
+        .timer_store
+        .del_tasks_by_tag(rt_ctx.guild_id, TaskTag { key, value })
+        .await?;
+    Ok(del)
+}
+
 #[op]
 async fn op_bl_get_task(
     state: Rc<RefCell<OpState>>,
@@ -119,7 +261,7 @@ async fn op_bl_get_task(
 ) -> Result<Option<ScheduledTask>, AnyError> {
     let rt_ctx = get_rt_ctx(&state);
 
-    RateLimiters::task_ops(&state).await;
+    RateLimiters::task_ops(&state).await?;
 
     Ok(rt_ctx
         .timer_store
@@ -135,7 +277,7 @@ async fn op_bl_get_task_by_key(
     key: String,
 ) -> Result<Option<ScheduledTask>, AnyError> {
     let rt_ctx = get_rt_ctx(&state);
-    RateLimiters::task_ops(&state).await;
+    RateLimiters::task_ops(&state).await?;
 
     Ok(rt_ctx
         .timer_store
@@ -144,20 +286,88 @@ async fn op_bl_get_task_by_key(
         .map(Into::into))
 }
 
+#[op]
+async fn op_bl_task_exists(
+    state: Rc<RefCell<OpState>>,
+    name: String,
+    key: String,
+) -> Result<bool, AnyError> {
+    let rt_ctx = get_rt_ctx(&state);
+    RateLimiters::task_ops(&state).await?;
+
+    Ok(rt_ctx
+        .timer_store
+        .task_exists_by_key(rt_ctx.guild_id, name, key)
+        .await?)
+}
+
 #[op]
 async fn op_bl_get_all_tasks(
     state: Rc<RefCell<OpState>>,
     name: Option<String>,
     after_id: u64,
+    tag: Option<(String, String)>,
 ) -> Result<Vec<ScheduledTask>, AnyError> {
     let rt_ctx = get_rt_ctx(&state);
-    RateLimiters::task_ops(&state).await;
+    RateLimiters::task_ops(&state).await?;
+
+    let tag = tag.map(|(key, value)| TaskTag { key, value });
+
+    Ok(rt_ctx
+        .timer_store
+        .get_tasks(rt_ctx.guild_id, name, tag, after_id, 25)
+        .await?
+        .into_iter()
+        .map(Into::into)
+        .collect())
+}
+
+/// Max number of namespaces returned by op_bl_get_task_summary
+const TASK_SUMMARY_MAX_NAMESPACES: usize = 100;
+
+#[op]
+async fn op_bl_get_task_summary(
+    state: Rc<RefCell<OpState>>,
+) -> Result<Vec<TaskNamespaceSummary>, AnyError> {
+    let rt_ctx = get_rt_ctx(&state);
+    RateLimiters::task_ops(&state).await?;
 
     Ok(rt_ctx
         .timer_store
-        .get_tasks(rt_ctx.guild_id, name, after_id, 25)
+        .get_task_namespace_summary(rt_ctx.guild_id, TASK_SUMMARY_MAX_NAMESPACES)
         .await?
         .into_iter()
         .map(Into::into)
         .collect())
 }
+
+/// Max number of executions returned by op_bl_get_task_history
+const TASK_HISTORY_MAX_LIMIT: usize = 100;
+
+#[op]
+async fn op_bl_get_task_history(
+    state: Rc<RefCell<OpState>>,
+    name: String,
+    limit: usize,
+) -> Result<Vec<TaskExecution>, AnyError> {
+    let rt_ctx = get_rt_ctx(&state);
+    RateLimiters::task_ops(&state).await?;
+
+    Ok(rt_ctx
+        .timer_store
+        .get_task_executions(rt_ctx.guild_id, name, limit.min(TASK_HISTORY_MAX_LIMIT))
+        .await?
+        .into_iter()
+        .map(Into::into)
+        .collect())
+}
+
+// called by the task handler wrapper once the handler is done, the scheduler records the
+// execution from this
+#[op]
+fn op_bl_task_handled(state: &mut OpState, task_id: u64, error: Option<String>) {
+    let rt_ctx = state.borrow::<RuntimeContext>();
+    let _ = rt_ctx
+        .event_tx
+        .send(RuntimeEvent::TaskHandled { task_id, error });
+}
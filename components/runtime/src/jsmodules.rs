@@ -1,4 +1,12 @@
-use vm::moduleloader::ModuleEntry;
+use std::{
+    collections::{HashMap, HashSet},
+    str::FromStr,
+    sync::{Arc, RwLock},
+};
+
+use stores::config::PremiumSlotTier;
+use url::Url;
+use vm::moduleloader::{ModuleAccessCheck, ModuleEntry};
 
 macro_rules! include_js {
     ($f:tt) => {
@@ -17,3 +25,85 @@ pub fn create_module_map() -> Vec<ModuleEntry> {
         })
         .collect()
 }
+
+/// What's required to import a gated builtin module
+#[derive(Clone, Debug)]
+pub enum BuiltinModuleGate {
+    /// The guild needs at least this premium tier
+    PremiumTier(PremiumSlotTier),
+    /// The feature flag needs to be enabled
+    FeatureFlag(String),
+}
+
+impl FromStr for BuiltinModuleGate {
+    type Err = String;
+
+    /// Parses "lite", "premium" or "flag:<name>"
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "lite" => Ok(Self::PremiumTier(PremiumSlotTier::Lite)),
+            "premium" => Ok(Self::PremiumTier(PremiumSlotTier::Premium)),
+            _ => match s.strip_prefix("flag:") {
+                Some(flag) if !flag.is_empty() => Ok(Self::FeatureFlag(flag.to_string())),
+                _ => Err(format!("unknown builtin module gate: {s}")),
+            },
+        }
+    }
+}
+
+/// Restricts which builtin modules scripts can import.
+///
+/// Modules without a gate are always available, so the default allows everything.
+///
+/// Modules are identified by their path without the extension, e.g. "discord/index".
+/// Note that gating a module that's re-exported by another builtin (like "index") will also
+/// make that module fail to load.
+#[derive(Clone, Debug, Default)]
+pub struct BuiltinModuleAllowlist {
+    pub gates: HashMap<String, BuiltinModuleGate>,
+    pub enabled_feature_flags: HashSet<String>,
+}
+
+impl BuiltinModuleAllowlist {
+    pub fn check(
+        &self,
+        module_name: &str,
+        premium_tier: Option<PremiumSlotTier>,
+    ) -> Result<(), String> {
+        match self.gates.get(module_name) {
+            None => Ok(()),
+            Some(BuiltinModuleGate::PremiumTier(required)) => match premium_tier {
+                Some(tier) if !required.is_higher_than(tier) => Ok(()),
+                _ => Err(format!(
+                    "module {module_name} is not available on your plan"
+                )),
+            },
+            Some(BuiltinModuleGate::FeatureFlag(flag)) => {
+                if self.enabled_feature_flags.contains(flag) {
+                    Ok(())
+                } else {
+                    Err(format!("module {module_name} is not available yet"))
+                }
+            }
+        }
+    }
+
+    /// Creates a check for the vm's module loader, the premium tier is read when a module is
+    /// loaded so it stays up to date across vm restarts
+    pub fn into_access_check(
+        self,
+        premium_tier: Arc<RwLock<Option<PremiumSlotTier>>>,
+    ) -> Option<ModuleAccessCheck> {
+        if self.gates.is_empty() {
+            return None;
+        }
+
+        Some(Box::new(move |specifier: &Url| {
+            let path = specifier.path().trim_start_matches('/');
+            let name = path.strip_suffix(".js").unwrap_or(path);
+
+            let tier = *premium_tier.read().unwrap();
+            self.check(name, tier)
+        }))
+    }
+}
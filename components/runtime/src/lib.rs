@@ -1,33 +1,58 @@
 use std::{
     cell::RefCell,
+    collections::{HashMap, HashSet},
     rc::Rc,
     sync::{Arc, RwLock},
+    time::{Duration, Instant},
 };
 
+use chrono::Offset;
 use common::DiscordConfig;
-use deno_core::{op, Extension, OpState, ResourceId, ResourceTable};
+use deno_core::{op, Extension, OpState, ResourceId, ResourceTable, ZeroCopyBuf};
 use guild_logger::{GuildLogger, LogEntry};
-use runtime_models::internal::script::ScriptMeta;
+use rand::{rngs::OsRng, Rng, RngCore};
+use runtime_models::internal::script::{
+    EventHandlerRegistration, LocalTime, RestartInfo, RestartReason, ScriptContributes, ScriptMeta,
+};
+use runtime_models::util::NotBigU64;
 use stores::{
     bucketstore::BucketStore,
-    config::{ConfigStore, PremiumSlotTier},
+    config::{ConfigStore, ConfigStoreError, PremiumSlotTier},
+    ratelimits::RateLimitStore,
     timers::TimerStore,
 };
 use tokio::sync::mpsc;
 use tracing::info;
+use twilight_model::gateway::Intents;
 use twilight_model::id::marker::GuildMarker;
 use twilight_model::id::Id;
-use vm::{vm::VmRole, AnyError, JsValue};
+use vm::{
+    vm::{LastRestartInfo, VmRole, VmShutdownHandle},
+    AnyError, JsValue, ScriptsStateStoreHandle,
+};
 
 use crate::limits::RateLimiters;
 
 pub mod extensions;
 pub mod jsmodules;
 pub mod limits;
+pub mod sandbox;
+
+/// Checks that the address can be used as the proxy for the script http client
+pub fn validate_http_proxy_addr(addr: &str) -> Result<(), AnyError> {
+    reqwest::Proxy::all(addr)?;
+    Ok(())
+}
 
 pub fn create_extensions(ctx: CreateRuntimeContext) -> Vec<Extension> {
+    // the guild specific proxy takes precedence over the global one
+    let http_client_proxy = ctx
+        .guild_http_client_proxy
+        .clone()
+        .or_else(|| ctx.script_http_client_proxy.clone());
+
     let mut http_client_builder = reqwest::ClientBuilder::new();
-    if let Some(proxy_addr) = &ctx.script_http_client_proxy {
+    if let Some(proxy_addr) = &http_client_proxy {
         info!("using http client proxy: {}", proxy_addr);
         let proxy = reqwest::Proxy::all(proxy_addr).expect("valid http proxy address");
         http_client_builder = http_client_builder.proxy(proxy);
@@ -36,6 +61,7 @@ pub fn create_extensions(ctx: CreateRuntimeContext) -> Vec<Extension> {
         tracing::warn!("no proxy set in release!");
     }
     let http_client = http_client_builder.build().expect("valid http client");
+    let sandbox = ctx.sandbox;
 
     let core_extension = Extension::builder("bl_script_core")
         .ops(vec![
@@ -43,6 +69,18 @@ pub fn create_extensions(ctx: CreateRuntimeContext) -> Vec<Extension> {
             op_botloader_script_start::decl(),
             op_get_current_bot_user::decl(),
             op_get_current_guild_id::decl(),
+            op_bl_get_gateway_intents::decl(),
+            op_bl_snowflake_timestamp::decl(),
+            op_bl_list_event_handlers::decl(),
+            op_bl_last_restart_reason::decl(),
+            op_bl_extend_deadline::decl(),
+            op_bl_feature_enabled::decl(),
+            op_bl_get_gateway_latency::decl(),
+            op_bl_now_local::decl(),
+            op_bl_random_bytes::decl(),
+            op_bl_random_int::decl(),
+            op_bl_get_script_contributes::decl(),
+            op_bl_get_source_map::decl(),
         ])
         .state(move |state| {
             let premium_tier = *ctx.premium_tier.read().unwrap();
@@ -53,26 +91,39 @@ pub fn create_extensions(ctx: CreateRuntimeContext) -> Vec<Extension> {
                 discord_config: ctx.discord_config.clone(),
                 role: ctx.role,
                 guild_logger: ctx.guild_logger.clone(),
-                script_http_client_proxy: ctx.script_http_client_proxy.clone(),
+                script_http_client_proxy: http_client_proxy.clone(),
                 event_tx: ctx.event_tx.clone(),
                 premium_tier,
+                feature_flags: ctx.feature_flags.clone(),
 
                 bucket_store: ctx.bucket_store.clone(),
                 config_store: ctx.config_store.clone(),
                 timer_store: ctx.timer_store.clone(),
             });
             state.put(http_client.clone());
-
-            state.put(Rc::new(RateLimiters::new(premium_tier)));
+            state.put(EventHandlerRegistrations::default());
+            state.put(IntervalTimerCounts::default());
+
+            let shared_ratelimiter =
+                ctx.ratelimit_store
+                    .clone()
+                    .map(|store| limits::SharedRateLimiter {
+                        store,
+                        guild_id: ctx.guild_id,
+                    });
+            state.put(Rc::new(RateLimiters::new(premium_tier, shared_ratelimiter)));
 
             Ok(())
         })
-        .middleware(|deno_op| match deno_op.name {
-            // we have our own custom print function
-            "op_print" => disabled_op::decl(),
-            "op_wasm_streaming_feed" => disabled_op::decl(),
-            "op_wasm_streaming_set_url" => disabled_op::decl(),
-            _ => deno_op,
+        .middleware(move |deno_op| {
+            // the compiler rejects direct uses of these as well, see tscompiler::disabled_apis
+            if tscompiler::disabled_apis::is_disabled_op(deno_op.name) {
+                disabled_op::decl()
+            } else if sandbox && sandbox::is_disabled_in_sandbox(deno_op.name) {
+                sandbox_disabled_op::decl()
+            } else {
+                deno_op
+            }
         })
         .build();
 
@@ -80,9 +131,12 @@ pub fn create_extensions(ctx: CreateRuntimeContext) -> Vec<Extension> {
         core_extension,
         extensions::storage::extension(),
         extensions::discord::extension(),
+        extensions::events::extension(),
         extensions::console::extension(),
         extensions::httpclient::extension(),
         extensions::tasks::extension(),
+        extensions::plugins::extension(),
+        extensions::commands::extension(),
     ]
 }
 
@@ -95,6 +149,11 @@ pub fn disabled_op() -> Result<(), AnyError> {
     Err(anyhow::anyhow!("this op is disabled"))
 }
 
+#[op]
+pub fn sandbox_disabled_op() -> Result<(), AnyError> {
+    Err(anyhow::anyhow!("this op is not available in test runs"))
+}
+
 #[derive(Clone)]
 pub struct RuntimeContext {
     pub guild_id: Id<GuildMarker>,
@@ -105,6 +164,8 @@ pub struct RuntimeContext {
     pub script_http_client_proxy: Option<String>,
     pub event_tx: mpsc::UnboundedSender<RuntimeEvent>,
     pub premium_tier: Option<PremiumSlotTier>,
+    /// The feature flags enabled for the guild when the vm was started
+    pub feature_flags: Arc<HashSet<String>>,
 
     pub bucket_store: Arc<dyn BucketStore>,
     pub config_store: Arc<dyn ConfigStore>,
@@ -119,12 +180,20 @@ pub struct CreateRuntimeContext {
     pub role: VmRole,
     pub guild_logger: GuildLogger,
     pub script_http_client_proxy: Option<String>,
+    /// Overrides `script_http_client_proxy` for this guild, used for routing specific guilds
+    /// through their own egress
+    pub guild_http_client_proxy: Option<String>,
     pub event_tx: mpsc::UnboundedSender<RuntimeEvent>,
     pub premium_tier: Arc<RwLock<Option<PremiumSlotTier>>>,
+    pub feature_flags: Arc<HashSet<String>>,
 
     pub bucket_store: Arc<dyn BucketStore>,
     pub config_store: Arc<dyn ConfigStore>,
     pub timer_store: Arc<dyn TimerStore>,
+    /// Enforce rate limits across all the guild's vm's using this store, None to only limit per vm
+    pub ratelimit_store: Option<Arc<dyn RateLimitStore>>,
+    /// Replace all ops with side effects with ones that throw, see [sandbox]
+    pub sandbox: bool,
 }
 
 #[op]
@@ -135,6 +204,82 @@ pub fn op_get_current_bot_user(
     Ok(ctx.discord_config.bot_user.clone().into())
 }
 
+#[op]
+pub fn op_bl_get_gateway_intents(
+    state: &mut OpState,
+) -> runtime_models::internal::misc_op::GatewayIntents {
+    let ctx = state.borrow::<RuntimeContext>();
+
+    // an intent is only available if the broker requests it and the application has been granted it
+    let requested = common::gateway_intents();
+    let has_intent = |intent: Intents| {
+        requested.contains(intent) && ctx.discord_config.has_privileged_intent(intent)
+    };
+
+    runtime_models::internal::misc_op::GatewayIntents {
+        message_content: has_intent(Intents::MESSAGE_CONTENT),
+        guild_members: has_intent(Intents::GUILD_MEMBERS),
+        presences: has_intent(Intents::GUILD_PRESENCES),
+    }
+}
+
+/// The version of the script facing runtime API this host provides, scripts can pin the
+/// version they were written against and are refused if it's newer than this.
+///
+/// Bump this when ops or their behavior change in a way that can break existing scripts.
+pub const RUNTIME_API_VERSION: u32 = 1;
+
+// milliseconds since the unix epoch at the start of 2015, the first second of discord snowflakes
+const DISCORD_EPOCH: i64 = 1_420_070_400_000;
+
+/// Returns the creation time of the snowflake as unix millis
+#[op]
+pub fn op_bl_snowflake_timestamp(id: String) -> Result<i64, AnyError> {
+    let id: u64 = id
+        .parse()
+        .map_err(|_| anyhow::anyhow!("invalid snowflake id: {id}"))?;
+
+    Ok((id >> 22) as i64 + DISCORD_EPOCH)
+}
+
+// same limit as crypto.getRandomValues
+const RANDOM_BYTES_MAX: u32 = 65536;
+
+// the range of integers a js number can represent exactly
+const JS_MAX_SAFE_INTEGER: i64 = (1 << 53) - 1;
+
+/// Random bytes from the os' cryptographically secure rng
+#[op]
+pub fn op_bl_random_bytes(n: u32) -> Result<ZeroCopyBuf, AnyError> {
+    if n > RANDOM_BYTES_MAX {
+        return Err(anyhow::anyhow!(
+            "can't generate more than {RANDOM_BYTES_MAX} random bytes at a time"
+        ));
+    }
+
+    let mut buf = vec![0; n as usize];
+    OsRng.try_fill_bytes(&mut buf)?;
+    Ok(buf.into())
+}
+
+/// A random integer in `min..max` from the os' cryptographically secure rng
+#[op]
+pub fn op_bl_random_int(min: i64, max: i64) -> Result<i64, AnyError> {
+    if min >= max {
+        return Err(anyhow::anyhow!(
+            "min ({min}) has to be less than max ({max})"
+        ));
+    }
+
+    if min < -JS_MAX_SAFE_INTEGER || max > JS_MAX_SAFE_INTEGER {
+        return Err(anyhow::anyhow!(
+            "min and max have to be safe integers (Number.isSafeInteger)"
+        ));
+    }
+
+    Ok(OsRng.gen_range(min..max))
+}
+
 #[op]
 pub fn op_get_current_guild_id(state: &mut OpState) -> Result<String, AnyError> {
     let ctx = state.borrow::<RuntimeContext>();
@@ -142,9 +287,20 @@ pub fn op_get_current_guild_id(state: &mut OpState) -> Result<String, AnyError>
 }
 
 #[op]
-pub fn op_botloader_script_start(state: &mut OpState, args: JsValue) -> Result<(), AnyError> {
+pub fn op_botloader_script_start(state: &mut OpState, args: JsValue) -> Result<u64, AnyError> {
     let des: ScriptMeta = serde_json::from_value(args)?;
 
+    // only the module of the script being loaded can report it as started
+    let loading_script = state
+        .borrow::<ScriptsStateStoreHandle>()
+        .borrow()
+        .loading_script;
+    if loading_script != Some(des.script_id.0) {
+        return Err(anyhow::anyhow!(
+            "scripts can only be started from their own module"
+        ));
+    }
+
     info!(
         "running script! {}, commands: {}",
         des.script_id.0,
@@ -153,7 +309,13 @@ pub fn op_botloader_script_start(state: &mut OpState, args: JsValue) -> Result<(
 
     let ctx = state.borrow::<RuntimeContext>();
 
-    if let Err(err) = validate_script_meta(&des) {
+    let validated = validate_script_meta(&des, ctx.premium_tier).and_then(|_| {
+        state
+            .borrow::<IntervalTimerCounts>()
+            .check_guild_limit(&des, ctx.premium_tier)
+    });
+
+    if let Err(err) = validated {
         // error!(%err, "script meta validation failed");
         ctx.guild_logger.log(LogEntry::script_error(
             ctx.guild_id,
@@ -161,15 +323,274 @@ pub fn op_botloader_script_start(state: &mut OpState, args: JsValue) -> Result<(
             format!("{}", des.script_id),
             None,
         ));
+        let _ = ctx.event_tx.send(RuntimeEvent::ScriptValidationFailed {
+            script_id: des.script_id.0,
+            reason: err.to_string(),
+        });
         return Err(err);
     }
 
+    let registrations = des
+        .event_names
+        .iter()
+        .map(|name| EventHandlerRegistration {
+            event_name: name.clone(),
+            script_id: des.script_id,
+        })
+        .collect::<Vec<_>>();
+
+    let script_id = des.script_id.0;
+    let num_interval_timers = des.interval_timers.len();
+
+    // a script reloaded in place reports its handlers again
+    let handlers = &mut state.borrow_mut::<EventHandlerRegistrations>().0;
+    handlers.retain(|v| v.script_id.0 != script_id);
+    handlers.extend(registrations);
+
+    state
+        .borrow_mut::<IntervalTimerCounts>()
+        .0
+        .insert(script_id, num_interval_timers);
+
+    state
+        .borrow_mut::<extensions::commands::ScriptCommandClaims>()
+        .record(&des);
+
+    let commands_token = state
+        .borrow_mut::<extensions::commands::ScriptCommandTokens>()
+        .issue(script_id);
+
+    state
+        .borrow::<ScriptsStateStoreHandle>()
+        .borrow_mut()
+        .set_handled_events(script_id, handled_events(&des));
+
+    let ctx = state.borrow::<RuntimeContext>();
     let _ = ctx.event_tx.send(RuntimeEvent::ScriptStarted(des));
 
+    Ok(commands_token)
+}
+
+// the events that reach the script's handlers, the BOTLOADER_ prefixed ones aren't in event_names
+// but follow from its commands, timers and tasks. Component and modal listeners are added after
+// the script has started, so those interactions aren't included.
+fn handled_events(meta: &ScriptMeta) -> HashSet<String> {
+    let mut events = meta.event_names.iter().cloned().collect::<HashSet<_>>();
+    if !meta.commands.is_empty() || !meta.command_groups.is_empty() {
+        events.insert("BOTLOADER_COMMAND_INTERACTION_CREATE".to_string());
+    }
+    if !meta.interval_timers.is_empty() {
+        events.insert("BOTLOADER_INTERVAL_TIMER_FIRED".to_string());
+    }
+    if !meta.task_names.is_empty() {
+        events.insert("BOTLOADER_SCHEDULED_TASK_FIRED".to_string());
+    }
+    events
+}
+
+/// The event handlers registered by the scripts running in this vm, as reported on script start
+#[derive(Default)]
+struct EventHandlerRegistrations(Vec<EventHandlerRegistration>);
+
+/// The number of interval timers registered by each script in this vm, keyed by script id
+#[derive(Default)]
+struct IntervalTimerCounts(HashMap<u64, usize>);
+
+impl IntervalTimerCounts {
+    fn check_guild_limit(
+        &self,
+        meta: &ScriptMeta,
+        premium_tier: Option<PremiumSlotTier>,
+    ) -> Result<(), anyhow::Error> {
+        let others: usize = self
+            .0
+            .iter()
+            .filter(|(script_id, _)| **script_id != meta.script_id.0)
+            .map(|(_, count)| *count)
+            .sum();
+
+        let total = others + meta.interval_timers.len();
+        let max = limits::interval_timers_guild_for_tier(premium_tier);
+        if total as u64 > max {
+            return Err(anyhow::anyhow!(
+                "script {} brings the number of interval timers on this server to {}, the max is {}",
+                meta.script_id,
+                total,
+                max
+            ));
+        }
+
+        Ok(())
+    }
+}
+
+#[op]
+pub fn op_bl_list_event_handlers(state: &mut OpState) -> Vec<EventHandlerRegistration> {
+    state.borrow::<EventHandlerRegistrations>().0.clone()
+}
+
+#[op]
+pub fn op_bl_last_restart_reason(state: &mut OpState) -> Option<RestartInfo> {
+    let info = state.borrow::<LastRestartInfo>().0.as_ref()?;
+
+    Some(RestartInfo {
+        reason: match info.reason {
+            vm::vm::RestartReason::ScriptsChanged => RestartReason::ScriptsChanged,
+            vm::vm::RestartReason::OutOfMemory => RestartReason::OutOfMemory,
+            vm::vm::RestartReason::Runaway => RestartReason::Runaway,
+            vm::vm::RestartReason::Hibernation => RestartReason::Hibernation,
+            vm::vm::RestartReason::Other => RestartReason::Other,
+        },
+        at: NotBigU64(info.at.timestamp_millis() as u64),
+    })
+}
+
+/// The commands and interval timers a script in this guild registered the last time it ran
+#[op]
+pub async fn op_bl_get_script_contributes(
+    state: Rc<RefCell<OpState>>,
+    script_id: u64,
+) -> Result<ScriptContributes, AnyError> {
+    RateLimiters::script_contributes(&state).await?;
+
+    let rt_ctx = get_rt_ctx(&state);
+    match rt_ctx
+        .config_store
+        .get_script_by_id(rt_ctx.guild_id, script_id)
+        .await
+    {
+        Ok(script) => Ok(script.contributes.into()),
+        Err(ConfigStoreError::ScriptNotFound) => Err(extensions::discord::not_found_error(
+            format!("unknown script: {script_id}"),
+        )),
+        Err(err) => Err(err.into()),
+    }
+}
+
+/// The raw source map of a script running in this vm, None if there's no such script
+#[op]
+pub async fn op_bl_get_source_map(
+    state: Rc<RefCell<OpState>>,
+    script_id: u64,
+) -> Result<Option<String>, AnyError> {
+    RateLimiters::source_maps(&state).await?;
+
+    let state = state.borrow();
+    let scripts = state.borrow::<ScriptsStateStoreHandle>().borrow();
+    Ok(scripts
+        .get_script(script_id)
+        .map(|script| script.compiled.source_map_raw.clone()))
+}
+
+/// Gives the current event extra time before the vm is considered a runaway and shut down,
+/// meant for known slow work like bulk imports
+#[op]
+pub fn op_bl_extend_deadline(state: &mut OpState, extra_ms: u64) -> Result<(), AnyError> {
+    let ctx = state.borrow::<RuntimeContext>();
+
+    let max = limits::deadline_extension_ms_for_tier(ctx.premium_tier);
+    if extra_ms > max {
+        return Err(anyhow::anyhow!(
+            "can't extend the deadline by more than {max}ms on your plan"
+        ));
+    }
+
+    let handle = state.borrow::<VmShutdownHandle>();
+    if !handle.extend_deadline(Duration::from_millis(extra_ms)) {
+        return Err(anyhow::anyhow!(
+            "the deadline was already extended for this event"
+        ));
+    }
+
+    info!(guild_id = %ctx.guild_id, "extended event deadline by {extra_ms}ms");
+    ctx.guild_logger.log(LogEntry::info(
+        ctx.guild_id,
+        format!("extended the deadline of the current event by {extra_ms}ms"),
+    ));
+
     Ok(())
 }
 
-pub(crate) fn validate_script_meta(meta: &ScriptMeta) -> Result<(), anyhow::Error> {
+/// Unknown flags are simply not enabled
+#[op]
+pub fn op_bl_feature_enabled(state: &mut OpState, flag: String) -> bool {
+    let ctx = state.borrow::<RuntimeContext>();
+    ctx.feature_flags.contains(&flag)
+}
+
+/// The last gateway latency fetched from the bot state, heartbeats are tens of seconds apart so
+/// it's reused for a little while instead of asking the bot state on every call
+struct CachedGatewayLatency {
+    fetched_at: Instant,
+    latency: Option<u64>,
+}
+
+const GATEWAY_LATENCY_CACHE_TTL: Duration = Duration::from_secs(5);
+
+/// Heartbeat latency in milliseconds of the shard the guild is on, None if it's not known yet
+#[op]
+pub async fn op_bl_get_gateway_latency(
+    state: Rc<RefCell<OpState>>,
+) -> Result<Option<u64>, AnyError> {
+    if let Some(cached) = state.borrow().try_borrow::<CachedGatewayLatency>() {
+        if cached.fetched_at.elapsed() < GATEWAY_LATENCY_CACHE_TTL {
+            return Ok(cached.latency);
+        }
+    }
+
+    let rt_ctx = get_rt_ctx(&state);
+
+    let latency = rt_ctx
+        .bot_state
+        .get_gateway_latency(rt_ctx.guild_id)
+        .await?;
+
+    state.borrow_mut().put(CachedGatewayLatency {
+        fetched_at: Instant::now(),
+        latency,
+    });
+    Ok(latency)
+}
+
+/// The guild's timezone, fetched the first time a script needs it. Changing it in the settings
+/// restarts the vm so this doesn't go stale.
+struct GuildTimezone(chrono_tz::Tz);
+
+/// The current time in the guild's configured timezone, falls back to UTC if none is configured
+#[op]
+pub async fn op_bl_now_local(state: Rc<RefCell<OpState>>) -> Result<LocalTime, AnyError> {
+    let cached = state.borrow().try_borrow::<GuildTimezone>().map(|v| v.0);
+    let tz = if let Some(tz) = cached {
+        tz
+    } else {
+        let rt_ctx = get_rt_ctx(&state);
+        let conf = rt_ctx
+            .config_store
+            .get_guild_meta_config_or_default(rt_ctx.guild_id)
+            .await?;
+
+        // an invalid zone can only be fixed by changing the config, so don't fail the script over it
+        let tz = conf
+            .timezone
+            .and_then(|name| name.parse::<chrono_tz::Tz>().ok())
+            .unwrap_or(chrono_tz::UTC);
+
+        state.borrow_mut().put(GuildTimezone(tz));
+        tz
+    };
+
+    let now = chrono::Utc::now().with_timezone(&tz);
+    Ok(LocalTime {
+        iso: now.to_rfc3339(),
+        timezone: tz.name().to_string(),
+        offset_minutes: now.offset().fix().local_minus_utc() / 60,
+    })
+}
+
+pub(crate) fn validate_script_meta(
+    meta: &ScriptMeta,
+    premium_tier: Option<PremiumSlotTier>,
+) -> Result<(), anyhow::Error> {
     let mut outbuf = String::new();
 
     for command in &meta.commands {
@@ -180,6 +601,19 @@ pub(crate) fn validate_script_meta(meta: &ScriptMeta) -> Result<(), anyhow::Erro
         }
     }
 
+    let max_interval_timers = limits::interval_timers_script_for_tier(premium_tier);
+    if meta.interval_timers.len() as u64 > max_interval_timers {
+        outbuf.push_str(
+            format!(
+                "\nscript {} has {} interval timers, the max is {}",
+                meta.script_id,
+                meta.interval_timers.len(),
+                max_interval_timers
+            )
+            .as_str(),
+        );
+    }
+
     for group in &meta.command_groups {
         if let Err(verrs) = validation::validate(group) {
             for verr in verrs {
@@ -188,6 +622,18 @@ pub(crate) fn validate_script_meta(meta: &ScriptMeta) -> Result<(), anyhow::Erro
         }
     }
 
+    match meta.api_version {
+        Some(0) => outbuf.push_str("\nruntime api versions start at 1"),
+        Some(v) if v > RUNTIME_API_VERSION => outbuf.push_str(
+            format!(
+                "\nscript targets runtime api version {v}, but this host only supports up to \
+                 version {RUNTIME_API_VERSION}"
+            )
+            .as_str(),
+        ),
+        _ => {}
+    }
+
     if outbuf.is_empty() {
         Ok(())
     } else {
@@ -214,7 +660,9 @@ pub fn try_insert_resource_table<T: deno_core::Resource>(
 
 pub enum RuntimeEvent {
     ScriptStarted(ScriptMeta),
+    ScriptValidationFailed { script_id: u64, reason: String },
     NewTaskScheduled,
+    TaskHandled { task_id: u64, error: Option<String> },
     InvalidRequestsExceeded,
 }
 
@@ -1,4 +1,4 @@
-use std::{cell::RefCell, num::NonZeroU32, rc::Rc};
+use std::{cell::RefCell, num::NonZeroU32, rc::Rc, sync::Arc};
 
 use crate::RuntimeContext;
 use deno_core::OpState;
@@ -7,7 +7,12 @@ use governor::{
     state::{InMemoryState, NotKeyed},
     Quota,
 };
-use stores::config::PremiumSlotTier;
+use guild_logger::LogEntry;
+use stores::{config::PremiumSlotTier, ratelimits::RateLimitStore};
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+use tracing::warn;
+use twilight_model::id::{marker::GuildMarker, Id};
+use vm::{dispatch_ops::DispatchOps, AnyError};
 
 pub type RateLimiter = governor::RateLimiter<NotKeyed, InMemoryState, DefaultClock>;
 
@@ -15,17 +20,34 @@ macro_rules! ratelimits {
     ($($name:ident => [$none:literal, $lite:literal, $premium:literal]),*) => {
         pub struct RateLimiters {
             $($name: RateLimiter,)*
+            tier: Option<PremiumSlotTier>,
+            shared: Option<SharedRateLimiter>,
         }
 
         impl RateLimiters {
-            $(pub async fn $name(op_state: &Rc<RefCell<OpState>>) {
+            $(pub async fn $name(op_state: &Rc<RefCell<OpState>>) -> Result<(), AnyError> {
+                count_dispatch_op(op_state)?;
+
                 let ratelimiters = { op_state.borrow().borrow::<Rc<RateLimiters>>().clone() };
                 ratelimiters.$name.until_ready().await;
+
+                // the in memory limiter is only per vm, the shared one is the authority
+                if let Some(shared) = &ratelimiters.shared {
+                    let per_second = match ratelimiters.tier {
+                        None => $none,
+                        Some(PremiumSlotTier::Lite) => $lite,
+                        Some(PremiumSlotTier::Premium) => $premium,
+                    };
+
+                    shared.until_ready(stringify!($name), per_second).await;
+                }
+
+                Ok(())
             })*
         }
 
         impl RateLimiters {
-            pub fn new(tier: Option<PremiumSlotTier>) -> Self {
+            pub fn new(tier: Option<PremiumSlotTier>, shared: Option<SharedRateLimiter>) -> Self {
                 Self {
                     $(
                         $name: RateLimiter::direct(Quota::per_second(
@@ -37,41 +59,231 @@ macro_rules! ratelimits {
                             .unwrap(),
                         )),
                     )*
+                    tier,
+                    shared,
                 }
             }
         }
     };
 }
 
+/// Rate limiter backed by a store, shared by all the vm's of a guild
+pub struct SharedRateLimiter {
+    pub store: Arc<dyn RateLimitStore>,
+    pub guild_id: Id<GuildMarker>,
+}
+
+impl SharedRateLimiter {
+    async fn until_ready(&self, bucket: &str, per_second: u32) {
+        match self
+            .store
+            .take_token(self.guild_id, bucket, per_second, per_second)
+            .await
+        {
+            Ok(Some(wait)) => tokio::time::sleep(wait).await,
+            Ok(None) => {}
+            Err(err) => {
+                // don't block scripts if the store is having issues, the in memory limiter still applies
+                warn!(%err, guild_id = %self.guild_id, "failed taking shared ratelimit token");
+            }
+        }
+    }
+}
+
+/// Caps the number of operations in flight at the same time, further ones wait until a permit is released
+#[derive(Clone)]
+pub struct ConcurrencyLimiter(Arc<Semaphore>);
+
+impl ConcurrencyLimiter {
+    pub fn new(max_in_flight: usize) -> Self {
+        Self(Arc::new(Semaphore::new(max_in_flight)))
+    }
+
+    /// The operation is considered in flight until the returned permit is dropped
+    pub async fn acquire(&self) -> OwnedSemaphorePermit {
+        self.0
+            .clone()
+            .acquire_owned()
+            .await
+            .expect("concurrency limiter semaphore is never closed")
+    }
+}
+
 macro_rules! numeric_limit {
     ($name:ident => [$none:literal, $lite:literal, $premium:literal]) => {
         pub fn $name(op_state: &Rc<RefCell<OpState>>) -> u64 {
-            let premium_tier = {
-                let state = op_state.borrow();
-                state.borrow::<RuntimeContext>().premium_tier
-            };
-
+            match current_premium_tier(op_state) {
+                None => $none,
+                Some(PremiumSlotTier::Lite) => $lite,
+                Some(PremiumSlotTier::Premium) => $premium,
+            }
+        }
+    };
+    ($name:ident, $tier_name:ident => [$none:literal, $lite:literal, $premium:literal]) => {
+        pub fn $tier_name(premium_tier: Option<PremiumSlotTier>) -> u64 {
             match premium_tier {
                 None => $none,
                 Some(PremiumSlotTier::Lite) => $lite,
                 Some(PremiumSlotTier::Premium) => $premium,
             }
         }
+
+        pub fn $name(op_state: &Rc<RefCell<OpState>>) -> u64 {
+            $tier_name(current_premium_tier(op_state))
+        }
     };
 }
 
+fn current_premium_tier(op_state: &Rc<RefCell<OpState>>) -> Option<PremiumSlotTier> {
+    let state = op_state.borrow();
+    state.borrow::<RuntimeContext>().premium_tier
+}
+
 ratelimits! {
     // number of guild http requests per second
     user_http => [1, 2, 2],
     // number of task operations per second
-    task_ops => [1, 2, 3]
+    task_ops => [1, 2, 3],
+    // number of messages sent per second by broadcasts
+    broadcast_messages => [1, 2, 3],
+    // number of messages edited per second by batch edits
+    batch_message_edits => [1, 2, 3],
+    // number of script contributes lookups per second
+    script_contributes => [1, 2, 3],
+    // number of script source map lookups per second
+    source_maps => [1, 2, 3],
+    // number of discord requests per second for listing, pruning and restoring registered commands
+    command_requests => [1, 2, 3]
+}
+
+// max total amount of bucket storage used on a guild, in bytes, shared with the api which
+// checks it on bundle imports
+pub fn storage_total_bytes_for_tier(premium_tier: Option<PremiumSlotTier>) -> u64 {
+    validation::web::storage_total_bytes_max(premium_tier)
+}
+
+pub fn storage_total_bytes(op_state: &Rc<RefCell<OpState>>) -> u64 {
+    storage_total_bytes_for_tier(current_premium_tier(op_state))
 }
 
-// max total amount of bucket storage used on a guild
-numeric_limit! {storage_total_size => [1_000_000, 10_000_000, 100_000_000]}
+// max serialized size of a single bucket storage value, in bytes
+numeric_limit! {storage_value_size, storage_value_size_for_tier => [1_000_000, 2_000_000, 5_000_000]}
 
 // max data size in a single task
 numeric_limit! {tasks_data_size => [1_000, 10_000, 10_000]}
 
 // max number of scheduled tasks
 numeric_limit! {tasks_scheduled_count => [10_000, 100_000, 100_000]}
+
+// max number of http requests in flight at the same time in a vm
+numeric_limit! {user_http_concurrent_requests => [2, 5, 10]}
+
+// max number of discord, storage and rate limited ops started between two event dispatches
+//
+// ops aren't attributed to the event whose handler started them, so this is shared by all the
+// handlers running in that time, including ones still running from earlier events
+numeric_limit! {ops_between_dispatches, ops_between_dispatches_for_tier => [2_000, 5_000, 10_000]}
+
+/// Counts an op towards [ops_between_dispatches], once that's exceeded further ops fail until
+/// the next event is dispatched, aborting the handlers unless they catch the error
+pub fn count_dispatch_op(op_state: &Rc<RefCell<OpState>>) -> Result<(), AnyError> {
+    let max = ops_between_dispatches(op_state);
+    let count = {
+        let state = op_state.borrow();
+        if let Err(count) = count_op(state.borrow::<Rc<DispatchOps>>(), max) {
+            count
+        } else {
+            return Ok(());
+        }
+    };
+
+    // the handler could catch the error, so make sure it shows up in the log at least once
+    if count == max + 1 {
+        let state = op_state.borrow();
+        let ctx = state.borrow::<RuntimeContext>();
+        ctx.guild_logger.log(LogEntry::error(
+            ctx.guild_id,
+            format!(
+                "Your scripts made more than {max} discord, storage or other limited calls since \
+                 the last event, further calls fail until the next event. Are you making a call \
+                 for every item in a large list?"
+            ),
+        ));
+    }
+
+    Err(anyhow::anyhow!(
+        "can't make more than {max} discord, storage or other limited calls between events on \
+         your guild's plan"
+    ))
+}
+
+// counts the op, failing with the number of ops since the last dispatch once that's over max
+fn count_op(ops: &DispatchOps, max: u64) -> Result<(), u64> {
+    let count = ops.increment();
+    if count <= max {
+        Ok(())
+    } else {
+        Err(count)
+    }
+}
+
+// max number of interval timers a single script can register
+numeric_limit! {interval_timers_script, interval_timers_script_for_tier => [20, 40, 80]}
+
+// max number of interval timers across all the scripts on a guild
+numeric_limit! {interval_timers_guild, interval_timers_guild_for_tier => [25, 50, 100]}
+
+// max extra time in milliseconds an event can extend its runaway deadline by
+numeric_limit! {deadline_extension_ms, deadline_extension_ms_for_tier => [5_000, 10_000, 20_000]}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use super::*;
+
+    #[tokio::test]
+    async fn concurrency_limiter_queues_excess() {
+        let limiter = ConcurrencyLimiter::new(2);
+        let in_flight = Arc::new(AtomicUsize::new(0));
+        let max_seen = Arc::new(AtomicUsize::new(0));
+
+        let handles = (0..6)
+            .map(|_| {
+                let limiter = limiter.clone();
+                let in_flight = in_flight.clone();
+                let max_seen = max_seen.clone();
+                tokio::spawn(async move {
+                    let _permit = limiter.acquire().await;
+                    let current = in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+                    max_seen.fetch_max(current, Ordering::SeqCst);
+                    tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+                    in_flight.fetch_sub(1, Ordering::SeqCst);
+                })
+            })
+            .collect::<Vec<_>>();
+
+        for handle in handles {
+            handle.await.unwrap();
+        }
+
+        assert_eq!(max_seen.load(Ordering::SeqCst), 2);
+        assert_eq!(in_flight.load(Ordering::SeqCst), 0);
+    }
+
+    #[test]
+    fn ops_over_the_cap_fail_until_next_dispatch() {
+        let max = ops_between_dispatches_for_tier(None);
+        let ops = DispatchOps::new();
+
+        for _ in 0..max {
+            assert!(count_op(&ops, max).is_ok());
+        }
+        assert_eq!(count_op(&ops, max), Err(max + 1));
+        assert_eq!(count_op(&ops, max), Err(max + 2));
+
+        // what the vm does when it dispatches the next event
+        ops.reset();
+        assert!(count_op(&ops, max).is_ok());
+    }
+}
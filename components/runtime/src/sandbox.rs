@@ -0,0 +1,88 @@
+//! Sandboxed vm's are used for test runs from the editor, they run the guild's script against a
+//! sample event without being able to affect the guild in any way.
+//!
+//! Only the ops listed here are available, every other op is replaced with one that throws. New
+//! ops are disabled in sandboxes until they're added to the list, so check that they only touch
+//! state local to the vm before doing that.
+
+/// Ops from deno_core itself, they only touch the isolate and resources created by other ops
+const CORE_OPS: &[&str] = &[
+    "op_close",
+    "op_try_close",
+    "op_print",
+    "op_resources",
+    "op_wasm_streaming_feed",
+    "op_wasm_streaming_set_url",
+    "op_void_sync",
+    "op_void_async",
+    "op_add",
+    "op_read",
+    "op_read_all",
+    "op_write",
+    "op_write_all",
+    "op_shutdown",
+    "op_metrics",
+    "op_format_file_name",
+    "op_is_proxy",
+    "op_str_byte_length",
+    "op_ref_op",
+    "op_unref_op",
+    "op_set_macrotask_callback",
+    "op_set_next_tick_callback",
+    "op_set_promise_reject_callback",
+    "op_run_microtasks",
+    "op_has_tick_scheduled",
+    "op_set_has_tick_scheduled",
+    "op_eval_context",
+    "op_queue_microtask",
+    "op_create_host_object",
+    "op_encode",
+    "op_decode",
+    "op_serialize",
+    "op_deserialize",
+    "op_set_promise_hooks",
+    "op_get_promise_details",
+    "op_get_proxy_details",
+    "op_memory_usage",
+    "op_set_wasm_streaming_callback",
+    "op_abort_wasm_streaming",
+    "op_destructure_error",
+    "op_dispatch_exception",
+    "op_op_names",
+    "op_apply_source_map",
+    "op_set_format_exception_callback",
+    "op_event_loop_has_more_work",
+    "op_store_pending_promise_rejection",
+    "op_remove_pending_promise_rejection",
+    "op_has_pending_promise_rejection",
+    "op_arraybuffer_was_detached",
+];
+
+/// Botloader ops that only read or compute things within the vm
+const SANDBOX_ALLOWED_OPS: &[&str] = &[
+    // script lifecycle and logging, the logs are the result of a test run
+    "op_botloader_script_start",
+    "op_botloader_log",
+    // static info about the bot and the vm
+    "op_get_current_bot_user",
+    "op_get_current_guild_id",
+    "op_bl_get_gateway_intents",
+    "op_bl_list_event_handlers",
+    "op_bl_last_restart_reason",
+    "op_bl_feature_enabled",
+    "op_bl_storage_namespace",
+    "op_bl_get_source_map",
+    "op_bl_extend_deadline",
+    // pure helpers
+    "op_bl_snowflake_timestamp",
+    "op_bl_random_bytes",
+    "op_bl_random_int",
+    // events within the vm
+    "op_bl_await_any",
+    "op_bl_resolve_event_waiters",
+    "op_discord_update_command_permissions_cache",
+];
+
+pub fn is_disabled_in_sandbox(op_name: &str) -> bool {
+    !CORE_OPS.contains(&op_name) && !SANDBOX_ALLOWED_OPS.contains(&op_name)
+}
@@ -12,6 +12,11 @@ import { OpWrappers } from "./op_wrappers";
  */
 export namespace Commands {
 
+    /**
+     * @internal
+     */
+    export const SCRIPT_DISABLED_MESSAGE = "This script is temporarily disabled because it errored too often, try again later";
+
     /**
      * @internal
      */
@@ -19,24 +24,51 @@ export namespace Commands {
 
         commands: Command[] = [];
 
-        addCommand(cmd: Command) {
+        // the script that registered each command, so they can be removed when it's reloaded
+        private owners = new Map<Command, number>();
+
+        addCommand(cmd: Command, scriptId?: number) {
             if (this.commands.find(v => matchesCommand(cmd, v.name, v.group?.name, v.group?.parent?.name))) {
                 throw new Error(`Duplicate commands registered! Cmd: ${cmd.name}, parent: ${cmd.group?.name}, parent of parent: ${cmd.group?.parent?.name}`)
             }
 
             this.commands.push(cmd);
+            if (scriptId !== undefined) {
+                this.owners.set(cmd, scriptId);
+            }
+        }
+
+        /**
+         * @internal
+         */
+        removeScriptCommands(scriptId: number) {
+            this.commands = this.commands.filter(cmd => this.owners.get(cmd) !== scriptId);
+            for (const [cmd, owner] of this.owners) {
+                if (owner === scriptId) {
+                    this.owners.delete(cmd);
+                }
+            }
         }
 
         /**
          * @internal
          */
-        async handleInteractionCreate(interaction: Internal.CommandInteraction) {
+        async handleInteractionCreate(interaction: Internal.CommandInteraction, disabledScripts: number[]) {
             let command = this.commands.find(cmd => matchesCommand(cmd, interaction.name, interaction.parentName, interaction.parentParentName));
             if (!command) {
                 return;
             }
 
             let ctx = new ExecutedCommandContext(interaction);
+
+            const owner = this.owners.get(command);
+            if (owner !== undefined && disabledScripts.includes(owner)) {
+                await ctx.ackWithMessage({
+                    content: SCRIPT_DISABLED_MESSAGE,
+                    flags: { ephemeral: true },
+                });
+                return;
+            }
             if (command.ackMode === "DeferredMessage") {
                 await ctx.ackWithDeferredMessage();
             }
@@ -157,6 +189,13 @@ export namespace Commands {
     export class ExecutedCommandContext extends Interaction {
         channelId: string;
 
+        /**
+         * Id of the command, subcommands share the id of their top level command
+         * 
+         * See `getCommandPermissions` for checking who the server allows to use it
+         */
+        commandId: string;
+
         /**
          * Name of the command triggered
          * 
@@ -186,6 +225,7 @@ export namespace Commands {
             super(interaction.id, interaction.token, new Member(interaction.member));
 
             this.channelId = interaction.channelId;
+            this.commandId = interaction.commandId;
             this.commandName = interaction.name;
             this.parentName = interaction.parentName ?? undefined;
             this.parentParentName = interaction.parentParentName ?? undefined;
@@ -210,6 +250,17 @@ export namespace Commands {
                 }
             })
         }
+
+        /**
+         * Acknowledge this interaction by showing the user a form with up to 5 text inputs.
+         * 
+         * Works the same as `ComponentInteraction.showModal`, see that for the limits and an example.
+         */
+        async showModal(modal: Internal.OpShowModal) {
+            this.setCallbackSent();
+
+            return OpWrappers.showModal(this.interactionId, this.token, modal);
+        }
     }
 
 
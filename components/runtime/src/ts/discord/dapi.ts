@@ -1,4 +1,4 @@
-import { Guild, Role, Embed, IComponent, AuditLogExtras, SendEmoji, IPermissionOverwrite, VideoQualityMode, ChannelType, PermissionOverwriteType } from '../generated/discord/index';
+import { GuildResult, GuildBoostInfo, MemberCounts, CommandPermissions, Role, Embed, IComponent, AuditLogExtras, SendEmoji, IPermissionOverwrite, VideoQualityMode, ChannelType, PermissionOverwriteType } from '../generated/discord/index';
 import * as Internal from '../generated/internal/index';
 import { OpWrappers } from '../op_wrappers';
 import { GuildChannel, guildChannelFromInternal } from './channel';
@@ -21,10 +21,80 @@ export function getCurrentGuildId(): string {
     return OpWrappers.getCurrentGuildId();
 }
 
+/**
+ * Returns which privileged gateway intents the bot has, events relying on an intent the bot doesn't have are never delivered.
+ * 
+ * You can use this to warn the server admins that a feature will not work, for example message content will be empty without `messageContent`.
+ */
+export function getGatewayIntents(): Internal.GatewayIntents {
+    return OpWrappers.getGatewayIntents();
+}
+
+/**
+ * Returns the most recent heartbeat latency in milliseconds of the gateway connection this server is on.
+ * 
+ * Returns null if the connection hasn't received a heartbeat yet.
+ * 
+ * @example ```ts
+ * const latency = await getGatewayLatency();
+ * await createMessage(channelId, { content: `Gateway latency: ${latency ?? "unknown"}ms` });
+ * ```
+ */
+export function getGatewayLatency(): Promise<number | null> {
+    return OpWrappers.getGatewayLatency();
+}
+
 // Guild functions
-export function getGuild(): Promise<Guild> {
+/**
+ * Get the server from the bot's state.
+ * 
+ * If it's not in there yet only what botloader has stored about it is known, `kind` is "Stored" in that case instead of "Full".
+ * 
+ * @example ```ts
+ * const guild = await getGuild();
+ * if (guild.kind === "Full") {
+ *     console.log(`${guild.name} has ${guild.memberCount} members`);
+ * }
+ * ```
+ */
+export function getGuild(): Promise<GuildResult> {
     return OpWrappers.getGuild()
 }
+
+/**
+ * Get the server's discord boost level and number of boosts, this is separate from botloader premium.
+ * 
+ * Returns a boost count of 0 and a tier of "None" if the server isn't known yet.
+ * 
+ * @example ```ts
+ * const boosts = await getBoostInfo();
+ * if (boosts.premiumTier === "Tier3") {
+ *     // unlock the fancy stuff
+ * }
+ * ```
+ */
+export function getBoostInfo(): Promise<GuildBoostInfo> {
+    return OpWrappers.getBoostInfo()
+}
+
+/**
+ * Get the number of members in the server, and roughly how many of them are online.
+ * 
+ * Both counts are discord's own estimates, which are only updated every few minutes, so they can
+ * lag behind members joining and leaving. Exact online counts need presence updates which require the
+ * privileged `GUILD_PRESENCES` gateway intent, which botloader doesn't request. `online` is null if
+ * discord didn't provide an estimate.
+ * 
+ * The counts are cached for a minute, so calling this often won't give more recent numbers.
+ * 
+ * @example ```ts
+ * const counts = await getMemberCounts();
+ * await editChannel(statsChannelId, { name: `Members: ${counts.total}` });
+ * ```
+ */
+export function getMemberCounts(): Promise<MemberCounts> {
+    return OpWrappers.getMemberCounts()
+}
 function editGuild() { }
 
 // Message functions
@@ -141,6 +211,84 @@ export async function createMessage(channelId: string, fields: CreateMessageFiel
         fields: toOpMessageFields(fields),
     }));
 }
+
+export interface BroadcastResult {
+    channelId: string,
+    /**
+     * The message that was sent, if it succeeded
+     */
+    message?: Message,
+    /**
+     * Why sending to this channel failed, if it did
+     */
+    error?: string,
+}
+
+/**
+ * Sends the same message to multiple channels (max 50), the sends are paced to avoid hitting ratelimits.
+ * 
+ * Failing to send to one channel (missing permissions for example) does not stop the rest, check the `error` field of each result.
+ */
+export async function broadcastMessage(channelIds: string[], fields: CreateMessageFields): Promise<BroadcastResult[]> {
+    const results = await OpWrappers.broadcastChannelMessage({
+        channelIds,
+        fields: toOpMessageFields(fields),
+    });
+
+    return results.map(v => ({
+        channelId: v.channelId,
+        message: v.message ? new Message(v.message) : undefined,
+        error: v.error ?? undefined,
+    }));
+}
+export interface CreatePollFields {
+    /**
+     * The question, max 300 characters
+     */
+    question: string,
+    /**
+     * Between 1 and 10 answers, max 55 characters each
+     */
+    answers: string[],
+    /**
+     * How long the poll is open for in hours, between 1 and 768 (32 days)
+     */
+    durationHours: number,
+    /**
+     * Allow voting for more than one answer, defaults to false
+     */
+    allowMultiselect?: boolean,
+}
+
+/**
+ * Creates a native discord poll in the channel.
+ * 
+ * Requires the bot to have the Send Polls permission in the channel, otherwise discord rejects the poll and this throws instead.
+ * 
+ * Votes are not delivered as events, use {@link getPollResults} to read the current counts.
+ * 
+ * @example ```ts
+ * const poll = await createPoll(channelId, {
+ *     question: "Next game night?",
+ *     answers: ["Friday", "Saturday", "Sunday"],
+ *     durationHours: 24,
+ * });
+ * ```
+ */
+export async function createPoll(channelId: string, fields: CreatePollFields): Promise<Message> {
+    return new Message(await OpWrappers.createPoll(channelId, fields));
+}
+
+/**
+ * Get the current vote counts of a poll created with {@link createPoll} (or by anyone else).
+ * 
+ * The counts are final once `isFinalized` is true, before that they may still change.
+ * Throws if the message is not a poll.
+ */
+export async function getPollResults(channelId: string, messageId: string): Promise<Internal.PollResults> {
+    return await OpWrappers.getPollResults(channelId, messageId);
+}
+
 export async function editMessage(channelId: string, messageId: string, fields: CreateMessageFields): Promise<Message> {
     return new Message(await OpWrappers.editChannelMessage({
         channelId,
@@ -149,6 +297,45 @@ export async function editMessage(channelId: string, messageId: string, fields:
     }));
 }
 
+export interface EditMessagesEntry {
+    channelId: string,
+    messageId: string,
+    fields: CreateMessageFields,
+}
+
+export interface EditMessagesResult {
+    channelId: string,
+    messageId: string,
+    /**
+     * The edited message, if it succeeded
+     */
+    message?: Message,
+    /**
+     * Why editing this message failed, if it did
+     */
+    error?: string,
+}
+
+/**
+ * Edits multiple messages (max 50), the edits are paced to avoid hitting ratelimits.
+ * 
+ * Failing to edit one message (it was deleted for example) does not stop the rest, check the `error` field of each result.
+ */
+export async function editMessages(edits: EditMessagesEntry[]): Promise<EditMessagesResult[]> {
+    const results = await OpWrappers.editChannelMessages(edits.map(v => ({
+        channelId: v.channelId,
+        messageId: v.messageId,
+        fields: toOpMessageFields(v.fields),
+    })));
+
+    return results.map(v => ({
+        channelId: v.channelId,
+        messageId: v.messageId,
+        message: v.message ? new Message(v.message) : undefined,
+        error: v.error ?? undefined,
+    }));
+}
+
 export async function crosspostMessage(channelId: string, messageId: string): Promise<void> {
     return OpWrappers.crosspostChannelMessage(channelId, messageId);
 }
@@ -175,6 +362,21 @@ export function getRole(roleId: string): Promise<Role> {
 export function getRoles(): Promise<Role[]> {
     return OpWrappers.getRoles();
 }
+/**
+ * Lists the roles on the guild, sorted by position with the highest role first
+ */
+export function listRoles(): Promise<Role[]> {
+    return OpWrappers.listRoles();
+}
+/**
+ * Returns the bot's highest role on the guild, useful for checking if the bot is able to manage a role or member
+ * before attempting to do so.
+ * 
+ * Returns the everyone role if the bot has no other roles.
+ */
+export function getBotHighestRole(): Promise<Role> {
+    return OpWrappers.getBotHighestRole();
+}
 
 async function createRole() { }
 async function editRole() { }
@@ -188,6 +390,15 @@ export async function getChannels(): Promise<GuildChannel[]> {
     return (await OpWrappers.getChannels()).map(v => guildChannelFromInternal(v));
 }
 
+/**
+ * Lists the channels on the guild, sorted the same way as they're displayed in discord
+ * 
+ * @param typeFilter Only return channels of this type, e.g "Text" for a log channel picker
+ */
+export async function listChannels(typeFilter?: ChannelType): Promise<GuildChannel[]> {
+    return (await OpWrappers.listChannels(typeFilter ?? null)).map(v => guildChannelFromInternal(v));
+}
+
 export interface ICreateChannel {
     name: string;
     kind?: ChannelType;
@@ -247,10 +458,41 @@ export async function deleteChannel(channelId: string): Promise<GuildChannel> {
     return guildChannelFromInternal(await OpWrappers.deleteChannel(channelId));
 }
 
+/**
+ * Set the slowmode of a channel, the time users have to wait between sending messages.
+ *
+ * Requires the bot to have the Manage Channels permission in the channel.
+ *
+ * @param seconds The slowmode in seconds, between 0 (disabled) and 21600 (6 hours)
+ *
+ * @example ```ts
+ * // slow down a channel during a raid
+ * await setSlowmode(channelId, 30);
+ * ```
+ */
+export async function setSlowmode(channelId: string, seconds: number): Promise<GuildChannel> {
+    return guildChannelFromInternal(await OpWrappers.setSlowmode(channelId, seconds));
+}
+
+/**
+ * Create or replace the permission overwrite for a role or member in a channel, for example to lock a channel during a raid.
+ *
+ * Requires the bot to have the Manage Roles permission in the channel, and it can only allow or deny permissions it has itself.
+ *
+ * Throws if the same permission is both allowed and denied.
+ *
+ * @example ```ts
+ * // stop everyone from sending messages in the channel
+ * await editChannelPermission(channelId, PermissionOverwrite.everyone(new Permissions(), Permissions.SendMessages));
+ * ```
+ */
 export async function editChannelPermission(channelId: string, overwrite: IPermissionOverwrite): Promise<void> {
     return OpWrappers.updateChannelPermission(channelId, overwrite);
 }
 
+/**
+ * Remove the permission overwrite for a role or member in a channel, the same requirements as {@link editChannelPermission} apply.
+ */
 export async function deleteChannelPermission(channelId: string, kind: PermissionOverwriteType, id: string): Promise<void> {
     return OpWrappers.deleteChannelPermission(channelId, kind, id);
 }
@@ -266,6 +508,16 @@ export async function deletePin(channelId: string, messageId: string): Promise<v
     return OpWrappers.op_discord_delete_pin(channelId, messageId);
 }
 
+/**
+ * Shows the "Bot is typing..." indicator in the channel.
+ * 
+ * The indicator lasts around 10 seconds or until the bot sends a message, 
+ * so call this periodically if you're doing work that takes longer than that.
+ */
+export async function triggerTyping(channelId: string): Promise<void> {
+    return OpWrappers.op_discord_trigger_typing(channelId);
+}
+
 // Invite functions
 async function getInvite() { }
 async function getInvites() { }
@@ -287,6 +539,15 @@ async function createSticker() { }
 async function editSticker() { }
 async function deleteSticker() { }
 
+/**
+ * Fetches a user by their id, this works for users that are not members of the guild as well
+ * 
+ * @throws a not found error if the user does not exist
+ */
+export async function getUser(id: string): Promise<User> {
+    return new User(await OpWrappers.getUser(id));
+}
+
 export async function getMember(id: string): Promise<Member | undefined> {
     const member = (await OpWrappers.getMembers([id]))[0];
     if (member) {
@@ -300,6 +561,56 @@ export async function getMembers(ids: string[]): Promise<(Member | null)[]> {
     return (await OpWrappers.getMembers(ids)).map(v => v ? new Member(v) : null);
 }
 
+export interface MemberDetail {
+    /**
+     * When the member joined the server, in milliseconds since the unix epoch
+     */
+    joinedAt: number;
+
+    /**
+     * The ids of the member's roles
+     */
+    roles: string[];
+
+    nick: string | null;
+
+    /**
+     * When the member started boosting the server, in milliseconds since the unix epoch, null if they're not boosting
+     */
+    premiumSince: number | null;
+}
+
+/**
+ * Fetches a member's join date, roles, nickname and boosting status in one call, useful for things like tenure based roles
+ * 
+ * @throws a not found error if the user is not a member of the server
+ * 
+ * @example ```ts
+ * const detail = await getMemberDetail(userId);
+ * const daysInServer = (Date.now() - detail.joinedAt) / (1000 * 60 * 60 * 24);
+ * ```
+ */
+export async function getMemberDetail(userId: string): Promise<MemberDetail> {
+    return await OpWrappers.getMemberDetail(userId);
+}
+
+/**
+ * Returns true if the member has not passed membership screening yet, only relevant if the server has it enabled
+ * 
+ * Pending members can't interact with the server yet, so you may want to defer granting roles until they pass it
+ * 
+ * @throws a not found error if the user is not a member of the server
+ * 
+ * @example ```ts
+ * if (!await isMemberPending(userId)) {
+ *     await addMemberRole(userId, verifiedRoleId);
+ * }
+ * ```
+ */
+export async function isMemberPending(userId: string): Promise<boolean> {
+    return await OpWrappers.memberPending(userId);
+}
+
 /**
  * Fields that are not provided will be left unchanged.
  */
@@ -416,6 +727,24 @@ export async function getMemberChannelPermissions(memberOrUserId: Member | strin
     };
 }
 
+/**
+ * Get the overrides configured in the server settings (Integrations) for who can use a command and in which channels
+ * 
+ * Discord enforces these before the command reaches your script, this is for when you want to double check,
+ * e.g. before running something the command triggered later. The result is cached and kept up to date.
+ * 
+ * @param commandId The id of the command, available as `commandId` on the context passed to command handlers
+ * @returns The overrides, an empty list of permissions if none are set up
+ * 
+ * @example ```ts
+ * const perms = await getCommandPermissions(ctx.commandId);
+ * const denied = perms.permissions.some(p => p.kind === "User" && p.id === ctx.member.user.id && !p.allowed);
+ * ```
+ */
+export async function getCommandPermissions(commandId: string): Promise<CommandPermissions> {
+    return await OpWrappers.getCommandPermissions(commandId);
+}
+
 // Guild bans
 export interface CreateBanExtras extends AuditLogExtras {
     deleteMessageDays: number
@@ -456,17 +785,37 @@ export interface GetReactionsExtras {
     after?: string,
 
     /**
-     * Limit the number of results, defaults to 25, max 100 at the time of writing
+     * Limit the number of results, defaults to 25, max 100
      */
     limit?: number,
 }
 
-export async function getReactions(channelId: string, messageId: string, emoji: SendEmoji, extra?: GetReactionsExtras): Promise<User[]> {
+/**
+ * Fetches the users that reacted with the emoji, use `extra.after` with the id of the last user to paginate through all of them.
+ * 
+ * @param emoji Either a {@link SendEmoji} or a string, strings in the `name:id` form are treated as custom emojis and anything else as a unicode emoji
+ * 
+ * @throws {@link NotFoundError} if the message or emoji no longer exists
+ */
+export async function getReactions(channelId: string, messageId: string, emoji: SendEmoji | string, extra?: GetReactionsExtras): Promise<User[]> {
     return (await OpWrappers.discord_get_reactions(channelId, messageId, {
         ...extra,
-        emoji: emoji,
+        emoji: toSendEmoji(emoji),
     })).map(v => new User(v));
 }
+
+function toSendEmoji(emoji: SendEmoji | string): SendEmoji {
+    if (typeof emoji !== "string") {
+        return emoji;
+    }
+
+    const match = /^(?:<a?:)?([\w~]+):(\d+)>?$/.exec(emoji);
+    if (match) {
+        return { name: match[1], id: match[2] };
+    }
+
+    return { unicode: emoji };
+}
 export async function deleteAllReactions(channelId: string, messageId: string): Promise<void> {
     return OpWrappers.discord_delete_all_reactions(channelId, messageId);
 }
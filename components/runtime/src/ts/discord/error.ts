@@ -43,8 +43,21 @@ export class GenericError extends Error {
     }
 }
 
+/**
+ * Thrown when responding to an interaction after its token has expired, interaction tokens are valid for 15 minutes
+ */
+export class InteractionExpiredError extends Error {
+    message: string;
+
+    constructor(message: string) {
+        super(message)
+        this.message = "Discord.InteractionExpiredError: " + message;
+    }
+}
+
 Deno.core.registerErrorClass("DiscordNotFoundError", NotFoundError);
 Deno.core.registerErrorClass("DiscordPermissionsError", PermissionsError);
 Deno.core.registerErrorClass("DiscordLimitReachedError", LimitReachedError);
 Deno.core.registerErrorClass("DiscordServerErrorResponse", ServerError);
 Deno.core.registerErrorClass("DiscordGenericErrorResponse", GenericError);
+Deno.core.registerErrorClass("DiscordInteractionExpiredError", InteractionExpiredError);
@@ -4,6 +4,13 @@ import { CreateMessageFields, InteractionMessageFlags, InteractionCreateMessageF
 import { OpWrappers } from '../op_wrappers';
 import { Member } from './member';
 import { Message } from './message';
+import { snowflakeTimestamp } from './snowflake';
+import { InteractionExpiredError } from './error';
+
+/**
+ * How long an interaction token can be used for followups and editing the original response
+ */
+const INTERACTION_TOKEN_LIFETIME_MS = 15 * 60 * 1000;
 
 /**
  * Base interaction class, this class should be considered UNSTABLE and may change a lot in the future.
@@ -29,6 +36,24 @@ export class Interaction {
         this.token = token;
     }
 
+    /**
+     * Unix timestamp in milliseconds after which the token can no longer be used for followups
+     * or editing the original response
+     */
+    get tokenExpiresAt(): number {
+        return snowflakeTimestamp(this.interactionId) + INTERACTION_TOKEN_LIFETIME_MS;
+    }
+
+    get isTokenExpired(): boolean {
+        return Date.now() >= this.tokenExpiresAt;
+    }
+
+    protected assertTokenValid() {
+        if (this.isTokenExpired) {
+            throw new InteractionExpiredError("the interaction token expired, followups can only be sent within 15 minutes of the interaction");
+        }
+    }
+
     protected setCallbackSent() {
         if (this.hasSentCallback) {
             throw new Error("tried sending a callback when one has already been sent, only one callback per interaction can be sent.")
@@ -103,18 +128,22 @@ export class Interaction {
     }
 
     async getOriginalResponse() {
+        this.assertTokenValid();
         return getInteractionOriginalResponse(this.token);
     }
 
     async editOriginalResponse(fields: InteractionCreateMessageFields) {
+        this.assertTokenValid();
         return editInteractionOriginalResponse(this.token, fields)
     }
 
     async deleteOriginalResponse() {
+        this.assertTokenValid();
         return deleteInteractionOriginalResponse(this.token);
     }
 
     async getFollowup(messageId: string) {
+        this.assertTokenValid();
         return getInteractionFollowupMessage(this.token, messageId);
     }
 
@@ -122,18 +151,30 @@ export class Interaction {
      * @deprecated use {@link createFollowup} instead
      */
     async sendFollowup(resp: string | InteractionCreateMessageFields) {
+        this.assertTokenValid();
         return createInteractionFollowupMessage(this.token, resp);
     }
 
+    /**
+     * Sends a followup message, this can be used multiple times after the interaction has been acknowledged.
+     * 
+     * @throws {@link InteractionExpiredError} if it's been more than 15 minutes since the interaction was created
+     */
     async createFollowup(resp: string | InteractionCreateMessageFields) {
+        this.assertTokenValid();
         return createInteractionFollowupMessage(this.token, resp);
     }
 
+    /**
+     * @throws {@link InteractionExpiredError} if it's been more than 15 minutes since the interaction was created
+     */
     async editFollowup(messageId: string, fields: InteractionCreateMessageFields) {
+        this.assertTokenValid();
         return editInteractionFollowupMessage(this.token, messageId, fields);
     }
 
     async deleteFollowup(id: string) {
+        this.assertTokenValid();
         return deleteInteractionFollowupMessage(this.token, id);
     }
 }
@@ -231,6 +272,32 @@ export class ComponentInteraction extends Interaction {
             }
         })
     }
+
+    /**
+     * Acknowledge this interaction by showing the user a form with up to 5 text inputs.
+     * 
+     * The submission is delivered as a {@link ModalSubmitInteraction} to the handler registered with
+     * {@link Script.onInteractionModalSubmit} for the name used in the custom id, see {@link encodeInteractionCustomId}.
+     * 
+     * Titles and labels can be up to 45 characters, and inputs up to 4000 characters long.
+     * 
+     * You have to acknowledge the interaction within 3 seconds, and it can only be done once.
+     * 
+     * @example ```ts
+     * await interaction.showModal({
+     *     title: "Report a user",
+     *     customId: encodeInteractionCustomId("report", null),
+     *     inputs: [
+     *         { customId: "reason", label: "Reason", style: "Paragraph", required: true, maxLength: 1000, minLength: null, placeholder: null, value: null },
+     *     ],
+     * });
+     * ```
+     */
+    async showModal(modal: Internal.OpShowModal) {
+        this.setCallbackSent();
+
+        return OpWrappers.showModal(this.interactionId, this.token, modal);
+    }
 }
 
 export interface IModalFields {
@@ -1,10 +1,13 @@
+import { OpWrappers } from '../op_wrappers';
+
 export const DiscordEpoch = 1420070400000n;
 
 /**
  * Gets the timestamp from a Discord ID.
  * @param id The snowflake to deconstruct.
- * @returns The snowflake timestamp.
+ * @returns The snowflake timestamp in unix milliseconds.
+ * @throws If the id is not a valid snowflake.
  */
 export function snowflakeTimestamp(id: string): number {
-    return Number((BigInt(id) >> 22n) + DiscordEpoch);
+    return OpWrappers.snowflakeTimestamp(id);
 }
@@ -1,44 +1,72 @@
 import { Commands } from './commands';
-import { ComponentInteraction, ModalSubmitInteraction, EventMemberRemove, EventMessageDelete, EventMessageReactionAdd, guildChannelFromInternal, EventMessageReactionRemove, EventMessageReactionRemoveAll, IEventThreadDelete, EventMessageReactionRemoveAllEmoji, EventMessageUpdate, Interaction, Member, Message, GuildChannel, SelectMenuInteraction, parseInteractionCustomId, ChannelType } from './discord/index';
+import { ComponentInteraction, ModalSubmitInteraction, EventMemberRemove, EventMessageDelete, EventMessageReactionAdd, guildChannelFromInternal, EventMessageReactionRemove, EventMessageReactionRemoveAll, IEventThreadDelete, EventMessageReactionRemoveAllEmoji, EventMessageUpdate, CommandPermissions, Interaction, Member, Message, GuildChannel, SelectMenuInteraction, parseInteractionCustomId, ChannelType } from './discord/index';
 import * as Internal from './generated/internal/index';
+import { OpWrappers } from './op_wrappers';
 
 export namespace EventSystem {
 
-    const buttonComponentListeners: { name: string, cb: (data: ComponentInteraction, extra: any) => any }[] = [];
-    const selectMenuListeners: { name: string, cb: (data: SelectMenuInteraction, extra: any) => any }[] = [];
-    const modalSubmitListeners: { name: string, cb: (data: ModalSubmitInteraction, extra: any) => any }[] = [];
+    let buttonComponentListeners: { scriptId: number, name: string, cb: (data: ComponentInteraction, extra: any) => any }[] = [];
+    let selectMenuListeners: { scriptId: number, name: string, cb: (data: SelectMenuInteraction, extra: any) => any }[] = [];
+    let modalSubmitListeners: { scriptId: number, name: string, cb: (data: ModalSubmitInteraction, extra: any) => any }[] = [];
 
     /**
      * @internal
      */
     export const commandSystem = new Commands.System();
 
-    const eventMuxers: Muxer[] = [];
+    let eventMuxers: { scriptId: number, muxer: Muxer }[] = [];
 
     /**
      * @internal
      */
-    export function registerEventMuxer(muxer: Muxer) {
-        eventMuxers.push(muxer)
+    export function registerEventMuxer(scriptId: number, muxer: Muxer) {
+        eventMuxers.push({ scriptId, muxer })
+    }
+
+    /**
+     * Removes everything a script registered so a new version of it can be loaded in its place,
+     * called by the vm when a single script is reloaded without restarting the others.
+     * 
+     * @internal
+     */
+    export function unloadScript(scriptId: number) {
+        eventMuxers = eventMuxers.filter(entry => entry.scriptId !== scriptId);
+        buttonComponentListeners = buttonComponentListeners.filter(entry => entry.scriptId !== scriptId);
+        selectMenuListeners = selectMenuListeners.filter(entry => entry.scriptId !== scriptId);
+        modalSubmitListeners = modalSubmitListeners.filter(entry => entry.scriptId !== scriptId);
+        commandSystem.removeScriptCommands(scriptId);
     }
 
     /**
      * @internal
      */
     export function dispatchEvent(evt: DispatchEvent) {
+        if (pendingWaits > 0) {
+            OpWrappers.resolveEventWaiters(evt.name, evt.data);
+        }
+
+        if (evt.name === "APPLICATION_COMMAND_PERMISSIONS_UPDATE") {
+            OpWrappers.updateCommandPermissionsCache(evt.data);
+        }
+
         let data = evt.data;
         if (evt.name in converters) {
             data = converters[evt.name as keyof typeof converters](evt.data);
         }
 
         if (evt.name === "BOTLOADER_COMPONENT_INTERACTION_CREATE") {
-            handleComponentInteraction(data);
+            handleComponentInteraction(data, evt.disabledScripts);
         } else if (evt.name === "BOTLOADER_COMMAND_INTERACTION_CREATE") {
-            commandSystem.handleInteractionCreate(data);
+            commandSystem.handleInteractionCreate(data, evt.disabledScripts);
         } else if (evt.name == "BOTLOADER_MODAL_SUBMIT_INTERACTION_CREATE") {
-            handleModalSubmitInteraction(data);
+            handleModalSubmitInteraction(data, evt.disabledScripts);
         } else {
-            for (let muxer of eventMuxers) {
+            for (let { scriptId, muxer } of eventMuxers) {
+                // tasks are acked once dispatched, so they're still delivered to not lose them
+                if (evt.disabledScripts.includes(scriptId) && evt.name !== "BOTLOADER_SCHEDULED_TASK_FIRED") {
+                    continue;
+                }
+
                 muxer.handleEvent(evt.name, data);
             }
         }
@@ -46,6 +74,7 @@ export namespace EventSystem {
 
 
     BotloaderCore.dispatchEvent = dispatchEvent;
+    BotloaderCore.unloadScript = unloadScript;
 
     export interface EventTypes {
         /**
@@ -93,12 +122,18 @@ export namespace EventSystem {
         THREAD_CREATE: GuildChannel,
         THREAD_UPDATE: GuildChannel,
         THREAD_DELETE: IEventThreadDelete,
+
+        APPLICATION_COMMAND_PERMISSIONS_UPDATE: CommandPermissions,
     }
 
 
     interface DispatchEvent {
         name: string,
         data: any,
+        /**
+         * Scripts that errored too often recently, their handlers are disabled for a while
+         */
+        disabledScripts: number[],
     }
 
     type ListenerMap = {
@@ -139,29 +174,165 @@ export namespace EventSystem {
             }
         }
 
+        /**
+         * @internal
+         */
+        eventNames(): string[] {
+            // the BOTLOADER_ prefixed events are implementation details of commands, tasks and so on
+            return Object.keys(this.listeners).filter(name => !name.startsWith("BOTLOADER_"));
+        }
+
+    }
+
+    /**
+     * Lists the event handlers registered by all the scripts running on this server, useful for checking that your handlers were actually registered.
+     * 
+     * Handlers are reported when the script has finished loading, so handlers added later on are not included.
+     */
+    export function listEventHandlers(): Internal.EventHandlerRegistration[] {
+        return OpWrappers.listEventHandlers();
+    }
+
+    // number of awaitAny calls in progress, events are only sent to the waiters while there are any
+    let pendingWaits = 0;
+
+    /**
+     * Filter for a regular event in {@link awaitAny}
+     */
+    export interface AwaitEventFilter {
+        event: Exclude<keyof EventTypes, `BOTLOADER_${string}`>,
+        channelId?: string,
+        /**
+         * The user that caused the event, e.g. the author of a message or the user that added a reaction
+         */
+        userId?: string,
+    }
+
+    /**
+     * Filter for a component interaction or modal submission in {@link awaitAny}
+     */
+    export interface AwaitInteractionFilter {
+        interaction: "button" | "selectMenu" | "modalSubmit",
+        /**
+         * The name used when creating the custom id with `encodeInteractionCustomId`
+         */
+        name: string,
+        channelId?: string,
+        userId?: string,
+    }
+
+    export type AwaitFilter = AwaitEventFilter | AwaitInteractionFilter;
+
+    export interface AwaitAnyResult {
+        /**
+         * Index of the filter that matched
+         */
+        index: number,
+        /**
+         * The event, an interaction object for {@link AwaitInteractionFilter} filters
+         */
+        event: any,
+    }
+
+    /**
+     * Waits for the first event matching any of the filters, useful for multi step flows where the user can
+     * for example either click a button or send a message.
+     * 
+     * The event is still dispatched to the regular handlers as well.
+     * Interactions are not acknowledged for you, so make sure to respond to them.
+     * 
+     * @param filters up to 10 filters
+     * @param timeoutMs how long to wait at most, up to 15 minutes
+     * @returns the matched event, or null if the wait timed out
+     * 
+     * @example ```ts
+     * const result = await EventSystem.awaitAny([
+     *     { interaction: "button", name: "confirm", userId: user.id },
+     *     { event: "MESSAGE_CREATE", channelId: channel.id, userId: user.id },
+     * ], 60 * 1000);
+     * 
+     * if (result === null) {
+     *     // timed out
+     * } else if (result.index === 0) {
+     *     await result.event.ackWithMessage({ content: "confirmed!" });
+     * }
+     * ```
+     */
+    export async function awaitAny(filters: AwaitFilter[], timeoutMs: number): Promise<AwaitAnyResult | null> {
+        const internalFilters = filters.map(toInternalFilter);
+
+        pendingWaits++;
+        try {
+            const result = await OpWrappers.awaitAny(internalFilters, timeoutMs);
+            if (!result) {
+                return null;
+            }
+
+            return {
+                index: result.index,
+                event: convertAwaitedEvent(result.name, result.event),
+            };
+        } finally {
+            pendingWaits--;
+        }
+    }
+
+    function toInternalFilter(filter: AwaitFilter): Internal.EventFilter {
+        if ("event" in filter) {
+            return {
+                event: filter.event,
+                channelId: filter.channelId,
+                userId: filter.userId,
+            };
+        }
+
+        return {
+            event: filter.interaction === "modalSubmit"
+                ? "BOTLOADER_MODAL_SUBMIT_INTERACTION_CREATE"
+                : "BOTLOADER_COMPONENT_INTERACTION_CREATE",
+            channelId: filter.channelId,
+            userId: filter.userId,
+            // see encodeInteractionCustomId
+            customIdPrefix: "0:" + filter.name + ":",
+        };
+    }
+
+    function convertAwaitedEvent(name: string, data: any): any {
+        if (name === "BOTLOADER_COMPONENT_INTERACTION_CREATE") {
+            const interaction = data as Internal.MessageComponentInteraction;
+            return interaction.componentType === "SelectMenu"
+                ? new SelectMenuInteraction(interaction)
+                : new ComponentInteraction(interaction);
+        } else if (name === "BOTLOADER_MODAL_SUBMIT_INTERACTION_CREATE") {
+            return new ModalSubmitInteraction(data);
+        } else if (name in converters) {
+            return converters[name as keyof typeof converters](data);
+        }
+
+        return data;
     }
 
     /**
      * @internal
      */
-    export function onInteractionButton<T>(name: string, cb: (interaction: ComponentInteraction, extraData: T) => any) {
-        buttonComponentListeners.push({ name: name, cb: cb })
+    export function onInteractionButton<T>(scriptId: number, name: string, cb: (interaction: ComponentInteraction, extraData: T) => any) {
+        buttonComponentListeners.push({ scriptId, name, cb })
     }
     /**
      * @internal
      */
-    export function onInteractionSelectMenu<T>(name: string, cb: (interaction: SelectMenuInteraction, extraData: T) => any) {
-        selectMenuListeners.push({ name: name, cb: cb })
+    export function onInteractionSelectMenu<T>(scriptId: number, name: string, cb: (interaction: SelectMenuInteraction, extraData: T) => any) {
+        selectMenuListeners.push({ scriptId, name, cb })
     }
 
     /**
      * @internal
      */
-    export function onInteractionModalSubmit<T>(name: string, cb: (interaction: ModalSubmitInteraction, customData: T) => any) {
-        modalSubmitListeners.push({ name: name, cb: cb })
+    export function onInteractionModalSubmit<T>(scriptId: number, name: string, cb: (interaction: ModalSubmitInteraction, customData: T) => any) {
+        modalSubmitListeners.push({ scriptId, name, cb })
     }
 
-    async function handleComponentInteraction(interaction: Internal.MessageComponentInteraction) {
+    async function handleComponentInteraction(interaction: Internal.MessageComponentInteraction, disabledScripts: number[]) {
         if (!interaction.customId.startsWith("0:")) {
             return;
         }
@@ -172,7 +343,7 @@ export namespace EventSystem {
             let listener = buttonComponentListeners.find((elem) => elem.name === name);
             if (listener) {
                 let convInteraction = new ComponentInteraction(interaction);
-                handleInteractionCallback(convInteraction, async () => {
+                handleInteractionCallback(convInteraction, listener.scriptId, disabledScripts, async () => {
                     await listener!.cb(convInteraction, extras);
                 })
             }
@@ -180,14 +351,14 @@ export namespace EventSystem {
             let listener = selectMenuListeners.find((elem) => elem.name === name);
             if (listener) {
                 let convInteraction = new SelectMenuInteraction(interaction);
-                handleInteractionCallback(convInteraction, async () => {
+                handleInteractionCallback(convInteraction, listener.scriptId, disabledScripts, async () => {
                     await listener!.cb(convInteraction, extras);
                 })
             }
         }
     }
 
-    async function handleModalSubmitInteraction(interaction: Internal.IModalInteraction) {
+    async function handleModalSubmitInteraction(interaction: Internal.IModalInteraction, disabledScripts: number[]) {
         if (!interaction.customId.startsWith("0:")) {
             return;
         }
@@ -197,13 +368,21 @@ export namespace EventSystem {
         let listener = modalSubmitListeners.find((elem) => elem.name === name);
         if (listener) {
             let convInteraction = new ModalSubmitInteraction(interaction);
-            handleInteractionCallback(convInteraction, async () => {
+            handleInteractionCallback(convInteraction, listener.scriptId, disabledScripts, async () => {
                 await listener!.cb(convInteraction, extras);
             })
         }
     }
 
-    async function handleInteractionCallback(interaction: Interaction, inner: () => any) {
+    async function handleInteractionCallback(interaction: Interaction, scriptId: number, disabledScripts: number[], inner: () => any) {
+        if (disabledScripts.includes(scriptId)) {
+            await interaction.ackWithMessage({
+                content: Commands.SCRIPT_DISABLED_MESSAGE,
+                flags: { ephemeral: true },
+            });
+            return;
+        }
+
         try {
             await inner();
         } catch (e) {
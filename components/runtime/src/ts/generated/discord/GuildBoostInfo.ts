@@ -0,0 +1,4 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { PremiumTier } from "./PremiumTier";
+
+export interface GuildBoostInfo { premiumTier: PremiumTier, boostCount: number, }
\ No newline at end of file
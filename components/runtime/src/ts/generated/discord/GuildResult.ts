@@ -0,0 +1,5 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { Guild } from "./Guild";
+import type { StoredGuild } from "./StoredGuild";
+
+export type GuildResult = { kind: "Full" } & Guild | { kind: "Stored" } & StoredGuild;
\ No newline at end of file
@@ -4,6 +4,9 @@ export * from './AuditLogExtras'
 export * from './ButtonStyle'
 export * from './ChannelMention'
 export * from './ChannelType'
+export * from './CommandPermissionKind'
+export * from './CommandPermissions'
+export * from './CommandPermission'
 export * from './ComponentType'
 export * from './DefaultMessageNotificationLevel'
 export * from './EmbedAuthor'
@@ -20,6 +23,8 @@ export * from './EventMessageReactionRemoveAll'
 export * from './EventMessageReactionRemove'
 export * from './ExplicitContentFilter'
 export * from './Guild'
+export * from './GuildBoostInfo'
+export * from './GuildResult'
 export * from './IActionRow'
 export * from './IButton'
 export * from './IComponent'
@@ -29,6 +34,7 @@ export * from './ISelectMenuOption'
 export * from './ISelectMenu'
 export * from './ITextInput'
 export * from './IUnknownComponent'
+export * from './MemberCounts'
 export * from './MessageActivity'
 export * from './MessageActivityType'
 export * from './MessageApplication'
@@ -45,6 +51,7 @@ export * from './ReactionType'
 export * from './RoleTags'
 export * from './Role'
 export * from './SendEmoji'
+export * from './StoredGuild'
 export * from './SystemChannelFlags'
 export * from './TextInputStyle'
 export * from './ThreadMetadata'
@@ -0,0 +1,4 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { OpCreateMessageFields } from "./CreateMessageFields";
+
+export interface OpBroadcastMessage { channelIds: Array<string>, fields: OpCreateMessageFields, }
\ No newline at end of file
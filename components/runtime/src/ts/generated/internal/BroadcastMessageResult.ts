@@ -0,0 +1,4 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { IMessage } from "./IMessage";
+
+export interface BroadcastMessageResult { channelId: string, message: IMessage | null, error: string | null, }
\ No newline at end of file
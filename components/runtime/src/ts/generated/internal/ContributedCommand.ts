@@ -0,0 +1,4 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { CommandType } from "./CommandType";
+
+export interface ContributedCommand { name: string, description: string, kind: CommandType, subCommands: Array<string>, }
\ No newline at end of file
@@ -1,3 +1,3 @@
 // This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
 
-export interface CreateScheduledTask { namespace: string, uniqueKey?: string, data: any, executeAt: number, }
\ No newline at end of file
+export interface CreateScheduledTask { namespace: string, uniqueKey?: string, tags?: Record<string, string>, data: any, executeAt: number, }
\ No newline at end of file
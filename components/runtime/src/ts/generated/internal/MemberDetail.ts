@@ -0,0 +1,3 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+export interface IMemberDetail { joinedAt: number, roles: Array<string>, nick: string | null, premiumSince: number | null, }
\ No newline at end of file
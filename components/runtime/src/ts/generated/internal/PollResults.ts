@@ -0,0 +1,4 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { PollAnswerResult } from "./PollAnswerResult";
+
+export interface PollResults { question: string, answers: Array<PollAnswerResult>, isFinalized: boolean, expiresAt: string | null, }
\ No newline at end of file
@@ -0,0 +1,4 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { CommandType } from "./CommandType";
+
+export interface RegisteredCommand { id: string, name: string, kind: CommandType, claimed: boolean, }
\ No newline at end of file
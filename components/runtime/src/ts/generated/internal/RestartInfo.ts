@@ -0,0 +1,4 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { RestartReason } from "./RestartReason";
+
+export interface RestartInfo { reason: RestartReason, at: number, }
\ No newline at end of file
@@ -1,3 +1,3 @@
 // This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
 
-export interface ScheduledTask { id: number, namespace: string, key?: string, executeAt: number, data: unknown, }
\ No newline at end of file
+export interface ScheduledTask { id: number, namespace: string, key?: string, tags: Record<string, string>, executeAt: number, remainingMs: number, data: unknown, }
\ No newline at end of file
@@ -0,0 +1,5 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { ContributedCommand } from "./ContributedCommand";
+import type { IntervalTimer } from "./IntervalTimer";
+
+export interface ScriptContributes { commands: Array<ContributedCommand>, intervalTimers: Array<IntervalTimer>, apiVersion?: number, }
\ No newline at end of file
@@ -0,0 +1,4 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { ITextInput } from "../discord/ITextInput";
+
+export interface OpShowModal { title: string, customId: string, inputs: Array<ITextInput>, }
\ No newline at end of file
@@ -0,0 +1,4 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { TaskOutcome } from "./TaskOutcome";
+
+export interface TaskExecution { taskId: number, namespace: string, firedAt: number, outcome: TaskOutcome, error?: string, }
\ No newline at end of file
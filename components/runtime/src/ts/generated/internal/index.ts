@@ -1,6 +1,10 @@
 // generated index file using gen-index.bash
 export * from './AllowedMentions'
+export * from './AwaitAnyResult'
+export * from './BackgroundTaskData'
 export * from './Ban'
+export * from './BroadcastMessageResult'
+export * from './BroadcastMessage'
 export * from './CategoryChannel'
 export * from './ClientHttpRequest'
 export * from './ClientHttpResponse'
@@ -15,19 +19,25 @@ export * from './CommandSubGroup'
 export * from './Command'
 export * from './CommandType'
 export * from './ConsoleLogMessage'
+export * from './ContributedCommand'
 export * from './CreateBanFields'
 export * from './CreateChannelMessage'
 export * from './CreateFollowUpMessage'
 export * from './CreateMessageFields'
+export * from './CreatePoll'
 export * from './CreateScheduledTask'
 export * from './DeleteMessagesBulk'
 export * from './DeleteMessage'
 export * from './EditChannelMessage'
 export * from './EditChannel'
+export * from './EditMessageResult'
+export * from './EventFilter'
+export * from './EventHandlerRegistration'
 export * from './EventMemberRemove'
 export * from './EventMessageReactionAdd'
 export * from './EventMessageUpdate'
 export * from './ExtraCommandOptions'
+export * from './GatewayIntents'
 export * from './GetMessages'
 export * from './GetReactions'
 export * from './GuildChannel'
@@ -48,15 +58,25 @@ export * from './IntervalType'
 export * from './ISelfThreadMember'
 export * from './IUserFlags'
 export * from './IUser'
+export * from './LocalTime'
 export * from './Member'
+export * from './MemberDetail'
 export * from './MentionParseTypes'
 export * from './MessageComponentInteraction'
 export * from './NewsThread'
+export * from './PollAnswerResult'
+export * from './PollResults'
 export * from './PremiumType'
 export * from './PrivateThread'
 export * from './PublicThread'
+export * from './RegisteredCommand'
+export * from './RestartInfo'
+export * from './RestartReason'
+export * from './RunBackgroundTask'
 export * from './ScheduledTask'
+export * from './ScriptContributes'
 export * from './ScriptMeta'
+export * from './ShowModal'
 export * from './StorageBucketEntryId'
 export * from './StorageBucketEntry'
 export * from './StorageBucketIncr'
@@ -68,6 +88,9 @@ export * from './StorageBucketSetValue'
 export * from './StorageBucketSortedList'
 export * from './StorageBucket'
 export * from './StorageBucketValue'
+export * from './TaskExecution'
+export * from './TaskNamespaceSummary'
+export * from './TaskOutcome'
 export * from './TextChannel'
 export * from './ThreadMember'
 export * from './UnknownChannel'
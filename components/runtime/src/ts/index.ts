@@ -8,5 +8,6 @@ export * from './script';
 export * from './storage';
 export * from './httpclient';
 export * from './scheduled_tasks';
+export * from './random';
 export * as Discord from './discord/index';
 export * as Unstable from './unstable/index';
@@ -1,3 +1,4 @@
 declare let BotloaderCore: {
-    dispatchEvent: (evt: { name: string, data: any }) => void;
+    dispatchEvent: (evt: { name: string, data: any, disabledScripts: number[] }) => void;
+    unloadScript: (scriptId: number) => void;
 };
\ No newline at end of file
@@ -37,6 +37,10 @@ export namespace OpWrappers {
             return Deno.core.opAsync("op_bl_schedule_task", data)
         }
 
+        export function runBackground(opts: Internal.RunBackgroundTask): Promise<Internal.ScheduledTask> {
+            return Deno.core.opAsync("op_bl_run_background", opts)
+        }
+
         export function delTask(taskId: number): Promise<boolean> {
             return Deno.core.opAsync("op_bl_del_task", taskId)
         }
@@ -49,6 +53,10 @@ export namespace OpWrappers {
             return Deno.core.opAsync("op_bl_del_all_tasks", name)
         }
 
+        export function delTasksByTag(key: string, value: string): Promise<number> {
+            return Deno.core.opAsync("op_bl_del_tasks_by_tag", key, value)
+        }
+
         export function getTask(taskId: number): Promise<Internal.ScheduledTask | null> {
             return Deno.core.opAsync("op_bl_get_task", taskId)
         }
@@ -57,27 +65,118 @@ export namespace OpWrappers {
             return Deno.core.opAsync("op_bl_get_task_by_key", name, key)
         }
 
-        export function getAllTasks(name: string | undefined, after_id: number): Promise<Internal.ScheduledTask[]> {
-            return Deno.core.opAsync("op_bl_get_all_tasks", name, after_id)
+        export function taskExists(name: string, key: string): Promise<boolean> {
+            return Deno.core.opAsync("op_bl_task_exists", name, key)
+        }
+
+        export function getAllTasks(name: string | undefined, after_id: number, tag?: [string, string]): Promise<Internal.ScheduledTask[]> {
+            return Deno.core.opAsync("op_bl_get_all_tasks", name, after_id, tag ?? null)
+        }
+
+        export function getTaskSummary(): Promise<Internal.TaskNamespaceSummary[]> {
+            return Deno.core.opAsync("op_bl_get_task_summary")
+        }
+
+        export function getTaskHistory(namespace: string, limit: number): Promise<Internal.TaskExecution[]> {
+            return Deno.core.opAsync("op_bl_get_task_history", namespace, limit)
+        }
+
+        export function taskHandled(taskId: number, error?: string) {
+            Deno.core.ops.op_bl_task_handled(taskId, error ?? null)
+        }
+    }
+
+    export namespace plugins {
+        export function getPluginConfig(scriptId: number, key: string): Promise<string | null> {
+            return Deno.core.opAsync("op_bl_get_plugin_config", scriptId, key)
+        }
+    }
+
+    export namespace commands {
+        export function listRegisteredCommands(): Promise<Internal.RegisteredCommand[]> {
+            return Deno.core.opAsync("op_bl_list_registered_commands")
+        }
+
+        export function pruneOrphanCommands(token: number, confirm: boolean): Promise<Internal.RegisteredCommand[]> {
+            return Deno.core.opAsync("op_bl_prune_orphan_commands", token, confirm)
+        }
+
+        export function restorePrunedCommands(token: number): Promise<Internal.RegisteredCommand[]> {
+            return Deno.core.opAsync("op_bl_restore_pruned_commands", token)
         }
     }
 
-    export function scriptStarted(meta: Internal.ScriptMeta) {
-        Deno.core.ops.op_botloader_script_start(
+    /**
+     * @returns The token the script manages the server's commands with
+     */
+    export function scriptStarted(meta: Internal.ScriptMeta): number {
+        return Deno.core.ops.op_botloader_script_start(
             meta
         );
     }
 
+    export function listEventHandlers(): Internal.EventHandlerRegistration[] {
+        return Deno.core.ops.op_bl_list_event_handlers();
+    }
+
+    export function lastRestartInfo(): Internal.RestartInfo | null {
+        return Deno.core.ops.op_bl_last_restart_reason();
+    }
+
+    export async function getScriptContributes(scriptId: number): Promise<Internal.ScriptContributes> {
+        return await Deno.core.opAsync("op_bl_get_script_contributes", scriptId);
+    }
+
+    export async function getSourceMap(scriptId: number): Promise<string | null> {
+        return await Deno.core.opAsync("op_bl_get_source_map", scriptId);
+    }
+
+    export function extendDeadline(extraMs: number) {
+        Deno.core.ops.op_bl_extend_deadline(extraMs);
+    }
+
+    export function featureEnabled(flag: string): boolean {
+        return Deno.core.ops.op_bl_feature_enabled(flag);
+    }
+
+    export async function getGatewayLatency(): Promise<number | null> {
+        return await Deno.core.opAsync("op_bl_get_gateway_latency");
+    }
+
+    export async function nowLocal(): Promise<Internal.LocalTime> {
+        return await Deno.core.opAsync("op_bl_now_local");
+    }
+
+    export async function awaitAny(filters: Internal.EventFilter[], timeoutMs: number): Promise<Internal.AwaitAnyResult | null> {
+        return await Deno.core.opAsync(
+            "op_bl_await_any",
+            filters,
+            timeoutMs,
+        );
+    }
+
+    export function resolveEventWaiters(name: string, data: any) {
+        Deno.core.ops.op_bl_resolve_event_waiters(name, data);
+    }
+
     export function consoleLog(args: Internal.ConsoleLogMessage) {
         Deno.core.ops.op_botloader_log(
             args
         );
     }
 
-    export async function getGuild(): Promise<Discord.Guild> {
+    export async function getGuild(): Promise<Discord.GuildResult> {
         return Deno.core.opAsync("op_discord_get_guild");
     }
 
+    export async function getBoostInfo(): Promise<Discord.GuildBoostInfo> {
+        return Deno.core.opAsync("op_discord_get_boost_info");
+    }
+
+    export async function getMemberCounts(): Promise<Discord.MemberCounts> {
+        return Deno.core.opAsync("op_discord_get_member_counts");
+    }
+
     export function getCurrentUser(): Internal.IUser {
         return Deno.core.ops.op_get_current_bot_user();
     }
@@ -86,6 +185,22 @@ export namespace OpWrappers {
         return Deno.core.ops.op_get_current_guild_id();
     }
 
+    export function getGatewayIntents(): Internal.GatewayIntents {
+        return Deno.core.ops.op_bl_get_gateway_intents();
+    }
+
+    export function snowflakeTimestamp(id: string): number {
+        return Deno.core.ops.op_bl_snowflake_timestamp(id);
+    }
+
+    export function randomBytes(n: number): Uint8Array {
+        return Deno.core.ops.op_bl_random_bytes(n);
+    }
+
+    export function randomInt(min: number, max: number): number {
+        return Deno.core.ops.op_bl_random_int(min, max);
+    }
+
     // Messages
     export async function getMessage(channelId: string, messageId: string): Promise<Internal.IMessage> {
         return await Deno.core.opAsync(
@@ -109,6 +224,29 @@ export namespace OpWrappers {
         );
     }
 
+    export async function createPoll(channelId: string, args: Internal.OpCreatePoll): Promise<Internal.IMessage> {
+        return await Deno.core.opAsync(
+            "op_discord_create_poll",
+            channelId,
+            args
+        );
+    }
+
+    export async function getPollResults(channelId: string, messageId: string): Promise<Internal.PollResults> {
+        return await Deno.core.opAsync(
+            "op_discord_get_poll_results",
+            channelId,
+            messageId,
+        );
+    }
+
+    export async function broadcastChannelMessage(args: Internal.OpBroadcastMessage): Promise<Internal.BroadcastMessageResult[]> {
+        return await Deno.core.opAsync(
+            "op_discord_broadcast_message",
+            args
+        );
+    }
+
     export async function crosspostChannelMessage(channelId: string, messageId: string): Promise<void> {
         return await Deno.core.opAsync(
             "op_discord_crosspost_message",
@@ -124,6 +262,13 @@ export namespace OpWrappers {
         );
     }
 
+    export async function editChannelMessages(edits: Internal.OpEditChannelMessage[]): Promise<Internal.EditMessageResult[]> {
+        return await Deno.core.opAsync(
+            "op_discord_edit_messages",
+            edits
+        );
+    }
+
     export async function deleteChannelMessage(args: Internal.OpDeleteMessage): Promise<void> {
         await Deno.core.opAsync(
             "op_discord_delete_message",
@@ -145,6 +290,15 @@ export namespace OpWrappers {
         );
     }
 
+    export async function showModal(interactionId: string, interactionToken: string, modal: Internal.OpShowModal): Promise<void> {
+        return await Deno.core.opAsync(
+            "op_discord_show_modal",
+            interactionId,
+            interactionToken,
+            modal
+        );
+    }
+
     export async function getInteractionFollowupMessage(token: string, messageId: string): Promise<Internal.IMessage> {
         return await Deno.core.opAsync(
             "op_discord_interaction_get_followup_message",
@@ -212,6 +366,26 @@ export namespace OpWrappers {
         );
     }
 
+    export async function listRoles(): Promise<Discord.Role[]> {
+        return await Deno.core.opAsync(
+            "op_discord_list_roles",
+        );
+    }
+
+    export async function getBotHighestRole(): Promise<Discord.Role> {
+        return await Deno.core.opAsync(
+            "op_discord_bot_highest_role",
+        );
+    }
+
+    // Users
+    export async function getUser(userId: string): Promise<Internal.IUser> {
+        return await Deno.core.opAsync(
+            "op_discord_get_user",
+            userId,
+        );
+    }
+
     // Channels
     export async function getChannels(): Promise<Internal.InternalGuildChannel[]> {
         return await Deno.core.opAsync(
@@ -219,6 +393,13 @@ export namespace OpWrappers {
         );
     }
 
+    export async function listChannels(typeFilter: Discord.ChannelType | null): Promise<Internal.InternalGuildChannel[]> {
+        return await Deno.core.opAsync(
+            "op_discord_list_channels",
+            typeFilter,
+        );
+    }
+
     export async function getChannel(channelId: string): Promise<Internal.InternalGuildChannel> {
         return await Deno.core.opAsync(
             "op_discord_get_channel",
@@ -248,6 +429,14 @@ export namespace OpWrappers {
         );
     }
 
+    export async function setSlowmode(channelId: string, seconds: number): Promise<Internal.InternalGuildChannel> {
+        return await Deno.core.opAsync(
+            "op_discord_set_slowmode",
+            channelId,
+            seconds,
+        );
+    }
+
     export async function updateChannelPermission(channelId: string, overwrite: Discord.IPermissionOverwrite): Promise<void> {
         return await Deno.core.opAsync(
             "op_discord_update_channel_permission",
@@ -287,6 +476,13 @@ export namespace OpWrappers {
         );
     }
 
+    export async function op_discord_trigger_typing(channelId: string): Promise<void> {
+        return await Deno.core.opAsync(
+            "op_discord_trigger_typing",
+            channelId,
+        );
+    }
+
     // Members
     export async function getMembers(ids: string[]): Promise<(Internal.IMember | null)[]> {
         return await Deno.core.opAsync(
@@ -295,6 +491,31 @@ export namespace OpWrappers {
         );
     }
 
+    export async function getMemberDetail(userId: string): Promise<Internal.IMemberDetail> {
+        return await Deno.core.opAsync(
+            "op_discord_get_member_detail",
+            userId,
+        );
+    }
+
+    export async function getCommandPermissions(commandId: string): Promise<Discord.CommandPermissions> {
+        return await Deno.core.opAsync(
+            "op_discord_get_command_permissions",
+            commandId,
+        );
+    }
+
+    export function updateCommandPermissionsCache(permissions: Discord.CommandPermissions) {
+        Deno.core.ops.op_discord_update_command_permissions_cache(permissions);
+    }
+
+    export async function memberPending(userId: string): Promise<boolean> {
+        return await Deno.core.opAsync(
+            "op_discord_member_pending",
+            userId,
+        );
+    }
+
     export async function updateMember(userId: string, fields: Internal.UpdateGuildMemberFields): Promise<Internal.IMember> {
         return await Deno.core.opAsync(
             "op_discord_update_member",
@@ -327,6 +548,10 @@ export namespace OpWrappers {
     }
 
     // Storage
+    export function storageNamespace(scriptId: number): string {
+        return Deno.core.ops.op_bl_storage_namespace(scriptId);
+    }
+
     export async function bucketStorageSet(opts: Internal.OpStorageBucketSetValue): Promise<Internal.OpStorageBucketEntry> {
         return await Deno.core.opAsync("op_botloader_bucket_storage_set", opts);
     }
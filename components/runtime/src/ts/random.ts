@@ -0,0 +1,55 @@
+import { OpWrappers } from "./op_wrappers";
+
+/**
+ * Cryptographically secure randomness, backed by the operating system's random number generator (CSPRNG)
+ * 
+ * Use this instead of `Math.random` for anything that needs to be unpredictable or fair, like tokens, nonces and giveaway picks.
+ */
+export namespace SecureRandom {
+
+    /**
+     * Generates random bytes
+     * 
+     * @param n The number of bytes, at most 65536
+     */
+    export function bytes(n: number): Uint8Array {
+        return OpWrappers.randomBytes(n);
+    }
+
+    /**
+     * Generates a random integer that is at least `min` and less than `max`
+     * 
+     * Both have to be safe integers (see `Number.isSafeInteger`)
+     * 
+     * @example ```ts
+     * const roll = SecureRandom.int(1, 7);
+     * ```
+     */
+    export function int(min: number, max: number): number {
+        return OpWrappers.randomInt(min, max);
+    }
+
+    /**
+     * Picks a random element from the array, returns undefined if it's empty
+     * 
+     * @example ```ts
+     * const winner = SecureRandom.pick(entrants);
+     * ```
+     */
+    export function pick<T>(items: T[]): T | undefined {
+        if (items.length === 0) {
+            return undefined;
+        }
+
+        return items[int(0, items.length)];
+    }
+
+    /**
+     * Generates a random hex string, useful for tokens and nonces
+     * 
+     * @param numBytes The number of random bytes, the string is twice as long
+     */
+    export function hex(numBytes: number): string {
+        return Array.from(bytes(numBytes), (b) => b.toString(16).padStart(2, "0")).join("");
+    }
+}
@@ -11,6 +11,11 @@ export namespace Tasks {
     /**
      * Create a new scheduled task.
      * 
+     * The task is stored before this returns, so it survives your vm being restarted (script updates, crashes and so on) right after scheduling it.
+     * 
+     * Once due, the task is delivered to your vm as soon as a script with a handler for the namespace is running.
+     * It is removed as it starts being handled, so if the vm is shut down before your handler finishes it's not retried: tasks run at most once.
+     * 
      * @param namespace The namespace for this task, register a handler for the namespace using {@link Script.registerTaskHandler}
      * @param execute_at When to execute this task
      * @param opts Additional optional options, see {@link CreateOptions} for more info.
@@ -22,9 +27,95 @@ export namespace Tasks {
             executeAt: execute_at.getTime(),
             data: opts?.data ?? null,
             uniqueKey: opts?.key,
+            tags: opts?.tags,
+        });
+    }
+
+    /**
+     * Run a handler registered with {@link Script.onBackgroundTask} in the background, this returns right away without waiting for the handler.
+     * 
+     * Useful when you need to respond quickly, for example to an interaction, but have some heavy work to do afterwards.
+     * 
+     * This creates a task that's due immediately so it counts against your task limits, and the data has the same size limit as normal tasks.
+     * 
+     * To start more background work from within a background handler use {@link BackgroundTaskContext.runInBackground} instead.
+     * 
+     * @param handlerName The name the handler was registered with
+     * @param data Optional data to pass to the handler, json encoded
+     * @param opts Additional optional options, see {@link BackgroundOptions}
+     * 
+     * @example ```ts
+     * script.onBackgroundTask<string, string>("build-report", async (channelId) => {
+     *     const report = await buildBigReport();
+     *     await Discord.createMessage(channelId, { content: report });
+     *     return channelId;
+     * });
+     * 
+     * script.onBackgroundTask<string>("report-done", async (channelId) => {
+     *     console.log(`report posted in ${channelId}`);
+     * });
+     * 
+     * // somewhere else, like in a command handler
+     * await Tasks.runInBackground("build-report", ctx.channelId, { resultHandler: "report-done" });
+     * ```
+     */
+    export async function runInBackground(handlerName: string, data?: any, opts?: BackgroundOptions): Promise<ScheduledTask> {
+        return OpWrappers.tasks.runBackground({
+            handlerName,
+            data: data ?? null,
+            depth: 0,
+            resultHandler: opts?.resultHandler,
         });
     }
 
+    export interface BackgroundOptions {
+        /**
+         * Name of another background handler to run once this one is done, it gets the value this one returned as its data
+         */
+        resultHandler?: string,
+    }
+
+    /**
+     * How deep background tasks can be chained, counting the ones started from within background handlers and result handlers
+     */
+    export const MAX_BACKGROUND_TASK_DEPTH = 10;
+
+    /**
+     * Passed to background handlers, see {@link Script.onBackgroundTask}
+     */
+    export class BackgroundTaskContext {
+        /**
+         * How many background tasks deep this one is, 1 if it was started outside of a background handler
+         */
+        readonly depth: number;
+
+        /**
+         * @internal
+         */
+        constructor(depth: number) {
+            this.depth = depth;
+        }
+
+        /**
+         * Same as {@link Tasks.runInBackground} but counts towards the depth of this chain of background tasks
+         * 
+         * Throws if the chain would be deeper than {@link MAX_BACKGROUND_TASK_DEPTH}.
+         */
+        async runInBackground(handlerName: string, data?: any, opts?: BackgroundOptions): Promise<ScheduledTask> {
+            return OpWrappers.tasks.runBackground({
+                handlerName,
+                data: data ?? null,
+                depth: this.depth,
+                resultHandler: opts?.resultHandler,
+            });
+        }
+    }
+
+    /**
+     * @internal
+     */
+    export const BACKGROUND_TASK_NAMESPACE_PREFIX = "__bl_background:";
+
     export interface CreateOptions {
         /**
          * A namespaced unique key (unique to tasks with the same "name")
@@ -40,6 +131,19 @@ export namespace Tasks {
          */
         key?: string,
 
+        /**
+         * Optional tags for finding and deleting related tasks across namespaces, see {@link deleteByTag} and {@link ListOptions.tag}
+         * 
+         * At most 10 tags, keys can be up to 32 characters and values up to 100 characters.
+         * 
+         * @example ```ts
+         * await Tasks.schedule("remind", at, { tags: { user: userId } });
+         * // later, when the user opts out of everything
+         * await Tasks.deleteByTag("user", userId);
+         * ```
+         */
+        tags?: Record<string, string>,
+
         /**
          * Optional data to pass to the task
          * 
@@ -74,6 +178,14 @@ export namespace Tasks {
         return OpWrappers.tasks.delAllTasks(namespace);
     }
 
+    /**
+     * Delete all tasks with the provided tag, in all namespaces
+     * @returns the number of tasks deleted
+     */
+    export async function deleteByTag(key: string, value: string): Promise<number> {
+        return OpWrappers.tasks.delTasksByTag(key, value);
+    }
+
     /**
      * Retrieve a task by its globally unique ID (NOT uniqueKey)
      * @returns The task if found, or undefined if not found
@@ -90,6 +202,15 @@ export namespace Tasks {
         return await OpWrappers.tasks.getTaskByKey(namespace, key) ?? undefined;
     }
 
+    /**
+     * Check if a task exists by its namespaced unique key, this is cheaper than fetching the task
+     * 
+     * @returns true if a task with the key exists
+     */
+    export async function existsByKey(namespace: string, key: string): Promise<boolean> {
+        return OpWrappers.tasks.taskExists(namespace, key);
+    }
+
     /**
      * Paginate through all scheduled tasks, optionally filtered by namespace
      * 
@@ -101,7 +222,61 @@ export namespace Tasks {
      * @returns 
      */
     export async function getMany(options?: ListOptions): Promise<ScheduledTask[]> {
-        return OpWrappers.tasks.getAllTasks(options?.namespace, options?.afterId ?? 0);
+        const tag: [string, string] | undefined = options?.tag && [options.tag.key, options.tag.value];
+        return OpWrappers.tasks.getAllTasks(options?.namespace, options?.afterId ?? 0, tag);
+    }
+
+    /**
+     * Returns an overview of the scheduled tasks on this guild, grouped by namespace
+     * 
+     * Each entry has the number of tasks in the namespace and when the next one is executed.
+     * 
+     * Entries are sorted by the earliest next execution, and at most 100 namespaces are returned.
+     */
+    export async function getNamespaceSummary(): Promise<NamespaceSummary[]> {
+        return OpWrappers.tasks.getTaskSummary();
+    }
+
+    /**
+     * Returns the most recent executions of tasks in the namespace, newest first
+     * 
+     * An execution is recorded each time your task handler finishes (`Succeeded`), throws or doesn't finish
+     * because the vm was shut down (`Failed`), or when the vm was shut down before the task was handed to your
+     * script (`Interrupted`), in which case the task is delivered again later.
+     * 
+     * Only a limited number of executions are kept per server (100, more with premium), older ones are removed.
+     * 
+     * @param namespace The task namespace
+     * @param limit Max number of executions to return, at most 100
+     */
+    export async function getHistory(namespace: string, limit = 25): Promise<TaskExecution[]> {
+        return OpWrappers.tasks.getTaskHistory(namespace, limit);
+    }
+
+    export interface TaskExecution {
+        taskId: number;
+        namespace: string;
+
+        /**
+         * Unix timestamp in milliseconds of when the task was dispatched
+         */
+        firedAt: number;
+        outcome: "Succeeded" | "Failed" | "Interrupted";
+
+        /**
+         * The error thrown by the handler, or why the task was interrupted
+         */
+        error?: string;
+    }
+
+    export interface NamespaceSummary {
+        namespace: string;
+        count: number;
+
+        /**
+         * Unix timestamp in milliseconds of the next task to execute in this namespace
+         */
+        nextExecuteAt: number;
     }
 
     export interface ListOptions {
@@ -110,6 +285,11 @@ export namespace Tasks {
          */
         namespace?: string,
 
+        /**
+         * Optionally only return tasks with this tag
+         */
+        tag?: { key: string, value: string },
+
         /**
          * Optionally only return items after the provided id
          * 
@@ -126,7 +306,14 @@ export namespace Tasks {
         id: number;
         namespace: string;
         key?: string;
+        tags: Record<string, string>;
         executeAt: number;
+        /**
+         * Milliseconds until the task will be executed, computed by the server when the task was fetched.
+         * 
+         * This is negative if the task is overdue, which can happen if the scheduler is lagging behind.
+         */
+        remainingMs: number;
         data: unknown;
     }
 }
@@ -23,8 +23,10 @@ export class Script {
     private storageBuckets: Storage.Bucket<unknown>[] = [];
     private taskHandlerNames: string[] = [];
     private commands: Commands.Command[] = [];
+    private apiVersion?: number;
 
     private runCalled = false;
+    #commandsToken?: number;
 
     /**
      * @internal
@@ -42,7 +44,45 @@ export class Script {
      */
     createCommand(command: Commands.Command) {
         this.commands.push(command);
-        EventSystem.commandSystem.addCommand(command);
+        EventSystem.commandSystem.addCommand(command, this.scriptId);
+    }
+
+    /**
+     * Pins this script to a runtime API version, the script will refuse to start on hosts
+     * that only support older versions instead of breaking in subtle ways.
+     * 
+     * Has to be called before the script finishes loading.
+     * 
+     * @example ```ts
+     * script.pinApiVersion(1);
+     * ```
+     */
+    pinApiVersion(version: number) {
+        if (this.runCalled) {
+            throw new Error("pinApiVersion has to be called before the script has loaded");
+        }
+
+        if (!Number.isInteger(version) || version < 1) {
+            throw new Error("runtime api versions are positive integers");
+        }
+
+        this.apiVersion = version;
+    }
+
+    /**
+     * Returns a key prefix unique to this script, use it to avoid key collisions with other scripts using the same storage buckets.
+     * 
+     * The prefix is stable across restarts and script updates, for plugins it's derived from the plugin so it also stays the same when the plugin is updated.
+     * 
+     * Combine it with `deleteAll` and a pattern like `${prefix}%` to clean up everything this script has stored in a bucket.
+     * 
+     * @example ```ts
+     * const bucket = script.createStorageJson<number>("shared");
+     * await bucket.set(script.storageNamespace() + "counter", 1);
+     * ```
+     */
+    storageNamespace(): string {
+        return OpWrappers.storageNamespace(this.scriptId);
     }
 
     /**
@@ -154,22 +194,242 @@ export class Script {
 
         this.events.on("BOTLOADER_SCHEDULED_TASK_FIRED", async (evt) => {
             if (evt.namespace === namespace) {
-                await cb({
-                    ...evt,
-                    data: evt.data as T,
-                });
+                try {
+                    await cb({
+                        ...evt,
+                        data: evt.data as T,
+                    });
+                } catch (e) {
+                    OpWrappers.tasks.taskHandled(evt.id, String(e));
+                    throw e;
+                }
+
+                OpWrappers.tasks.taskHandled(evt.id);
             }
         })
     }
 
+    /**
+     * Register a handler for work started with {@link Tasks.runInBackground}.
+     * 
+     * If the work was started with a result handler, that one is run in the background afterwards with the value returned by this one.
+     * 
+     * @param handlerName The name to start it with
+     * @param cb The callback function to run, with the data passed to {@link Tasks.runInBackground}
+     * 
+     * @example ```ts
+     * script.onBackgroundTask<string>("build-report", async (channelId, ctx) => {
+     *     const report = await buildBigReport();
+     *     await Discord.createMessage(channelId, { content: report });
+     * 
+     *     // more work can be started from here, up to Tasks.MAX_BACKGROUND_TASK_DEPTH deep
+     *     await ctx.runInBackground("archive-report", report);
+     * });
+     * ```
+     */
+    onBackgroundTask<T, R = unknown>(handlerName: string, cb: (data: T, ctx: Tasks.BackgroundTaskContext) => R | Promise<R>) {
+        this.onTask<Internal.BackgroundTaskData>(Tasks.BACKGROUND_TASK_NAMESPACE_PREFIX + handlerName, async (task) => {
+            const ctx = new Tasks.BackgroundTaskContext(task.data.depth);
+            const result = await cb(task.data.data as T, ctx);
+
+            if (task.data.resultHandler) {
+                await ctx.runInBackground(task.data.resultHandler, result);
+            }
+        });
+    }
+
+    /**
+     * Get a configuration value set by the server admins in the dashboard for this plugin.
+     *
+     * This is only available to plugin scripts, and will throw for normal guild scripts.
+     *
+     * @param key The key of the config value
+     * @returns The value, or undefined if it has not been set
+     *
+     * @example ```ts
+     * const logChannelId = await script.getPluginConfig("log_channel");
+     * ```
+     */
+    async getPluginConfig(key: string): Promise<string | undefined> {
+        const value = await OpWrappers.plugins.getPluginConfig(this.scriptId, key);
+        return value ?? undefined;
+    }
+
+    /**
+     * Get information about why the vm running this script was last restarted.
+     *
+     * Useful for things like skipping a startup announcement when the restart was only caused by a script update,
+     * or logging a warning when the previous vm ran out of memory.
+     *
+     * @returns The reason and time of the last restart, or undefined if this is the first vm started for the server since the worker started
+     *
+     * @example ```ts
+     * const lastRestart = script.getLastRestartInfo();
+     * if (lastRestart?.reason === "OutOfMemory") {
+     *     console.log("the previous vm ran out of memory at", lastRestart.at);
+     * }
+     * ```
+     */
+    getLastRestartInfo(): RestartInfo | undefined {
+        const info = OpWrappers.lastRestartInfo();
+        if (!info) {
+            return undefined;
+        }
+
+        return {
+            reason: info.reason,
+            at: new Date(info.at),
+        };
+    }
+
+    /**
+     * Give the current event handler extra time before the server's vm is considered stuck and shut down.
+     *
+     * Use this before known slow work, like a bulk import, that would otherwise block for too long.
+     * It can only be called once per event and the extra time is capped depending on your plan (5 seconds without premium).
+     *
+     * @param extraMs The extra time in milliseconds
+     *
+     * @example ```ts
+     * script.on("MESSAGE_CREATE", (msg) => {
+     *     if (msg.content === "!import") {
+     *         script.extendDeadline(5000);
+     *         runBigImport();
+     *     }
+     * });
+     * ```
+     */
+    extendDeadline(extraMs: number) {
+        OpWrappers.extendDeadline(extraMs);
+    }
+
+    /**
+     * Check if a platform feature flag is enabled for this server.
+     *
+     * Use this to only use new features where they're available, falling back to something else on other servers.
+     * The flags are read when the vm starts, unknown flags are never enabled.
+     *
+     * @param flag The name of the feature flag
+     *
+     * @example ```ts
+     * if (script.isFeatureEnabled("polls")) {
+     *     // use the new poll api
+     * } else {
+     *     // fall back to reactions
+     * }
+     * ```
+     */
+    isFeatureEnabled(flag: string): boolean {
+        return OpWrappers.featureEnabled(flag);
+    }
+
+    /**
+     * Get the current time in the server's configured timezone, daylight saving time is taken into account.
+     *
+     * Falls back to UTC if the server has no timezone configured.
+     *
+     * @example ```ts
+     * const now = await script.getLocalTime();
+     * // iso is a timestamp including the offset, e.g. "2023-03-11T09:00:00+01:00"
+     * const hour = Number(now.iso.slice(11, 13));
+     * if (hour < 12) {
+     *     await Discord.createMessage(channelId, { content: "Good morning!" });
+     * }
+     * ```
+     */
+    async getLocalTime(): Promise<Internal.LocalTime> {
+        return await OpWrappers.nowLocal();
+    }
+
+    /**
+     * Get the commands and interval timers a script in this server registered the last time it ran.
+     *
+     * Useful for building a "what does this script do" command, this is rate limited so cache the result
+     * if you need it often.
+     *
+     * @param scriptId The id of the script, defaults to this script
+     *
+     * @example ```ts
+     * const contributes = await script.getScriptContributes();
+     * const names = contributes.commands.map(cmd => cmd.name);
+     * ```
+     */
+    async getScriptContributes(scriptId?: number): Promise<Internal.ScriptContributes> {
+        return await OpWrappers.getScriptContributes(scriptId ?? this.scriptId);
+    }
+
+    /**
+     * Returns the raw source map of a script running on this server, for mapping stack traces of the
+     * compiled javascript back to the typescript source yourself.
+     *
+     * This is rate limited so cache the result if you need it often.
+     *
+     * @param scriptId The id of the script, defaults to this script
+     * @returns The source map as a JSON string, or null if no such script is running
+     */
+    async getSourceMap(scriptId?: number): Promise<string | null> {
+        return await OpWrappers.getSourceMap(scriptId ?? this.scriptId);
+    }
+
+    /**
+     * List the commands registered on discord for this server.
+     *
+     * Commands that no running script claims are marked with `claimed: false`, these are usually
+     * left over from deleted scripts.
+     */
+    async listRegisteredCommands(): Promise<RegisteredCommand[]> {
+        return await OpWrappers.commands.listRegisteredCommands();
+    }
+
+    /**
+     * Remove the registered commands that no running script claims.
+     *
+     * Without `confirm` this only returns the commands that would be removed, so you can check them first.
+     * Removed commands can be registered again with {@link restorePrunedCommands} until the vm is restarted.
+     *
+     * This is only available to the server's own scripts, and will throw for plugin scripts.
+     *
+     * @param confirm Actually remove the commands
+     * @returns The commands that were (or would be) removed
+     *
+     * @example ```ts
+     * const orphans = await script.pruneOrphanCommands();
+     * console.log("would remove", orphans.map(cmd => cmd.name));
+     *
+     * await script.pruneOrphanCommands(true);
+     * ```
+     */
+    async pruneOrphanCommands(confirm = false): Promise<RegisteredCommand[]> {
+        return await OpWrappers.commands.pruneOrphanCommands(this.commandsToken(), confirm);
+    }
+
+    /**
+     * Register the commands removed by {@link pruneOrphanCommands} again.
+     *
+     * This is only available to the server's own scripts, and will throw for plugin scripts.
+     *
+     * @returns All the commands registered for this server afterwards
+     */
+    async restorePrunedCommands(): Promise<RegisteredCommand[]> {
+        return await OpWrappers.commands.restorePrunedCommands(this.commandsToken());
+    }
+
+    private commandsToken(): number {
+        if (this.#commandsToken === undefined) {
+            throw new Error("the script has not started yet");
+        }
+
+        return this.#commandsToken;
+    }
+
     onInteractionButton<T>(name: string, cb: (interaction: ComponentInteraction, extraData: T) => any) {
-        EventSystem.onInteractionButton(name, cb);
+        EventSystem.onInteractionButton(this.scriptId, name, cb);
     }
     onInteractionSelectMenu<T>(name: string, cb: (interaction: SelectMenuInteraction, extraData: T) => any) {
-        EventSystem.onInteractionSelectMenu(name, cb);
+        EventSystem.onInteractionSelectMenu(this.scriptId, name, cb);
     }
     onInteractionModalSubmit<T>(name: string, cb: (interaction: ModalSubmitInteraction, customData: T) => any) {
-        EventSystem.onInteractionModalSubmit(name, cb);
+        EventSystem.onInteractionModalSubmit(this.scriptId, name, cb);
     }
     // onInteractionModalSubmit<T>(name: string, cb: (ctx: InteractionContext, submittedValues: SubmittedComponentValue[], data: T) => any) { }
 
@@ -222,6 +482,7 @@ export class Script {
     on(eventType: "THREAD_CREATE", cb: (evt: EventSystem.EventTypes["THREAD_CREATE"]) => void): void;
     on(eventType: "THREAD_UPDATE", cb: (evt: EventSystem.EventTypes["THREAD_UPDATE"]) => void): void;
     on(eventType: "THREAD_DELETE", cb: (evt: EventSystem.EventTypes["THREAD_DELETE"]) => void): void;
+    on(eventType: "APPLICATION_COMMAND_PERMISSIONS_UPDATE", cb: (evt: EventSystem.EventTypes["APPLICATION_COMMAND_PERMISSIONS_UPDATE"]) => void): void;
 
     /**
      * Register a general event handler such as for arbitrary discord events like when a new message is sent in the server (MESSAGE_CREATE)
@@ -243,16 +504,18 @@ export class Script {
 
         const [cmds, groups] = this.genCommandsBinding();
 
-        OpWrappers.scriptStarted({
+        this.#commandsToken = OpWrappers.scriptStarted({
             description: this.description,
             commands: cmds,
             commandGroups: groups,
             scriptId: this.scriptId,
             intervalTimers: this.intervalTimers.map(inner => inner.timer),
             taskNames: this.taskHandlerNames,
+            eventNames: this.events.eventNames(),
+            apiVersion: this.apiVersion,
         });
 
-        EventSystem.registerEventMuxer(this.events);
+        EventSystem.registerEventMuxer(this.scriptId, this.events);
 
         this.events.on("BOTLOADER_INTERVAL_TIMER_FIRED", this.handleIntervalEvent.bind(this));
     }
@@ -355,6 +618,21 @@ export class Script {
     }
 }
 
+export interface RestartInfo {
+    reason: Internal.RestartReason,
+    at: Date,
+}
+
+export interface RegisteredCommand {
+    id: string,
+    name: string,
+    kind: Internal.CommandType,
+    /**
+     * Whether a running script claims this command
+     */
+    claimed: boolean,
+}
+
 interface IntervalTimerListener {
     timer: Internal.IntervalTimer,
     callback: () => any,
@@ -10,6 +10,9 @@ pub enum SchedulerMessage {
     Dispatch(VmDispatchEvent),
     /// stops the current vm and creates a new one to run the provided scripts
     CreateScriptsVm(CreateScriptsVmReq),
+    /// stops the current vm and creates a sandboxed one for a test run, where all ops with side
+    /// effects (discord, storage, http etc.) throw
+    CreateSandboxVm(CreateScriptsVmReq),
     Complete,
     Shutdown,
 }
@@ -20,6 +23,9 @@ pub struct CreateScriptsVmReq {
     pub premium_tier: Option<PremiumSlotTier>,
     pub guild_id: Id<GuildMarker>,
     pub scripts: Vec<Script>,
+    /// Set when only this script changed since the last request, so the worker can reload it
+    /// in place instead of restarting the vm
+    pub changed_script: Option<u64>,
 }
 
 #[derive(Deserialize, Serialize)]
@@ -37,6 +43,8 @@ pub enum WorkerMessage {
     ScriptsInit,
     NonePending,
     TaskScheduled,
+    // a scheduled task handler finished, with the error it threw if it failed
+    TaskHandled { task_id: u64, error: Option<String> },
     GuildLog(guild_logger::LogEntry),
     Hello(u64),
     Metric(String, MetricEvent, HashMap<String, String>),
@@ -48,6 +56,7 @@ pub enum ShutdownReason {
     OutOfMemory,
     Other,
     TooManyInvalidRequests,
+    Timeout,
 }
 
 #[derive(Deserialize, Serialize, Debug)]
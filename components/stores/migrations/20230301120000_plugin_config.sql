@@ -0,0 +1,9 @@
+-- Add migration script here
+CREATE TABLE plugin_config (
+    guild_id bigint NOT NULL,
+    plugin_id bigint NOT NULL REFERENCES plugins (id) ON DELETE CASCADE,
+    key text NOT NULL,
+    value text NOT NULL,
+    updated_at timestamptz NOT NULL DEFAULT now(),
+    PRIMARY KEY (guild_id, plugin_id, key)
+);
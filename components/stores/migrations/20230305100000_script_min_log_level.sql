@@ -0,0 +1,3 @@
+-- Add migration script here
+ALTER TABLE guild_scripts
+    ADD COLUMN min_log_level SMALLINT;
@@ -0,0 +1,9 @@
+-- Add migration script here
+CREATE TABLE guild_ratelimit_buckets (
+    guild_id bigint NOT NULL,
+    bucket text NOT NULL,
+    tokens double precision NOT NULL,
+    updated_at timestamp with time zone NOT NULL,
+
+    PRIMARY KEY (guild_id, bucket)
+);
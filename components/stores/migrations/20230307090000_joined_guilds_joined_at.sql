@@ -0,0 +1,5 @@
+-- Add migration script here
+ALTER TABLE joined_guilds ADD COLUMN joined_at timestamp with time zone NOT NULL DEFAULT now();
+
+CREATE INDEX joined_guilds_name_idx ON joined_guilds (name, id);
+CREATE INDEX joined_guilds_joined_at_idx ON joined_guilds (joined_at, id);
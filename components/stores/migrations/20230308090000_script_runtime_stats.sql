@@ -0,0 +1,9 @@
+-- Add migration script here
+CREATE TABLE script_runtime_stats (
+    script_id bigint PRIMARY KEY REFERENCES guild_scripts(id) ON DELETE CASCADE,
+    guild_id bigint NOT NULL,
+    dispatches bigint NOT NULL DEFAULT 0,
+    errors bigint NOT NULL DEFAULT 0,
+    last_run_at timestamp with time zone,
+    last_error_at timestamp with time zone
+);
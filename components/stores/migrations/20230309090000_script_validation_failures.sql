@@ -0,0 +1,9 @@
+-- Add migration script here
+CREATE TABLE script_validation_failures (
+    script_id bigint PRIMARY KEY REFERENCES guild_scripts(id) ON DELETE CASCADE,
+    guild_id bigint NOT NULL,
+    reason text NOT NULL,
+    failed_at timestamp with time zone NOT NULL
+);
+
+CREATE INDEX script_validation_failures_guild_id_idx ON script_validation_failures (guild_id);
@@ -0,0 +1,5 @@
+-- Add migration script here
+ALTER TABLE scheduled_tasks ADD COLUMN tags jsonb NOT NULL DEFAULT '{}';
+
+-- used for filtering and deleting tasks by tag
+CREATE INDEX scheduled_tasks_tags_idx ON scheduled_tasks USING GIN (tags jsonb_path_ops);
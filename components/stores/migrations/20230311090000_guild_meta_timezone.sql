@@ -0,0 +1,2 @@
+-- Add migration script here
+ALTER TABLE guild_meta_configs ADD COLUMN timezone TEXT;
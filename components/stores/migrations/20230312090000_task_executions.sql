@@ -0,0 +1,12 @@
+-- Add migration script here
+CREATE TABLE task_executions (
+    id bigserial PRIMARY KEY,
+    guild_id bigint NOT NULL,
+    task_id bigint NOT NULL,
+    name text NOT NULL,
+    fired_at timestamp with time zone NOT NULL,
+    outcome smallint NOT NULL,
+    error text
+);
+
+CREATE INDEX task_executions_guild_id_name_idx ON task_executions (guild_id, name, id DESC);
@@ -0,0 +1,6 @@
+-- Add migration script here
+CREATE TABLE scheduler_guild_leases (
+    guild_id bigint PRIMARY KEY,
+    node_id text NOT NULL,
+    expires_at timestamp with time zone NOT NULL
+);
@@ -0,0 +1,2 @@
+-- Add migration script here
+ALTER TABLE guild_scripts ADD COLUMN contributes_api_version INT;
@@ -0,0 +1,4 @@
+-- Add migration script here
+CREATE SEQUENCE scheduler_guild_lease_generations;
+
+ALTER TABLE scheduler_guild_leases ADD COLUMN generation BIGINT NOT NULL DEFAULT nextval('scheduler_guild_lease_generations');
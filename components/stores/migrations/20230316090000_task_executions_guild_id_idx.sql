@@ -0,0 +1,2 @@
+-- Add migration script here
+CREATE INDEX task_executions_guild_id_id_idx ON task_executions (guild_id, id DESC);
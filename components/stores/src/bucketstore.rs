@@ -66,6 +66,15 @@ pub trait BucketStore: Send + Sync {
         limit: u32,
     ) -> StoreResult<Vec<Entry>>;
 
+    /// Returns the entries across all the guild's buckets, ordered by bucket and key
+    async fn get_all_guild_entries(
+        &self,
+        guild_id: Id<GuildMarker>,
+        after_bucket: String,
+        after_key: String,
+        limit: u32,
+    ) -> StoreResult<Vec<Entry>>;
+
     async fn count(
         &self,
         guild_id: Id<GuildMarker>,
@@ -0,0 +1,503 @@
+//! Exporting and importing all of a guild's scripts, config and optionally storage as a single
+//! json document, used for backups and for moving scripts between guilds.
+//!
+//! Plugin config values are never included as they commonly hold api keys and other secrets.
+
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use common::plugin::PluginData;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+use twilight_model::id::{
+    marker::{ChannelMarker, GuildMarker},
+    Id,
+};
+
+use crate::{
+    bucketstore::{BucketStore, Entry, StoreError, StoreValue},
+    config::{
+        ConfigStore, ConfigStoreError, CreateScript, GuildMetaConfig, ScriptContributes,
+        ScriptLogLevel,
+    },
+};
+
+pub const BUNDLE_FORMAT_VERSION: u32 = 1;
+
+/// The max size of an exported bundle, also the max size accepted on import
+pub const MAX_BUNDLE_SIZE_BYTES: usize = 10 * 1024 * 1024;
+
+const STORAGE_PAGE_SIZE: u32 = 500;
+
+#[derive(Debug, Error)]
+pub enum BundleError {
+    #[error("bundle is too large ({size} bytes), the limit is {limit} bytes")]
+    TooLarge { size: usize, limit: usize },
+
+    #[error("unsupported bundle version: {0}")]
+    UnsupportedVersion(u32),
+
+    #[error("invalid bundle: {0}")]
+    Invalid(#[from] serde_json::Error),
+
+    #[error("bundle includes storage but no bucket store was provided")]
+    MissingBucketStore,
+
+    #[error("config store error: {0}")]
+    Config(#[from] ConfigStoreError),
+
+    #[error("storage error: {0}")]
+    Storage(#[from] StoreError),
+}
+
+pub type BundleResult<T> = Result<T, BundleError>;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GuildBundle {
+    pub version: u32,
+    pub source_guild_id: Id<GuildMarker>,
+    pub exported_at: DateTime<Utc>,
+    pub scripts: Vec<BundleScript>,
+    pub meta_config: Option<BundleMetaConfig>,
+    /// None if storage was not included in the export
+    pub storage: Option<Vec<BundleStorageEntry>>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BundleScript {
+    pub name: String,
+    pub original_source: String,
+    pub enabled: bool,
+    pub contributes: ScriptContributes,
+    pub plugin_id: Option<u64>,
+    pub plugin_auto_update: Option<bool>,
+    pub min_log_level: Option<ScriptLogLevel>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BundleMetaConfig {
+    /// Only restored when importing into the guild the bundle was exported from
+    pub error_channel_id: Option<Id<ChannelMarker>>,
+    #[serde(default)]
+    pub timezone: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BundleStorageEntry {
+    pub bucket: String,
+    pub key: String,
+    pub value: BundleStorageValue,
+    pub expires_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum BundleStorageValue {
+    Json(serde_json::Value),
+    Float(f64),
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct ExportOptions {
+    pub include_storage: bool,
+    /// Buckets left out of the export, e.g. ones used for tokens by the guild's scripts
+    pub exclude_buckets: Vec<String>,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ImportSummary {
+    pub scripts: usize,
+    pub storage_entries: usize,
+    pub meta_config: bool,
+}
+
+/// Exports the guild's scripts, meta config and optionally storage as a json document
+pub async fn export_guild_bundle(
+    config_store: &dyn ConfigStore,
+    bucket_store: Option<&dyn BucketStore>,
+    guild_id: Id<GuildMarker>,
+    options: &ExportOptions,
+) -> BundleResult<Vec<u8>> {
+    let scripts = config_store
+        .list_scripts(guild_id)
+        .await?
+        .into_iter()
+        .map(|script| BundleScript {
+            name: script.name,
+            original_source: script.original_source,
+            enabled: script.enabled,
+            contributes: script.contributes,
+            plugin_id: script.plugin_id,
+            plugin_auto_update: script.plugin_auto_update,
+            min_log_level: Some(script.min_log_level),
+        })
+        .collect::<Vec<_>>();
+
+    let mut size = scripts
+        .iter()
+        .map(|script| script.original_source.len())
+        .sum::<usize>();
+    check_size(size)?;
+
+    let meta_config = config_store
+        .get_guild_meta_config(guild_id)
+        .await?
+        .map(|conf| BundleMetaConfig {
+            error_channel_id: conf.error_channel_id,
+            timezone: conf.timezone,
+        });
+
+    let storage = if options.include_storage {
+        let bucket_store = bucket_store.ok_or(BundleError::MissingBucketStore)?;
+        Some(export_storage(bucket_store, guild_id, options, &mut size).await?)
+    } else {
+        None
+    };
+
+    let bundle = GuildBundle {
+        version: BUNDLE_FORMAT_VERSION,
+        source_guild_id: guild_id,
+        exported_at: Utc::now(),
+        scripts,
+        meta_config,
+        storage,
+    };
+
+    let serialized = serde_json::to_vec(&bundle)?;
+    check_size(serialized.len())?;
+    Ok(serialized)
+}
+
+async fn export_storage(
+    bucket_store: &dyn BucketStore,
+    guild_id: Id<GuildMarker>,
+    options: &ExportOptions,
+    size: &mut usize,
+) -> BundleResult<Vec<BundleStorageEntry>> {
+    let mut result = Vec::new();
+    let mut after_bucket = String::new();
+    let mut after_key = String::new();
+
+    loop {
+        let page = bucket_store
+            .get_all_guild_entries(
+                guild_id,
+                after_bucket.clone(),
+                after_key.clone(),
+                STORAGE_PAGE_SIZE,
+            )
+            .await?;
+
+        let done = page.len() < STORAGE_PAGE_SIZE as usize;
+        if let Some(last) = page.last() {
+            after_bucket = last.bucket.clone();
+            after_key = last.key.clone();
+        }
+
+        for entry in page {
+            result.extend(export_entry(entry, options, size)?);
+        }
+
+        if done {
+            return Ok(result);
+        }
+    }
+}
+
+// None if the entry's bucket is excluded, adds the entry to the size of the export so far
+fn export_entry(
+    entry: Entry,
+    options: &ExportOptions,
+    size: &mut usize,
+) -> BundleResult<Option<BundleStorageEntry>> {
+    if options.exclude_buckets.contains(&entry.bucket) {
+        return Ok(None);
+    }
+
+    // rough estimate, the final size is checked after serializing
+    *size += entry.bucket.len() + entry.key.len();
+    let value = match entry.value {
+        StoreValue::Json(v) => {
+            *size += v.to_string().len();
+            BundleStorageValue::Json(v)
+        }
+        StoreValue::Float(v) => BundleStorageValue::Float(v),
+    };
+    check_size(*size)?;
+
+    Ok(Some(BundleStorageEntry {
+        bucket: entry.bucket,
+        key: entry.key,
+        value,
+        expires_at: entry.expires_at,
+    }))
+}
+
+/// Parses a bundle, checking its size and version
+pub fn parse_guild_bundle(data: &[u8]) -> BundleResult<GuildBundle> {
+    check_size(data.len())?;
+
+    let bundle: GuildBundle = serde_json::from_slice(data)?;
+    if bundle.version != BUNDLE_FORMAT_VERSION {
+        return Err(BundleError::UnsupportedVersion(bundle.version));
+    }
+
+    Ok(bundle)
+}
+
+impl GuildBundle {
+    /// Estimated number of bytes the storage entries use once imported
+    pub fn storage_size_bytes(&self) -> u64 {
+        self.storage
+            .iter()
+            .flatten()
+            .map(|entry| {
+                let value_size = match &entry.value {
+                    BundleStorageValue::Json(v) => v.to_string().len(),
+                    BundleStorageValue::Float(_) => 8,
+                };
+                (entry.bucket.len() + entry.key.len() + value_size) as u64
+            })
+            .sum()
+    }
+
+    // channel ids are specific to the guild the bundle was exported from, so the meta config is
+    // only restored there
+    fn meta_config_for(&self, guild_id: Id<GuildMarker>) -> Option<GuildMetaConfig> {
+        if self.source_guild_id != guild_id {
+            return None;
+        }
+
+        self.meta_config.as_ref().map(|conf| GuildMetaConfig {
+            guild_id,
+            error_channel_id: conf.error_channel_id,
+            timezone: conf.timezone.clone(),
+        })
+    }
+}
+
+impl BundleStorageEntry {
+    // how long the entry has left when imported at `now`, the outer None if it has already expired
+    fn remaining_ttl(&self, now: DateTime<Utc>) -> Option<Option<Duration>> {
+        match self.expires_at {
+            Some(expires_at) if expires_at <= now => None,
+            Some(expires_at) => Some((expires_at - now).to_std().ok()),
+            None => Some(None),
+        }
+    }
+}
+
+/// Recreates the scripts, meta config and storage from a bundle in the guild
+///
+/// All the scripts are created in one transaction, so a failure (e.g. a name that's already
+/// taken) leaves the guild's scripts untouched. The bundle isn't validated here, that's up to
+/// the caller as the limits depend on the guild and the user importing it.
+///
+/// Plugin scripts get the plugin's published source, the one in the bundle is ignored so a bundle
+/// can't run its own code under a plugin's name.
+pub async fn import_guild_bundle(
+    config_store: &dyn ConfigStore,
+    bucket_store: Option<&dyn BucketStore>,
+    guild_id: Id<GuildMarker>,
+    bundle: GuildBundle,
+) -> BundleResult<ImportSummary> {
+    if bundle.storage.is_some() && bucket_store.is_none() {
+        return Err(BundleError::MissingBucketStore);
+    }
+
+    let mut summary = ImportSummary::default();
+
+    let mut sources = Vec::with_capacity(bundle.scripts.len());
+    for script in &bundle.scripts {
+        let source = if let Some(plugin_id) = script.plugin_id {
+            match config_store.get_plugin(plugin_id).await?.data {
+                PluginData::ScriptPluginData(d) => d.published_version.unwrap_or_default(),
+            }
+        } else {
+            script.original_source.clone()
+        };
+        sources.push(source);
+    }
+
+    let meta_config = bundle.meta_config_for(guild_id);
+
+    let mut tx = config_store.transaction().await?;
+    let mut created = Vec::with_capacity(bundle.scripts.len());
+    for (script, original_source) in bundle.scripts.into_iter().zip(sources) {
+        let new_script = tx
+            .create_script(
+                guild_id,
+                CreateScript {
+                    name: script.name,
+                    original_source,
+                    enabled: script.enabled,
+                    plugin_id: script.plugin_id,
+                    plugin_auto_update: script.plugin_auto_update,
+                },
+            )
+            .await?;
+
+        tx.update_script_contributes(guild_id, new_script.id, script.contributes)
+            .await?;
+
+        created.push((new_script.id, script.min_log_level.unwrap_or_default()));
+    }
+    tx.commit().await?;
+    summary.scripts = created.len();
+
+    for (script_id, min_log_level) in created {
+        if min_log_level != ScriptLogLevel::Inherit {
+            config_store
+                .set_script_min_log_level(guild_id, script_id, min_log_level)
+                .await?;
+        }
+    }
+
+    if let Some(meta_config) = meta_config {
+        config_store.update_guild_meta_config(&meta_config).await?;
+        summary.meta_config = true;
+    }
+
+    if let (Some(entries), Some(bucket_store)) = (bundle.storage, bucket_store) {
+        let now = Utc::now();
+        for entry in entries {
+            let ttl = if let Some(ttl) = entry.remaining_ttl(now) {
+                ttl
+            } else {
+                continue;
+            };
+
+            let value = match entry.value {
+                BundleStorageValue::Json(v) => StoreValue::Json(v),
+                BundleStorageValue::Float(v) => StoreValue::Float(v),
+            };
+
+            bucket_store
+                .set(guild_id, entry.bucket, entry.key, value, ttl)
+                .await?;
+            summary.storage_entries += 1;
+        }
+    }
+
+    Ok(summary)
+}
+
+fn check_size(size: usize) -> BundleResult<()> {
+    if size > MAX_BUNDLE_SIZE_BYTES {
+        Err(BundleError::TooLarge {
+            size,
+            limit: MAX_BUNDLE_SIZE_BYTES,
+        })
+    } else {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_bundle(version: u32) -> GuildBundle {
+        GuildBundle {
+            version,
+            source_guild_id: Id::new(1),
+            exported_at: Utc::now(),
+            scripts: Vec::new(),
+            meta_config: Some(BundleMetaConfig {
+                error_channel_id: Some(Id::new(10)),
+                timezone: Some("Europe/Oslo".to_string()),
+            }),
+            storage: None,
+        }
+    }
+
+    fn test_entry(bucket: &str) -> Entry {
+        Entry {
+            bucket: bucket.to_string(),
+            key: "key".to_string(),
+            value: StoreValue::Json(serde_json::json!({"a": 1})),
+            expires_at: None,
+        }
+    }
+
+    fn test_storage_entry(expires_at: Option<DateTime<Utc>>) -> BundleStorageEntry {
+        BundleStorageEntry {
+            bucket: "bucket".to_string(),
+            key: "key".to_string(),
+            value: BundleStorageValue::Float(1.0),
+            expires_at,
+        }
+    }
+
+    #[test]
+    fn expired_entries_are_skipped() {
+        let now = Utc::now();
+
+        let expired = test_storage_entry(Some(now - chrono::Duration::seconds(1)));
+        assert_eq!(expired.remaining_ttl(now), None);
+
+        let expiring_now = test_storage_entry(Some(now));
+        assert_eq!(expiring_now.remaining_ttl(now), None);
+
+        let live = test_storage_entry(Some(now + chrono::Duration::seconds(60)));
+        assert_eq!(live.remaining_ttl(now), Some(Some(Duration::from_secs(60))));
+
+        let no_ttl = test_storage_entry(None);
+        assert_eq!(no_ttl.remaining_ttl(now), Some(None));
+    }
+
+    #[test]
+    fn meta_config_only_restored_into_source_guild() {
+        let bundle = test_bundle(BUNDLE_FORMAT_VERSION);
+
+        let conf = bundle.meta_config_for(Id::new(1)).unwrap();
+        assert_eq!(conf.guild_id, Id::new(1));
+        assert_eq!(conf.error_channel_id, Some(Id::new(10)));
+        assert_eq!(conf.timezone.as_deref(), Some("Europe/Oslo"));
+
+        assert!(bundle.meta_config_for(Id::new(2)).is_none());
+    }
+
+    #[test]
+    fn excluded_buckets_are_left_out_of_export() {
+        let options = ExportOptions {
+            include_storage: true,
+            exclude_buckets: vec!["tokens".to_string()],
+        };
+        let mut size = 0;
+
+        let excluded = export_entry(test_entry("tokens"), &options, &mut size).unwrap();
+        assert!(excluded.is_none());
+        assert_eq!(size, 0);
+
+        let included = export_entry(test_entry("other"), &options, &mut size)
+            .unwrap()
+            .unwrap();
+        assert_eq!(included.bucket, "other");
+        assert!(size > 0);
+    }
+
+    #[test]
+    fn export_fails_when_over_size_limit() {
+        let mut size = MAX_BUNDLE_SIZE_BYTES;
+        let res = export_entry(test_entry("other"), &ExportOptions::default(), &mut size);
+        assert!(matches!(res, Err(BundleError::TooLarge { .. })));
+    }
+
+    #[test]
+    fn parse_checks_size_and_version() {
+        let data = serde_json::to_vec(&test_bundle(BUNDLE_FORMAT_VERSION)).unwrap();
+        assert!(parse_guild_bundle(&data).is_ok());
+
+        let data = serde_json::to_vec(&test_bundle(BUNDLE_FORMAT_VERSION + 1)).unwrap();
+        assert!(matches!(
+            parse_guild_bundle(&data),
+            Err(BundleError::UnsupportedVersion(v)) if v == BUNDLE_FORMAT_VERSION + 1
+        ));
+
+        let data = vec![b' '; MAX_BUNDLE_SIZE_BYTES + 1];
+        assert!(matches!(
+            parse_guild_bundle(&data),
+            Err(BundleError::TooLarge { .. })
+        ));
+    }
+}
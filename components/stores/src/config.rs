@@ -32,12 +32,25 @@ pub enum ConfigStoreError {
 
     #[error("plugin is already on guild")]
     GuildAlreadyHasPlugin,
+
+    #[error("a script with that name already exists")]
+    ScriptNameTaken,
+
+    #[error("premium slot not found")]
+    PremiumSlotNotFound,
+
+    #[error("premium slot is not attached to the expected guild")]
+    PremiumSlotNotOnGuild,
 }
 
 pub type ConfigStoreResult<T> = Result<T, ConfigStoreError>;
 
 #[async_trait]
 pub trait ConfigStore: Send + Sync {
+    /// Starts a transaction, the operations done through it are only applied once it's committed
+    /// and are rolled back if it's dropped without committing
+    async fn transaction(&self) -> ConfigStoreResult<Box<dyn ConfigStoreTransaction>>;
+
     async fn get_script(
         &self,
         guild_id: Id<GuildMarker>,
@@ -48,6 +61,13 @@ pub trait ConfigStore: Send + Sync {
         guild_id: Id<GuildMarker>,
         script_id: u64,
     ) -> ConfigStoreResult<Script>;
+
+    /// Same as get_script_by_id but also returns the plugin the script was created from, if any
+    async fn get_script_with_plugin(
+        &self,
+        guild_id: Id<GuildMarker>,
+        script_id: u64,
+    ) -> ConfigStoreResult<(Script, Option<Plugin>)>;
     async fn create_script(
         &self,
         guild_id: Id<GuildMarker>,
@@ -64,6 +84,51 @@ pub trait ConfigStore: Send + Sync {
         script_id: u64,
         contribs: ScriptContributes,
     ) -> ConfigStoreResult<Script>;
+    async fn rename_script(
+        &self,
+        guild_id: Id<GuildMarker>,
+        script_id: u64,
+        new_name: String,
+    ) -> ConfigStoreResult<Script>;
+    async fn set_script_min_log_level(
+        &self,
+        guild_id: Id<GuildMarker>,
+        script_id: u64,
+        min_log_level: ScriptLogLevel,
+    ) -> ConfigStoreResult<Script>;
+
+    /// Adds the counters to the stored stats of each script, timestamps only move forward
+    async fn add_script_runtime_stats(
+        &self,
+        guild_id: Id<GuildMarker>,
+        stats: &[ScriptRuntimeStats],
+    ) -> ConfigStoreResult<()>;
+    async fn get_script_stats(
+        &self,
+        guild_id: Id<GuildMarker>,
+        script_id: u64,
+    ) -> ConfigStoreResult<Option<ScriptRuntimeStats>>;
+
+    /// Records that the script's meta failed validation when it was loaded, replacing any
+    /// previous failure
+    async fn set_script_validation_failure(
+        &self,
+        guild_id: Id<GuildMarker>,
+        script_id: u64,
+        reason: String,
+    ) -> ConfigStoreResult<()>;
+    /// Clears the recorded failure once the script loads successfully again
+    async fn clear_script_validation_failure(
+        &self,
+        guild_id: Id<GuildMarker>,
+        script_id: u64,
+    ) -> ConfigStoreResult<()>;
+    /// Returns the guild's scripts that failed validation the last time they were loaded
+    async fn get_failing_scripts(
+        &self,
+        guild_id: Id<GuildMarker>,
+    ) -> ConfigStoreResult<Vec<ScriptValidationFailure>>;
+
     async fn del_script(
         &self,
         guild_id: Id<GuildMarker>,
@@ -98,6 +163,13 @@ pub trait ConfigStore: Send + Sync {
         left: bool,
     ) -> ConfigStoreResult<JoinedGuild>;
 
+    /// Same as set_guild_left_status but for many guilds at once, returns the number of guilds updated
+    async fn mark_guilds_left(
+        &self,
+        guild_ids: &[Id<GuildMarker>],
+        left: bool,
+    ) -> ConfigStoreResult<u64>;
+
     async fn get_joined_guilds(
         &self,
         ids: &[Id<GuildMarker>],
@@ -113,16 +185,54 @@ pub trait ConfigStore: Send + Sync {
 
     async fn get_left_guilds(&self, threshold_hours: u64) -> ConfigStoreResult<Vec<JoinedGuild>>;
 
+    /// Removes everything stored for a left guild in a single transaction, ending with the joined guild itself
+    ///
+    /// Attached premium slots are detached rather than deleted as they belong to the user.
+    /// Returns None without removing anything if the guild is not marked as left, e.g. if it was joined again.
+    async fn purge_left_guild_data(
+        &self,
+        guild_id: Id<GuildMarker>,
+    ) -> ConfigStoreResult<Option<GuildDataPurge>>;
+
+    /// Pages through all joined guilds, `after` is the `next` cursor of the previous page
+    async fn list_joined_guilds(
+        &self,
+        after: Option<JoinedGuildsCursor>,
+        limit: u32,
+        sort: JoinedGuildsSort,
+        include_left: bool,
+    ) -> ConfigStoreResult<JoinedGuildsPage>;
+
     async fn get_guild_premium_slots(
         &self,
         guild_id: Id<GuildMarker>,
     ) -> ConfigStoreResult<Vec<PremiumSlot>>;
 
+    /// Returns active slots that expire within the provided number of hours
+    async fn get_expiring_premium_slots(
+        &self,
+        within_hours: u64,
+    ) -> ConfigStoreResult<Vec<PremiumSlot>>;
+
+    /// Marks active slots that are past their expiry date as expired and detaches them from their
+    /// guilds, returns the slots that were expired
+    async fn expire_lapsed_premium_slots(&self) -> ConfigStoreResult<Vec<PremiumSlot>>;
+
     async fn get_user_premium_slots(
         &self,
         user_id: Id<UserMarker>,
     ) -> ConfigStoreResult<Vec<PremiumSlot>>;
 
+    /// Returns the user's slots that likely represent the same subscription as another of their
+    /// slots from a different source, meaning they have the same tier and overlapping periods
+    ///
+    /// Meant for reviewing billing disputes, nothing is changed. Ordered by tier and creation
+    /// time so the likely duplicates end up next to each other.
+    async fn find_duplicate_slots(
+        &self,
+        user_id: Id<UserMarker>,
+    ) -> ConfigStoreResult<Vec<PremiumSlot>>;
+
     async fn create_update_premium_slot_by_source(
         &self,
         slot: CreateUpdatePremiumSlotBySource,
@@ -135,10 +245,47 @@ pub trait ConfigStore: Send + Sync {
         guild_id: Option<Id<GuildMarker>>,
     ) -> ConfigStoreResult<PremiumSlot>;
 
+    /// Moves a slot from one guild to another in a single transaction,
+    /// failing if the slot is not currently attached to `from_guild`
+    async fn reattach_premium_slot(
+        &self,
+        user_id: Id<UserMarker>,
+        slot_id: u64,
+        from_guild: Id<GuildMarker>,
+        to_guild: Id<GuildMarker>,
+    ) -> ConfigStoreResult<PremiumSlot>;
+
+    /// Returns the joined guilds the user owns or administrates that a slot of `tier` could be
+    /// attached to, meaning they don't already have a slot of the same or a higher tier
+    ///
+    /// Admin permissions are only known to discord, so the guilds the user administrates have
+    /// to be provided in `admin_guild_ids`
+    async fn get_attachable_guilds(
+        &self,
+        user_id: Id<UserMarker>,
+        admin_guild_ids: &[Id<GuildMarker>],
+        tier: PremiumSlotTier,
+    ) -> ConfigStoreResult<Vec<AttachableGuild>>;
+
     async fn create_plugin(&self, create_plugin: CreatePlugin) -> ConfigStoreResult<Plugin>;
     async fn get_plugin(&self, plugin_id: u64) -> ConfigStoreResult<Plugin>;
     async fn get_user_plugins(&self, user_id: u64) -> ConfigStoreResult<Vec<Plugin>>;
+    /// The plugins authored by any of the users, ordered by author
+    async fn get_plugins_by_authors(
+        &self,
+        user_ids: &[u64],
+        published_only: bool,
+    ) -> ConfigStoreResult<Vec<Plugin>>;
     async fn get_published_public_plugins(&self) -> ConfigStoreResult<Vec<Plugin>>;
+    /// Published public plugins with the most recently published version first,
+    /// plugins that have never had a version published come last
+    async fn get_recently_updated_plugins(&self, limit: u32) -> ConfigStoreResult<Vec<Plugin>>;
+    async fn get_plugin_publish_status(
+        &self,
+        plugin_id: u64,
+    ) -> ConfigStoreResult<PluginPublishStatus>;
+    /// Number of guilds the plugin is added to
+    async fn count_plugin_installations(&self, plugin_id: u64) -> ConfigStoreResult<u64>;
     async fn update_plugin_meta(
         &self,
         plugin_id: u64,
@@ -163,6 +310,73 @@ pub trait ConfigStore: Send + Sync {
     ) -> ConfigStoreResult<Script>;
 
     async fn get_user_meta(&self, user_id: u64) -> ConfigStoreResult<UserMeta>;
+
+    async fn get_guild_plugin_config(
+        &self,
+        guild_id: Id<GuildMarker>,
+        plugin_id: u64,
+    ) -> ConfigStoreResult<Vec<PluginConfigEntry>>;
+    async fn set_guild_plugin_config_value(
+        &self,
+        guild_id: Id<GuildMarker>,
+        plugin_id: u64,
+        key: String,
+        value: String,
+    ) -> ConfigStoreResult<PluginConfigEntry>;
+    async fn del_guild_plugin_config_value(
+        &self,
+        guild_id: Id<GuildMarker>,
+        plugin_id: u64,
+        key: String,
+    ) -> ConfigStoreResult<bool>;
+
+    /// Claims or renews the lease on a guild for a scheduler node, only one node can hold a guild at a time
+    ///
+    /// Returns the generation of the lease, which changes every time the guild is claimed by a node that
+    /// didn't hold an unexpired lease on it, or None if another node holds a lease on the guild that
+    /// hasn't expired yet.
+    async fn claim_guild(
+        &self,
+        guild_id: Id<GuildMarker>,
+        node_id: &str,
+        lease_duration: std::time::Duration,
+    ) -> ConfigStoreResult<Option<u64>>;
+
+    /// Gives up the lease on a guild so another node can claim it right away, does nothing if this node doesn't hold it
+    async fn release_guild(
+        &self,
+        guild_id: Id<GuildMarker>,
+        node_id: &str,
+    ) -> ConfigStoreResult<()>;
+}
+
+/// A subset of the [ConfigStore] operations that can be applied together atomically,
+/// see [ConfigStore::transaction]
+#[async_trait]
+pub trait ConfigStoreTransaction: Send {
+    async fn create_script(
+        &mut self,
+        guild_id: Id<GuildMarker>,
+        script: CreateScript,
+    ) -> ConfigStoreResult<Script>;
+    async fn update_script(
+        &mut self,
+        guild_id: Id<GuildMarker>,
+        script: UpdateScript,
+    ) -> ConfigStoreResult<Script>;
+    async fn update_script_contributes(
+        &mut self,
+        guild_id: Id<GuildMarker>,
+        script_id: u64,
+        contribs: ScriptContributes,
+    ) -> ConfigStoreResult<Script>;
+    async fn del_script(
+        &mut self,
+        guild_id: Id<GuildMarker>,
+        script_name: String,
+    ) -> ConfigStoreResult<()>;
+
+    async fn commit(self: Box<Self>) -> ConfigStoreResult<()>;
 }
 
 /// Struct you get back from the store
@@ -175,6 +389,53 @@ pub struct Script {
     pub contributes: ScriptContributes,
     pub plugin_id: Option<u64>,
     pub plugin_auto_update: Option<bool>,
+    /// Log entries from this script below this level are dropped
+    #[serde(default)]
+    pub min_log_level: ScriptLogLevel,
+}
+
+/// Severity floor for a script's guild log entries, ordered from least to most severe
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord)]
+pub enum ScriptLogLevel {
+    /// Use the guild's floor, there's no guild wide one yet so everything is logged
+    #[default]
+    Inherit,
+    ConsoleLog,
+    Info,
+    Warn,
+    Error,
+    Critical,
+}
+
+/// The reason a script failed to load, see [ConfigStore::get_failing_scripts]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScriptValidationFailure {
+    pub script_id: u64,
+    pub reason: String,
+    pub failed_at: DateTime<Utc>,
+}
+
+/// How often a script has been run and failed
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScriptRuntimeStats {
+    pub script_id: u64,
+    /// Number of events dispatched to the vm while the script was loaded
+    pub dispatches: u64,
+    pub errors: u64,
+    pub last_run_at: Option<DateTime<Utc>>,
+    pub last_error_at: Option<DateTime<Utc>>,
+}
+
+impl ScriptRuntimeStats {
+    pub fn new(script_id: u64) -> Self {
+        Self {
+            script_id,
+            dispatches: 0,
+            errors: 0,
+            last_run_at: None,
+            last_error_at: None,
+        }
+    }
 }
 
 /// Struct you get back from the store
@@ -202,6 +463,9 @@ pub struct CreateScript {
 pub struct ScriptContributes {
     pub commands: Vec<twilight_model::application::command::Command>,
     pub interval_timers: Vec<IntervalTimerContrib>,
+    /// The runtime API version the script pinned itself to, if any
+    #[serde(default)]
+    pub api_version: Option<u32>,
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
@@ -210,11 +474,30 @@ pub struct IntervalTimerContrib {
     pub interval: crate::timers::IntervalType,
 }
 
+/// Whether a plugin's dev version differs from its published one
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PluginPublishStatus {
+    pub has_unpublished_changes: bool,
+    pub dev_updated_at: Option<DateTime<Utc>>,
+    pub published_updated_at: Option<DateTime<Utc>>,
+}
+
+/// A admin provided configuration value for a plugin on a guild
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PluginConfigEntry {
+    pub plugin_id: u64,
+    pub key: String,
+    pub value: String,
+    pub updated_at: DateTime<Utc>,
+}
+
 /// A guilds config, for storing core botloader settings
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub struct GuildMetaConfig {
     pub guild_id: Id<GuildMarker>,
     pub error_channel_id: Option<Id<ChannelMarker>>,
+    /// IANA timezone name (e.g. "Europe/Oslo"), UTC is used if not set
+    pub timezone: Option<String>,
 }
 
 impl GuildMetaConfig {
@@ -222,10 +505,23 @@ impl GuildMetaConfig {
         Self {
             guild_id,
             error_channel_id: None,
+            timezone: None,
         }
     }
 }
 
+/// Number of rows removed per table by [ConfigStore::purge_left_guild_data]
+#[derive(Debug, Default)]
+pub struct GuildDataPurge {
+    pub tables: Vec<(&'static str, u64)>,
+}
+
+impl GuildDataPurge {
+    pub fn total(&self) -> u64 {
+        self.tables.iter().map(|(_, count)| count).sum()
+    }
+}
+
 /// A joined guild, we we store all guidls were connected to in the store
 #[derive(Debug, Serialize, Deserialize)]
 pub struct JoinedGuild {
@@ -236,6 +532,29 @@ pub struct JoinedGuild {
     pub left_at: Option<DateTime<Utc>>,
 }
 
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub enum JoinedGuildsSort {
+    Name,
+    /// Most recently joined first
+    RecentlyJoined,
+}
+
+/// The sort key values of the last guild on a page, so the next page doesn't depend on that
+/// guild still being around
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JoinedGuildsCursor {
+    pub id: Id<GuildMarker>,
+    pub name: String,
+    pub joined_at: DateTime<Utc>,
+}
+
+#[derive(Debug)]
+pub struct JoinedGuildsPage {
+    pub guilds: Vec<JoinedGuild>,
+    /// None if the page is empty
+    pub next: Option<JoinedGuildsCursor>,
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct PremiumSlot {
     pub id: u64,
@@ -253,6 +572,14 @@ pub struct PremiumSlot {
     pub attached_guild_id: Option<Id<GuildMarker>>,
 }
 
+/// A guild a premium slot could be attached to, see [ConfigStore::get_attachable_guilds]
+#[derive(Debug, Serialize, Deserialize)]
+pub struct AttachableGuild {
+    pub guild: JoinedGuild,
+    /// The highest tier slot running on the guild, ignoring cancelled and expired ones
+    pub current_slot: Option<PremiumSlot>,
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct CreateUpdatePremiumSlotBySource {
     pub title: String,
@@ -272,6 +599,7 @@ pub enum PremiumSlotState {
     Cancelling,
     Cancelled,
     PaymentFailed,
+    Expired,
 }
 
 #[derive(Debug, Serialize, Deserialize, Copy, Clone)]
@@ -287,6 +615,19 @@ impl PremiumSlotTier {
             (PremiumSlotTier::Premium, PremiumSlotTier::Lite)
         )
     }
+
+    /// Returns the highest tier out of the provided slots, or None if there are no slots
+    pub fn highest_of(slots: &[PremiumSlot]) -> Option<PremiumSlotTier> {
+        let mut highest_tier = Option::<PremiumSlotTier>::None;
+        for slot in slots {
+            match highest_tier {
+                Some(current_highest) if !slot.tier.is_higher_than(current_highest) => {}
+                _ => highest_tier = Some(slot.tier),
+            }
+        }
+
+        highest_tier
+    }
 }
 
 pub struct User {
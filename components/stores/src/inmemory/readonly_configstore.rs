@@ -1,7 +1,9 @@
 use crate::config::{
-    ConfigStore, ConfigStoreError, ConfigStoreResult, CreatePlugin, CreateScript,
-    CreateUpdatePremiumSlotBySource, GuildMetaConfig, JoinedGuild, PremiumSlot, Script,
-    ScriptContributes, UpdatePluginMeta, UpdateScript,
+    AttachableGuild, ConfigStore, ConfigStoreError, ConfigStoreResult, ConfigStoreTransaction,
+    CreatePlugin, CreateScript, CreateUpdatePremiumSlotBySource, GuildDataPurge, GuildMetaConfig,
+    JoinedGuild, JoinedGuildsCursor, JoinedGuildsPage, JoinedGuildsSort, PluginConfigEntry,
+    PluginPublishStatus, PremiumSlot, PremiumSlotTier, Script, ScriptContributes, ScriptLogLevel,
+    ScriptRuntimeStats, ScriptValidationFailure, UpdatePluginMeta, UpdateScript,
 };
 use async_trait::async_trait;
 use common::{plugin::Plugin, user::UserMeta};
@@ -18,6 +20,10 @@ pub struct ReadOnlyConfigStore {
 
 #[async_trait]
 impl ConfigStore for ReadOnlyConfigStore {
+    async fn transaction(&self) -> ConfigStoreResult<Box<dyn ConfigStoreTransaction>> {
+        todo!();
+    }
+
     async fn get_script(
         &self,
         guild_id: Id<GuildMarker>,
@@ -50,6 +56,16 @@ impl ConfigStore for ReadOnlyConfigStore {
             .ok_or(ConfigStoreError::ScriptNotFound)
     }
 
+    async fn get_script_with_plugin(
+        &self,
+        guild_id: Id<GuildMarker>,
+        script_id: u64,
+    ) -> ConfigStoreResult<(Script, Option<Plugin>)> {
+        // plugins aren't available here
+        let script = self.get_script_by_id(guild_id, script_id).await?;
+        Ok((script, None))
+    }
+
     async fn create_script(
         &self,
         _guild_id: Id<GuildMarker>,
@@ -78,6 +94,64 @@ impl ConfigStore for ReadOnlyConfigStore {
         Ok(script)
     }
 
+    async fn rename_script(
+        &self,
+        _guild_id: Id<GuildMarker>,
+        _script_id: u64,
+        _new_name: String,
+    ) -> ConfigStoreResult<Script> {
+        todo!();
+    }
+
+    async fn set_script_min_log_level(
+        &self,
+        _guild_id: Id<GuildMarker>,
+        _script_id: u64,
+        _min_log_level: ScriptLogLevel,
+    ) -> ConfigStoreResult<Script> {
+        todo!();
+    }
+
+    async fn add_script_runtime_stats(
+        &self,
+        _guild_id: Id<GuildMarker>,
+        _stats: &[ScriptRuntimeStats],
+    ) -> ConfigStoreResult<()> {
+        Ok(())
+    }
+
+    async fn get_script_stats(
+        &self,
+        _guild_id: Id<GuildMarker>,
+        _script_id: u64,
+    ) -> ConfigStoreResult<Option<ScriptRuntimeStats>> {
+        Ok(None)
+    }
+
+    async fn set_script_validation_failure(
+        &self,
+        _guild_id: Id<GuildMarker>,
+        _script_id: u64,
+        _reason: String,
+    ) -> ConfigStoreResult<()> {
+        Ok(())
+    }
+
+    async fn clear_script_validation_failure(
+        &self,
+        _guild_id: Id<GuildMarker>,
+        _script_id: u64,
+    ) -> ConfigStoreResult<()> {
+        Ok(())
+    }
+
+    async fn get_failing_scripts(
+        &self,
+        _guild_id: Id<GuildMarker>,
+    ) -> ConfigStoreResult<Vec<ScriptValidationFailure>> {
+        Ok(Vec::new())
+    }
+
     async fn del_script(
         &self,
         _guild_id: Id<GuildMarker>,
@@ -134,6 +208,14 @@ impl ConfigStore for ReadOnlyConfigStore {
         todo!();
     }
 
+    async fn mark_guilds_left(
+        &self,
+        _guild_ids: &[Id<GuildMarker>],
+        _left: bool,
+    ) -> ConfigStoreResult<u64> {
+        todo!()
+    }
+
     async fn is_guild_whitelisted(&self, _id: Id<GuildMarker>) -> ConfigStoreResult<bool> {
         Ok(true)
     }
@@ -146,6 +228,23 @@ impl ConfigStore for ReadOnlyConfigStore {
         todo!();
     }
 
+    async fn purge_left_guild_data(
+        &self,
+        _guild_id: Id<GuildMarker>,
+    ) -> ConfigStoreResult<Option<GuildDataPurge>> {
+        Ok(None)
+    }
+
+    async fn list_joined_guilds(
+        &self,
+        _after: Option<JoinedGuildsCursor>,
+        _limit: u32,
+        _sort: JoinedGuildsSort,
+        _include_left: bool,
+    ) -> ConfigStoreResult<JoinedGuildsPage> {
+        todo!()
+    }
+
     async fn get_guild_premium_slots(
         &self,
         _guild_id: Id<GuildMarker>,
@@ -153,6 +252,17 @@ impl ConfigStore for ReadOnlyConfigStore {
         todo!()
     }
 
+    async fn get_expiring_premium_slots(
+        &self,
+        _within_hours: u64,
+    ) -> ConfigStoreResult<Vec<PremiumSlot>> {
+        todo!();
+    }
+
+    async fn expire_lapsed_premium_slots(&self) -> ConfigStoreResult<Vec<PremiumSlot>> {
+        todo!();
+    }
+
     async fn get_user_premium_slots(
         &self,
         _user_id: Id<UserMarker>,
@@ -160,6 +270,13 @@ impl ConfigStore for ReadOnlyConfigStore {
         todo!()
     }
 
+    async fn find_duplicate_slots(
+        &self,
+        _user_id: Id<UserMarker>,
+    ) -> ConfigStoreResult<Vec<PremiumSlot>> {
+        todo!()
+    }
+
     async fn create_update_premium_slot_by_source(
         &self,
         _slot: CreateUpdatePremiumSlotBySource,
@@ -176,6 +293,25 @@ impl ConfigStore for ReadOnlyConfigStore {
         todo!()
     }
 
+    async fn reattach_premium_slot(
+        &self,
+        _user_id: Id<UserMarker>,
+        _slot_id: u64,
+        _from_guild: Id<GuildMarker>,
+        _to_guild: Id<GuildMarker>,
+    ) -> ConfigStoreResult<PremiumSlot> {
+        todo!()
+    }
+
+    async fn get_attachable_guilds(
+        &self,
+        _user_id: Id<UserMarker>,
+        _admin_guild_ids: &[Id<GuildMarker>],
+        _tier: PremiumSlotTier,
+    ) -> ConfigStoreResult<Vec<AttachableGuild>> {
+        todo!()
+    }
+
     async fn create_plugin(&self, _create_plugin: CreatePlugin) -> ConfigStoreResult<Plugin> {
         todo!()
     }
@@ -213,10 +349,33 @@ impl ConfigStore for ReadOnlyConfigStore {
         todo!()
     }
 
+    async fn get_plugins_by_authors(
+        &self,
+        _user_ids: &[u64],
+        _published_only: bool,
+    ) -> ConfigStoreResult<Vec<Plugin>> {
+        todo!()
+    }
+
     async fn get_published_public_plugins(&self) -> ConfigStoreResult<Vec<Plugin>> {
         todo!()
     }
 
+    async fn get_recently_updated_plugins(&self, _limit: u32) -> ConfigStoreResult<Vec<Plugin>> {
+        todo!()
+    }
+
+    async fn get_plugin_publish_status(
+        &self,
+        _plugin_id: u64,
+    ) -> ConfigStoreResult<PluginPublishStatus> {
+        todo!()
+    }
+
+    async fn count_plugin_installations(&self, _plugin_id: u64) -> ConfigStoreResult<u64> {
+        todo!()
+    }
+
     async fn try_guild_add_script_plugin(
         &self,
         _guild_id: Id<GuildMarker>,
@@ -225,4 +384,48 @@ impl ConfigStore for ReadOnlyConfigStore {
     ) -> ConfigStoreResult<Script> {
         todo!()
     }
+
+    async fn get_guild_plugin_config(
+        &self,
+        _guild_id: Id<GuildMarker>,
+        _plugin_id: u64,
+    ) -> ConfigStoreResult<Vec<PluginConfigEntry>> {
+        todo!()
+    }
+
+    async fn set_guild_plugin_config_value(
+        &self,
+        _guild_id: Id<GuildMarker>,
+        _plugin_id: u64,
+        _key: String,
+        _value: String,
+    ) -> ConfigStoreResult<PluginConfigEntry> {
+        todo!()
+    }
+
+    async fn del_guild_plugin_config_value(
+        &self,
+        _guild_id: Id<GuildMarker>,
+        _plugin_id: u64,
+        _key: String,
+    ) -> ConfigStoreResult<bool> {
+        todo!()
+    }
+
+    async fn claim_guild(
+        &self,
+        _guild_id: Id<GuildMarker>,
+        _node_id: &str,
+        _lease_duration: std::time::Duration,
+    ) -> ConfigStoreResult<Option<u64>> {
+        Ok(Some(1))
+    }
+
+    async fn release_guild(
+        &self,
+        _guild_id: Id<GuildMarker>,
+        _node_id: &str,
+    ) -> ConfigStoreResult<()> {
+        Ok(())
+    }
 }
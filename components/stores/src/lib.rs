@@ -1,6 +1,8 @@
 pub mod bucketstore;
+pub mod bundle;
 pub mod config;
 pub mod inmemory;
 pub mod postgres;
+pub mod ratelimits;
 pub mod timers;
 pub mod web;
@@ -223,6 +223,29 @@ impl crate::bucketstore::BucketStore for Postgres {
         Ok(res.into_iter().map(Into::into).collect())
     }
 
+    async fn get_all_guild_entries(
+        &self,
+        guild_id: Id<GuildMarker>,
+        after_bucket: String,
+        after_key: String,
+        limit: u32,
+    ) -> StoreResult<Vec<Entry>> {
+        let res = sqlx::query_as!(
+            DbEntry,
+            "SELECT guild_id, bucket, key, created_at, updated_at, expires_at, value_json, \
+             value_float FROM bucket_store WHERE guild_id = $1 AND (bucket, key) > ($2, $3) AND \
+             (expires_at IS NULL OR expires_at > now()) ORDER BY (guild_id, bucket, key) LIMIT $4;",
+            guild_id.get() as i64,
+            after_bucket,
+            after_key,
+            limit as i64,
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(res.into_iter().map(Into::into).collect())
+    }
+
     async fn count(
         &self,
         guild_id: Id<GuildMarker>,
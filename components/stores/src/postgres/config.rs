@@ -5,16 +5,18 @@ use common::{
     plugin::{self, Plugin, PluginData, ScriptPluginData},
     user::UserMeta,
 };
-use sqlx::{postgres::types::PgInterval, Executor, PgConnection, PgExecutor};
+use sqlx::{postgres::types::PgInterval, Executor, PgConnection, PgExecutor, Transaction};
 use twilight_model::id::{
     marker::{GuildMarker, UserMarker},
     Id,
 };
 
 use crate::config::{
-    ConfigStoreError, ConfigStoreResult, CreatePlugin, CreateScript,
-    CreateUpdatePremiumSlotBySource, GuildMetaConfig, JoinedGuild, PremiumSlot, PremiumSlotState,
-    PremiumSlotTier, Script, ScriptContributes, UpdatePluginMeta, UpdateScript,
+    AttachableGuild, ConfigStoreError, ConfigStoreResult, ConfigStoreTransaction, CreatePlugin,
+    CreateScript, CreateUpdatePremiumSlotBySource, GuildDataPurge, GuildMetaConfig, JoinedGuild,
+    JoinedGuildsCursor, JoinedGuildsPage, JoinedGuildsSort, PluginConfigEntry, PluginPublishStatus,
+    PremiumSlot, PremiumSlotState, PremiumSlotTier, Script, ScriptContributes, ScriptLogLevel,
+    ScriptRuntimeStats, ScriptValidationFailure, UpdatePluginMeta, UpdateScript,
 };
 
 const GUILD_SCRIPT_COUNT_LIMIT: i64 = 100;
@@ -28,7 +30,8 @@ impl Postgres {
         match sqlx::query_as!(
             DbScript,
             "SELECT id, guild_id, original_source, name, enabled, contributes_commands, \
-             contributes_interval_timers, plugin_id, plugin_auto_update FROM guild_scripts WHERE \
+             contributes_interval_timers, contributes_api_version, plugin_id, plugin_auto_update, \
+             min_log_level FROM guild_scripts WHERE \
              guild_id = $1 AND name = $2;",
             guild_id.get() as i64,
             script_name
@@ -50,7 +53,8 @@ impl Postgres {
         Ok(sqlx::query_as!(
             DbScript,
             "SELECT id, guild_id, name, original_source, enabled, contributes_commands, \
-             contributes_interval_timers, plugin_id, plugin_auto_update FROM guild_scripts WHERE \
+             contributes_interval_timers, contributes_api_version, plugin_id, plugin_auto_update, \
+             min_log_level FROM guild_scripts WHERE \
              guild_id = $1 AND id = $2;",
             guild_id.get() as i64,
             id
@@ -80,7 +84,8 @@ impl Postgres {
         let res = sqlx::query_as!(
             DbScript,
             "SELECT id, guild_id, original_source, name, enabled, contributes_commands, \
-             contributes_interval_timers, plugin_id, plugin_auto_update FROM guild_scripts WHERE \
+             contributes_interval_timers, contributes_api_version, plugin_id, plugin_auto_update, \
+             min_log_level FROM guild_scripts WHERE \
              guild_id = $1",
             guild_id.get() as i64,
         )
@@ -109,7 +114,8 @@ impl Postgres {
              plugin_auto_update) 
 VALUES ($1, $2, $3, $4, $5, $6)
 RETURNING id, guild_id, name, original_source, enabled, contributes_commands, \
-             contributes_interval_timers, plugin_id, plugin_auto_update;",
+             contributes_interval_timers, contributes_api_version, plugin_id, plugin_auto_update, \
+             min_log_level;",
             guild_id.get() as i64,
             script.name,
             script.original_source,
@@ -152,10 +158,100 @@ FROM plugins WHERE id = $1"#,
         .ok_or(ConfigStoreError::PluginNotFound(plugin_id))
         .map(Into::into)
     }
+
+    async fn inner_update_script(
+        conn: &mut PgConnection,
+        guild_id: Id<GuildMarker>,
+        script: UpdateScript,
+    ) -> ConfigStoreResult<Script> {
+        let commands_enc = script.contributes.map(|v| serde_json::to_value(v).unwrap());
+
+        let res = sqlx::query_as!(
+            DbScript,
+            "
+                    UPDATE guild_scripts SET
+                    original_source = COALESCE($3, guild_scripts.original_source),
+                    enabled = COALESCE($4, guild_scripts.enabled),
+                    contributes_commands = COALESCE($5, guild_scripts.contributes_commands)
+                    WHERE guild_id = $1 AND id=$2
+                    RETURNING id, name, original_source, guild_id, enabled, contributes_commands, \
+             contributes_interval_timers, contributes_api_version, plugin_id, plugin_auto_update, \
+             min_log_level;
+                ",
+            guild_id.get() as i64,
+            script.id as i64,
+            script.original_source,
+            script.enabled,
+            commands_enc,
+        )
+        .fetch_one(conn)
+        .await?;
+
+        Ok(res.into())
+    }
+
+    async fn inner_update_script_contributes(
+        conn: &mut PgConnection,
+        guild_id: Id<GuildMarker>,
+        script_id: u64,
+        contribs: ScriptContributes,
+    ) -> ConfigStoreResult<Script> {
+        let commands_enc = serde_json::to_value(contribs.commands).unwrap();
+        let intervals_enc = serde_json::to_value(contribs.interval_timers).unwrap();
+
+        let res = sqlx::query_as!(
+            DbScript,
+            "
+                    UPDATE guild_scripts SET
+                    contributes_commands = $3,
+                    contributes_interval_timers = $4,
+                    contributes_api_version = $5
+                    WHERE guild_id = $1 AND id=$2
+                    RETURNING id, name, original_source, guild_id, enabled, contributes_commands, \
+             contributes_interval_timers, contributes_api_version, plugin_id, plugin_auto_update, \
+             min_log_level;
+                ",
+            guild_id.get() as i64,
+            script_id as i64,
+            commands_enc,
+            intervals_enc,
+            contribs.api_version.map(|v| v as i32),
+        )
+        .fetch_one(conn)
+        .await?;
+
+        Ok(res.into())
+    }
+
+    async fn inner_del_script(
+        conn: &mut PgConnection,
+        guild_id: Id<GuildMarker>,
+        script_name: String,
+    ) -> ConfigStoreResult<()> {
+        let res = sqlx::query!(
+            "DELETE FROM guild_scripts WHERE guild_id = $1 AND name = $2;",
+            guild_id.get() as i64,
+            script_name
+        )
+        .execute(conn)
+        .await?;
+
+        if res.rows_affected() > 0 {
+            Ok(())
+        } else {
+            Err(ConfigStoreError::ScriptNotFound)
+        }
+    }
 }
 
 #[async_trait]
 impl crate::config::ConfigStore for Postgres {
+    async fn transaction(&self) -> ConfigStoreResult<Box<dyn ConfigStoreTransaction>> {
+        Ok(Box::new(PostgresConfigTransaction(
+            self.pool.begin().await?,
+        )))
+    }
+
     async fn get_script(
         &self,
         guild_id: Id<GuildMarker>,
@@ -178,6 +274,94 @@ impl crate::config::ConfigStore for Postgres {
             .into())
     }
 
+    async fn get_script_with_plugin(
+        &self,
+        guild_id: Id<GuildMarker>,
+        script_id: u64,
+    ) -> ConfigStoreResult<(Script, Option<Plugin>)> {
+        let row = match sqlx::query!(
+            r#"SELECT guild_scripts.id,
+guild_scripts.guild_id,
+guild_scripts.name,
+guild_scripts.original_source,
+guild_scripts.enabled,
+guild_scripts.contributes_commands,
+guild_scripts.contributes_interval_timers,
+guild_scripts.contributes_api_version,
+guild_scripts.plugin_id,
+guild_scripts.plugin_auto_update,
+guild_scripts.min_log_level,
+plugins.created_at as "plugin_created_at?",
+plugins.name as "plugin_name?",
+plugins.short_description as "plugin_short_description?",
+plugins.long_description as "plugin_long_description?",
+plugins.is_published as "plugin_is_published?",
+plugins.is_official as "plugin_is_official?",
+plugins.plugin_kind as "plugin_kind?",
+plugins.current_version_number as "plugin_current_version_number?",
+plugins.script_published_source as "plugin_script_published_source?",
+plugins.script_published_version_updated_at as "plugin_script_published_version_updated_at?",
+plugins.script_dev_source as "plugin_script_dev_source?",
+plugins.script_dev_version_updated_at as "plugin_script_dev_version_updated_at?",
+plugins.author_id as "plugin_author_id?",
+plugins.is_public as "plugin_is_public?"
+FROM guild_scripts
+LEFT JOIN plugins ON plugins.id = guild_scripts.plugin_id
+WHERE guild_scripts.guild_id = $1 AND guild_scripts.id = $2"#,
+            guild_id.get() as i64,
+            script_id as i64,
+        )
+        .fetch_one(&self.pool)
+        .await
+        {
+            Ok(row) => row,
+            Err(sqlx::Error::RowNotFound) => return Err(ConfigStoreError::ScriptNotFound),
+            Err(err) => return Err(err.into()),
+        };
+
+        // all the plugin columns are set if the join matched
+        let plugin = match (row.plugin_id, row.plugin_name, row.plugin_created_at) {
+            (Some(plugin_id), Some(name), Some(created_at)) => Some(
+                DbPlugin {
+                    id: plugin_id,
+                    created_at,
+                    name,
+                    short_description: row.plugin_short_description.unwrap_or_default(),
+                    long_description: row.plugin_long_description.unwrap_or_default(),
+                    is_published: row.plugin_is_published.unwrap_or_default(),
+                    is_official: row.plugin_is_official.unwrap_or_default(),
+                    plugin_kind: row.plugin_kind.unwrap_or_default(),
+                    current_version_number: row.plugin_current_version_number.unwrap_or_default(),
+                    script_published_source: row.plugin_script_published_source,
+                    script_published_version_updated_at: row
+                        .plugin_script_published_version_updated_at,
+                    script_dev_source: row.plugin_script_dev_source,
+                    script_dev_version_updated_at: row.plugin_script_dev_version_updated_at,
+                    author_id: row.plugin_author_id.unwrap_or_default(),
+                    is_public: row.plugin_is_public.unwrap_or_default(),
+                }
+                .into(),
+            ),
+            _ => None,
+        };
+
+        let script = DbScript {
+            id: row.id,
+            guild_id: row.guild_id,
+            name: row.name,
+            original_source: row.original_source,
+            enabled: row.enabled,
+            contributes_commands: row.contributes_commands,
+            contributes_interval_timers: row.contributes_interval_timers,
+            contributes_api_version: row.contributes_api_version,
+            plugin_id: row.plugin_id,
+            plugin_auto_update: row.plugin_auto_update,
+            min_log_level: row.min_log_level,
+        };
+
+        Ok((script.into(), plugin))
+    }
+
     async fn create_script(
         &self,
         guild_id: Id<GuildMarker>,
@@ -191,79 +375,231 @@ impl crate::config::ConfigStore for Postgres {
         guild_id: Id<GuildMarker>,
         script: UpdateScript,
     ) -> ConfigStoreResult<Script> {
-        let commands_enc = script.contributes.map(|v| serde_json::to_value(v).unwrap());
+        Self::inner_update_script(&mut *self.pool.acquire().await?, guild_id, script).await
+    }
 
-        let res = sqlx::query_as!(
+    async fn update_script_contributes(
+        &self,
+        guild_id: Id<GuildMarker>,
+        script_id: u64,
+        contribs: ScriptContributes,
+    ) -> ConfigStoreResult<Script> {
+        Self::inner_update_script_contributes(
+            &mut *self.pool.acquire().await?,
+            guild_id,
+            script_id,
+            contribs,
+        )
+        .await
+    }
+
+    async fn rename_script(
+        &self,
+        guild_id: Id<GuildMarker>,
+        script_id: u64,
+        new_name: String,
+    ) -> ConfigStoreResult<Script> {
+        let mut tx = self.pool.begin().await?;
+
+        let existing = sqlx::query!(
+            "SELECT id FROM guild_scripts WHERE guild_id = $1 AND name = $2 AND id != $3;",
+            guild_id.get() as i64,
+            new_name,
+            script_id as i64,
+        )
+        .fetch_optional(&mut tx)
+        .await?;
+
+        if existing.is_some() {
+            return Err(ConfigStoreError::ScriptNameTaken);
+        }
+
+        let res = match sqlx::query_as!(
             DbScript,
             "
                     UPDATE guild_scripts SET
-                    original_source = COALESCE($3, guild_scripts.original_source),
-                    enabled = COALESCE($4, guild_scripts.enabled),
-                    contributes_commands = COALESCE($5, guild_scripts.contributes_commands)
+                    name = $3
                     WHERE guild_id = $1 AND id=$2
                     RETURNING id, name, original_source, guild_id, enabled, contributes_commands, \
-             contributes_interval_timers, plugin_id, plugin_auto_update;
+             contributes_interval_timers, contributes_api_version, plugin_id, plugin_auto_update, \
+             min_log_level;
                 ",
             guild_id.get() as i64,
-            script.id as i64,
-            script.original_source,
-            script.enabled,
-            commands_enc,
+            script_id as i64,
+            new_name,
         )
-        .fetch_one(&self.pool)
-        .await?;
+        .fetch_one(&mut tx)
+        .await
+        {
+            Ok(s) => s,
+            Err(sqlx::Error::RowNotFound) => return Err(ConfigStoreError::ScriptNotFound),
+            Err(e) => return Err(e.into()),
+        };
+
+        tx.commit().await?;
 
         Ok(res.into())
     }
 
-    async fn update_script_contributes(
+    async fn set_script_min_log_level(
         &self,
         guild_id: Id<GuildMarker>,
         script_id: u64,
-        contribs: ScriptContributes,
+        min_log_level: ScriptLogLevel,
     ) -> ConfigStoreResult<Script> {
-        let commands_enc = serde_json::to_value(contribs.commands).unwrap();
-        let intervals_enc = serde_json::to_value(contribs.interval_timers).unwrap();
-
-        let res = sqlx::query_as!(
+        let res = match sqlx::query_as!(
             DbScript,
             "
                     UPDATE guild_scripts SET
-                    contributes_commands = $3,
-                    contributes_interval_timers = $4
+                    min_log_level = $3
                     WHERE guild_id = $1 AND id=$2
                     RETURNING id, name, original_source, guild_id, enabled, contributes_commands, \
-             contributes_interval_timers, plugin_id, plugin_auto_update;
+             contributes_interval_timers, contributes_api_version, plugin_id, plugin_auto_update, \
+             min_log_level;
                 ",
             guild_id.get() as i64,
             script_id as i64,
-            commands_enc,
-            intervals_enc,
+            script_log_level_to_int(min_log_level),
         )
         .fetch_one(&self.pool)
-        .await?;
+        .await
+        {
+            Ok(s) => s,
+            Err(sqlx::Error::RowNotFound) => return Err(ConfigStoreError::ScriptNotFound),
+            Err(e) => return Err(e.into()),
+        };
 
         Ok(res.into())
     }
 
-    async fn del_script(
+    async fn add_script_runtime_stats(
         &self,
         guild_id: Id<GuildMarker>,
-        script_name: String,
+        stats: &[ScriptRuntimeStats],
     ) -> ConfigStoreResult<()> {
+        if stats.is_empty() {
+            return Ok(());
+        }
+
+        // scripts could have been deleted in the meantime, those are skipped
+        sqlx::query!(
+            "INSERT INTO script_runtime_stats (script_id, guild_id, dispatches, errors, \
+             last_run_at, last_error_at)
+            SELECT s.script_id, $1, s.dispatches, s.errors, s.last_run_at, s.last_error_at
+            FROM UNNEST($2::BIGINT[], $3::BIGINT[], $4::BIGINT[], $5::TIMESTAMPTZ[], \
+             $6::TIMESTAMPTZ[])
+                AS s(script_id, dispatches, errors, last_run_at, last_error_at)
+            WHERE EXISTS (SELECT 1 FROM guild_scripts WHERE id = s.script_id AND guild_id = $1)
+            ON CONFLICT (script_id) DO UPDATE SET
+            dispatches = script_runtime_stats.dispatches + excluded.dispatches,
+            errors = script_runtime_stats.errors + excluded.errors,
+            last_run_at = GREATEST(script_runtime_stats.last_run_at, excluded.last_run_at),
+            last_error_at = GREATEST(script_runtime_stats.last_error_at, excluded.last_error_at);",
+            guild_id.get() as i64,
+            &stats.iter().map(|v| v.script_id as i64).collect::<Vec<_>>(),
+            &stats
+                .iter()
+                .map(|v| v.dispatches as i64)
+                .collect::<Vec<_>>(),
+            &stats.iter().map(|v| v.errors as i64).collect::<Vec<_>>(),
+            &stats.iter().map(|v| v.last_run_at).collect::<Vec<_>>() as _,
+            &stats.iter().map(|v| v.last_error_at).collect::<Vec<_>>() as _,
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn get_script_stats(
+        &self,
+        guild_id: Id<GuildMarker>,
+        script_id: u64,
+    ) -> ConfigStoreResult<Option<ScriptRuntimeStats>> {
         let res = sqlx::query!(
-            "DELETE FROM guild_scripts WHERE guild_id = $1 AND name = $2;",
+            "SELECT script_id, dispatches, errors, last_run_at, last_error_at FROM \
+             script_runtime_stats WHERE guild_id = $1 AND script_id = $2;",
             guild_id.get() as i64,
-            script_name
+            script_id as i64,
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(res.map(|v| ScriptRuntimeStats {
+            script_id: v.script_id as u64,
+            dispatches: v.dispatches as u64,
+            errors: v.errors as u64,
+            last_run_at: v.last_run_at,
+            last_error_at: v.last_error_at,
+        }))
+    }
+
+    async fn set_script_validation_failure(
+        &self,
+        guild_id: Id<GuildMarker>,
+        script_id: u64,
+        reason: String,
+    ) -> ConfigStoreResult<()> {
+        // the script could have been deleted in the meantime
+        sqlx::query!(
+            "INSERT INTO script_validation_failures (script_id, guild_id, reason, failed_at) \
+             SELECT id, guild_id, $3, now() FROM guild_scripts WHERE id = $2 AND guild_id = $1 \
+             ON CONFLICT (script_id) DO UPDATE SET reason = excluded.reason, failed_at = \
+             excluded.failed_at;",
+            guild_id.get() as i64,
+            script_id as i64,
+            reason,
         )
         .execute(&self.pool)
         .await?;
 
-        if res.rows_affected() > 0 {
-            Ok(())
-        } else {
-            Err(ConfigStoreError::ScriptNotFound)
-        }
+        Ok(())
+    }
+
+    async fn clear_script_validation_failure(
+        &self,
+        guild_id: Id<GuildMarker>,
+        script_id: u64,
+    ) -> ConfigStoreResult<()> {
+        sqlx::query!(
+            "DELETE FROM script_validation_failures WHERE guild_id = $1 AND script_id = $2;",
+            guild_id.get() as i64,
+            script_id as i64,
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn get_failing_scripts(
+        &self,
+        guild_id: Id<GuildMarker>,
+    ) -> ConfigStoreResult<Vec<ScriptValidationFailure>> {
+        let res = sqlx::query!(
+            "SELECT script_id, reason, failed_at FROM script_validation_failures WHERE guild_id \
+             = $1 ORDER BY script_id;",
+            guild_id.get() as i64,
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(res
+            .into_iter()
+            .map(|v| ScriptValidationFailure {
+                script_id: v.script_id as u64,
+                reason: v.reason,
+                failed_at: v.failed_at,
+            })
+            .collect())
+    }
+
+    async fn del_script(
+        &self,
+        guild_id: Id<GuildMarker>,
+        script_name: String,
+    ) -> ConfigStoreResult<()> {
+        Self::inner_del_script(&mut *self.pool.acquire().await?, guild_id, script_name).await
     }
 
     async fn list_scripts(&self, guild_id: Id<GuildMarker>) -> ConfigStoreResult<Vec<Script>> {
@@ -276,7 +612,7 @@ impl crate::config::ConfigStore for Postgres {
     ) -> ConfigStoreResult<Option<GuildMetaConfig>> {
         match sqlx::query_as!(
             DbGuildMetaConfig,
-            "SELECT guild_id, error_channel_id FROM guild_meta_configs
+            "SELECT guild_id, error_channel_id, timezone FROM guild_meta_configs
         WHERE guild_id = $1;",
             guild_id.get() as i64,
         )
@@ -295,14 +631,16 @@ impl crate::config::ConfigStore for Postgres {
     ) -> ConfigStoreResult<GuildMetaConfig> {
         let db_conf = sqlx::query_as!(
             DbGuildMetaConfig,
-            "INSERT INTO guild_meta_configs (guild_id, error_channel_id) VALUES ($1, $2)
+            "INSERT INTO guild_meta_configs (guild_id, error_channel_id, timezone) VALUES ($1, $2, $3)
             ON CONFLICT (guild_id) DO UPDATE SET
-            error_channel_id = $2
-            RETURNING guild_id, error_channel_id;",
+            error_channel_id = $2,
+            timezone = $3
+            RETURNING guild_id, error_channel_id, timezone;",
             conf.guild_id.get() as i64,
             conf.error_channel_id
                 .map(|e| e.get() as i64)
                 .unwrap_or_default(),
+            conf.timezone.as_deref(),
         )
         .fetch_one(&self.pool)
         .await?;
@@ -316,7 +654,9 @@ impl crate::config::ConfigStore for Postgres {
             "INSERT INTO joined_guilds (id, name, icon, owner_id, left_at) VALUES ($1, $2, $3, \
              $4, null)
             ON CONFLICT (id) DO UPDATE SET 
-            name = $2, icon = $3, owner_id = $4, left_at = null
+            name = $2, icon = $3, owner_id = $4, left_at = null,
+            joined_at = CASE WHEN joined_guilds.left_at IS NULL THEN joined_guilds.joined_at \
+             ELSE now() END
             RETURNING id, name, icon, owner_id, left_at;",
             guild.id.get() as i64,
             &guild.name,
@@ -351,6 +691,27 @@ impl crate::config::ConfigStore for Postgres {
         Ok(db_guild.into())
     }
 
+    async fn mark_guilds_left(
+        &self,
+        guild_ids: &[Id<GuildMarker>],
+        left: bool,
+    ) -> ConfigStoreResult<u64> {
+        let res = sqlx::query!(
+            "UPDATE joined_guilds SET left_at = CASE 
+                WHEN left_at IS NULL AND $2 = true THEN now()
+                WHEN $2 = false THEN null
+                ELSE left_at
+                END
+            WHERE id = ANY ($1);",
+            &guild_ids.iter().map(|e| e.get() as i64).collect::<Vec<_>>(),
+            left
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(res.rows_affected())
+    }
+
     async fn get_joined_guilds(
         &self,
         ids: &[Id<GuildMarker>],
@@ -402,6 +763,202 @@ impl crate::config::ConfigStore for Postgres {
         Ok(guilds.into_iter().map(|e| e.into()).collect())
     }
 
+    async fn purge_left_guild_data(
+        &self,
+        guild_id: Id<GuildMarker>,
+    ) -> ConfigStoreResult<Option<GuildDataPurge>> {
+        let id = guild_id.get() as i64;
+        let mut tx = self.pool.begin().await?;
+
+        // locked so the guild can't be marked as joined again halfway through
+        let left = sqlx::query!(
+            "SELECT left_at FROM joined_guilds WHERE id = $1 FOR UPDATE;",
+            id
+        )
+        .fetch_optional(&mut tx)
+        .await?;
+        if !matches!(left, Some(row) if row.left_at.is_some()) {
+            return Ok(None);
+        }
+
+        let mut purge = GuildDataPurge::default();
+
+        let res = sqlx::query!("DELETE FROM bucket_store WHERE guild_id = $1;", id)
+            .execute(&mut tx)
+            .await?;
+        purge.tables.push(("bucket_store", res.rows_affected()));
+
+        let res = sqlx::query!("DELETE FROM scheduled_tasks WHERE guild_id = $1;", id)
+            .execute(&mut tx)
+            .await?;
+        purge.tables.push(("scheduled_tasks", res.rows_affected()));
+
+        let res = sqlx::query!("DELETE FROM interval_timers WHERE guild_id = $1;", id)
+            .execute(&mut tx)
+            .await?;
+        purge.tables.push(("interval_timers", res.rows_affected()));
+
+        let res = sqlx::query!("DELETE FROM task_executions WHERE guild_id = $1;", id)
+            .execute(&mut tx)
+            .await?;
+        purge.tables.push(("task_executions", res.rows_affected()));
+
+        let res = sqlx::query!(
+            "DELETE FROM scheduler_guild_leases WHERE guild_id = $1;",
+            id
+        )
+        .execute(&mut tx)
+        .await?;
+        purge
+            .tables
+            .push(("scheduler_guild_leases", res.rows_affected()));
+
+        let res = sqlx::query!(
+            "DELETE FROM guild_ratelimit_buckets WHERE guild_id = $1;",
+            id
+        )
+        .execute(&mut tx)
+        .await?;
+        purge
+            .tables
+            .push(("guild_ratelimit_buckets", res.rows_affected()));
+
+        // these reference the scripts, so they go first
+        let res = sqlx::query!("DELETE FROM script_runtime_stats WHERE guild_id = $1;", id)
+            .execute(&mut tx)
+            .await?;
+        purge
+            .tables
+            .push(("script_runtime_stats", res.rows_affected()));
+
+        let res = sqlx::query!(
+            "DELETE FROM script_validation_failures WHERE guild_id = $1;",
+            id
+        )
+        .execute(&mut tx)
+        .await?;
+        purge
+            .tables
+            .push(("script_validation_failures", res.rows_affected()));
+
+        let res = sqlx::query!("DELETE FROM guild_scripts WHERE guild_id = $1;", id)
+            .execute(&mut tx)
+            .await?;
+        purge.tables.push(("guild_scripts", res.rows_affected()));
+
+        let res = sqlx::query!("DELETE FROM plugin_config WHERE guild_id = $1;", id)
+            .execute(&mut tx)
+            .await?;
+        purge.tables.push(("plugin_config", res.rows_affected()));
+
+        let res = sqlx::query!(
+            "DELETE FROM guild_plugin_subscriptions WHERE guild_id = $1;",
+            id
+        )
+        .execute(&mut tx)
+        .await?;
+        purge
+            .tables
+            .push(("guild_plugin_subscriptions", res.rows_affected()));
+
+        let res = sqlx::query!("DELETE FROM guild_meta_configs WHERE guild_id = $1;", id)
+            .execute(&mut tx)
+            .await?;
+        purge
+            .tables
+            .push(("guild_meta_configs", res.rows_affected()));
+
+        let res = sqlx::query!(
+            "UPDATE premium_slots SET attached_guild_id = NULL, updated_at = now() WHERE \
+             attached_guild_id = $1;",
+            id
+        )
+        .execute(&mut tx)
+        .await?;
+        purge.tables.push(("premium_slots", res.rows_affected()));
+
+        let res = sqlx::query!("DELETE FROM joined_guilds WHERE id = $1;", id)
+            .execute(&mut tx)
+            .await?;
+        purge.tables.push(("joined_guilds", res.rows_affected()));
+
+        tx.commit().await?;
+
+        Ok(Some(purge))
+    }
+
+    async fn list_joined_guilds(
+        &self,
+        after: Option<JoinedGuildsCursor>,
+        limit: u32,
+        sort: JoinedGuildsSort,
+        include_left: bool,
+    ) -> ConfigStoreResult<JoinedGuildsPage> {
+        let (after_id, after_name, after_joined_at) = match after {
+            Some(cursor) => (
+                Some(cursor.id.get() as i64),
+                Some(cursor.name),
+                Some(cursor.joined_at),
+            ),
+            None => (None, None, None),
+        };
+
+        let guilds = match sort {
+            JoinedGuildsSort::Name => {
+                sqlx::query_as!(
+                    DbListedJoinedGuild,
+                    "SELECT id, name, icon, owner_id, left_at, joined_at FROM joined_guilds
+                    WHERE ($1::BIGINT IS NULL OR (name, id) > ($2::TEXT, $1))
+                    AND ($3 OR left_at IS NULL)
+                    ORDER BY name ASC, id ASC
+                    LIMIT $4;",
+                    after_id,
+                    after_name,
+                    include_left,
+                    limit as i64,
+                )
+                .fetch_all(&self.pool)
+                .await?
+            }
+            JoinedGuildsSort::RecentlyJoined => {
+                sqlx::query_as!(
+                    DbListedJoinedGuild,
+                    "SELECT id, name, icon, owner_id, left_at, joined_at FROM joined_guilds
+                    WHERE ($1::BIGINT IS NULL OR (joined_at, id) < ($2::TIMESTAMPTZ, $1))
+                    AND ($3 OR left_at IS NULL)
+                    ORDER BY joined_at DESC, id DESC
+                    LIMIT $4;",
+                    after_id,
+                    after_joined_at,
+                    include_left,
+                    limit as i64,
+                )
+                .fetch_all(&self.pool)
+                .await?
+            }
+        };
+
+        let next = guilds.last().map(|last| JoinedGuildsCursor {
+            id: Id::new(last.id as u64),
+            name: last.name.clone(),
+            joined_at: last.joined_at,
+        });
+
+        Ok(JoinedGuildsPage {
+            guilds: guilds
+                .into_iter()
+                .map(|g| JoinedGuild {
+                    id: Id::new(g.id as u64),
+                    name: g.name,
+                    icon: g.icon,
+                    owner_id: Id::new(g.owner_id as u64),
+                    left_at: g.left_at,
+                })
+                .collect(),
+            next,
+        })
+    }
+
     async fn is_guild_whitelisted(&self, guild_id: Id<GuildMarker>) -> ConfigStoreResult<bool> {
         let result = sqlx::query!(
             "SELECT count(*) FROM guild_whitelist WHERE guild_id = $1;",
@@ -418,21 +975,81 @@ impl crate::config::ConfigStore for Postgres {
             .execute(&self.pool)
             .await?;
 
-        // TODO: should we delete guild scripts as well?
+        // only the joined guild itself, purge_left_guild_data removes everything else as well
+
+        Ok(())
+    }
+
+    async fn get_guild_premium_slots(
+        &self,
+        guild_id: Id<GuildMarker>,
+    ) -> ConfigStoreResult<Vec<PremiumSlot>> {
+        let res = sqlx::query_as!(
+            DbPremiumSlot,
+            "SELECT id, title, user_id, message, source, source_id, tier, state, created_at, \
+             updated_at, expires_at, manage_url, attached_guild_id
+             FROM premium_slots WHERE attached_guild_id = $1;",
+            guild_id.get() as i64,
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(res.into_iter().map(Into::into).collect())
+    }
+
+    async fn get_expiring_premium_slots(
+        &self,
+        within_hours: u64,
+    ) -> ConfigStoreResult<Vec<PremiumSlot>> {
+        let interval = PgInterval {
+            days: 0,
+            months: 0,
+            microseconds: within_hours as i64 * 1000 * 1000 * 60 * 60,
+        };
+
+        let res = sqlx::query_as!(
+            DbPremiumSlot,
+            "SELECT id, title, user_id, message, source, source_id, tier, state, created_at, \
+             updated_at, expires_at, manage_url, attached_guild_id
+             FROM premium_slots WHERE state = $1 AND expires_at > now() AND expires_at < (now() \
+             + $2::interval);",
+            state_to_int(PremiumSlotState::Active),
+            interval,
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(res.into_iter().map(Into::into).collect())
+    }
+
+    async fn expire_lapsed_premium_slots(&self) -> ConfigStoreResult<Vec<PremiumSlot>> {
+        let res = sqlx::query_as!(
+            DbPremiumSlot,
+            r#"
+UPDATE premium_slots SET state = $1, attached_guild_id = NULL, updated_at = now()
+WHERE expires_at < now() AND state = $2
+RETURNING id, title, user_id, message, source, source_id, tier, state, created_at, 
+            updated_at, expires_at, manage_url, attached_guild_id;
+             "#,
+            state_to_int(PremiumSlotState::Expired),
+            state_to_int(PremiumSlotState::Active),
+        )
+        .fetch_all(&self.pool)
+        .await?;
 
-        Ok(())
+        Ok(res.into_iter().map(Into::into).collect())
     }
 
-    async fn get_guild_premium_slots(
+    async fn get_user_premium_slots(
         &self,
-        guild_id: Id<GuildMarker>,
+        user_id: Id<UserMarker>,
     ) -> ConfigStoreResult<Vec<PremiumSlot>> {
         let res = sqlx::query_as!(
             DbPremiumSlot,
             "SELECT id, title, user_id, message, source, source_id, tier, state, created_at, \
              updated_at, expires_at, manage_url, attached_guild_id
-             FROM premium_slots WHERE attached_guild_id = $1;",
-            guild_id.get() as i64,
+             FROM premium_slots WHERE user_id = $1;",
+            user_id.get() as i64,
         )
         .fetch_all(&self.pool)
         .await?;
@@ -440,7 +1057,7 @@ impl crate::config::ConfigStore for Postgres {
         Ok(res.into_iter().map(Into::into).collect())
     }
 
-    async fn get_user_premium_slots(
+    async fn find_duplicate_slots(
         &self,
         user_id: Id<UserMarker>,
     ) -> ConfigStoreResult<Vec<PremiumSlot>> {
@@ -448,7 +1065,14 @@ impl crate::config::ConfigStore for Postgres {
             DbPremiumSlot,
             "SELECT id, title, user_id, message, source, source_id, tier, state, created_at, \
              updated_at, expires_at, manage_url, attached_guild_id
-             FROM premium_slots WHERE user_id = $1;",
+             FROM premium_slots AS a
+             WHERE user_id = $1 AND EXISTS (
+                SELECT 1 FROM premium_slots AS b
+                WHERE b.user_id = a.user_id AND b.id != a.id AND b.tier = a.tier
+                AND b.source != a.source
+                AND b.created_at < a.expires_at AND a.created_at < b.expires_at
+             )
+             ORDER BY tier, created_at;",
             user_id.get() as i64,
         )
         .fetch_all(&self.pool)
@@ -523,6 +1147,116 @@ RETURNING id, title, user_id, message, source, source_id, tier, state, created_a
         Ok(res.into())
     }
 
+    async fn reattach_premium_slot(
+        &self,
+        user_id: Id<UserMarker>,
+        slot_id: u64,
+        from_guild: Id<GuildMarker>,
+        to_guild: Id<GuildMarker>,
+    ) -> ConfigStoreResult<PremiumSlot> {
+        let mut tx = self.pool.begin().await?;
+
+        let current = sqlx::query!(
+            "SELECT attached_guild_id FROM premium_slots WHERE id = $1 AND user_id = $2 FOR \
+             UPDATE;",
+            slot_id as i64,
+            user_id.get() as i64,
+        )
+        .fetch_optional(&mut tx)
+        .await?;
+
+        match current {
+            None => return Err(ConfigStoreError::PremiumSlotNotFound),
+            Some(row) if row.attached_guild_id != Some(from_guild.get() as i64) => {
+                return Err(ConfigStoreError::PremiumSlotNotOnGuild)
+            }
+            _ => {}
+        }
+
+        let res = sqlx::query_as!(
+            DbPremiumSlot,
+            r#"
+UPDATE premium_slots SET attached_guild_id = $2
+WHERE id = $1
+RETURNING id, title, user_id, message, source, source_id, tier, state, created_at, 
+            updated_at, expires_at, manage_url, attached_guild_id;
+             "#,
+            slot_id as i64,
+            to_guild.get() as i64,
+        )
+        .fetch_one(&mut tx)
+        .await?;
+
+        tx.commit().await?;
+
+        Ok(res.into())
+    }
+
+    async fn get_attachable_guilds(
+        &self,
+        user_id: Id<UserMarker>,
+        admin_guild_ids: &[Id<GuildMarker>],
+        tier: PremiumSlotTier,
+    ) -> ConfigStoreResult<Vec<AttachableGuild>> {
+        let guilds = sqlx::query_as!(
+            DbJoinedGuild,
+            "SELECT id, name, icon, owner_id, left_at FROM joined_guilds WHERE (owner_id = $1 OR \
+             id = ANY ($2)) AND left_at IS NULL ORDER BY name;",
+            user_id.get() as i64,
+            &admin_guild_ids
+                .iter()
+                .map(|e| e.get() as i64)
+                .collect::<Vec<_>>(),
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        // slots that are cancelling still run until they expire
+        let slots = sqlx::query_as!(
+            DbPremiumSlot,
+            "SELECT id, title, user_id, message, source, source_id, tier, state, created_at, \
+             updated_at, expires_at, manage_url, attached_guild_id FROM premium_slots WHERE \
+             attached_guild_id = ANY ($1) AND state = ANY ($2) AND expires_at > now();",
+            &guilds.iter().map(|g| g.id).collect::<Vec<_>>(),
+            &[
+                state_to_int(PremiumSlotState::Active),
+                state_to_int(PremiumSlotState::Cancelling),
+            ],
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        let mut slots = slots.into_iter().map(PremiumSlot::from).collect::<Vec<_>>();
+        let mut result = Vec::new();
+        for guild in guilds {
+            let guild = JoinedGuild::from(guild);
+
+            let (guild_slots, rest) = slots
+                .into_iter()
+                .partition::<Vec<_>, _>(|slot| slot.attached_guild_id == Some(guild.id));
+            slots = rest;
+
+            let current_slot = guild_slots.into_iter().reduce(|highest, slot| {
+                if slot.tier.is_higher_than(highest.tier) {
+                    slot
+                } else {
+                    highest
+                }
+            });
+
+            if matches!(&current_slot, Some(current) if !tier.is_higher_than(current.tier)) {
+                continue;
+            }
+
+            result.push(AttachableGuild {
+                guild,
+                current_slot,
+            });
+        }
+
+        Ok(result)
+    }
+
     async fn create_plugin(&self, create_plugin: CreatePlugin) -> ConfigStoreResult<Plugin> {
         let res = sqlx::query_as!(
             DbPlugin,
@@ -761,6 +1495,42 @@ FROM plugins WHERE author_id = $1"#,
         .collect())
     }
 
+    async fn get_plugins_by_authors(
+        &self,
+        user_ids: &[u64],
+        published_only: bool,
+    ) -> ConfigStoreResult<Vec<Plugin>> {
+        let user_ids = user_ids.iter().map(|v| *v as i64).collect::<Vec<_>>();
+
+        Ok(sqlx::query_as!(
+            DbPlugin,
+            r#"SELECT id,
+created_at,
+name,
+short_description,
+long_description,
+is_published,
+is_official,
+plugin_kind,
+current_version_number,
+script_published_source,
+script_published_version_updated_at,
+script_dev_source,
+script_dev_version_updated_at,
+author_id,
+is_public
+FROM plugins WHERE author_id = ANY($1) AND (is_published OR NOT $2)
+ORDER BY author_id, id"#,
+            &user_ids,
+            published_only,
+        )
+        .fetch_all(&self.pool)
+        .await?
+        .into_iter()
+        .map(Into::into)
+        .collect())
+    }
+
     async fn get_published_public_plugins(&self) -> ConfigStoreResult<Vec<Plugin>> {
         Ok(sqlx::query_as!(
             DbPlugin,
@@ -788,6 +1558,70 @@ FROM plugins WHERE is_published = true AND is_public = true"#,
         .collect())
     }
 
+    async fn get_recently_updated_plugins(&self, limit: u32) -> ConfigStoreResult<Vec<Plugin>> {
+        Ok(sqlx::query_as!(
+            DbPlugin,
+            r#"SELECT id,
+created_at,
+name,
+short_description,
+long_description,
+is_published,
+is_official,
+plugin_kind,
+current_version_number,
+script_published_source,
+script_published_version_updated_at,
+script_dev_source,
+script_dev_version_updated_at,
+author_id,
+is_public
+FROM plugins WHERE is_published = true AND is_public = true
+ORDER BY script_published_version_updated_at DESC NULLS LAST
+LIMIT $1"#,
+            limit as i64,
+        )
+        .fetch_all(&self.pool)
+        .await?
+        .into_iter()
+        .map(Into::into)
+        .collect())
+    }
+
+    async fn get_plugin_publish_status(
+        &self,
+        plugin_id: u64,
+    ) -> ConfigStoreResult<PluginPublishStatus> {
+        let res = sqlx::query!(
+            r#"SELECT
+(script_dev_source IS NOT NULL AND script_dev_source IS DISTINCT FROM script_published_source) AS "has_unpublished_changes!",
+script_dev_version_updated_at,
+script_published_version_updated_at
+FROM plugins WHERE id = $1"#,
+            plugin_id as i64,
+        )
+        .fetch_optional(&self.pool)
+        .await?
+        .ok_or(ConfigStoreError::PluginNotFound(plugin_id))?;
+
+        Ok(PluginPublishStatus {
+            has_unpublished_changes: res.has_unpublished_changes,
+            dev_updated_at: res.script_dev_version_updated_at,
+            published_updated_at: res.script_published_version_updated_at,
+        })
+    }
+
+    async fn count_plugin_installations(&self, plugin_id: u64) -> ConfigStoreResult<u64> {
+        let result = sqlx::query!(
+            "SELECT count(*) FROM guild_scripts WHERE plugin_id = $1;",
+            plugin_id as i64,
+        )
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(result.count.unwrap_or_default() as u64)
+    }
+
     async fn try_guild_add_script_plugin(
         &self,
         guild_id: Id<GuildMarker>,
@@ -807,7 +1641,7 @@ FROM plugins WHERE is_published = true AND is_public = true"#,
             PluginData::ScriptPluginData(d) => d.published_version.unwrap_or_default(),
         };
 
-        Self::inner_create_script(
+        let script = Self::inner_create_script(
             &mut tx,
             guild_id,
             CreateScript {
@@ -818,7 +1652,121 @@ FROM plugins WHERE is_published = true AND is_public = true"#,
                 plugin_id: Some(plugin_id),
             },
         )
-        .await
+        .await?;
+
+        tx.commit().await?;
+        Ok(script)
+    }
+
+    async fn get_guild_plugin_config(
+        &self,
+        guild_id: Id<GuildMarker>,
+        plugin_id: u64,
+    ) -> ConfigStoreResult<Vec<PluginConfigEntry>> {
+        let res = sqlx::query_as!(
+            DbPluginConfigEntry,
+            "SELECT plugin_id, key, value, updated_at FROM plugin_config WHERE guild_id = $1 AND \
+             plugin_id = $2 ORDER BY key;",
+            guild_id.get() as i64,
+            plugin_id as i64,
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(res.into_iter().map(Into::into).collect())
+    }
+
+    async fn set_guild_plugin_config_value(
+        &self,
+        guild_id: Id<GuildMarker>,
+        plugin_id: u64,
+        key: String,
+        value: String,
+    ) -> ConfigStoreResult<PluginConfigEntry> {
+        let res = sqlx::query_as!(
+            DbPluginConfigEntry,
+            "INSERT INTO plugin_config (guild_id, plugin_id, key, value, updated_at)
+VALUES ($1, $2, $3, $4, now())
+ON CONFLICT (guild_id, plugin_id, key) DO UPDATE SET
+value = excluded.value,
+updated_at = excluded.updated_at
+RETURNING plugin_id, key, value, updated_at;",
+            guild_id.get() as i64,
+            plugin_id as i64,
+            key,
+            value,
+        )
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(res.into())
+    }
+
+    async fn del_guild_plugin_config_value(
+        &self,
+        guild_id: Id<GuildMarker>,
+        plugin_id: u64,
+        key: String,
+    ) -> ConfigStoreResult<bool> {
+        let res = sqlx::query!(
+            "DELETE FROM plugin_config WHERE guild_id = $1 AND plugin_id = $2 AND key = $3;",
+            guild_id.get() as i64,
+            plugin_id as i64,
+            key,
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(res.rows_affected() > 0)
+    }
+
+    async fn claim_guild(
+        &self,
+        guild_id: Id<GuildMarker>,
+        node_id: &str,
+        lease_duration: std::time::Duration,
+    ) -> ConfigStoreResult<Option<u64>> {
+        // the database clock is used for the expiry so clock skew between nodes doesn't matter,
+        // and generations come from a sequence so they're never reused even if the row is deleted
+        let res = sqlx::query!(
+            "INSERT INTO scheduler_guild_leases (guild_id, node_id, expires_at)
+            VALUES ($1, $2, now() + make_interval(secs => $3))
+            ON CONFLICT (guild_id) DO UPDATE SET
+                node_id = excluded.node_id,
+                expires_at = excluded.expires_at,
+                generation = CASE
+                    WHEN scheduler_guild_leases.node_id = excluded.node_id
+                        AND scheduler_guild_leases.expires_at >= now()
+                    THEN scheduler_guild_leases.generation
+                    ELSE nextval('scheduler_guild_lease_generations')
+                END
+            WHERE scheduler_guild_leases.node_id = excluded.node_id
+                OR scheduler_guild_leases.expires_at < now()
+            RETURNING generation;",
+            guild_id.get() as i64,
+            node_id,
+            lease_duration.as_secs_f64(),
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(res.map(|v| v.generation as u64))
+    }
+
+    async fn release_guild(
+        &self,
+        guild_id: Id<GuildMarker>,
+        node_id: &str,
+    ) -> ConfigStoreResult<()> {
+        sqlx::query!(
+            "DELETE FROM scheduler_guild_leases WHERE guild_id = $1 AND node_id = $2;",
+            guild_id.get() as i64,
+            node_id,
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
     }
 }
 
@@ -831,8 +1779,10 @@ struct DbScript {
     enabled: bool,
     contributes_commands: serde_json::Value,
     contributes_interval_timers: serde_json::Value,
+    contributes_api_version: Option<i32>,
     plugin_id: Option<i64>,
     plugin_auto_update: Option<bool>,
+    min_log_level: Option<i16>,
 }
 
 impl From<DbScript> for Script {
@@ -849,9 +1799,11 @@ impl From<DbScript> for Script {
             contributes: ScriptContributes {
                 commands: commands_dec,
                 interval_timers: intervals_dec,
+                api_version: script.contributes_api_version.map(|v| v as u32),
             },
             plugin_id: script.plugin_id.map(|v| v as u64),
             plugin_auto_update: script.plugin_auto_update,
+            min_log_level: script_log_level_from_int(script.min_log_level),
         }
     }
 }
@@ -859,6 +1811,7 @@ impl From<DbScript> for Script {
 struct DbGuildMetaConfig {
     pub guild_id: i64,
     pub error_channel_id: i64,
+    pub timezone: Option<String>,
 }
 
 impl From<DbGuildMetaConfig> for GuildMetaConfig {
@@ -870,6 +1823,7 @@ impl From<DbGuildMetaConfig> for GuildMetaConfig {
             } else {
                 None
             },
+            timezone: mc.timezone,
         }
     }
 }
@@ -882,6 +1836,16 @@ pub struct DbJoinedGuild {
     pub left_at: Option<DateTime<Utc>>,
 }
 
+// a joined guild along with the sort key only used for paging
+pub struct DbListedJoinedGuild {
+    pub id: i64,
+    pub name: String,
+    pub icon: String,
+    pub owner_id: i64,
+    pub left_at: Option<DateTime<Utc>>,
+    pub joined_at: DateTime<Utc>,
+}
+
 impl From<DbJoinedGuild> for JoinedGuild {
     fn from(g: DbJoinedGuild) -> Self {
         Self {
@@ -894,6 +1858,49 @@ impl From<DbJoinedGuild> for JoinedGuild {
     }
 }
 
+pub struct PostgresConfigTransaction(Transaction<'static, sqlx::Postgres>);
+
+#[async_trait]
+impl ConfigStoreTransaction for PostgresConfigTransaction {
+    async fn create_script(
+        &mut self,
+        guild_id: Id<GuildMarker>,
+        script: CreateScript,
+    ) -> ConfigStoreResult<Script> {
+        Postgres::inner_create_script(&mut self.0, guild_id, script).await
+    }
+
+    async fn update_script(
+        &mut self,
+        guild_id: Id<GuildMarker>,
+        script: UpdateScript,
+    ) -> ConfigStoreResult<Script> {
+        Postgres::inner_update_script(&mut self.0, guild_id, script).await
+    }
+
+    async fn update_script_contributes(
+        &mut self,
+        guild_id: Id<GuildMarker>,
+        script_id: u64,
+        contribs: ScriptContributes,
+    ) -> ConfigStoreResult<Script> {
+        Postgres::inner_update_script_contributes(&mut self.0, guild_id, script_id, contribs).await
+    }
+
+    async fn del_script(
+        &mut self,
+        guild_id: Id<GuildMarker>,
+        script_name: String,
+    ) -> ConfigStoreResult<()> {
+        Postgres::inner_del_script(&mut self.0, guild_id, script_name).await
+    }
+
+    async fn commit(self: Box<Self>) -> ConfigStoreResult<()> {
+        self.0.commit().await?;
+        Ok(())
+    }
+}
+
 impl From<sqlx::Error> for ConfigStoreError {
     fn from(err: sqlx::Error) -> Self {
         Self::Other(Box::new(err))
@@ -935,6 +1942,7 @@ impl From<DbPremiumSlot> for PremiumSlot {
                 2 => PremiumSlotState::Cancelling,
                 3 => PremiumSlotState::Cancelled,
                 4 => PremiumSlotState::PaymentFailed,
+                5 => PremiumSlotState::Expired,
                 _ => panic!(
                     "unknown premium slot state, id: {}, state: {}",
                     v.id, v.state
@@ -966,6 +1974,30 @@ fn state_to_int(state: PremiumSlotState) -> i32 {
         PremiumSlotState::Cancelling => 2,
         PremiumSlotState::Cancelled => 3,
         PremiumSlotState::PaymentFailed => 4,
+        PremiumSlotState::Expired => 5,
+    }
+}
+
+// scripts that inherit the guild's floor are stored as null
+fn script_log_level_to_int(level: ScriptLogLevel) -> Option<i16> {
+    match level {
+        ScriptLogLevel::Inherit => None,
+        ScriptLogLevel::ConsoleLog => Some(1),
+        ScriptLogLevel::Info => Some(2),
+        ScriptLogLevel::Warn => Some(3),
+        ScriptLogLevel::Error => Some(4),
+        ScriptLogLevel::Critical => Some(5),
+    }
+}
+
+fn script_log_level_from_int(level: Option<i16>) -> ScriptLogLevel {
+    match level {
+        Some(1) => ScriptLogLevel::ConsoleLog,
+        Some(2) => ScriptLogLevel::Info,
+        Some(3) => ScriptLogLevel::Warn,
+        Some(4) => ScriptLogLevel::Error,
+        Some(5) => ScriptLogLevel::Critical,
+        _ => ScriptLogLevel::Inherit,
     }
 }
 
@@ -1030,3 +2062,21 @@ impl From<DbUserMeta> for UserMeta {
         }
     }
 }
+
+struct DbPluginConfigEntry {
+    plugin_id: i64,
+    key: String,
+    value: String,
+    updated_at: DateTime<Utc>,
+}
+
+impl From<DbPluginConfigEntry> for PluginConfigEntry {
+    fn from(value: DbPluginConfigEntry) -> Self {
+        Self {
+            plugin_id: value.plugin_id as u64,
+            key: value.key,
+            value: value.value,
+            updated_at: value.updated_at,
+        }
+    }
+}
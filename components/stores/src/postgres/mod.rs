@@ -2,6 +2,7 @@ use sqlx::{postgres::PgPoolOptions, PgPool};
 
 pub mod bucketstore;
 pub mod config;
+pub mod ratelimits;
 pub mod timers;
 pub mod web;
 
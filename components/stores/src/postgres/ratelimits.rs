@@ -0,0 +1,49 @@
+use std::time::Duration;
+
+use crate::ratelimits::{RateLimitStoreError, RateLimitStoreResult};
+
+use super::Postgres;
+use async_trait::async_trait;
+use twilight_model::id::{marker::GuildMarker, Id};
+
+impl From<sqlx::Error> for RateLimitStoreError {
+    fn from(err: sqlx::Error) -> Self {
+        Self::Other(Box::new(err))
+    }
+}
+
+#[async_trait]
+impl crate::ratelimits::RateLimitStore for Postgres {
+    async fn take_token(
+        &self,
+        guild_id: Id<GuildMarker>,
+        bucket: &str,
+        capacity: u32,
+        refill_per_second: u32,
+    ) -> RateLimitStoreResult<Option<Duration>> {
+        // tokens can go negative, the caller then waits until the bucket would have refilled to 0
+        let res = sqlx::query!(
+            "INSERT INTO guild_ratelimit_buckets (guild_id, bucket, tokens, updated_at)
+            VALUES ($1, $2, $3 - 1, now())
+            ON CONFLICT (guild_id, bucket) DO UPDATE SET
+            tokens = LEAST($3, guild_ratelimit_buckets.tokens + EXTRACT(EPOCH FROM (now() - \
+             guild_ratelimit_buckets.updated_at)) * $4) - 1,
+            updated_at = now()
+            RETURNING tokens;",
+            guild_id.get() as i64,
+            bucket,
+            capacity as f64,
+            refill_per_second as f64,
+        )
+        .fetch_one(&self.pool)
+        .await?;
+
+        if res.tokens >= 0.0 || refill_per_second == 0 {
+            Ok(None)
+        } else {
+            Ok(Some(Duration::from_secs_f64(
+                -res.tokens / refill_per_second as f64,
+            )))
+        }
+    }
+}
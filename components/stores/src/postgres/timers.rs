@@ -1,7 +1,8 @@
-use std::convert::TryFrom;
+use std::{collections::HashMap, convert::TryFrom};
 
 use crate::timers::{
-    IntervalTimer, IntervalType, ScheduledTask, TimerStoreError, TimerStoreResult,
+    IntervalTimer, IntervalType, ScheduledTask, TaskExecution, TaskNamespaceSummary, TaskOutcome,
+    TaskTag, TimerStoreError, TimerStoreResult,
 };
 
 use super::Postgres;
@@ -110,20 +111,23 @@ impl crate::timers::TimerStore for Postgres {
         guild_id: Id<GuildMarker>,
         name: String,
         unique_key: Option<String>,
+        tags: HashMap<String, String>,
         data: serde_json::Value,
         at: DateTime<Utc>,
     ) -> TimerStoreResult<ScheduledTask> {
         let res = sqlx::query_as!(
             DbScheduledTask,
-            "INSERT INTO scheduled_tasks (guild_id, name, unique_key, value, exec_at) VALUES($1, \
-             $2, $3, $4, $5)
+            "INSERT INTO scheduled_tasks (guild_id, name, unique_key, tags, value, exec_at) \
+             VALUES($1, $2, $3, $4, $5, $6)
             ON CONFLICT (guild_id, name, unique_key) WHERE unique_key IS NOT NULL DO UPDATE SET
+            tags = excluded.tags,
             value = excluded.value,
             exec_at = excluded.exec_at
-            RETURNING id, guild_id, name, unique_key, value, exec_at",
+            RETURNING id, guild_id, name, unique_key, tags, value, exec_at",
             guild_id.get() as i64,
             name,
             unique_key,
+            serde_json::to_value(tags).unwrap(),
             data,
             at,
         )
@@ -140,8 +144,8 @@ impl crate::timers::TimerStore for Postgres {
     ) -> TimerStoreResult<Option<ScheduledTask>> {
         let res = sqlx::query_as!(
             DbScheduledTask,
-            "SELECT id, guild_id, name, unique_key, value, exec_at FROM scheduled_tasks WHERE \
-             guild_id = $1 AND id = $2",
+            "SELECT id, guild_id, name, unique_key, tags, value, exec_at FROM scheduled_tasks \
+             WHERE guild_id = $1 AND id = $2",
             guild_id.get() as i64,
             id as i64,
         )
@@ -158,8 +162,8 @@ impl crate::timers::TimerStore for Postgres {
     ) -> TimerStoreResult<Option<ScheduledTask>> {
         let res = sqlx::query_as!(
             DbScheduledTask,
-            "SELECT id, guild_id, name, unique_key, value, exec_at FROM scheduled_tasks WHERE \
-             guild_id = $1 AND name = $2 AND unique_key = $3",
+            "SELECT id, guild_id, name, unique_key, tags, value, exec_at FROM scheduled_tasks \
+             WHERE guild_id = $1 AND name = $2 AND unique_key = $3",
             guild_id.get() as i64,
             name,
             key,
@@ -170,19 +174,42 @@ impl crate::timers::TimerStore for Postgres {
         Ok(res.map(Into::into))
     }
 
+    async fn task_exists_by_key(
+        &self,
+        guild_id: Id<GuildMarker>,
+        name: String,
+        key: String,
+    ) -> TimerStoreResult<bool> {
+        let res = sqlx::query!(
+            r#"SELECT EXISTS(
+                SELECT 1 FROM scheduled_tasks WHERE guild_id = $1 AND name = $2 AND unique_key = $3
+            ) as "exists!";"#,
+            guild_id.get() as i64,
+            name,
+            key,
+        )
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(res.exists)
+    }
+
     async fn get_tasks(
         &self,
         guild_id: Id<GuildMarker>,
         name: Option<String>,
+        tag: Option<TaskTag>,
         id_after: u64,
         limit: usize,
     ) -> TimerStoreResult<Vec<ScheduledTask>> {
         let res = sqlx::query_as!(
             DbScheduledTask,
-            "SELECT id, guild_id, name, unique_key, value, exec_at FROM scheduled_tasks WHERE \
-             guild_id = $1 AND (name = $2 OR $2 IS NULL) AND id > $3 ORDER BY ID ASC LIMIT $4",
+            "SELECT id, guild_id, name, unique_key, tags, value, exec_at FROM scheduled_tasks \
+             WHERE guild_id = $1 AND (name = $2 OR $2 IS NULL) AND (tags @> $3 OR $3 IS NULL) \
+             AND id > $4 ORDER BY ID ASC LIMIT $5",
             guild_id.get() as i64,
             name,
+            tag.map(tag_filter),
             id_after as i64,
             limit as i64,
         )
@@ -242,6 +269,22 @@ impl crate::timers::TimerStore for Postgres {
         Ok(res.rows_affected())
     }
 
+    async fn del_tasks_by_tag(
+        &self,
+        guild_id: Id<GuildMarker>,
+        tag: TaskTag,
+    ) -> TimerStoreResult<u64> {
+        let res = sqlx::query!(
+            "DELETE FROM scheduled_tasks WHERE guild_id = $1 AND tags @> $2",
+            guild_id.get() as i64,
+            tag_filter(tag),
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(res.rows_affected())
+    }
+
     async fn get_next_task_time(
         &self,
         guild_id: Id<GuildMarker>,
@@ -266,16 +309,20 @@ impl crate::timers::TimerStore for Postgres {
         t: DateTime<Utc>,
         ignore_ids: &[u64],
         names: &[String],
+        lease_generation: Option<u64>,
     ) -> TimerStoreResult<Vec<ScheduledTask>> {
         let res = sqlx::query_as!(
             DbScheduledTask,
-            "SELECT id, guild_id, name, unique_key, value, exec_at FROM scheduled_tasks WHERE \
-             guild_id = $1 AND exec_at < $2 AND name = ANY($3::TEXT[]) AND (NOT id = ANY \
-             ($4::BIGINT[]))",
+            "SELECT id, guild_id, name, unique_key, tags, value, exec_at FROM scheduled_tasks \
+             WHERE guild_id = $1 AND exec_at < $2 AND name = ANY($3::TEXT[]) AND (NOT id = ANY \
+             ($4::BIGINT[])) AND ($5::BIGINT IS NULL OR EXISTS (SELECT 1 FROM \
+             scheduler_guild_leases WHERE guild_id = $1 AND generation = $5 AND expires_at > \
+             now()))",
             guild_id.get() as i64,
             t,
             names,
             &ignore_ids.iter().map(|v| *v as i64).collect::<Vec<_>>(),
+            lease_generation.map(|v| v as i64),
         )
         .fetch_all(&self.pool)
         .await?;
@@ -294,6 +341,81 @@ impl crate::timers::TimerStore for Postgres {
         Ok(res.count.unwrap_or_default() as u64)
     }
 
+    async fn get_task_namespace_summary(
+        &self,
+        guild_id: Id<GuildMarker>,
+        limit: usize,
+    ) -> TimerStoreResult<Vec<TaskNamespaceSummary>> {
+        let res = sqlx::query!(
+            r#"SELECT name, COUNT(*) as "count!", MIN(exec_at) as "next_execute_at!"
+            FROM scheduled_tasks
+            WHERE guild_id = $1
+            GROUP BY name
+            ORDER BY MIN(exec_at) ASC
+            LIMIT $2;"#,
+            guild_id.get() as i64,
+            limit as i64,
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(res
+            .into_iter()
+            .map(|v| TaskNamespaceSummary {
+                name: v.name,
+                count: v.count as u64,
+                next_execute_at: v.next_execute_at,
+            })
+            .collect())
+    }
+
+    async fn record_task_execution(
+        &self,
+        guild_id: Id<GuildMarker>,
+        execution: TaskExecution,
+        keep: u64,
+    ) -> TimerStoreResult<()> {
+        // inserts and prunes everything past the newest `keep` executions in one go, the delete
+        // doesn't see the inserted row so one less of the existing ones is kept
+        sqlx::query!(
+            "WITH inserted AS (INSERT INTO task_executions (guild_id, task_id, name, fired_at, \
+             outcome, error) VALUES ($1, $2, $3, $4, $5, $6)) DELETE FROM task_executions WHERE \
+             guild_id = $1 AND id <= (SELECT id FROM task_executions WHERE guild_id = $1 ORDER BY \
+             id DESC OFFSET $7 LIMIT 1);",
+            guild_id.get() as i64,
+            execution.task_id as i64,
+            execution.name,
+            execution.fired_at,
+            task_outcome_to_int(execution.outcome),
+            execution.error,
+            keep.saturating_sub(1) as i64,
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn get_task_executions(
+        &self,
+        guild_id: Id<GuildMarker>,
+        name: String,
+        limit: usize,
+    ) -> TimerStoreResult<Vec<TaskExecution>> {
+        let res = sqlx::query_as!(
+            DbTaskExecution,
+            "SELECT task_id, name, fired_at, outcome, error FROM task_executions WHERE guild_id = \
+             $1 AND name = $2 ORDER BY id DESC LIMIT $3;",
+            guild_id.get() as i64,
+            name,
+            limit as i64,
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(res.into_iter().filter_map(|v| v.try_into().ok()).collect())
+    }
+
     async fn delete_guild_timer_data(&self, id: Id<GuildMarker>) -> TimerStoreResult<()> {
         sqlx::query!(
             "DELETE FROM scheduled_tasks WHERE guild_id = $1;",
@@ -309,6 +431,13 @@ impl crate::timers::TimerStore for Postgres {
         .execute(&self.pool)
         .await?;
 
+        sqlx::query!(
+            "DELETE FROM task_executions WHERE guild_id = $1;",
+            id.get() as i64
+        )
+        .execute(&self.pool)
+        .await?;
+
         Ok(())
     }
 }
@@ -354,6 +483,7 @@ struct DbScheduledTask {
     guild_id: i64,
     name: String,
     unique_key: Option<String>,
+    tags: serde_json::Value,
     value: serde_json::Value,
     exec_at: DateTime<Utc>,
 }
@@ -364,8 +494,53 @@ impl From<DbScheduledTask> for ScheduledTask {
             id: v.id as u64,
             name: v.name,
             unique_key: v.unique_key,
+            tags: serde_json::from_value(v.tags).unwrap_or_default(),
             data: v.value,
             execute_at: v.exec_at,
         }
     }
 }
+
+struct DbTaskExecution {
+    task_id: i64,
+    name: String,
+    fired_at: DateTime<Utc>,
+    outcome: i16,
+    error: Option<String>,
+}
+
+impl TryFrom<DbTaskExecution> for TaskExecution {
+    type Error = ();
+
+    fn try_from(v: DbTaskExecution) -> Result<Self, Self::Error> {
+        Ok(Self {
+            task_id: v.task_id as u64,
+            name: v.name,
+            fired_at: v.fired_at,
+            outcome: task_outcome_from_int(v.outcome).ok_or(())?,
+            error: v.error,
+        })
+    }
+}
+
+fn task_outcome_to_int(outcome: TaskOutcome) -> i16 {
+    match outcome {
+        TaskOutcome::Succeeded => 1,
+        TaskOutcome::Interrupted => 2,
+        TaskOutcome::Failed => 3,
+    }
+}
+
+fn task_outcome_from_int(outcome: i16) -> Option<TaskOutcome> {
+    match outcome {
+        1 => Some(TaskOutcome::Succeeded),
+        2 => Some(TaskOutcome::Interrupted),
+        3 => Some(TaskOutcome::Failed),
+        _ => None,
+    }
+}
+
+// tasks containing this object have the tag, the containment check uses the tags index
+fn tag_filter(tag: TaskTag) -> serde_json::Value {
+    serde_json::json!({ tag.key: tag.value })
+}
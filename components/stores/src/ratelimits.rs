@@ -0,0 +1,28 @@
+use std::time::Duration;
+
+use thiserror::Error;
+use twilight_model::id::{marker::GuildMarker, Id};
+
+#[derive(Debug, Error)]
+pub enum RateLimitStoreError {
+    #[error("inner error occured: {0}")]
+    Other(#[from] Box<dyn std::error::Error + Send + Sync>),
+}
+
+pub type RateLimitStoreResult<T> = Result<T, RateLimitStoreError>;
+
+/// Token buckets shared between all the vm's of a guild, surviving vm restarts
+#[async_trait::async_trait]
+pub trait RateLimitStore: Send + Sync {
+    /// Takes a token from the guild's bucket, refilling it first based on the time since it was last used.
+    ///
+    /// The token is always taken, if the bucket was empty the caller has to wait for the returned duration
+    /// before proceeding.
+    async fn take_token(
+        &self,
+        guild_id: Id<GuildMarker>,
+        bucket: &str,
+        capacity: u32,
+        refill_per_second: u32,
+    ) -> RateLimitStoreResult<Option<Duration>>;
+}
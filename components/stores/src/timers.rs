@@ -1,3 +1,5 @@
+use std::collections::HashMap;
+
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use thiserror::Error;
@@ -35,6 +37,7 @@ pub trait TimerStore: Send + Sync {
         guild_id: Id<GuildMarker>,
         name: String,
         unique_key: Option<String>,
+        tags: HashMap<String, String>,
         data: serde_json::Value,
         at: DateTime<Utc>,
     ) -> TimerStoreResult<ScheduledTask>;
@@ -50,10 +53,17 @@ pub trait TimerStore: Send + Sync {
         name: String,
         key: String,
     ) -> TimerStoreResult<Option<ScheduledTask>>;
+    async fn task_exists_by_key(
+        &self,
+        guild_id: Id<GuildMarker>,
+        name: String,
+        key: String,
+    ) -> TimerStoreResult<bool>;
     async fn get_tasks(
         &self,
         guild_id: Id<GuildMarker>,
         name: Option<String>,
+        tag: Option<TaskTag>,
         id_after: u64,
         limit: usize,
     ) -> TimerStoreResult<Vec<ScheduledTask>>;
@@ -77,6 +87,13 @@ pub trait TimerStore: Send + Sync {
         name: Option<String>,
     ) -> TimerStoreResult<u64>;
 
+    /// Delete all tasks on a guild that have the provided tag, across all names
+    async fn del_tasks_by_tag(
+        &self,
+        guild_id: Id<GuildMarker>,
+        tag: TaskTag,
+    ) -> TimerStoreResult<u64>;
+
     // async fn get_next_task_time(
     //     &self,
     //     guild_id: Id<GuildMarker>,
@@ -89,6 +106,14 @@ pub trait TimerStore: Send + Sync {
 
     async fn get_task_count(&self, guild_id: Id<GuildMarker>) -> TimerStoreResult<u64>;
 
+    /// Returns the number of tasks and the next execution time per namespace,
+    /// sorted by the earliest next execution time
+    async fn get_task_namespace_summary(
+        &self,
+        guild_id: Id<GuildMarker>,
+        limit: usize,
+    ) -> TimerStoreResult<Vec<TaskNamespaceSummary>>;
+
     async fn get_next_task_time(
         &self,
         guild_id: Id<GuildMarker>,
@@ -96,14 +121,36 @@ pub trait TimerStore: Send + Sync {
         names: &[String],
     ) -> TimerStoreResult<Option<DateTime<Utc>>>;
 
+    /// Returns the tasks due before `t`
+    ///
+    /// If `lease_generation` is set nothing is returned unless that lease on the guild is still
+    /// held, see [ConfigStore::claim_guild](crate::config::ConfigStore::claim_guild).
     async fn get_triggered_tasks(
         &self,
         guild_id: Id<GuildMarker>,
         t: DateTime<Utc>,
         ignore_ids: &[u64],
         names: &[String],
+        lease_generation: Option<u64>,
     ) -> TimerStoreResult<Vec<ScheduledTask>>;
 
+    /// Records what happened to a task when it was due, only the `keep` most recent
+    /// executions are kept per guild
+    async fn record_task_execution(
+        &self,
+        guild_id: Id<GuildMarker>,
+        execution: TaskExecution,
+        keep: u64,
+    ) -> TimerStoreResult<()>;
+
+    /// Returns the most recent executions of tasks with the provided name, newest first
+    async fn get_task_executions(
+        &self,
+        guild_id: Id<GuildMarker>,
+        name: String,
+        limit: usize,
+    ) -> TimerStoreResult<Vec<TaskExecution>>;
+
     async fn delete_guild_timer_data(&self, guild_id: Id<GuildMarker>) -> TimerStoreResult<()>;
 }
 
@@ -126,7 +173,42 @@ pub struct ScheduledTask {
     pub name: String,
 
     pub unique_key: Option<String>,
+    pub tags: HashMap<String, String>,
 
     pub data: serde_json::Value,
     pub execute_at: DateTime<Utc>,
 }
+
+/// A single key value pair out of a task's tags
+#[derive(Clone, Debug)]
+pub struct TaskTag {
+    pub key: String,
+    pub value: String,
+}
+
+/// A task firing, see [TimerStore::record_task_execution]
+#[derive(Clone, Debug)]
+pub struct TaskExecution {
+    pub task_id: u64,
+    pub name: String,
+    pub fired_at: DateTime<Utc>,
+    pub outcome: TaskOutcome,
+    pub error: Option<String>,
+}
+
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub enum TaskOutcome {
+    /// The task handler finished, tasks are deleted as their handler starts
+    Succeeded,
+    /// The task handler threw or the vm was shut down before it finished, it's not retried
+    Failed,
+    /// The vm was shut down before the task was delivered, it's dispatched again later
+    Interrupted,
+}
+
+#[derive(Clone, Debug)]
+pub struct TaskNamespaceSummary {
+    pub name: String,
+    pub count: u64,
+    pub next_execute_at: DateTime<Utc>,
+}
@@ -0,0 +1,156 @@
+use std::sync::{
+    atomic::{AtomicU64, Ordering},
+    Mutex,
+};
+
+use lru::LruCache;
+
+use crate::{CompileError, CompiledItem};
+
+type CompileFn = fn(&str) -> Result<CompiledItem, CompileError>;
+
+/// Caches the output of [compile_typescript](crate::compile_typescript) by a hash of the source
+///
+/// Scripts rarely change between vm restarts, so this saves running swc again for the same source.
+/// Only successful compiles are cached, least recently used entries are evicted once the cached
+/// items take up more than `max_bytes`.
+pub struct CompileCache {
+    entries: Mutex<Entries>,
+    max_bytes: usize,
+    compile_fn: CompileFn,
+
+    hits: AtomicU64,
+    misses: AtomicU64,
+}
+
+struct Entries {
+    items: LruCache<blake3::Hash, CompiledItem>,
+    bytes: usize,
+}
+
+// scripts vary a lot in size so the cache is bounded by the size of what it holds rather than the
+// number of items, the parsed source map takes up about as much as the raw one
+fn item_size(item: &CompiledItem) -> usize {
+    item.output.len() + item.source_map_raw.len() * 2
+}
+
+impl CompileCache {
+    pub fn new(max_bytes: usize) -> Self {
+        Self::with_compile_fn(max_bytes, crate::compile_typescript)
+    }
+
+    pub(crate) fn with_compile_fn(max_bytes: usize, compile_fn: CompileFn) -> Self {
+        Self {
+            entries: Mutex::new(Entries {
+                items: LruCache::unbounded(),
+                bytes: 0,
+            }),
+            max_bytes,
+            compile_fn,
+            hits: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
+        }
+    }
+
+    pub fn compile(&self, input: &str) -> Result<CompiledItem, CompileError> {
+        let key = blake3::hash(input.as_bytes());
+        if let Some(item) = self.entries.lock().unwrap().items.get(&key) {
+            self.hits.fetch_add(1, Ordering::Relaxed);
+            return Ok(item.clone());
+        }
+
+        self.misses.fetch_add(1, Ordering::Relaxed);
+
+        // compiling can take a while, don't block other threads on it
+        let compiled = (self.compile_fn)(input)?;
+
+        let size = item_size(&compiled);
+        if size <= self.max_bytes {
+            let mut entries = self.entries.lock().unwrap();
+            if let Some(replaced) = entries.items.put(key, compiled.clone()) {
+                entries.bytes -= item_size(&replaced);
+            }
+            entries.bytes += size;
+
+            while entries.bytes > self.max_bytes {
+                match entries.items.pop_lru() {
+                    Some((_, evicted)) => entries.bytes -= item_size(&evicted),
+                    None => break,
+                }
+            }
+        }
+
+        Ok(compiled)
+    }
+
+    /// Fraction of compiles served from the cache, 0 if nothing has been compiled yet
+    pub fn hit_rate(&self) -> f64 {
+        let hits = self.hits.load(Ordering::Relaxed);
+        let misses = self.misses.load(Ordering::Relaxed);
+        if hits + misses == 0 {
+            return 0.0;
+        }
+
+        hits as f64 / (hits + misses) as f64
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.lock().unwrap().items.len()
+    }
+
+    /// Approximate memory used by the cached items
+    pub fn bytes(&self) -> usize {
+        self.entries.lock().unwrap().bytes
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use super::{item_size, CompileCache};
+    use crate::{CompileError, CompiledItem};
+
+    static COMPILE_CALLS: AtomicUsize = AtomicUsize::new(0);
+
+    fn counting_compile(input: &str) -> Result<CompiledItem, CompileError> {
+        COMPILE_CALLS.fetch_add(1, Ordering::SeqCst);
+        crate::compile_typescript(input)
+    }
+
+    #[test]
+    fn tst_compile_cache() {
+        // room for two of the small scripts below
+        let max_bytes = item_size(&crate::compile_typescript("let b = 1").unwrap()) * 2;
+        let cache = CompileCache::with_compile_fn(max_bytes, counting_compile);
+
+        let first = cache.compile("let a: string = 'asd'").unwrap();
+        let second = cache.compile("let a: string = 'asd'").unwrap();
+        assert_eq!(COMPILE_CALLS.load(Ordering::SeqCst), 1);
+        assert_eq!(first.output, second.output);
+        assert_eq!(cache.hit_rate(), 0.5);
+
+        // errors are not cached
+        assert!(cache.compile("let a = ;").is_err());
+        assert!(cache.compile("let a = ;").is_err());
+        assert_eq!(COMPILE_CALLS.load(Ordering::SeqCst), 3);
+
+        // evicts the least recently used entry
+        cache.compile("let b = 1").unwrap();
+        cache.compile("let c = 2").unwrap();
+        assert_eq!(cache.len(), 2);
+        assert!(cache.bytes() <= max_bytes);
+        cache.compile("let a: string = 'asd'").unwrap();
+        assert_eq!(COMPILE_CALLS.load(Ordering::SeqCst), 6);
+
+        // items bigger than the whole cache aren't kept
+        let big = format!("let d = '{}'", "a".repeat(max_bytes));
+        cache.compile(&big).unwrap();
+        assert!(cache.bytes() <= max_bytes);
+        assert_eq!(COMPILE_CALLS.load(Ordering::SeqCst), 7);
+    }
+}
@@ -1,64 +1,423 @@
-use std::sync::Arc;
+use std::time::{Duration, Instant};
 
 use swc::{
-    config::{JscConfig, SourceMapsConfig},
-    Compiler,
+    config::{JsMinifyFormatOptions, JsMinifyOptions, JscConfig, SourceMapsConfig},
+    BoolOrDataConfig, Compiler,
 };
 
-use swc_common::{self, FileName, SourceMap};
-use swc_ecma_ast::EsVersion;
+use swc_common::{self, FileName};
 use swc_ecma_parser::{Syntax, TsConfig};
+use thiserror::Error;
 
-pub fn compile_typescript(input: &str) -> Result<CompiledItem, String> {
-    compile_typescript_inner(input)
+pub use swc_ecma_ast::EsVersion;
+
+#[derive(Debug, Error)]
+pub enum CompileError {
+    /// The input was rejected, e.g. because of syntax errors or uses of disabled apis
+    #[error("{0}")]
+    Rejected(String),
+
+    /// Swc produced output we could not handle, this is a bug and not a problem with the input
+    #[error("internal compiler error: {0}")]
+    Internal(String),
+
+    /// The input is larger than [CompileOptions::max_source_bytes], it was not parsed at all
+    #[error("script too large: the source is {size} bytes, the max is {max} bytes")]
+    TooLarge { size: usize, max: usize },
 }
 
-fn compile_typescript_inner(input: &str) -> Result<CompiledItem, String> {
-    swc_common::GLOBALS.set(&Default::default(), || {
-        let cm: Arc<SourceMap> = Arc::new(SourceMap::default());
+/// Default for [CompileOptions::max_source_bytes], well above what the api lets you save
+pub const MAX_SOURCE_BYTES: usize = 512 * 1024;
+
+#[derive(Debug, Clone)]
+#[non_exhaustive]
+pub struct CompileOptions {
+    /// Text inserted before the compiled output, the source map is shifted accordingly
+    pub banner: Option<String>,
 
-        let c = Compiler::new(cm.clone());
-        let fm = cm.new_source_file(FileName::Custom("script.ts".into()), input.into());
+    /// Syntax extensions, only decorators are turned on by default as they're commonly used
+    /// for things like command registration helpers
+    pub parser: ParserOptions,
 
-        match swc::try_with_handler(
-            cm,
-            swc::HandlerOpts {
-                color: swc_common::errors::ColorConfig::Never,
-                skip_filename: false,
+    /// Syntax newer than this is transpiled down, defaults to es2022 which the vm supports fully
+    pub target: EsVersion,
+
+    /// Larger sources are rejected up front, a huge source can keep swc busy for a long time
+    pub max_source_bytes: usize,
+
+    /// Append the source map to the output as a base64 `sourceMappingURL` comment, the
+    /// separate source map fields are still populated
+    pub inline_source_map: bool,
+
+    /// Keep the comments from the source in the output, turning this off makes the output
+    /// smaller but also drops things like license headers
+    pub keep_comments: bool,
+}
+
+impl Default for CompileOptions {
+    fn default() -> Self {
+        Self {
+            banner: None,
+            parser: ParserOptions {
+                decorators: true,
+                ..Default::default()
             },
-            |handler| {
-                c.process_js_file(
-                    fm,
-                    handler,
-                    &swc::config::Options {
-                        config: swc::config::Config {
-                            jsc: JscConfig {
-                                syntax: Some(Syntax::Typescript(TsConfig {
-                                    ..Default::default()
-                                })),
-                                target: Some(EsVersion::Es2022),
-                                ..Default::default()
-                            },
+            target: EsVersion::Es2022,
+            max_source_bytes: MAX_SOURCE_BYTES,
+            inline_source_map: false,
+            keep_comments: true,
+        }
+    }
+}
+
+// the struct is non exhaustive, so other crates change the defaults through these
+impl CompileOptions {
+    pub fn with_banner(mut self, banner: impl Into<String>) -> Self {
+        self.banner = Some(banner.into());
+        self
+    }
+
+    pub fn with_parser(mut self, parser: ParserOptions) -> Self {
+        self.parser = parser;
+        self
+    }
+
+    pub fn with_target(mut self, target: EsVersion) -> Self {
+        self.target = target;
+        self
+    }
+
+    pub fn with_max_source_bytes(mut self, max_source_bytes: usize) -> Self {
+        self.max_source_bytes = max_source_bytes;
+        self
+    }
+
+    pub fn with_inline_source_map(mut self, inline_source_map: bool) -> Self {
+        self.inline_source_map = inline_source_map;
+        self
+    }
+
+    pub fn with_keep_comments(mut self, keep_comments: bool) -> Self {
+        self.keep_comments = keep_comments;
+        self
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ParserOptions {
+    /// Allow decorators on classes and class members
+    pub decorators: bool,
+
+    /// Allow jsx, note that this makes generic arrow functions (`<T>() => ...`) ambiguous
+    pub tsx: bool,
+}
+
+impl ParserOptions {
+    pub(crate) fn syntax(&self) -> Syntax {
+        Syntax::Typescript(TsConfig {
+            decorators: self.decorators,
+            tsx: self.tsx,
+            ..Default::default()
+        })
+    }
+}
+
+pub fn compile_typescript(input: &str) -> Result<CompiledItem, CompileError> {
+    compile_typescript_with(input, &CompileOptions::default())
+}
+
+pub fn compile_typescript_with(
+    input: &str,
+    options: &CompileOptions,
+) -> Result<CompiledItem, CompileError> {
+    compile_typescript_timed(input, options).map(|(compiled, _)| compiled)
+}
+
+/// Same as [compile_typescript_with] but also returns how long it took
+pub fn compile_typescript_timed(
+    input: &str,
+    options: &CompileOptions,
+) -> Result<(CompiledItem, CompileStats), CompileError> {
+    swc_common::GLOBALS.set(&Default::default(), || {
+        let compiler = Compiler::new(Default::default());
+        compile_file(&compiler, "script.ts", input, options)
+    })
+}
+
+/// Compiles several files with the default options, sharing the swc setup between them
+///
+/// Each file is compiled on its own, a file failing to compile doesn't affect the others.
+/// The results are in the same order as the inputs, keyed by the provided file name.
+pub fn compile_many(
+    inputs: &[(String, &str)],
+) -> Vec<(String, Result<CompiledItem, CompileError>)> {
+    let options = CompileOptions::default();
+
+    swc_common::GLOBALS.set(&Default::default(), || {
+        let compiler = Compiler::new(Default::default());
+
+        inputs
+            .iter()
+            .map(|(name, input)| {
+                let result =
+                    compile_file(&compiler, name, input, &options).map(|(compiled, _)| compiled);
+                (name.clone(), result)
+            })
+            .collect()
+    })
+}
+
+// has to be called within a swc GLOBALS scope
+fn compile_file(
+    compiler: &Compiler,
+    file_name: &str,
+    input: &str,
+    options: &CompileOptions,
+) -> Result<(CompiledItem, CompileStats), CompileError> {
+    check_source_size(input, options.max_source_bytes)?;
+
+    // these would fail at runtime with a confusing error, so give a better one up front
+    let disabled_uses =
+        crate::disabled_apis::find_disabled_api_uses(input, options.parser.syntax());
+    if !disabled_uses.is_empty() {
+        return Err(CompileError::Rejected(disabled_uses.join("\n")));
+    }
+
+    let (compiled, duration) = match compile_typescript_inner(
+        compiler,
+        file_name,
+        input,
+        options.parser.syntax(),
+        options.target,
+        options.keep_comments,
+    ) {
+        Ok(compiled) => compiled,
+        Err(CompileError::Rejected(err)) => {
+            // only reparse on failure, most of the time it compiles fine
+            let diagnostic =
+                crate::diagnostics::find_syntax_mistake(input, options.parser.syntax());
+            return Err(CompileError::Rejected(diagnostic.unwrap_or(err)));
+        }
+        Err(err) => return Err(err),
+    };
+
+    let mut compiled = match &options.banner {
+        Some(banner) => prepend_banner(compiled, banner)?,
+        None => compiled,
+    };
+
+    if options.inline_source_map {
+        append_inline_source_map(&mut compiled);
+    }
+
+    let stats = CompileStats {
+        duration,
+        input_bytes: input.len(),
+        output_bytes: compiled.output.len(),
+    };
+
+    Ok((compiled, stats))
+}
+
+/// Strips the types and emits js as fast as possible, for quick "does this produce runnable code" checks
+///
+/// Unlike [compile_typescript_with] this skips the source map, the disabled api check and the
+/// friendlier syntax error diagnostics, so the full compile should still be used for the code that's run.
+pub fn transform_only(input: &str, parser: ParserOptions) -> Result<String, CompileError> {
+    check_source_size(input, MAX_SOURCE_BYTES)?;
+    let (output, _) = run_swc(input, parser.syntax(), EsVersion::Es2022, false)?;
+    Ok(output.code)
+}
+
+fn check_source_size(input: &str, max: usize) -> Result<(), CompileError> {
+    if input.len() > max {
+        return Err(CompileError::TooLarge {
+            size: input.len(),
+            max,
+        });
+    }
+
+    Ok(())
+}
+
+fn prepend_banner(compiled: CompiledItem, banner: &str) -> Result<CompiledItem, CompileError> {
+    let mut output = banner.to_string();
+    if !output.is_empty() && !output.ends_with('\n') {
+        output.push('\n');
+    }
+
+    let num_lines = output.matches('\n').count() as u32;
+    output.push_str(&compiled.output);
+
+    let source_map = offset_source_map_lines(&compiled.source_map, num_lines);
+    let mut source_map_raw = Vec::new();
+    source_map
+        .to_writer(&mut source_map_raw)
+        .map_err(|err| CompileError::Internal(format!("failed writing source map: {err}")))?;
+
+    let source_map_raw = String::from_utf8(source_map_raw)
+        .map_err(|err| CompileError::Internal(format!("source map is not valid utf8: {err}")))?;
+
+    Ok(CompiledItem {
+        output,
+        source_map,
+        source_map_raw,
+    })
+}
+
+// done last as the comment has to be the last line of the output
+fn append_inline_source_map(compiled: &mut CompiledItem) {
+    if !compiled.output.is_empty() && !compiled.output.ends_with('\n') {
+        compiled.output.push('\n');
+    }
+
+    compiled
+        .output
+        .push_str("//# sourceMappingURL=data:application/json;base64,");
+    compiled
+        .output
+        .push_str(&base64::encode(&compiled.source_map_raw));
+    compiled.output.push('\n');
+}
+
+fn offset_source_map_lines(map: &sourcemap::SourceMap, num_lines: u32) -> sourcemap::SourceMap {
+    let mut builder = sourcemap::SourceMapBuilder::new(map.get_file());
+
+    for i in 0..map.get_source_count() {
+        if let Some(source) = map.get_source(i) {
+            let id = builder.add_source(source);
+            builder.set_source_contents(id, map.get_source_contents(i));
+        }
+    }
+
+    for token in map.tokens() {
+        builder.add(
+            token.get_dst_line() + num_lines,
+            token.get_dst_col(),
+            token.get_src_line(),
+            token.get_src_col(),
+            token.get_source(),
+            token.get_name(),
+        );
+    }
+
+    builder.into_sourcemap()
+}
+
+fn compile_typescript_inner(
+    compiler: &Compiler,
+    file_name: &str,
+    input: &str,
+    syntax: Syntax,
+    target: EsVersion,
+    keep_comments: bool,
+) -> Result<(CompiledItem, Duration), CompileError> {
+    let (output, duration) = run_swc_with(
+        compiler,
+        file_name,
+        input,
+        syntax,
+        target,
+        true,
+        keep_comments,
+    )?;
+
+    let map_raw = output
+        .map
+        .ok_or_else(|| CompileError::Internal("no source map was generated".to_string()))?;
+    let map_parsed = sourcemap::SourceMap::from_slice(map_raw.as_bytes())
+        .map_err(|err| CompileError::Internal(format!("failed parsing source map: {err}")))?;
+
+    Ok((
+        CompiledItem {
+            output: output.code,
+            source_map: map_parsed,
+            source_map_raw: map_raw,
+        },
+        duration,
+    ))
+}
+
+fn run_swc(
+    input: &str,
+    syntax: Syntax,
+    target: EsVersion,
+    source_maps: bool,
+) -> Result<(swc::TransformOutput, Duration), CompileError> {
+    swc_common::GLOBALS.set(&Default::default(), || {
+        let c = Compiler::new(Default::default());
+        run_swc_with(&c, "script.ts", input, syntax, target, source_maps, true)
+    })
+}
+
+// has to be called within a swc GLOBALS scope, the compiler can be reused for several files
+//
+// also returns the time spent in swc itself
+fn run_swc_with(
+    c: &Compiler,
+    file_name: &str,
+    input: &str,
+    syntax: Syntax,
+    target: EsVersion,
+    source_maps: bool,
+    keep_comments: bool,
+) -> Result<(swc::TransformOutput, Duration), CompileError> {
+    let fm =
+        c.cm.new_source_file(FileName::Custom(file_name.into()), input.into());
+
+    let mut duration = Duration::ZERO;
+    let output = swc::try_with_handler(
+        c.cm.clone(),
+        swc::HandlerOpts {
+            color: swc_common::errors::ColorConfig::Never,
+            skip_filename: false,
+        },
+        |handler| {
+            let started = Instant::now();
+            let output = c.process_js_file(
+                fm,
+                handler,
+                &swc::config::Options {
+                    config: swc::config::Config {
+                        // swc always uses the legacy (experimentalDecorators) transform
+                        // for typescript, so decorators are compiled away and don't
+                        // depend on v8 supporting them
+                        jsc: JscConfig {
+                            syntax: Some(syntax),
+                            target: Some(target),
+                            minify: comments_minify_options(keep_comments),
                             ..Default::default()
                         },
-                        source_maps: Some(SourceMapsConfig::Bool(true)),
                         ..Default::default()
                     },
-                )
-            },
-        ) {
-            Ok(output) => {
-                let map_raw = output.map.unwrap();
-                let map_parsed = sourcemap::SourceMap::from_slice(map_raw.as_bytes()).unwrap();
-
-                Ok(CompiledItem {
-                    output: output.code,
-                    source_map: map_parsed,
-                    source_map_raw: map_raw,
-                })
-            }
-            Err(err) => Err(err.to_string()),
-        }
+                    source_maps: Some(SourceMapsConfig::Bool(source_maps)),
+                    ..Default::default()
+                },
+            );
+            duration = started.elapsed();
+            output
+        },
+    )
+    .map_err(|err| CompileError::Rejected(err.to_string()))?;
+
+    Ok((output, duration))
+}
+
+// swc only strips comments through the minify options, compression and mangling stay off
+// so the output is otherwise the same
+fn comments_minify_options(keep_comments: bool) -> Option<JsMinifyOptions> {
+    if keep_comments {
+        return None;
+    }
+
+    Some(JsMinifyOptions {
+        compress: BoolOrDataConfig::from_bool(false),
+        mangle: BoolOrDataConfig::from_bool(false),
+        format: JsMinifyFormatOptions {
+            comments: BoolOrDataConfig::from_bool(false),
+            ..Default::default()
+        },
+        ..Default::default()
     })
 }
 
@@ -69,9 +428,35 @@ pub struct CompiledItem {
     pub source_map_raw: String,
 }
 
+impl CompiledItem {
+    /// Drops the source map, for when errors don't need to be mapped back to the source
+    ///
+    /// Lookups on the source map return nothing afterwards.
+    pub fn without_source_map(self) -> Self {
+        Self {
+            output: self.output,
+            source_map: sourcemap::SourceMapBuilder::new(None).into_sourcemap(),
+            source_map_raw: String::new(),
+        }
+    }
+}
+
+/// Numbers about a compile, for keeping track of how much time goes into compiling scripts
+#[derive(Debug, Clone, Copy)]
+pub struct CompileStats {
+    /// Time spent in swc transpiling, excluding the checks done before and after
+    pub duration: Duration,
+    pub input_bytes: usize,
+    /// Size of the final output, including the banner and inline source map if enabled
+    pub output_bytes: usize,
+}
+
 #[cfg(test)]
 mod tests {
-    use crate::compile_typescript;
+    use crate::{
+        compile_many, compile_typescript, compile_typescript_timed, compile_typescript_with,
+        transform_only, CompileError, CompileOptions, EsVersion, ParserOptions, MAX_SOURCE_BYTES,
+    };
 
     fn compile(input: &str, expected_output: &str) {
         let output = compile_typescript(input).unwrap();
@@ -82,4 +467,288 @@ mod tests {
     fn tst_simple() {
         compile("let a: string = 'asd'", "let a = 'asd';\n");
     }
+
+    #[test]
+    fn tst_compile_many() {
+        let results = compile_many(&[
+            ("a.ts".to_string(), "let a: string = 'a'"),
+            ("broken.ts".to_string(), "let b = ;"),
+            ("c.ts".to_string(), "let c: number = 1"),
+        ]);
+
+        let names = results
+            .iter()
+            .map(|(name, _)| name.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(names, vec!["a.ts", "broken.ts", "c.ts"]);
+
+        assert_eq!(results[0].1.as_ref().unwrap().output, "let a = 'a';\n");
+        assert!(matches!(results[1].1, Err(CompileError::Rejected(_))));
+        assert_eq!(results[2].1.as_ref().unwrap().output, "let c = 1;\n");
+
+        // source maps point at the right file
+        let map = &results[2].1.as_ref().unwrap().source_map;
+        assert_eq!(map.get_source(0), Some("<c.ts>"));
+    }
+
+    #[test]
+    fn tst_compile_stats() {
+        let input = "let a: string = 'asd'";
+        let (compiled, stats) =
+            compile_typescript_timed(input, &CompileOptions::default()).unwrap();
+
+        assert_eq!(stats.input_bytes, input.len());
+        assert_eq!(stats.output_bytes, compiled.output.len());
+    }
+
+    #[test]
+    fn tst_strip_comments() {
+        let input = "/* banner */\nfunction greet(name: string) {\n    // say hi\n    return `hi ${name}`;\n}";
+
+        let kept = compile_typescript_with(input, &CompileOptions::default()).unwrap();
+        assert!(kept.output.contains("/* banner */"));
+        assert!(kept.output.contains("// say hi"));
+
+        let stripped =
+            compile_typescript_with(input, &CompileOptions::default().with_keep_comments(false))
+                .unwrap();
+        assert!(!stripped.output.contains("banner"));
+        assert!(!stripped.output.contains("say hi"));
+        // not minified otherwise
+        assert!(stripped.output.contains("function greet(name) {"));
+    }
+
+    #[test]
+    fn tst_banner() {
+        let plain = compile_typescript("let a: string = 'asd'").unwrap();
+        let output = compile_typescript_with(
+            "let a: string = 'asd'",
+            &CompileOptions::default().with_banner("// header\n// header 2"),
+        )
+        .unwrap();
+
+        assert_eq!(output.output, "// header\n// header 2\nlet a = 'asd';\n");
+
+        let plain_token = plain.source_map.lookup_token(0, 4).unwrap();
+        let token = output.source_map.lookup_token(2, 4).unwrap();
+        assert_eq!(token.get_src(), plain_token.get_src());
+    }
+
+    #[test]
+    fn tst_disabled_apis() {
+        let err = compile_typescript(
+            "let a = 1;\nawait WebAssembly.instantiateStreaming(fetch('a.wasm'));",
+        )
+        .unwrap_err()
+        .to_string();
+        assert!(err.starts_with("script.ts:2:7: `WebAssembly.instantiateStreaming`"));
+
+        let err = compile_typescript("globalThis.Deno.core.print('hi')")
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("use console.log instead"));
+
+        compile_typescript("WebAssembly.instantiate(new Uint8Array())").unwrap();
+    }
+
+    #[test]
+    fn tst_syntax_mistakes() {
+        let err = compile_typescript("let a = 1;\nlet b = `hello ${a}\nlet c = 2;")
+            .unwrap_err()
+            .to_string();
+        assert!(err.starts_with("script.ts:2:9: unterminated template literal"));
+
+        let err = compile_typescript("let a = 'hello\n';")
+            .unwrap_err()
+            .to_string();
+        assert!(err.starts_with("script.ts:1:9: unterminated string"));
+
+        let err = compile_typescript("let a = /abc/gg;")
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("flag `g` is used more than once"));
+    }
+
+    fn compile_with_parser(input: &str, parser: ParserOptions) -> bool {
+        compile_typescript_with(input, &CompileOptions::default().with_parser(parser)).is_ok()
+    }
+
+    #[test]
+    fn tst_parser_decorators() {
+        let input = "function dec(target: any) {}\n@dec\nclass A {}";
+
+        assert!(!compile_with_parser(input, ParserOptions::default()));
+        assert!(compile_with_parser(
+            input,
+            ParserOptions {
+                decorators: true,
+                ..Default::default()
+            }
+        ));
+    }
+
+    #[test]
+    fn tst_decorators_by_default() {
+        let input = r#"
+const commands: string[] = [];
+function command(name: string) {
+    return function (target: any, key: string) {
+        commands.push(name + ":" + key);
+    };
+}
+
+function group(target: Function) {
+    commands.push("group:" + target.name);
+}
+
+@group
+class Commands {
+    @command("ping")
+    ping() {
+        return "pong";
+    }
+}
+"#;
+
+        let output = compile_typescript(input).unwrap().output;
+        assert!(!output.contains("@command"));
+        assert!(output.contains("__decorate(["));
+    }
+
+    #[test]
+    fn tst_parser_tsx() {
+        let input = "let a = <div>hello</div>;";
+
+        assert!(!compile_with_parser(input, ParserOptions::default()));
+        assert!(compile_with_parser(
+            input,
+            ParserOptions {
+                tsx: true,
+                ..Default::default()
+            }
+        ));
+    }
+
+    #[test]
+    fn tst_transform_only() {
+        let input = "interface A { b: string }\nlet a: A = { b: 'asd' };\nexport {}";
+        let compiled = compile_typescript(input).unwrap();
+        assert_eq!(
+            transform_only(input, ParserOptions::default()).unwrap(),
+            compiled.output
+        );
+
+        assert!(transform_only("let a = ;", ParserOptions::default()).is_err());
+    }
+
+    #[test]
+    fn tst_target() {
+        let input = "const get = async (a?: { b: number }) => a?.b ?? (() => 0)();";
+        let compile_target = |target| {
+            compile_typescript_with(input, &CompileOptions::default().with_target(target))
+                .unwrap()
+                .output
+        };
+
+        let es2022 = compile_target(EsVersion::Es2022);
+        assert_eq!(es2022, compile_typescript(input).unwrap().output);
+        assert!(es2022.contains("async (a)=>a?.b ?? (()=>0)()"));
+
+        let es2015 = compile_target(EsVersion::Es2015);
+        assert_ne!(es2015, es2022);
+        assert!(!es2015.contains("async ("));
+        assert!(es2015.contains("_asyncToGenerator"));
+        assert!(!es2015.contains("?."));
+    }
+
+    #[test]
+    fn tst_max_source_size() {
+        let input = format!("let a = '{}';", "a".repeat(MAX_SOURCE_BYTES));
+        assert!(matches!(
+            compile_typescript(&input),
+            Err(CompileError::TooLarge { size, max: MAX_SOURCE_BYTES }) if size == input.len()
+        ));
+
+        let options = CompileOptions::default().with_max_source_bytes(10);
+        assert!(compile_typescript_with("let a = 1;", &options).is_ok());
+        assert!(matches!(
+            compile_typescript_with("let a = 10;", &options),
+            Err(CompileError::TooLarge { .. })
+        ));
+    }
+
+    #[test]
+    fn tst_inline_source_map() {
+        let output = compile_typescript_with(
+            "let a: string = 'asd'",
+            &CompileOptions::default()
+                .with_banner("// header")
+                .with_inline_source_map(true),
+        )
+        .unwrap();
+
+        let (code, url) = output.output.split_once("//# sourceMappingURL=").unwrap();
+        assert_eq!(code, "// header\nlet a = 'asd';\n");
+
+        let inline = match sourcemap::decode_data_url(url.trim_end()).unwrap() {
+            sourcemap::DecodedMap::Regular(map) => map,
+            _ => panic!("expected a regular source map"),
+        };
+        assert_eq!(
+            inline
+                .tokens()
+                .map(|t| t.get_raw_token())
+                .collect::<Vec<_>>(),
+            output
+                .source_map
+                .tokens()
+                .map(|t| t.get_raw_token())
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(inline.lookup_token(1, 4).unwrap().get_src(), (0, 4));
+    }
+
+    #[test]
+    fn tst_without_source_map() {
+        let output = compile_typescript("let a: string = 'asd'").unwrap();
+        assert!(output.source_map.lookup_token(0, 4).is_some());
+
+        let stripped = output.without_source_map();
+        assert_eq!(stripped.output, "let a = 'asd';\n");
+        assert!(stripped.source_map_raw.is_empty());
+        assert!(stripped.source_map.lookup_token(0, 4).is_none());
+    }
+
+    // cargo test -p tscompiler --release -- --ignored --nocapture bench_transform_only
+    #[test]
+    #[ignore]
+    fn bench_transform_only() {
+        let mut input = String::new();
+        for i in 0..2000 {
+            input.push_str(&format!(
+                "interface Item{i} {{ id: number, name: string }}\n\
+                 export function handle{i}(items: Item{i}[]): string[] {{\n\
+                 \x20   return items.filter((v) => v.id > {i}).map((v) => `${{v.name}}: ${{v.id}}`);\n\
+                 }}\n"
+            ));
+        }
+
+        let runs = 10;
+        let started = std::time::Instant::now();
+        for _ in 0..runs {
+            compile_typescript(&input).unwrap();
+        }
+        let full = started.elapsed() / runs;
+
+        let started = std::time::Instant::now();
+        for _ in 0..runs {
+            transform_only(&input, ParserOptions::default()).unwrap();
+        }
+        let transform = started.elapsed() / runs;
+
+        println!(
+            "{} bytes: compile_typescript {full:?}, transform_only {transform:?}",
+            input.len()
+        );
+    }
 }
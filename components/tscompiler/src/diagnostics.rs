@@ -0,0 +1,95 @@
+use std::sync::Arc;
+
+use swc_common::{FileName, SourceMap, Spanned};
+use swc_ecma_ast::EsVersion;
+use swc_ecma_parser::{error::SyntaxError, lexer::Lexer, token::Token, StringInput, Syntax};
+
+/// A problem found in the source, positions are 1 based
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CompileDiagnostic {
+    pub line: usize,
+    pub col: usize,
+    pub message: String,
+}
+
+impl std::fmt::Display for CompileDiagnostic {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "script.ts:{}:{}: {}", self.line, self.col, self.message)
+    }
+}
+
+/// Returns a friendlier diagnostic for common beginner mistakes, like unterminated strings,
+/// template literals and regular expressions
+///
+/// Returns None if the source parses or the error is not one of those, swc's own error is
+/// good enough in that case
+pub fn find_syntax_mistake(input: &str, syntax: Syntax) -> Option<String> {
+    swc_common::GLOBALS.set(&Default::default(), || {
+        let cm: Arc<SourceMap> = Arc::new(SourceMap::default());
+        let fm = cm.new_source_file(FileName::Custom("script.ts".into()), input.into());
+
+        let mut recovered = Vec::new();
+        let fatal = swc_ecma_parser::parse_file_as_module(
+            &fm,
+            syntax,
+            EsVersion::Es2022,
+            None,
+            &mut recovered,
+        )
+        .err();
+
+        // recovered errors come before the fatal one in the source
+        let mut errors = recovered.into_iter().chain(fatal).collect::<Vec<_>>();
+
+        // the parser reports unterminated template literals as an unexpected end of file,
+        // the lexer knows where the template started though
+        if matches!(errors.last().map(|err| err.kind()), Some(SyntaxError::Eof)) {
+            let lexer = Lexer::new(syntax, EsVersion::Es2022, StringInput::from(&*fm), None);
+            errors.extend(lexer.filter_map(|token| match token.token {
+                Token::Error(err) => Some(err),
+                _ => None,
+            }));
+        }
+
+        errors.iter().find_map(|err| {
+            let message = friendly_message(err.kind())?;
+            let loc = cm.lookup_char_pos(err.span().lo);
+            Some(format!(
+                "script.ts:{}:{}: {}",
+                loc.line,
+                loc.col.0 + 1,
+                message
+            ))
+        })
+    })
+}
+
+pub(crate) fn friendly_message(kind: &SyntaxError) -> Option<String> {
+    let message = match kind {
+        SyntaxError::UnterminatedStrLit => {
+            "unterminated string, add the missing closing quote (use a template literal with \
+             backticks for strings spanning multiple lines)"
+                .to_string()
+        }
+        SyntaxError::UnterminatedTpl => {
+            "unterminated template literal, add the missing closing backtick (`)".to_string()
+        }
+        SyntaxError::UnterminatedRegExp => "unterminated regular expression, add the missing \
+                                            closing slash (/) or escape it (\\/) if it's part \
+                                            of the pattern"
+            .to_string(),
+        SyntaxError::UnterminatedBlockComment => {
+            "unterminated block comment, add the missing closing */".to_string()
+        }
+        SyntaxError::DuplicatedRegExpFlags(flag) => {
+            format!("the regular expression flag `{flag}` is used more than once")
+        }
+        SyntaxError::UnknownRegExpFlags => {
+            "unknown regular expression flag, the valid flags are d, g, i, m, s, u and y"
+                .to_string()
+        }
+        _ => return None,
+    };
+
+    Some(message)
+}
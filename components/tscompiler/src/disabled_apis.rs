@@ -0,0 +1,110 @@
+use std::sync::Arc;
+
+use swc_common::{FileName, SourceMap};
+use swc_ecma_ast::{EsVersion, Expr, MemberExpr, MemberProp};
+use swc_ecma_parser::Syntax;
+use swc_ecma_visit::{Visit, VisitWith};
+
+/// A global api that relies on ops disabled in the runtime
+pub struct DisabledApi {
+    /// Path to the api, e.g `WebAssembly.instantiateStreaming`
+    pub path: &'static str,
+    /// The ops backing this api, these are replaced with a disabled op in the runtime
+    pub ops: &'static [&'static str],
+    /// What to use instead
+    pub hint: &'static str,
+}
+
+pub const DISABLED_APIS: &[DisabledApi] = &[
+    DisabledApi {
+        path: "Deno.core.print",
+        ops: &["op_print"],
+        hint: "use console.log instead",
+    },
+    DisabledApi {
+        path: "WebAssembly.instantiateStreaming",
+        ops: &["op_wasm_streaming_feed", "op_wasm_streaming_set_url"],
+        hint: "streaming wasm compilation is not supported",
+    },
+    DisabledApi {
+        path: "WebAssembly.compileStreaming",
+        ops: &["op_wasm_streaming_feed", "op_wasm_streaming_set_url"],
+        hint: "streaming wasm compilation is not supported",
+    },
+];
+
+pub fn is_disabled_op(op_name: &str) -> bool {
+    DISABLED_APIS.iter().any(|api| api.ops.contains(&op_name))
+}
+
+/// Returns a diagnostic for every direct use of a disabled api in the source
+///
+/// Sources that fail to parse return no diagnostics, the compiler reports those errors instead
+pub fn find_disabled_api_uses(input: &str, syntax: Syntax) -> Vec<String> {
+    swc_common::GLOBALS.set(&Default::default(), || {
+        let cm: Arc<SourceMap> = Arc::new(SourceMap::default());
+        let fm = cm.new_source_file(FileName::Custom("script.ts".into()), input.into());
+
+        let module = match swc_ecma_parser::parse_file_as_module(
+            &fm,
+            syntax,
+            EsVersion::Es2022,
+            None,
+            &mut Vec::new(),
+        ) {
+            Ok(module) => module,
+            Err(_) => return Vec::new(),
+        };
+
+        let mut finder = DisabledApiFinder {
+            cm: cm.clone(),
+            diagnostics: Vec::new(),
+        };
+        module.visit_with(&mut finder);
+        finder.diagnostics
+    })
+}
+
+struct DisabledApiFinder {
+    cm: Arc<SourceMap>,
+    diagnostics: Vec<String>,
+}
+
+impl Visit for DisabledApiFinder {
+    fn visit_member_expr(&mut self, n: &MemberExpr) {
+        if let Some(path) = member_expr_path(n) {
+            let path = path.strip_prefix("globalThis.").unwrap_or(&path);
+            if let Some(api) = DISABLED_APIS.iter().find(|api| api.path == path) {
+                let loc = self.cm.lookup_char_pos(n.span.lo);
+                self.diagnostics.push(format!(
+                    "script.ts:{}:{}: `{}` is not available in botloader, {}",
+                    loc.line,
+                    loc.col.0 + 1,
+                    api.path,
+                    api.hint
+                ));
+
+                // don't report the inner parts of the same expression
+                return;
+            }
+        }
+
+        n.visit_children_with(self);
+    }
+}
+
+// returns the dotted path of a member expression like `a.b.c`, or None if any part of it is not a plain identifier
+fn member_expr_path(n: &MemberExpr) -> Option<String> {
+    let prop = match &n.prop {
+        MemberProp::Ident(ident) => &*ident.sym,
+        _ => return None,
+    };
+
+    let obj = match &*n.obj {
+        Expr::Ident(ident) => ident.sym.to_string(),
+        Expr::Member(inner) => member_expr_path(inner)?,
+        _ => return None,
+    };
+
+    Some(format!("{obj}.{prop}"))
+}
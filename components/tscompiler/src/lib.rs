@@ -1,3 +1,10 @@
+pub mod cache;
 pub mod compiler;
+pub mod diagnostics;
+pub mod disabled_apis;
+pub mod parse;
 
+pub use cache::CompileCache;
 pub use compiler::*;
+pub use diagnostics::CompileDiagnostic;
+pub use parse::{parse_module, ParsedModule};
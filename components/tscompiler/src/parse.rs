@@ -0,0 +1,110 @@
+use std::sync::Arc;
+
+use swc_common::{BytePos, FileName, SourceMap, Spanned};
+use swc_ecma_ast::{EsVersion, Module};
+
+use crate::{diagnostics::CompileDiagnostic, ParserOptions};
+
+/// A parsed module, for walking the ast with a [swc_ecma_visit::Visit] implementation
+///
+/// Nothing is resolved or transformed so the module has no hygiene marks that depend on
+/// swc's thread local globals, it can be used after [parse_module] returns.
+pub struct ParsedModule {
+    pub module: Module,
+    source_map: Arc<SourceMap>,
+}
+
+impl ParsedModule {
+    /// Resolves a position from one of the module's spans to a 1 based line and column
+    pub fn line_col(&self, pos: BytePos) -> (usize, usize) {
+        let loc = self.source_map.lookup_char_pos(pos);
+        (loc.line, loc.col.0 + 1)
+    }
+}
+
+/// Parses the source without emitting any code, for running lint rules on it
+///
+/// Returns every syntax error the parser found if it did not parse cleanly.
+pub fn parse_module(
+    input: &str,
+    parser: ParserOptions,
+) -> Result<ParsedModule, Vec<CompileDiagnostic>> {
+    swc_common::GLOBALS.set(&Default::default(), || {
+        let cm: Arc<SourceMap> = Arc::new(SourceMap::default());
+        let fm = cm.new_source_file(FileName::Custom("script.ts".into()), input.into());
+
+        let mut errors = Vec::new();
+        let result = swc_ecma_parser::parse_file_as_module(
+            &fm,
+            parser.syntax(),
+            EsVersion::Es2022,
+            None,
+            &mut errors,
+        );
+
+        // recovered errors still make it invalid, swc rejects the source when compiling it
+        match result {
+            Ok(module) if errors.is_empty() => {
+                return Ok(ParsedModule {
+                    module,
+                    source_map: cm,
+                })
+            }
+            Ok(_) => {}
+            Err(fatal) => errors.push(fatal),
+        }
+
+        Err(errors
+            .into_iter()
+            .map(|err| {
+                let loc = cm.lookup_char_pos(err.span().lo);
+                CompileDiagnostic {
+                    line: loc.line,
+                    col: loc.col.0 + 1,
+                    message: crate::diagnostics::friendly_message(err.kind())
+                        .unwrap_or_else(|| err.kind().msg().into_owned()),
+                }
+            })
+            .collect())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use swc_ecma_ast::ModuleItem;
+
+    use super::parse_module;
+    use crate::ParserOptions;
+
+    #[test]
+    fn tst_parse_module() {
+        let parsed = parse_module(
+            "import { Script } from 'botloader';\nlet a = 1;\nfunction b() { while (true) {} }\nexport {}",
+            ParserOptions::default(),
+        )
+        .unwrap();
+
+        assert_eq!(parsed.module.body.len(), 4);
+        let stmts = parsed
+            .module
+            .body
+            .iter()
+            .filter(|item| matches!(item, ModuleItem::Stmt(_)))
+            .count();
+        assert_eq!(stmts, 2);
+
+        let function = &parsed.module.body[2];
+        assert_eq!(
+            parsed.line_col(swc_common::Spanned::span(function).lo),
+            (3, 1)
+        );
+
+        let errors = parse_module("let a = 1;\nlet b = 'hello\n';", ParserOptions::default())
+            .err()
+            .unwrap();
+        assert_eq!(errors[0].line, 2);
+        assert!(errors[0]
+            .to_string()
+            .starts_with("script.ts:2:9: unterminated string"));
+    }
+}
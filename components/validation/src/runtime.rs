@@ -1,8 +1,13 @@
 use lazy_static::lazy_static;
 use regex::Regex;
-use runtime_models::internal::{
-    interaction::CommandType,
-    script::{Command, CommandGroup, CommandOption, CommandSubGroup},
+use runtime_models::{
+    discord::component::{ActionRow, Button, ButtonStyle, Component, SelectMenu, TextInput},
+    internal::{
+        interaction::CommandType,
+        interactions::{ModalCallbackData, OpShowModal},
+        messages::OpCreateMessageFields,
+        script::{Command, CommandGroup, CommandOption, CommandSubGroup},
+    },
 };
 
 use crate::{ValidationContext, Validator};
@@ -74,6 +79,273 @@ impl Validator for CommandSubGroup {
     }
 }
 
+impl Validator for OpCreateMessageFields {
+    fn validate(&self, ctx: &mut ValidationContext) {
+        if let Some(components) = &self.components {
+            if components.len() > 5 {
+                ctx.push_error("components", "max 5 action rows".to_string());
+            }
+
+            for (i, component) in components.iter().enumerate() {
+                ctx.push_field(format!("components[{i}]"));
+                match component {
+                    Component::ActionRow(row) => row.validate(ctx),
+                    _ => ctx.push_error(
+                        "kind",
+                        "top level components have to be action rows".to_string(),
+                    ),
+                }
+                ctx.pop_field();
+            }
+        }
+    }
+}
+
+impl Validator for ActionRow {
+    fn validate(&self, ctx: &mut ValidationContext) {
+        if self.components.is_empty() {
+            ctx.push_error(
+                "components",
+                "has to contain atleast 1 component".to_string(),
+            );
+        }
+
+        let num_buttons = self
+            .components
+            .iter()
+            .filter(|v| matches!(v, Component::Button(_)))
+            .count();
+        if num_buttons > 5 {
+            ctx.push_error("components", "max 5 buttons per action row".to_string());
+        }
+
+        if num_buttons != self.components.len() && self.components.len() > 1 {
+            ctx.push_error(
+                "components",
+                "a select menu has to be the only component in its action row".to_string(),
+            );
+        }
+
+        for (i, component) in self.components.iter().enumerate() {
+            ctx.push_field(format!("components[{i}]"));
+            match component {
+                Component::Button(button) => button.validate(ctx),
+                Component::SelectMenu(menu) => menu.validate(ctx),
+                Component::ActionRow(_) => {
+                    ctx.push_error("kind", "action rows cannot be nested".to_string())
+                }
+                Component::TextInput(_) => {
+                    ctx.push_error("kind", "text inputs can only be used in modals".to_string())
+                }
+                Component::Unknown(_) => {
+                    ctx.push_error("kind", "unknown component kind".to_string())
+                }
+            }
+            ctx.pop_field();
+        }
+    }
+}
+
+impl Validator for Button {
+    fn validate(&self, ctx: &mut ValidationContext) {
+        if matches!(self.style, ButtonStyle::Link) {
+            if self.url.is_none() {
+                ctx.push_error("url", "link buttons need a url".to_string());
+            }
+            if self.custom_id.is_some() {
+                ctx.push_error(
+                    "custom_id",
+                    "link buttons cannot have a custom_id".to_string(),
+                );
+            }
+        } else {
+            if self.url.is_some() {
+                ctx.push_error("url", "only link buttons can have a url".to_string());
+            }
+
+            match &self.custom_id {
+                Some(custom_id) => check_custom_id_field(ctx, "custom_id", custom_id),
+                None => {
+                    ctx.push_error("custom_id", "non-link buttons need a custom_id".to_string())
+                }
+            }
+        }
+
+        if self.label.is_none() && self.emoji.is_none() {
+            ctx.push_error("label", "buttons need a label or an emoji".to_string());
+        }
+
+        if let Some(label) = &self.label {
+            if label.chars().count() > 80 {
+                ctx.push_error("label", "can be max 80 characters long".to_string());
+            }
+        }
+    }
+}
+
+impl Validator for SelectMenu {
+    fn validate(&self, ctx: &mut ValidationContext) {
+        check_custom_id_field(ctx, "custom_id", &self.custom_id);
+
+        if self.options.is_empty() || self.options.len() > 25 {
+            ctx.push_error(
+                "options",
+                "has to have between 1 and 25 options".to_string(),
+            );
+        }
+
+        if let Some(placeholder) = &self.placeholder {
+            if placeholder.chars().count() > 150 {
+                ctx.push_error("placeholder", "can be max 150 characters long".to_string());
+            }
+        }
+
+        let min_values = self.min_values.unwrap_or(1);
+        let max_values = self.max_values.unwrap_or(1);
+        if min_values > 25 {
+            ctx.push_error("min_values", "can be max 25".to_string());
+        }
+        if !(1..=25).contains(&max_values) {
+            ctx.push_error("max_values", "has to be between 1 and 25".to_string());
+        }
+        if min_values > max_values {
+            ctx.push_error(
+                "min_values",
+                "cannot be greater than max_values".to_string(),
+            );
+        }
+
+        for (i, option) in self.options.iter().enumerate() {
+            ctx.push_field(format!("options[{i}]"));
+            if option.label.chars().count() > 100 {
+                ctx.push_error("label", "can be max 100 characters long".to_string());
+            }
+            if option.value.chars().count() > 100 {
+                ctx.push_error("value", "can be max 100 characters long".to_string());
+            }
+            if let Some(description) = &option.description {
+                if description.chars().count() > 100 {
+                    ctx.push_error("description", "can be max 100 characters long".to_string());
+                }
+            }
+            ctx.pop_field();
+        }
+    }
+}
+
+impl Validator for ModalCallbackData {
+    fn validate(&self, ctx: &mut ValidationContext) {
+        check_modal_title_field(ctx, "title", &self.title);
+        check_custom_id_field(ctx, "custom_id", &self.custom_id);
+
+        if self.components.is_empty() || self.components.len() > 5 {
+            ctx.push_error(
+                "components",
+                "has to have between 1 and 5 action rows".to_string(),
+            );
+        }
+
+        for (i, component) in self.components.iter().enumerate() {
+            ctx.push_field(format!("components[{i}]"));
+            match component {
+                Component::ActionRow(row) => match row.components.as_slice() {
+                    [Component::TextInput(input)] => {
+                        ctx.push_field("components[0]".to_string());
+                        input.validate(ctx);
+                        ctx.pop_field();
+                    }
+                    _ => ctx.push_error(
+                        "components",
+                        "action rows in modals have to contain a single text input".to_string(),
+                    ),
+                },
+                _ => ctx.push_error(
+                    "kind",
+                    "top level components have to be action rows".to_string(),
+                ),
+            }
+            ctx.pop_field();
+        }
+    }
+}
+
+impl Validator for OpShowModal {
+    fn validate(&self, ctx: &mut ValidationContext) {
+        check_modal_title_field(ctx, "title", &self.title);
+        check_custom_id_field(ctx, "custom_id", &self.custom_id);
+
+        if self.inputs.is_empty() || self.inputs.len() > 5 {
+            ctx.push_error("inputs", "has to have between 1 and 5 inputs".to_string());
+        }
+
+        for (i, input) in self.inputs.iter().enumerate() {
+            ctx.push_field(format!("inputs[{i}]"));
+            input.validate(ctx);
+            ctx.pop_field();
+        }
+    }
+}
+
+impl Validator for TextInput {
+    fn validate(&self, ctx: &mut ValidationContext) {
+        check_custom_id_field(ctx, "custom_id", &self.custom_id);
+
+        let label_len = self.label.chars().count();
+        if !(1..=45).contains(&label_len) {
+            ctx.push_error("label", "has to be between 1 and 45 characters".to_string());
+        }
+
+        if let Some(placeholder) = &self.placeholder {
+            if placeholder.chars().count() > 100 {
+                ctx.push_error("placeholder", "can be max 100 characters long".to_string());
+            }
+        }
+
+        if let Some(value) = &self.value {
+            if value.chars().count() > 4000 {
+                ctx.push_error("value", "can be max 4000 characters long".to_string());
+            }
+        }
+
+        if let Some(min_length) = self.min_length {
+            if min_length > 4000 {
+                ctx.push_error("min_length", "can be max 4000".to_string());
+            }
+        }
+
+        if let Some(max_length) = self.max_length {
+            if !(1..=4000).contains(&max_length) {
+                ctx.push_error("max_length", "has to be between 1 and 4000".to_string());
+            }
+        }
+
+        if let (Some(min_length), Some(max_length)) = (self.min_length, self.max_length) {
+            if min_length > max_length {
+                ctx.push_error(
+                    "min_length",
+                    "cannot be greater than max_length".to_string(),
+                );
+            }
+        }
+    }
+}
+
+fn check_modal_title_field(ctx: &mut ValidationContext, field: &str, value: &str) {
+    let len = value.chars().count();
+    if !(1..=45).contains(&len) {
+        ctx.push_error(field, "has to be between 1 and 45 characters".to_string());
+    }
+}
+
+fn check_custom_id_field(ctx: &mut ValidationContext, field: &str, value: &str) {
+    if value.is_empty() {
+        ctx.push_error(field, "has to be atleast 1 character".to_string());
+    }
+    if value.chars().count() > 100 {
+        ctx.push_error(field, "can be max 100 characters long".to_string());
+    }
+}
+
 fn check_name_field(ctx: &mut ValidationContext, field: &str, value: &str) {
     if value.chars().count() < 1 {
         ctx.push_error(field, "has to be atleast 1 character".to_string());
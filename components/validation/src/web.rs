@@ -1,13 +1,17 @@
 use lazy_static::lazy_static;
 use regex::Regex;
-use stores::config::{CreatePlugin, CreateScript, UpdatePluginMeta, UpdateScript};
+use stores::{
+    bundle::GuildBundle,
+    config::{CreatePlugin, CreateScript, PremiumSlotTier, UpdatePluginMeta, UpdateScript},
+};
 
 use crate::{ValidationContext, Validator};
 
+// the source size depends on the guild's premium tier, it's checked by the api against
+// script_source_max_len
 impl Validator for CreateScript {
     fn validate(&self, ctx: &mut ValidationContext) {
         check_script_name(ctx, &self.name);
-        check_script_source(ctx, "original_source", &self.original_source);
     }
 }
 
@@ -16,9 +20,24 @@ impl Validator for UpdateScript {
         if let Some(name) = &self.name {
             check_script_name(ctx, name);
         }
+    }
+}
 
-        if let Some(source) = &self.original_source {
-            check_script_source(ctx, "original_source", source);
+// the tier specific source and storage limits and plugin access are checked by the api
+impl Validator for GuildBundle {
+    fn validate(&self, ctx: &mut ValidationContext) {
+        for script in &self.scripts {
+            ctx.push_field("scripts".to_string());
+            check_script_name(ctx, &script.name);
+            ctx.pop_field();
+        }
+
+        if let Some(timezone) = self
+            .meta_config
+            .as_ref()
+            .and_then(|conf| conf.timezone.as_ref())
+        {
+            check_timezone(ctx, "timezone", timezone);
         }
     }
 }
@@ -81,7 +100,7 @@ fn check_plugin_name(ctx: &mut ValidationContext, name: &str) {
     }
 }
 
-fn check_script_name(ctx: &mut ValidationContext, name: &str) {
+pub fn check_script_name(ctx: &mut ValidationContext, name: &str) {
     if name.chars().count() > 32 {
         ctx.push_error("name", "name can be max 32 characters long".to_string());
     }
@@ -97,8 +116,53 @@ fn check_script_name(ctx: &mut ValidationContext, name: &str) {
     }
 }
 
-pub fn check_script_source(ctx: &mut ValidationContext, field_name: &str, source: &str) {
-    if source.len() > 100_000 {
-        ctx.push_error(field_name, "source can be max 100KiB".to_string());
+/// Max size in bytes of a single script's source on a guild with the provided premium tier
+pub fn script_source_max_len(tier: Option<PremiumSlotTier>) -> usize {
+    match tier {
+        None => 100_000,
+        Some(PremiumSlotTier::Lite) => 250_000,
+        Some(PremiumSlotTier::Premium) => 500_000,
+    }
+}
+
+/// Max total size in bytes of a guild's bucket storage with the provided premium tier
+pub fn storage_total_bytes_max(tier: Option<PremiumSlotTier>) -> u64 {
+    match tier {
+        None => 1_000_000,
+        Some(PremiumSlotTier::Lite) => 10_000_000,
+        Some(PremiumSlotTier::Premium) => 100_000_000,
+    }
+}
+
+/// Timezones are names from the tz database, e.g. "Europe/Oslo"
+pub fn check_timezone(ctx: &mut ValidationContext, field_name: &str, timezone: &str) {
+    if timezone.parse::<chrono_tz::Tz>().is_err() {
+        ctx.push_error(
+            field_name,
+            "unknown timezone, use a name from the tz database like Europe/Oslo".to_string(),
+        );
+    }
+}
+
+pub fn check_plugin_config_key(ctx: &mut ValidationContext, key: &str) {
+    if key.chars().count() < 1 {
+        ctx.push_error("key", "key has to be atleast 1 character".to_string());
+    }
+
+    if key.chars().count() > 32 {
+        ctx.push_error("key", "key can be max 32 characters long".to_string());
+    }
+
+    lazy_static! {
+        static ref RE: Regex = Regex::new(r#"^[\w_-]*$"#).unwrap();
+    }
+    if !RE.is_match(key) {
+        ctx.push_error("key", "key can only contain 'a-z', '-' and '_'".to_string());
+    }
+}
+
+pub fn check_plugin_config_value(ctx: &mut ValidationContext, value: &str) {
+    if value.len() > 2000 {
+        ctx.push_error("value", "value can be max 2000 bytes".to_string());
     }
 }
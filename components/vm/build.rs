@@ -5,8 +5,10 @@ use deno_core::RuntimeOptions;
 use std::env;
 use std::path::PathBuf;
 
+include!("src/snapshot_version.rs");
+
 fn main() {
-    let core_extension = deno_core::Extension::builder("bl_core")
+    let core_extension = deno_core::Extension::builder(SNAPSHOT_EXTENSIONS[0])
         .js(deno_core::include_js_files!(
           prefix "bl:core",
           "src/botloader-core.js",
@@ -20,7 +22,25 @@ fn main() {
         extensions_with_js: vec![core_extension],
         ..Default::default()
     };
-    let isolate = JsRuntime::new(options);
+    let mut isolate = JsRuntime::new(options);
+
+    // checked against the expected version when creating vm's, see vm::core_snapshot_version
+    let op_names = runtime_op_names(&mut isolate).unwrap();
+    let version = snapshot_version(
+        include_str!("src/botloader-core.js"),
+        deno_core::v8_version(),
+        &op_names,
+    );
+    isolate
+        .execute_script(
+            "bl:snapshot_version",
+            &format!(
+                "BotloaderCore.snapshotVersion = {version:?}; BotloaderCore.snapshotOpCount = {};",
+                op_names.len()
+            ),
+        )
+        .unwrap();
+    println!("Snapshot version: {version}");
 
     let snapshot = isolate.snapshot();
     let snapshot_slice: &[u8] = &snapshot;
@@ -0,0 +1,407 @@
+use std::{
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Condvar, Mutex,
+    },
+    time::{Duration, Instant},
+};
+
+use stores::config::PremiumSlotTier;
+use vmthread::{ShutdownHandle, ShutdownReason};
+
+#[derive(Debug, Clone, Copy)]
+pub struct CpuBudgetConfig {
+    /// Budget for guilds without premium, 0 turns the watchdog off for that tier
+    pub free: Duration,
+    pub lite: Duration,
+    pub premium: Duration,
+}
+
+impl CpuBudgetConfig {
+    pub fn for_tier(&self, tier: Option<PremiumSlotTier>) -> Duration {
+        match tier {
+            None => self.free,
+            Some(PremiumSlotTier::Lite) => self.lite,
+            Some(PremiumSlotTier::Premium) => self.premium,
+        }
+    }
+}
+
+/// Returned from polling the event loop when the continuations run in it went over the budget,
+/// the vm is being shut down when this is returned
+#[derive(Debug)]
+pub struct CpuBudgetExceeded;
+
+impl std::fmt::Display for CpuBudgetExceeded {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("cpu time budget exceeded")
+    }
+}
+
+impl std::error::Error for CpuBudgetExceeded {}
+
+/// Shuts down the vm when a single event dispatch or module evaluation runs for longer than
+/// its budget, so a script stuck in a loop doesn't hog the thread until the runaway checker
+/// in vmthread notices it.
+///
+/// The time is measured in wall clock time from when the watchdog is armed, as the js runs
+/// synchronously on the vm thread it's a close enough stand in for the cpu time used.
+pub struct CpuWatchdog {
+    shared: Arc<WatchdogShared>,
+}
+
+struct WatchdogShared {
+    state: Mutex<WatchdogState>,
+    changed: Condvar,
+    timed_out: AtomicBool,
+}
+
+#[derive(Default)]
+struct WatchdogState {
+    deadline: Option<Instant>,
+    stopped: bool,
+}
+
+impl CpuWatchdog {
+    pub fn spawn<H>(handle: H) -> Self
+    where
+        H: ShutdownHandle + Send + 'static,
+    {
+        let shared = Arc::new(WatchdogShared {
+            state: Mutex::new(WatchdogState::default()),
+            changed: Condvar::new(),
+            timed_out: AtomicBool::new(false),
+        });
+
+        let cloned = shared.clone();
+        std::thread::Builder::new()
+            .name("vm-cpu-watchdog".to_string())
+            .spawn(move || run_watchdog(&cloned, &handle))
+            .expect("failed spawning cpu watchdog thread");
+
+        Self { shared }
+    }
+
+    /// Starts counting down the budget, the watchdog is disarmed again when the returned guard
+    /// is dropped so the next dispatch starts with a fresh budget
+    pub fn arm(&self, budget: Duration) -> WatchdogGuard<'_> {
+        self.shared.timed_out.store(false, Ordering::SeqCst);
+
+        if !budget.is_zero() {
+            let mut state = self.shared.state.lock().unwrap();
+            state.deadline = Some(Instant::now() + budget);
+            self.shared.changed.notify_one();
+        }
+
+        WatchdogGuard { watchdog: self }
+    }
+
+    fn disarm(&self) {
+        let mut state = self.shared.state.lock().unwrap();
+        state.deadline = None;
+        self.shared.changed.notify_one();
+    }
+}
+
+impl Drop for CpuWatchdog {
+    fn drop(&mut self) {
+        let mut state = self.shared.state.lock().unwrap();
+        state.stopped = true;
+        self.shared.changed.notify_one();
+    }
+}
+
+pub struct WatchdogGuard<'a> {
+    watchdog: &'a CpuWatchdog,
+}
+
+impl WatchdogGuard<'_> {
+    /// Whether the budget ran out and the vm was told to shut down
+    pub fn timed_out(&self) -> bool {
+        self.watchdog.shared.timed_out.load(Ordering::SeqCst)
+    }
+}
+
+impl Drop for WatchdogGuard<'_> {
+    fn drop(&mut self) {
+        self.watchdog.disarm();
+    }
+}
+
+fn run_watchdog<H: ShutdownHandle>(shared: &WatchdogShared, handle: &H) {
+    let mut state = shared.state.lock().unwrap();
+    loop {
+        if state.stopped {
+            return;
+        }
+
+        let deadline = if let Some(deadline) = state.deadline {
+            deadline
+        } else {
+            state = shared.changed.wait(state).unwrap();
+            continue;
+        };
+
+        let now = Instant::now();
+        if now < deadline {
+            state = shared
+                .changed
+                .wait_timeout(state, deadline - now)
+                .unwrap()
+                .0;
+            continue;
+        }
+
+        // the script may have been granted extra time for known slow work
+        if let Some(extension) = handle.take_budget_extension() {
+            state.deadline = Some(now + extension);
+            continue;
+        }
+
+        state.deadline = None;
+        shared.timed_out.store(true, Ordering::SeqCst);
+        handle.shutdown_vm(ShutdownReason::Timeout, true);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        sync::{Arc, Mutex},
+        time::Duration,
+    };
+
+    use deno_core::Extension;
+    use guild_logger::GuildLoggerBuilder;
+    use stores::config::{Script, ScriptContributes};
+    use tokio::sync::mpsc;
+    use twilight_model::id::Id;
+    use url::Url;
+    use vmthread::{ShutdownHandle, ShutdownReason, VmThreadCommand, VmThreadFuture};
+
+    use super::{CpuBudgetConfig, CpuWatchdog};
+    use crate::{
+        dispatch_dedup::DispatchDedupConfig,
+        error_breaker::ErrorBreakerConfig,
+        moduleloader::ModuleEntry,
+        vm::{CreateRt, FailedScripts, Vm, VmCommand, VmContext, VmEvent, VmRole},
+    };
+
+    #[derive(Clone, Default)]
+    struct RecordingHandle {
+        reason: Arc<Mutex<Option<ShutdownReason>>>,
+        extension: Arc<Mutex<Option<Duration>>>,
+    }
+
+    impl ShutdownHandle for RecordingHandle {
+        fn shutdown_vm(&self, reason: ShutdownReason, _force: bool) {
+            *self.reason.lock().unwrap() = Some(reason);
+        }
+
+        fn take_budget_extension(&self) -> Option<Duration> {
+            self.extension.lock().unwrap().take()
+        }
+    }
+
+    #[test]
+    fn test_watchdog_budget() {
+        let handle = RecordingHandle::default();
+        let watchdog = CpuWatchdog::spawn(handle.clone());
+
+        // finishing within the budget, repeatedly, never trips it
+        for _ in 0..5 {
+            let guard = watchdog.arm(Duration::from_millis(100));
+            std::thread::sleep(Duration::from_millis(30));
+            assert!(!guard.timed_out());
+        }
+        std::thread::sleep(Duration::from_millis(150));
+        assert!(handle.reason.lock().unwrap().is_none());
+
+        // an extension pushes the deadline back once
+        *handle.extension.lock().unwrap() = Some(Duration::from_millis(200));
+        let guard = watchdog.arm(Duration::from_millis(50));
+        std::thread::sleep(Duration::from_millis(150));
+        assert!(!guard.timed_out());
+        std::thread::sleep(Duration::from_millis(250));
+        assert!(guard.timed_out());
+        assert!(matches!(
+            *handle.reason.lock().unwrap(),
+            Some(ShutdownReason::Timeout)
+        ));
+        drop(guard);
+
+        // a zero budget turns it off
+        *handle.reason.lock().unwrap() = None;
+        let guard = watchdog.arm(Duration::ZERO);
+        std::thread::sleep(Duration::from_millis(50));
+        assert!(!guard.timed_out());
+        assert!(handle.reason.lock().unwrap().is_none());
+    }
+
+    // stands in for the event system and script module in the runtime
+    const CORE: &str = r#"
+        BotloaderCore.handlers = [];
+        BotloaderCore.dispatchEvent = (evt) => {
+            for (const cb of BotloaderCore.handlers) {
+                cb(evt.data);
+            }
+        };
+    "#;
+
+    const SCRIPT_MODULE: &str = r#"
+        export class Script {
+            on(cb) {
+                BotloaderCore.handlers.push(cb);
+            }
+
+            run() {}
+        }
+    "#;
+
+    fn test_script(source: &str) -> Script {
+        Script {
+            id: 1,
+            name: "looping".to_string(),
+            original_source: source.to_string(),
+            enabled: true,
+            contributes: ScriptContributes {
+                commands: Vec::new(),
+                interval_timers: Vec::new(),
+                api_version: None,
+            },
+            plugin_id: None,
+            plugin_auto_update: None,
+            min_log_level: Default::default(),
+        }
+    }
+
+    // runs the script in a vm with a 100ms budget until it shuts down, or goes idle if
+    // stop_on_idle is set
+    async fn run_vm(
+        source: &str,
+        dispatch: bool,
+        stop_on_idle: bool,
+        failed_scripts: FailedScripts,
+        failed_script_retry_grace: Duration,
+    ) -> Option<VmEvent> {
+        let (cmd_tx, cmd_rx) = mpsc::unbounded_channel();
+        let (evt_tx, mut evt_rx) = mpsc::unbounded_channel();
+
+        let vm_thread = VmThreadFuture::<Vm>::create();
+        let _ = vm_thread.send_cmd.send(VmThreadCommand::StartVM(CreateRt {
+            guild_logger: GuildLoggerBuilder::new().run(),
+            rx: cmd_rx,
+            tx: evt_tx,
+            ctx: VmContext {
+                guild_id: Id::new(1),
+                role: VmRole::Main,
+            },
+            load_scripts: vec![test_script(source)],
+            extension_factory: Box::new(|| {
+                vec![Extension::builder("bl_test")
+                    .js(vec![("bl:test_core", CORE)])
+                    .build()]
+            }),
+            extension_modules: vec![ModuleEntry {
+                specifier: Url::parse("file:///script.js").unwrap(),
+                source: SCRIPT_MODULE,
+            }],
+            extension_module_access: None,
+            source_maps: false,
+            last_restart: None,
+            failed_script_retry_grace,
+            failed_scripts,
+            error_breaker: ErrorBreakerConfig {
+                threshold: 0,
+                window: Duration::ZERO,
+                cooldown: Duration::ZERO,
+            },
+            dispatch_dedup: DispatchDedupConfig {
+                window: Duration::ZERO,
+                max_events: 0,
+            },
+            cpu_budget: CpuBudgetConfig {
+                free: Duration::from_millis(100),
+                lite: Duration::from_millis(100),
+                premium: Duration::from_millis(100),
+            },
+            premium_tier: Default::default(),
+        }));
+
+        if dispatch {
+            cmd_tx
+                .send(VmCommand::DispatchEvent(
+                    "TEST".to_string(),
+                    serde_json::json!({}),
+                    1,
+                ))
+                .unwrap();
+        }
+
+        let evt = tokio::time::timeout(Duration::from_secs(10), async {
+            while let Some((_, _, evt)) = evt_rx.recv().await {
+                if matches!(evt, VmEvent::Shutdown(_))
+                    || (stop_on_idle && matches!(evt, VmEvent::Idle))
+                {
+                    return Some(evt);
+                }
+            }
+            None
+        })
+        .await
+        .ok()
+        .flatten();
+
+        let _ = vm_thread.send_cmd.send(VmThreadCommand::Shutdown);
+        evt
+    }
+
+    // returns why the vm shut down, if it did
+    async fn run_until_shutdown(source: &str, dispatch: bool) -> Option<ShutdownReason> {
+        match run_vm(source, dispatch, false, Default::default(), Duration::ZERO).await {
+            Some(VmEvent::Shutdown(reason)) => Some(reason),
+            _ => None,
+        }
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_tight_loop_on_load_is_terminated() {
+        let reason = run_until_shutdown("while (true) {}", false).await;
+        assert!(matches!(reason, Some(ShutdownReason::Timeout)));
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_tight_loop_in_async_handler_is_terminated() {
+        // the loop runs in a continuation, which is run from the event loop and not the dispatch
+        let reason = run_until_shutdown(
+            "script.on(async () => { await null; while (true) {} });",
+            true,
+        )
+        .await;
+        assert!(matches!(reason, Some(ShutdownReason::Timeout)));
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_script_timing_out_on_load_is_skipped_by_next_vm() {
+        let failed_scripts = FailedScripts::default();
+        let retry_grace = Duration::from_secs(60);
+
+        let first = run_vm(
+            "while (true) {}",
+            false,
+            true,
+            failed_scripts.clone(),
+            retry_grace,
+        )
+        .await;
+        assert!(matches!(
+            first,
+            Some(VmEvent::Shutdown(ShutdownReason::Timeout))
+        ));
+        assert!(failed_scripts.contains(1));
+
+        // what the worker does when it starts the next vm for the guild
+        let second = run_vm("while (true) {}", false, true, failed_scripts, retry_grace).await;
+        assert!(matches!(second, Some(VmEvent::Idle)));
+    }
+}
@@ -0,0 +1,112 @@
+use std::time::{Duration, Instant};
+
+use lru::LruCache;
+
+#[derive(Debug, Clone, Copy)]
+pub struct DispatchDedupConfig {
+    /// How long an event is remembered, 0 turns deduplication off
+    pub window: Duration,
+    /// Max number of events remembered, the least recently seen ones are forgotten first
+    pub max_events: usize,
+}
+
+/// Remembers recently dispatched events so the ones the gateway delivers again after a
+/// reconnect don't run the handlers twice
+///
+/// Only events that can happen once per id are deduplicated (e.g. a message being created),
+/// events like reactions or channel updates can legitimately repeat with the same payload.
+pub struct DispatchDedup {
+    config: DispatchDedupConfig,
+    seen: LruCache<(String, String), Instant>,
+}
+
+impl DispatchDedup {
+    pub fn new(config: DispatchDedupConfig) -> Self {
+        Self {
+            config,
+            seen: LruCache::new(config.max_events),
+        }
+    }
+
+    /// Records the event, returns true if it was already dispatched within the window
+    pub fn is_duplicate(&mut self, name: &str, data: &serde_json::Value) -> bool {
+        self.is_duplicate_at(name, data, Instant::now())
+    }
+
+    fn is_duplicate_at(&mut self, name: &str, data: &serde_json::Value, now: Instant) -> bool {
+        if self.config.window.is_zero() || self.config.max_events == 0 {
+            return false;
+        }
+
+        let id = match unique_event_id(name, data) {
+            Some(id) => id,
+            None => return false,
+        };
+
+        let key = (name.to_string(), id.to_string());
+        if let Some(seen_at) = self.seen.get(&key) {
+            if now.duration_since(*seen_at) < self.config.window {
+                return true;
+            }
+        }
+
+        self.seen.put(key, now);
+        false
+    }
+}
+
+// the id identifying a single occurrence of the event, None for events that can repeat
+fn unique_event_id<'a>(name: &str, data: &'a serde_json::Value) -> Option<&'a str> {
+    match name {
+        "MESSAGE_CREATE"
+        | "MESSAGE_DELETE"
+        | "CHANNEL_CREATE"
+        | "CHANNEL_DELETE"
+        | "THREAD_CREATE"
+        | "THREAD_DELETE"
+        | "BOTLOADER_COMMAND_INTERACTION_CREATE"
+        | "BOTLOADER_COMPONENT_INTERACTION_CREATE"
+        | "BOTLOADER_MODAL_SUBMIT_INTERACTION_CREATE" => data.get("id")?.as_str(),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+
+    use serde_json::json;
+
+    use super::{DispatchDedup, DispatchDedupConfig};
+
+    #[test]
+    fn test_dispatch_dedup() {
+        let mut dedup = DispatchDedup::new(DispatchDedupConfig {
+            window: Duration::from_secs(60),
+            max_events: 2,
+        });
+
+        let start = Instant::now();
+        let at = |secs| start + Duration::from_secs(secs);
+
+        let msg = json!({"id": "1", "content": "hello"});
+        assert!(!dedup.is_duplicate_at("MESSAGE_CREATE", &msg, at(0)));
+        assert!(dedup.is_duplicate_at("MESSAGE_CREATE", &msg, at(1)));
+
+        // same id for a different event is not a duplicate
+        assert!(!dedup.is_duplicate_at("MESSAGE_DELETE", &msg, at(1)));
+
+        // events that can repeat are never deduplicated
+        let reaction = json!({"messageId": "1", "userId": "2"});
+        assert!(!dedup.is_duplicate_at("MESSAGE_REACTION_ADD", &reaction, at(2)));
+        assert!(!dedup.is_duplicate_at("MESSAGE_REACTION_ADD", &reaction, at(2)));
+
+        // outside of the window
+        assert!(!dedup.is_duplicate_at("MESSAGE_CREATE", &msg, at(62)));
+
+        // evicted once more than max_events have been seen
+        assert!(!dedup.is_duplicate_at("MESSAGE_CREATE", &json!({"id": "2"}), at(63)));
+        assert!(!dedup.is_duplicate_at("MESSAGE_CREATE", &json!({"id": "3"}), at(63)));
+        assert!(!dedup.is_duplicate_at("MESSAGE_CREATE", &msg, at(64)));
+    }
+}
@@ -0,0 +1,29 @@
+use std::cell::Cell;
+
+/// Counts the ops started since the last event was dispatched, the runtime caps this so the
+/// scripts can't make an unbounded number of api calls without new events coming in.
+///
+/// Available in the op state as `Rc<DispatchOps>`, the vm resets it on every dispatch. Ops are
+/// not attributed to the event that started them, so this counts the ops of every handler
+/// running in between two dispatches.
+#[derive(Default)]
+pub struct DispatchOps {
+    count: Cell<u64>,
+}
+
+impl DispatchOps {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn reset(&self) {
+        self.count.set(0);
+    }
+
+    /// Counts an op, returns the number of ops since the last dispatch including this one
+    pub fn increment(&self) -> u64 {
+        let count = self.count.get().saturating_add(1);
+        self.count.set(count);
+        count
+    }
+}
@@ -23,6 +23,29 @@ pub fn source_map_error(
     }
 }
 
+/// Returns the id of the guild script the error was thrown from, if any of its frames
+/// points to one.
+pub fn error_script_id(
+    loaded_scripts: &ScriptsStateStoreHandle,
+    err: &anyhow::Error,
+) -> Option<u64> {
+    let js_err = err.downcast_ref::<JsError>()?;
+    let borrow = loaded_scripts.borrow();
+
+    js_err
+        .frames
+        .iter()
+        .filter_map(|frame| frame.file_name.as_deref())
+        .filter_map(ScriptsStateStore::get_guild_script_name)
+        .find_map(|name| {
+            borrow
+                .scripts
+                .iter()
+                .find(|v| v.script.name == name)
+                .map(|v| v.script.id)
+        })
+}
+
 fn parse_transform_stack(scripts: &ScriptsStateStore, stack: &str) -> String {
     let mut output = String::new();
 
@@ -0,0 +1,163 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    time::{Duration, Instant},
+};
+
+#[derive(Debug, Clone, Copy)]
+pub struct ErrorBreakerConfig {
+    /// Number of errors within `window` that disables a script's handlers, 0 turns the breaker off
+    pub threshold: u32,
+    pub window: Duration,
+    /// How long the handlers stay disabled before the script gets another chance
+    pub cooldown: Duration,
+}
+
+/// Tracks the error rate of each script so the handlers of a script that errors on every
+/// event can be disabled for a while, instead of spamming the guild log and wasting cpu.
+pub struct ErrorBreaker {
+    config: ErrorBreakerConfig,
+    scripts: HashMap<u64, ScriptErrors>,
+}
+
+#[derive(Default)]
+struct ScriptErrors {
+    recent: VecDeque<Instant>,
+    tripped_at: Option<Instant>,
+}
+
+impl ErrorBreaker {
+    pub fn new(config: ErrorBreakerConfig) -> Self {
+        Self {
+            config,
+            scripts: HashMap::new(),
+        }
+    }
+
+    pub fn config(&self) -> &ErrorBreakerConfig {
+        &self.config
+    }
+
+    /// Records an error from a script, returns true if this error disabled its handlers
+    pub fn record_error(&mut self, script_id: u64) -> bool {
+        self.record_error_at(script_id, Instant::now())
+    }
+
+    fn record_error_at(&mut self, script_id: u64, now: Instant) -> bool {
+        if self.config.threshold == 0 {
+            return false;
+        }
+
+        let errors = self.scripts.entry(script_id).or_default();
+        if errors.is_tripped(&self.config, now) {
+            // in-flight handlers can still error after it tripped
+            return false;
+        }
+
+        errors.recent.push_back(now);
+        while let Some(oldest) = errors.recent.front() {
+            if now.duration_since(*oldest) > self.config.window {
+                errors.recent.pop_front();
+            } else {
+                break;
+            }
+        }
+
+        if errors.recent.len() >= self.config.threshold as usize {
+            errors.recent.clear();
+            errors.tripped_at = Some(now);
+            return true;
+        }
+
+        false
+    }
+
+    /// The scripts that currently have their handlers disabled, scripts are re-enabled
+    /// once their cooldown is over
+    pub fn disabled_scripts(&mut self) -> Vec<u64> {
+        self.disabled_scripts_at(Instant::now())
+    }
+
+    fn disabled_scripts_at(&mut self, now: Instant) -> Vec<u64> {
+        let config = self.config;
+        self.scripts
+            .retain(|_, errors| errors.is_tripped(&config, now) || !errors.recent.is_empty());
+
+        self.scripts
+            .iter()
+            .filter(|(_, errors)| errors.tripped_at.is_some())
+            .map(|(id, _)| *id)
+            .collect()
+    }
+
+    /// Gives all scripts a clean slate, for when the scripts are reloaded
+    pub fn reset(&mut self) {
+        self.scripts.clear();
+    }
+
+    /// Gives a single script a clean slate, for when a new version of it is loaded in place
+    pub fn reset_script(&mut self, script_id: u64) {
+        self.scripts.remove(&script_id);
+    }
+}
+
+impl ScriptErrors {
+    // also clears the tripped state if the cooldown is over
+    fn is_tripped(&mut self, config: &ErrorBreakerConfig, now: Instant) -> bool {
+        match self.tripped_at {
+            Some(at) if now.duration_since(at) < config.cooldown => true,
+            Some(_) => {
+                self.tripped_at = None;
+                false
+            }
+            None => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+
+    use super::{ErrorBreaker, ErrorBreakerConfig};
+
+    #[test]
+    fn test_error_breaker() {
+        let mut breaker = ErrorBreaker::new(ErrorBreakerConfig {
+            threshold: 3,
+            window: Duration::from_secs(10),
+            cooldown: Duration::from_secs(60),
+        });
+
+        let start = Instant::now();
+        let at = |secs| start + Duration::from_secs(secs);
+
+        // errors spread out over more than the window don't trip it
+        assert!(!breaker.record_error_at(1, at(0)));
+        assert!(!breaker.record_error_at(1, at(6)));
+        assert!(!breaker.record_error_at(1, at(12)));
+        assert!(breaker.disabled_scripts_at(at(12)).is_empty());
+
+        assert!(breaker.record_error_at(1, at(13)));
+        assert!(!breaker.record_error_at(1, at(14)));
+        assert_eq!(breaker.disabled_scripts_at(at(14)), vec![1]);
+
+        // other scripts are unaffected
+        assert!(!breaker.record_error_at(2, at(14)));
+
+        // re-enabled after the cooldown
+        assert!(breaker.disabled_scripts_at(at(73)).is_empty());
+        assert!(!breaker.record_error_at(1, at(74)));
+
+        // a new version of a script starts with a clean slate, leaving the others alone
+        assert!(!breaker.record_error_at(1, at(75)));
+        assert!(!breaker.record_error_at(2, at(75)));
+        assert!(!breaker.record_error_at(2, at(76)));
+        assert!(breaker.record_error_at(2, at(77)));
+        breaker.reset_script(1);
+        assert_eq!(breaker.disabled_scripts_at(at(77)), vec![2]);
+        assert!(!breaker.record_error_at(1, at(78)));
+
+        breaker.reset_script(2);
+        assert!(breaker.disabled_scripts_at(at(78)).is_empty());
+    }
+}
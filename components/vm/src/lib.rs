@@ -1,11 +1,18 @@
-use std::{cell::RefCell, rc::Rc};
+use std::{cell::RefCell, collections::HashSet, rc::Rc};
 
-use deno_core::{v8_set_flags, SourceMapGetter};
+use deno_core::{v8_set_flags, JsRuntime, SourceMapGetter};
+use lazy_static::lazy_static;
 use stores::config::Script;
-use tscompiler::CompiledItem;
+use tscompiler::{CompileCache, CompileError, CompiledItem};
 
+pub mod cpu_budget;
+pub mod dispatch_dedup;
+pub mod dispatch_ops;
 pub mod error;
+pub mod error_breaker;
 pub mod moduleloader;
+pub mod script_reload;
+pub mod snapshot_version;
 pub mod vm;
 
 /// Represents a value passed to or from JavaScript.
@@ -16,9 +23,38 @@ pub type JsValue = serde_json::Value;
 /// Polymorphic error type able to represent different error domains.
 pub type AnyError = deno_core::error::AnyError;
 
+lazy_static! {
+    /// Shared by all the vms in this process, scripts are compiled again on every vm restart otherwise
+    pub static ref COMPILE_CACHE: CompileCache = CompileCache::new(32 * 1024 * 1024);
+}
+
 pub static BOTLOADER_CORE_SNAPSHOT: &[u8] =
     include_bytes!(concat!(env!("OUT_DIR"), "/BOTLOADER_SNAPSHOT.bin"));
 
+/// The version the core snapshot should have, from the sources this crate was compiled with and
+/// the first `snapshot_op_count` ops registered in the runtime
+pub fn expected_core_snapshot_version(
+    rt: &mut JsRuntime,
+    snapshot_op_count: usize,
+) -> Option<String> {
+    let op_names = snapshot_version::runtime_op_names(rt)?;
+
+    Some(snapshot_version::snapshot_version(
+        include_str!("botloader-core.js"),
+        deno_core::v8_version(),
+        op_names.get(..snapshot_op_count)?,
+    ))
+}
+
+/// Reads the version and number of ops build.rs baked into the snapshot the runtime was created
+/// from
+pub fn core_snapshot_version(rt: &mut JsRuntime) -> Option<(String, usize)> {
+    snapshot_version::eval_script(
+        rt,
+        "[BotloaderCore.snapshotVersion, BotloaderCore.snapshotOpCount]",
+    )
+}
+
 pub fn prepend_script_source_header(source: &str, script: Option<&Script>) -> String {
     let mut result = gen_script_source_header(script);
     result.push_str(source);
@@ -85,6 +121,8 @@ pub struct ScriptState {
     pub script: Script,
     pub state: ScriptLoadState,
     pub compiled: CompiledItem,
+    /// The events the script has handlers for, reported when it's started
+    pub handled_events: HashSet<String>,
 }
 
 #[derive(Clone)]
@@ -108,12 +146,18 @@ pub type ScriptsStateStoreHandle = Rc<RefCell<ScriptsStateStore>>;
 #[derive(Clone)]
 pub struct ScriptsStateStore {
     pub scripts: Vec<ScriptState>,
+    /// Names of the scripts that import, or are imported by, another guild script
+    pub linked_scripts: HashSet<String>,
+    /// The script whose top level code is being evaluated, only it may report itself as started
+    pub loading_script: Option<u64>,
 }
 
 impl ScriptsStateStore {
     pub fn new() -> Self {
         Self {
             scripts: Vec::new(),
+            linked_scripts: HashSet::new(),
+            loading_script: None,
         }
     }
 
@@ -123,6 +167,8 @@ impl ScriptsStateStore {
 
     pub fn clear(&mut self) {
         self.scripts.clear();
+        self.linked_scripts.clear();
+        self.loading_script = None;
     }
 
     pub fn get_original_line_col(
@@ -148,6 +194,9 @@ impl ScriptsStateStore {
     }
 
     pub fn get_guild_script_name(res: &str) -> Option<&str> {
+        // scripts reloaded in place are loaded with a query to get a fresh module
+        let res = res.split_once('?').map(|(path, _)| path).unwrap_or(res);
+
         if let Some(stripped) = res.strip_prefix("file:///guild_scripts/") {
             if let Some(end_trimmed) = stripped.strip_suffix(".js") {
                 return Some(end_trimmed);
@@ -157,13 +206,26 @@ impl ScriptsStateStore {
         None
     }
 
-    pub fn compile_add_script(&mut self, script: Script) -> Result<ScriptState, String> {
-        match tscompiler::compile_typescript(&script.original_source) {
+    /// Compiles the script and adds it to the store, without `source_map` the script's source map
+    /// is not kept around and errors and log lines point at the compiled output instead
+    pub fn compile_add_script(
+        &mut self,
+        script: Script,
+        source_map: bool,
+    ) -> Result<ScriptState, CompileError> {
+        match COMPILE_CACHE.compile(&script.original_source) {
             Ok(compiled) => {
+                let compiled = if source_map {
+                    compiled
+                } else {
+                    compiled.without_source_map()
+                };
+
                 let item = ScriptState {
                     compiled,
                     script,
                     state: ScriptLoadState::Unloaded,
+                    handled_events: HashSet::new(),
                 };
 
                 self.scripts.push(item.clone());
@@ -187,6 +249,12 @@ impl ScriptsStateStore {
         }
     }
 
+    pub fn set_handled_events(&mut self, script_id: u64, events: HashSet<String>) {
+        if let Some(current) = self.get_script_mut(script_id) {
+            current.handled_events = events;
+        }
+    }
+
     pub fn get_script(&self, script_id: u64) -> Option<&ScriptState> {
         self.scripts.iter().find(|v| v.script.id == script_id)
     }
@@ -194,6 +262,11 @@ impl ScriptsStateStore {
     pub fn get_script_mut(&mut self, script_id: u64) -> Option<&mut ScriptState> {
         self.scripts.iter_mut().find(|v| v.script.id == script_id)
     }
+
+    pub fn remove_script(&mut self, script_id: u64) -> Option<ScriptState> {
+        let index = self.scripts.iter().position(|v| v.script.id == script_id)?;
+        Some(self.scripts.remove(index))
+    }
 }
 
 impl Default for ScriptsStateStore {
@@ -225,3 +298,22 @@ impl SourceMapGetter for ScriptStateStoreWrapper {
         Some(format!("{file_name}:{line_number}"))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_core_snapshot_version_matches() {
+        let mut rt = JsRuntime::new(deno_core::RuntimeOptions {
+            startup_snapshot: Some(deno_core::Snapshot::Static(BOTLOADER_CORE_SNAPSHOT)),
+            ..Default::default()
+        });
+
+        let (version, op_count) = core_snapshot_version(&mut rt).unwrap();
+        assert_eq!(
+            Some(version),
+            expected_core_snapshot_version(&mut rt, op_count)
+        );
+    }
+}
@@ -2,10 +2,16 @@ use deno_core::{ModuleLoader, ModuleSource, ModuleType, ResolutionKind};
 use futures::future::ready;
 use url::Url;
 
-use crate::{prepend_script_source_header, ScriptLoadState, ScriptsStateStoreHandle};
+use crate::{
+    prepend_script_source_header, ScriptLoadState, ScriptsStateStore, ScriptsStateStoreHandle,
+};
+
+/// Decides whether a builtin module can be imported, returning the reason if it can't
+pub type ModuleAccessCheck = Box<dyn Fn(&Url) -> Result<(), String> + Send>;
 
 pub struct ModuleManager {
     pub module_map: Vec<ModuleEntry>,
+    pub module_access_check: Option<ModuleAccessCheck>,
     pub guild_scripts: ScriptsStateStoreHandle,
 }
 
@@ -13,21 +19,30 @@ impl ModuleManager {
     fn try_load_std_module(
         &self,
         module_specifier: &deno_core::ModuleSpecifier,
-    ) -> Option<ModuleSource> {
-        self.module_map
+    ) -> Option<Result<ModuleSource, deno_core::error::AnyError>> {
+        let entry = self
+            .module_map
             .iter()
-            .find(|e| e.specifier == *module_specifier)
-            .map(|e| ModuleSource {
-                code: e.source.as_bytes().into(),
-                module_url_found: module_specifier.to_string(),
-                module_url_specified: module_specifier.to_string(),
-                module_type: ModuleType::JavaScript,
-            })
+            .find(|e| e.specifier == *module_specifier)?;
+
+        if let Some(check) = &self.module_access_check {
+            if let Err(reason) = check(module_specifier) {
+                return Some(Err(anyhow::anyhow!(reason)));
+            }
+        }
+
+        Some(Ok(ModuleSource {
+            code: entry.source.as_bytes().into(),
+            module_url_found: module_specifier.to_string(),
+            module_url_specified: module_specifier.to_string(),
+            module_type: ModuleType::JavaScript,
+        }))
     }
 
     fn try_load_script_module(
         &self,
         module_specifier: &deno_core::ModuleSpecifier,
+        maybe_referrer: Option<&deno_core::ModuleSpecifier>,
     ) -> Option<ModuleSource> {
         if !module_specifier.path().starts_with("/guild_scripts/") {
             return None;
@@ -42,6 +57,13 @@ impl ModuleManager {
         if let Some(script) = store.scripts.iter_mut().find(|v| v.script.name == name) {
             script.state = ScriptLoadState::Loaded;
 
+            // scripts sharing modules can't be reloaded on their own
+            let importer = maybe_referrer
+                .and_then(|referrer| ScriptsStateStore::get_guild_script_name(referrer.as_str()))
+                .map(ToString::to_string);
+            store.linked_scripts.insert(name.to_string());
+            store.linked_scripts.extend(importer);
+
             let source =
                 prepend_script_source_header(&script.compiled.output, Some(&script.script));
 
@@ -94,15 +116,17 @@ impl ModuleLoader for ModuleManager {
     fn load(
         &self,
         module_specifier: &deno_core::ModuleSpecifier,
-        _maybe_referrer: Option<deno_core::ModuleSpecifier>,
+        maybe_referrer: Option<deno_core::ModuleSpecifier>,
         _is_dyn_import: bool,
     ) -> std::pin::Pin<Box<deno_core::ModuleSourceFuture>> {
         // info!("loading module: {}", module_specifier.to_string());
 
         Box::pin(ready(
             if let Some(l) = self.try_load_std_module(module_specifier) {
-                Ok(l)
-            } else if let Some(l) = self.try_load_script_module(module_specifier) {
+                l
+            } else if let Some(l) =
+                self.try_load_script_module(module_specifier, maybe_referrer.as_ref())
+            {
                 Ok(l)
             } else {
                 Err(anyhow::anyhow!(
@@ -0,0 +1,234 @@
+use deno_core::JsRuntime;
+use url::Url;
+
+use crate::AnyError;
+
+/// The url a guild script is loaded from
+///
+/// v8 only evaluates a module once per url, so scripts reloaded in place get the number of
+/// times they've been reloaded in the query to get a fresh module.
+pub fn script_module_url(name: &str, generation: u64) -> Url {
+    let url = if generation == 0 {
+        format!("file:///guild_scripts/{name}.js")
+    } else {
+        format!("file:///guild_scripts/{name}.js?reload={generation}")
+    };
+
+    Url::parse(&url).unwrap()
+}
+
+/// Removes the event handlers, commands and interaction listeners a script registered so a new
+/// version of it can be loaded while the other scripts keep running
+pub fn unload_script(rt: &mut JsRuntime, script_id: u64) -> Result<(), AnyError> {
+    rt.execute_script(
+        "bl:unload_script",
+        &format!("BotloaderCore.unloadScript({script_id})"),
+    )?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        sync::{Arc, Mutex},
+        time::Duration,
+    };
+
+    use deno_core::{op, Extension, OpState};
+    use guild_logger::GuildLoggerBuilder;
+    use stores::config::{Script, ScriptContributes};
+    use tokio::sync::mpsc;
+    use twilight_model::id::Id;
+    use url::Url;
+    use vmthread::{VmThreadCommand, VmThreadFuture};
+
+    use super::script_module_url;
+    use crate::{
+        cpu_budget::CpuBudgetConfig,
+        dispatch_dedup::DispatchDedupConfig,
+        error_breaker::ErrorBreakerConfig,
+        moduleloader::ModuleEntry,
+        vm::{CreateRt, Vm, VmCommand, VmContext, VmRole},
+    };
+
+    // stands in for the event system in the runtime
+    const CORE: &str = r#"
+        BotloaderCore.handlers = [];
+        BotloaderCore.dispatchEvent = (evt) => {
+            for (const { cb } of BotloaderCore.handlers) {
+                cb(evt.data);
+            }
+        };
+        BotloaderCore.unloadScript = (scriptId) => {
+            BotloaderCore.handlers = BotloaderCore.handlers.filter(h => h.scriptId !== scriptId);
+        };
+    "#;
+
+    // stands in for the script module in the runtime
+    const SCRIPT_MODULE: &str = r#"
+        export class Script {
+            constructor(id) {
+                this.id = id;
+            }
+
+            on(cb) {
+                BotloaderCore.handlers.push({ scriptId: this.id, cb });
+            }
+
+            run() {}
+        }
+    "#;
+
+    const COUNTER_SCRIPT: &str = r#"
+        let seen = 0;
+        script.on(() => {
+            seen++;
+            Deno.core.ops.op_test_record(`counter:${seen}`);
+        });
+    "#;
+
+    #[derive(Clone, Default)]
+    struct Records(Arc<Mutex<Vec<String>>>);
+
+    impl Records {
+        async fn wait_for(&self, len: usize) -> Vec<String> {
+            for _ in 0..500 {
+                if self.0.lock().unwrap().len() >= len {
+                    break;
+                }
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+
+            self.0.lock().unwrap().clone()
+        }
+    }
+
+    #[op]
+    fn op_test_record(state: &mut OpState, entry: String) {
+        state.borrow::<Records>().0.lock().unwrap().push(entry);
+    }
+
+    fn test_script(id: u64, name: &str, source: &str) -> Script {
+        Script {
+            id,
+            name: name.to_string(),
+            original_source: source.to_string(),
+            enabled: true,
+            contributes: ScriptContributes {
+                commands: Vec::new(),
+                interval_timers: Vec::new(),
+                api_version: None,
+            },
+            plugin_id: None,
+            plugin_auto_update: None,
+            min_log_level: Default::default(),
+        }
+    }
+
+    fn version_script(version: u32) -> Script {
+        test_script(
+            2,
+            "version",
+            &format!("script.on(() => Deno.core.ops.op_test_record(\"version:{version}\"));"),
+        )
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_reload_keeps_other_scripts_running() {
+        let records = Records::default();
+        let ext_records = records.clone();
+
+        let (cmd_tx, cmd_rx) = mpsc::unbounded_channel();
+        let (evt_tx, _evt_rx) = mpsc::unbounded_channel();
+
+        let vm_thread = VmThreadFuture::<Vm>::create();
+        let _ = vm_thread.send_cmd.send(VmThreadCommand::StartVM(CreateRt {
+            guild_logger: GuildLoggerBuilder::new().run(),
+            rx: cmd_rx,
+            tx: evt_tx,
+            ctx: VmContext {
+                guild_id: Id::new(1),
+                role: VmRole::Main,
+            },
+            load_scripts: vec![test_script(1, "counter", COUNTER_SCRIPT), version_script(1)],
+            extension_factory: Box::new(move || {
+                let records = ext_records.clone();
+                vec![Extension::builder("bl_test")
+                    .js(vec![("bl:test_core", CORE)])
+                    .ops(vec![op_test_record::decl()])
+                    .state(move |state| {
+                        state.put(records.clone());
+                        Ok(())
+                    })
+                    .build()]
+            }),
+            extension_modules: vec![ModuleEntry {
+                specifier: Url::parse("file:///script.js").unwrap(),
+                source: SCRIPT_MODULE,
+            }],
+            extension_module_access: None,
+            source_maps: false,
+            last_restart: None,
+            failed_script_retry_grace: Duration::ZERO,
+            failed_scripts: Default::default(),
+            error_breaker: ErrorBreakerConfig {
+                threshold: 0,
+                window: Duration::ZERO,
+                cooldown: Duration::ZERO,
+            },
+            dispatch_dedup: DispatchDedupConfig {
+                window: Duration::ZERO,
+                max_events: 0,
+            },
+            cpu_budget: CpuBudgetConfig {
+                free: Duration::ZERO,
+                lite: Duration::ZERO,
+                premium: Duration::ZERO,
+            },
+            premium_tier: Default::default(),
+        }));
+
+        cmd_tx
+            .send(VmCommand::DispatchEvent(
+                "TEST".to_string(),
+                serde_json::json!({}),
+                1,
+            ))
+            .unwrap();
+        assert_eq!(records.wait_for(2).await, ["counter:1", "version:1"]);
+
+        cmd_tx
+            .send(VmCommand::UpdateScript(version_script(2)))
+            .unwrap();
+        cmd_tx
+            .send(VmCommand::DispatchEvent(
+                "TEST".to_string(),
+                serde_json::json!({}),
+                2,
+            ))
+            .unwrap();
+
+        // the counter script wasn't touched so it kept its state, and the old version is gone
+        records.wait_for(4).await;
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        assert_eq!(
+            records.wait_for(4).await,
+            ["counter:1", "version:1", "counter:2", "version:2"]
+        );
+
+        let _ = vm_thread.send_cmd.send(VmThreadCommand::Shutdown);
+    }
+
+    #[test]
+    fn test_module_url() {
+        assert_eq!(
+            script_module_url("a", 0).as_str(),
+            "file:///guild_scripts/a.js"
+        );
+        assert_eq!(
+            script_module_url("a", 3).as_str(),
+            "file:///guild_scripts/a.js?reload=3"
+        );
+    }
+}
@@ -0,0 +1,76 @@
+// this file is also included by build.rs, so it can only depend on crates that are also
+// build dependencies, and can't import anything build.rs imports itself
+
+use std::hash::Hasher;
+
+/// Bump this when changing how the core snapshot is built in a way not covered by [snapshot_version]
+pub const SNAPSHOT_FORMAT_VERSION: u32 = 2;
+
+/// The extensions with js that are baked into the core snapshot
+pub const SNAPSHOT_EXTENSIONS: &[&str] = &["bl_core"];
+
+/// Identifies a core snapshot by everything that goes into it
+///
+/// `op_names` are the ops registered while making the snapshot, in order. A runtime created from
+/// it has to register the same ones in front of the ones from its own extensions.
+pub fn snapshot_version(core_js: &str, v8_version: &str, op_names: &[String]) -> String {
+    // fnv is used as the std hasher's algorithm is unspecified and may differ between builds
+    let mut hasher = fnv::FnvHasher::default();
+    for extension in SNAPSHOT_EXTENSIONS {
+        hash_str(&mut hasher, extension);
+    }
+    hash_str(&mut hasher, core_js);
+    hash_str(&mut hasher, v8_version);
+    for op_name in op_names {
+        hash_str(&mut hasher, op_name);
+    }
+
+    format!("{SNAPSHOT_FORMAT_VERSION}-{:016x}", hasher.finish())
+}
+
+// length prefixed so moving bytes between two strings changes the hash as well
+fn hash_str(hasher: &mut fnv::FnvHasher, s: &str) {
+    hasher.write(&(s.len() as u64).to_le_bytes());
+    hasher.write(s.as_bytes());
+}
+
+/// Names of all the ops registered in the runtime, in the order they were registered
+pub fn runtime_op_names(rt: &mut deno_core::JsRuntime) -> Option<Vec<String>> {
+    eval_script(rt, "Deno.core.opNames()")
+}
+
+/// Evaluates `source` and deserializes the result, `None` if it threw or has the wrong type
+pub fn eval_script<T: deno_core::serde::de::DeserializeOwned>(
+    rt: &mut deno_core::JsRuntime,
+    source: &str,
+) -> Option<T> {
+    let value = rt.execute_script("bl:snapshot_version", source).ok()?;
+
+    let scope = &mut rt.handle_scope();
+    let value = deno_core::v8::Local::new(scope, value);
+    deno_core::serde_v8::from_v8(scope, value).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_snapshot_version_is_stable() {
+        assert_eq!(snapshot_version("", "", &[]), "2-ec105ecfa8e99b1a");
+    }
+
+    #[test]
+    fn test_snapshot_version_covers_ops() {
+        let ops = |names: &[&str]| names.iter().map(|v| v.to_string()).collect::<Vec<_>>();
+
+        assert_ne!(
+            snapshot_version("", "", &ops(&["op_a", "op_b"])),
+            snapshot_version("", "", &ops(&["op_b", "op_a"]))
+        );
+        assert_ne!(
+            snapshot_version("", "", &ops(&["op_ab"])),
+            snapshot_version("", "", &ops(&["op_a", "b"]))
+        );
+    }
+}
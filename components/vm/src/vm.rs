@@ -1,27 +1,34 @@
-use crate::error::source_map_error;
-use crate::moduleloader::{ModuleEntry, ModuleManager};
+use crate::cpu_budget::{CpuBudgetConfig, CpuBudgetExceeded, CpuWatchdog};
+use crate::dispatch_dedup::{DispatchDedup, DispatchDedupConfig};
+use crate::dispatch_ops::DispatchOps;
+use crate::error::{error_script_id, source_map_error};
+use crate::error_breaker::{ErrorBreaker, ErrorBreakerConfig};
+use crate::moduleloader::{ModuleAccessCheck, ModuleEntry, ModuleManager};
+use crate::script_reload;
 use crate::{
     prepend_script_source_header, AnyError, ScriptLoadState, ScriptState, ScriptStateStoreWrapper,
     ScriptsStateStore, ScriptsStateStoreHandle,
 };
-use deno_core::{Extension, RuntimeOptions, Snapshot};
+use deno_core::{Extension, JsRuntime, RuntimeOptions, Snapshot};
 use futures::{future::LocalBoxFuture, FutureExt};
 use guild_logger::{GuildLogger, LogEntry};
 use isolatecell::{IsolateCell, ManagedIsolate};
 use serde::Serialize;
+use std::collections::HashMap;
 use std::convert::TryFrom;
 use std::pin::Pin;
 use std::{
     fmt::Display,
     rc::Rc,
-    sync::{atomic::AtomicBool, Arc, RwLock as StdRwLock},
+    sync::{atomic::AtomicBool, Arc, Mutex as StdMutex, RwLock as StdRwLock},
     task::{Context, Poll, Wake, Waker},
+    time::{Duration, Instant},
 };
-use stores::config::Script;
+use stores::config::{PremiumSlotTier, Script, ScriptRuntimeStats};
 use tokio::sync::mpsc::{self, UnboundedReceiver, UnboundedSender};
-use tracing::{error, info, instrument};
+use tracing::{debug, error, info, instrument, warn};
+use tscompiler::CompileError;
 use twilight_model::id::{marker::GuildMarker, Id};
-use url::Url;
 use v8::{CreateParams, HeapStatistics, IsolateHandle};
 use vmthread::{CreateVmSuccess, ShutdownHandle, ShutdownReason, VmInterface};
 
@@ -33,7 +40,10 @@ pub enum VmCommand {
     // note that this also reloads the runtime, shutting it down and starting it again
     // we send a message when that has been accomplished
     UnloadScripts(Vec<Script>),
+    // swaps out a single script, leaving the others running when possible
     UpdateScript(Script),
+    // loads the current version of a script again, leaving the others running when possible
+    ReloadScript(u64),
     Restart(Vec<Script>),
 }
 
@@ -42,8 +52,87 @@ pub enum VmEvent {
     Shutdown(ShutdownReason),
     DispatchedEvent(u64),
     VmFinished,
+
+    // the event loop has no pending work left (no in-flight events or js timers)
+    // and the vm is just waiting for new commands
+    Idle,
+    // sent when the vm receives new work after having been idle
+    Busy,
+
+    // accumulated per script counters since the last flush, to be persisted by the worker
+    ScriptStats(Vec<ScriptRuntimeStats>),
+}
+
+// how often the per script stats are flushed to the worker while the vm is busy
+const SCRIPT_STATS_FLUSH_INTERVAL: Duration = Duration::from_secs(60);
+
+// v8 can't unload modules so every in place reload leaves the old version's module in memory,
+// after this many the vm is restarted instead to get rid of them
+const MAX_IN_PLACE_RELOADS: u64 = 20;
+
+// how long a reload waits for in-flight handlers before restarting the vm instead, so nothing
+// from the old version of the script is still running when the new one is loaded
+const RELOAD_DRAIN_TIMEOUT: Duration = Duration::from_secs(5);
+
+// a script that failed to load, restarts skip it until the retry grace period is over or its source changes
+struct FailedScript {
+    failed_at: Instant,
+    source: String,
+    // only log the first skip so a crash loop doesn't flood the guild log
+    skip_logged: bool,
+}
+
+/// The scripts that failed to load, the worker passes the same one to every vm it starts for a
+/// guild so a script that took the previous vm down while loading is skipped by the next one
+#[derive(Clone, Default)]
+pub struct FailedScripts(Arc<StdMutex<HashMap<u64, FailedScript>>>);
+
+impl FailedScripts {
+    fn insert(&self, script: &Script) {
+        self.0.lock().unwrap().insert(
+            script.id,
+            FailedScript {
+                failed_at: Instant::now(),
+                source: script.original_source.clone(),
+                skip_logged: false,
+            },
+        );
+    }
+
+    pub fn contains(&self, script_id: u64) -> bool {
+        self.0.lock().unwrap().contains_key(&script_id)
+    }
 }
 
+/// Why the vm was last restarted, scripts can use this to adapt (for example disabling caches after running out of memory)
+#[derive(Clone, Copy, Debug)]
+pub enum RestartReason {
+    ScriptsChanged,
+    OutOfMemory,
+    Runaway,
+    // the vm was shut down after being idle and started again for new events
+    Hibernation,
+    Other,
+}
+
+#[derive(Clone, Debug)]
+pub struct RestartInfo {
+    pub reason: RestartReason,
+    pub at: chrono::DateTime<chrono::Utc>,
+}
+
+impl RestartInfo {
+    pub fn now(reason: RestartReason) -> Self {
+        Self {
+            reason,
+            at: chrono::Utc::now(),
+        }
+    }
+}
+
+/// Put in the op state so the runtime can expose it to scripts
+pub struct LastRestartInfo(pub Option<RestartInfo>);
+
 #[derive(Clone, Copy, Debug)]
 pub enum VmRole {
     Main,
@@ -53,9 +142,12 @@ pub enum VmRole {
 pub type GuildVmEvent = (Id<GuildMarker>, VmRole, VmEvent);
 
 #[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
 struct ScriptDispatchData {
     name: String,
     data: serde_json::Value,
+    // scripts tripped by the error breaker, their handlers are skipped
+    disabled_scripts: Vec<u64>,
 }
 
 pub struct Vm {
@@ -76,6 +168,26 @@ pub struct Vm {
     module_manager: Rc<ModuleManager>,
 
     wakeup_rx: UnboundedReceiver<()>,
+    idle: bool,
+    source_maps: bool,
+    dispatch_ops: Rc<DispatchOps>,
+
+    script_stats: HashMap<u64, ScriptRuntimeStats>,
+    script_stats_flushed_at: Instant,
+    last_restart: Option<RestartInfo>,
+
+    failed_scripts: FailedScripts,
+    failed_script_retry_grace: Duration,
+
+    // number of times each script was reloaded in place since the last restart
+    reload_generations: HashMap<u64, u64>,
+
+    error_breaker: ErrorBreaker,
+    dispatch_dedup: DispatchDedup,
+
+    cpu_watchdog: CpuWatchdog,
+    cpu_budget: CpuBudgetConfig,
+    premium_tier: Arc<StdRwLock<Option<PremiumSlotTier>>>,
 }
 
 #[derive(Debug, Clone)]
@@ -92,9 +204,11 @@ impl Vm {
         wakeup_rx: UnboundedReceiver<()>,
     ) {
         let script_store = ScriptsStateStore::new_rc();
+        let dispatch_ops = Rc::new(DispatchOps::new());
 
         let module_manager = Rc::new(ModuleManager {
             module_map: create_req.extension_modules,
+            module_access_check: create_req.extension_module_access,
             guild_scripts: script_store.clone(),
         });
 
@@ -103,8 +217,13 @@ impl Vm {
             module_manager.clone(),
             script_store.clone(),
             timeout_handle.clone(),
+            create_req.source_maps,
+            dispatch_ops.clone(),
+            create_req.last_restart.clone(),
         );
 
+        let cpu_watchdog = CpuWatchdog::spawn(timeout_handle.clone());
+
         let mut rt = Self {
             guild_logger: create_req.guild_logger,
             ctx: create_req.ctx,
@@ -118,8 +237,31 @@ impl Vm {
             extension_factory: create_req.extension_factory,
             module_manager,
             wakeup_rx,
+            idle: false,
+            source_maps: create_req.source_maps,
+            dispatch_ops,
+            script_stats: HashMap::new(),
+            script_stats_flushed_at: Instant::now(),
+            last_restart: create_req.last_restart,
+            failed_scripts: create_req.failed_scripts,
+            failed_script_retry_grace: create_req.failed_script_retry_grace,
+            reload_generations: HashMap::new(),
+            error_breaker: ErrorBreaker::new(create_req.error_breaker),
+            dispatch_dedup: DispatchDedup::new(create_req.dispatch_dedup),
+            cpu_watchdog,
+            cpu_budget: create_req.cpu_budget,
+            premium_tier: create_req.premium_tier,
         };
 
+        if let Err(err) = rt.check_core_snapshot() {
+            error!(%err, "core snapshot mismatch, refusing to start vm");
+            rt.guild_logger.log(LogEntry::critical(
+                rt.ctx.guild_id,
+                "failed starting the vm due to an internal error, please report this".to_string(),
+            ));
+            return;
+        }
+
         rt.guild_logger.log(LogEntry::info(
             rt.ctx.guild_id,
             "starting fresh guild vm...".to_string(),
@@ -132,6 +274,11 @@ impl Vm {
         }
 
         for script in create_req.load_scripts {
+            // no point in running the rest if one of them got the vm shut down
+            if rt.check_terminated() {
+                break;
+            }
+
             rt.run_script(script.id).await;
         }
 
@@ -143,6 +290,9 @@ impl Vm {
         module_manager: Rc<ModuleManager>,
         script_load_states: ScriptsStateStoreHandle,
         shutdown_handle: VmShutdownHandle,
+        source_maps: bool,
+        dispatch_ops: Rc<DispatchOps>,
+        last_restart: Option<RestartInfo>,
     ) -> ManagedIsolate {
         // let create_err_fn = create_error_fn(script_load_states.clone());
 
@@ -157,6 +307,8 @@ impl Vm {
                 ))
                 .state(move |op| {
                     op.put(cloned_load_states.clone());
+                    op.put(dispatch_ops.clone());
+                    op.put(LastRestartInfo(last_restart.clone()));
                     Ok(())
                 })
                 .build(),
@@ -177,7 +329,11 @@ impl Vm {
             ),
             startup_snapshot: Some(Snapshot::Static(crate::BOTLOADER_CORE_SNAPSHOT)),
             // js_error_create_fn: Some(create_err_fn),
-            source_map_getter: Some(Box::new(ScriptStateStoreWrapper(script_load_states))),
+            source_map_getter: if source_maps {
+                Some(Box::new(ScriptStateStoreWrapper(script_load_states)))
+            } else {
+                None
+            },
             ..Default::default()
         };
 
@@ -222,10 +378,12 @@ impl Vm {
         let mut completed = false;
         while !self.check_terminated() {
             let fut = TickFuture {
+                budget: self.current_cpu_budget(),
                 rx: &mut self.rx,
                 rt: &mut self.runtime,
                 cell: &self.isolate_cell,
                 wakeup: &mut self.wakeup_rx,
+                watchdog: &self.cpu_watchdog,
                 completed,
             };
 
@@ -233,6 +391,7 @@ impl Vm {
 
             match fut.await {
                 TickResult::Command(Some(cmd)) => {
+                    self.set_busy();
                     self.handle_cmd(cmd).await;
                 }
                 TickResult::Command(None) => {
@@ -240,19 +399,14 @@ impl Vm {
                 }
                 TickResult::Continue => {}
                 TickResult::VmError(e) => {
-                    self.guild_logger.log(LogEntry::error(
-                        self.ctx.guild_id,
-                        format!(
-                            "Script error occurred: {}",
-                            source_map_error(&self.script_store, e)
-                        ),
-                    ));
+                    self.log_guild_err(e);
                 }
                 TickResult::Completed => {
                     let _ = self
                         .tx
                         .send((self.ctx.guild_id, self.ctx.role, VmEvent::VmFinished));
                     completed = true;
+                    self.set_idle();
                 }
             }
         }
@@ -273,6 +427,8 @@ impl Vm {
             self.stop_vm().await;
         }
 
+        self.flush_script_stats(true);
+
         self.tx
             .send((
                 self.ctx.guild_id,
@@ -286,6 +442,25 @@ impl Vm {
             .unwrap();
     }
 
+    fn set_idle(&mut self) {
+        if !self.idle {
+            self.idle = true;
+            self.flush_script_stats(true);
+            let _ = self
+                .tx
+                .send((self.ctx.guild_id, self.ctx.role, VmEvent::Idle));
+        }
+    }
+
+    fn set_busy(&mut self) {
+        if self.idle {
+            self.idle = false;
+            let _ = self
+                .tx
+                .send((self.ctx.guild_id, self.ctx.role, VmEvent::Busy));
+        }
+    }
+
     fn check_terminated(&mut self) -> bool {
         self.timeout_handle
             .terminated
@@ -295,7 +470,8 @@ impl Vm {
     async fn handle_cmd(&mut self, cmd: VmCommand) {
         match cmd {
             VmCommand::Restart(new_scripts) => {
-                self.restart(new_scripts).await;
+                self.restart(new_scripts, RestartReason::ScriptsChanged)
+                    .await;
             }
             VmCommand::DispatchEvent(name, evt, evt_id) => self.dispatch_event(&name, &evt, evt_id),
             VmCommand::LoadScript(script) => {
@@ -303,25 +479,16 @@ impl Vm {
                     self.run_script(script.script.id).await
                 }
             }
-            VmCommand::UpdateScript(script) => {
-                let mut cloned_scripts = self
+            VmCommand::UpdateScript(script) => self.reload_script(script).await,
+            VmCommand::ReloadScript(script_id) => {
+                let current = self
                     .script_store
                     .borrow()
-                    .scripts
-                    .iter()
-                    .map(|v| v.script.clone())
-                    .collect::<Vec<_>>();
-
-                let mut need_reset = false;
-                for old in &mut cloned_scripts {
-                    if old.id == script.id {
-                        *old = script.clone();
-                        need_reset = true;
-                    }
-                }
+                    .get_script(script_id)
+                    .map(|v| v.script.clone());
 
-                if need_reset {
-                    self.restart(cloned_scripts).await;
+                if let Some(script) = current {
+                    self.reload_script(script).await;
                 }
             }
             VmCommand::UnloadScripts(scripts) => {
@@ -339,7 +506,8 @@ impl Vm {
                     })
                     .collect::<Vec<_>>();
 
-                self.restart(new_scripts).await;
+                self.restart(new_scripts, RestartReason::ScriptsChanged)
+                    .await;
             }
         };
         // self._dump_heap_stats();
@@ -350,15 +518,43 @@ impl Vm {
         let mut script_store = self.script_store.borrow_mut();
 
         let name = script.name.clone();
-        match script_store.compile_add_script(script) {
-            Ok(compiled) => Some(compiled),
-            Err(e) => {
+
+        // a panic in swc would otherwise take down the whole worker
+        let res = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            script_store.compile_add_script(script, self.source_maps)
+        }));
+
+        match res {
+            Ok(Ok(compiled)) => Some(compiled),
+            Ok(Err(CompileError::TooLarge { size, max })) => {
+                self.guild_logger.log(LogEntry::error(
+                    self.ctx.guild_id,
+                    format!(
+                        "Script {name}.ts is too large to compile: it's {}KB, the max is {}KB",
+                        size / 1024,
+                        max / 1024
+                    ),
+                ));
+                None
+            }
+            Ok(Err(e)) => {
                 self.guild_logger.log(LogEntry::error(
                     self.ctx.guild_id,
                     format!("Script compilation failed for {name}.ts: {e}"),
                 ));
                 None
             }
+            Err(_) => {
+                error!(%name, "compiler panicked");
+                self.guild_logger.log(LogEntry::error(
+                    self.ctx.guild_id,
+                    format!(
+                        "Script compilation failed for {name}.ts: internal compiler error, \
+                         please report this"
+                    ),
+                ));
+                None
+            }
         }
     }
 
@@ -379,18 +575,35 @@ impl Vm {
             }
         };
 
-        {
+        if self.in_failure_backoff(&script.script) {
+            info!("script: failed recently, skipping");
             self.script_store
                 .borrow_mut()
-                .set_state(script_id, ScriptLoadState::Loaded);
+                .set_state(script_id, ScriptLoadState::Failed);
+            return;
         }
 
+        {
+            let mut store = self.script_store.borrow_mut();
+            store.set_state(script_id, ScriptLoadState::Loaded);
+            store.loading_script = Some(script_id);
+        }
+
+        // the top level code of a script gets its own op budget, like an event handler
+        self.dispatch_ops.reset();
+
+        let budget = self.current_cpu_budget();
+        let watchdog_guard = self.cpu_watchdog.arm(budget);
+
         let eval_res = {
             let mut rt = self.isolate_cell.enter_isolate(&mut self.runtime);
 
-            let parsed_uri =
-                Url::parse(format!("file:///guild_scripts/{}.js", script.script.name).as_str())
-                    .unwrap();
+            let generation = self
+                .reload_generations
+                .get(&script_id)
+                .copied()
+                .unwrap_or_default();
+            let parsed_uri = script_reload::script_module_url(&script.script.name, generation);
 
             let fut = rt.load_side_module(
                 &parsed_uri,
@@ -421,17 +634,162 @@ impl Vm {
             res.map(|id| rt.mod_evaluate(id))
         };
 
+        let timed_out = watchdog_guard.timed_out();
+        drop(watchdog_guard);
+        if timed_out {
+            {
+                let mut store = self.script_store.borrow_mut();
+                store.loading_script = None;
+                store.set_state(script_id, ScriptLoadState::Failed);
+            }
+            self.log_cpu_budget_exceeded(
+                format!("Loading guild_scripts/{}.ts", script.script.name),
+                budget,
+            );
+            // the vm is shut down over this, the next one skips it as the worker hands it the
+            // same failed scripts
+            self.failed_scripts.insert(&script.script);
+            return;
+        }
+
         match eval_res {
             Err(e) => {
                 self.log_guild_err(e);
                 self.script_store
                     .borrow_mut()
                     .set_state(script_id, ScriptLoadState::Failed);
+                self.failed_scripts.insert(&script.script);
             }
             Ok(rcv) => {
                 self.complete_module_eval(rcv).await;
             }
         }
+
+        self.script_store.borrow_mut().loading_script = None;
+    }
+
+    // replaces a loaded script with the new version without touching the other scripts,
+    // restarting the whole vm instead if it shares modules with them or can't be unloaded
+    async fn reload_script(&mut self, script: Script) {
+        let (all_scripts, linked) = {
+            let store = self.script_store.borrow();
+            let old_name = if let Some(old) = store.get_script(script.id) {
+                &old.script.name
+            } else {
+                return;
+            };
+
+            let linked = store.linked_scripts.contains(old_name)
+                || store.linked_scripts.contains(&script.name);
+
+            let all_scripts = store
+                .scripts
+                .iter()
+                .map(|v| {
+                    if v.script.id == script.id {
+                        script.clone()
+                    } else {
+                        v.script.clone()
+                    }
+                })
+                .collect::<Vec<_>>();
+
+            (all_scripts, linked)
+        };
+
+        if linked {
+            info!(
+                script_id = script.id,
+                "script shares modules with other scripts, restarting instead of reloading"
+            );
+            self.restart(all_scripts, RestartReason::ScriptsChanged)
+                .await;
+            return;
+        }
+
+        if self.reload_generations.values().sum::<u64>() >= MAX_IN_PLACE_RELOADS {
+            info!(
+                script_id = script.id,
+                "too many in place reloads, restarting to free the old modules"
+            );
+            self.restart(all_scripts, RestartReason::ScriptsChanged)
+                .await;
+            return;
+        }
+
+        // pending timers and promises of the old version would otherwise resume after it's
+        // been replaced
+        if tokio::time::timeout(RELOAD_DRAIN_TIMEOUT, self.run_until_completion())
+            .await
+            .is_err()
+        {
+            info!(
+                script_id = script.id,
+                "handlers still running, restarting instead of reloading"
+            );
+            self.restart(all_scripts, RestartReason::ScriptsChanged)
+                .await;
+            return;
+        }
+
+        let unloaded = {
+            let mut rt = self.isolate_cell.enter_isolate(&mut self.runtime);
+            script_reload::unload_script(&mut rt, script.id)
+        };
+        if let Err(err) = unloaded {
+            warn!(%err, script_id = script.id, "failed unloading script, restarting instead");
+            self.restart(all_scripts, RestartReason::ScriptsChanged)
+                .await;
+            return;
+        }
+
+        self.script_store.borrow_mut().remove_script(script.id);
+        *self.reload_generations.entry(script.id).or_default() += 1;
+        self.error_breaker.reset_script(script.id);
+
+        let script_id = script.id;
+        let name = script.name.clone();
+        if self.compile_script(script).is_some() {
+            self.run_script(script_id).await;
+            self.guild_logger.log(LogEntry::info(
+                self.ctx.guild_id,
+                format!("reloaded guild_scripts/{name}.ts"),
+            ));
+        }
+    }
+
+    // whether the script failed to load recently and should not be retried yet,
+    // clears the failure once the grace period is over or the source has changed
+    fn in_failure_backoff(&mut self, script: &Script) -> bool {
+        let mut failed_scripts = self.failed_scripts.0.lock().unwrap();
+        let failed = if let Some(failed) = failed_scripts.get_mut(&script.id) {
+            failed
+        } else {
+            return false;
+        };
+
+        let elapsed = failed.failed_at.elapsed();
+        if elapsed >= self.failed_script_retry_grace || failed.source != script.original_source {
+            failed_scripts.remove(&script.id);
+            return false;
+        }
+
+        if !failed.skip_logged {
+            failed.skip_logged = true;
+            let remaining = self.failed_script_retry_grace - elapsed;
+            self.guild_logger.log(LogEntry::script_warning(
+                self.ctx.guild_id,
+                format!(
+                    "Not running this script as it failed to load recently, it will be retried \
+                     in {}s or when it's changed",
+                    remaining.as_secs().max(1)
+                ),
+                format!("guild_scripts/{}.ts", script.name),
+                None,
+            ));
+        }
+
+        true
     }
 
     fn dispatch_event<P>(&mut self, name: &str, args: &P, evt_id: u64)
@@ -444,11 +802,25 @@ impl Vm {
             VmEvent::DispatchedEvent(evt_id),
         ));
 
+        let data = serde_json::to_value(args).unwrap();
+        if self.dispatch_dedup.is_duplicate(name, &data) {
+            debug!(event = name, evt_id, "skipping duplicate event");
+            return;
+        }
+
+        self.timeout_handle.reset_deadline_extension();
+        self.dispatch_ops.reset();
+
+        let disabled_scripts = self.error_breaker.disabled_scripts();
         let data = ScriptDispatchData {
-            data: serde_json::to_value(args).unwrap(),
+            data,
             name: name.to_string(),
+            disabled_scripts: disabled_scripts.clone(),
         };
 
+        let budget = self.current_cpu_budget();
+        let watchdog_guard = self.cpu_watchdog.arm(budget);
+
         let mut rt = self.isolate_cell.enter_isolate(&mut self.runtime);
         let global_ctx = rt.global_context();
         let ctx = global_ctx.open(rt.v8_isolate());
@@ -486,7 +858,52 @@ impl Vm {
         };
 
         let v = serde_v8::to_v8(&mut scope, &data).unwrap();
-        let _ = dispatch_fn.call(&mut scope, globals.into(), &[v]);
+        let dispatched = dispatch_fn.call(&mut scope, globals.into(), &[v]).is_some();
+        drop(scope);
+        drop(rt);
+
+        if dispatched {
+            self.record_dispatch(name, &disabled_scripts);
+        }
+
+        if watchdog_guard.timed_out() {
+            self.log_cpu_budget_exceeded(format!("Handling a {name} event"), budget);
+        }
+    }
+
+    fn current_cpu_budget(&self) -> Duration {
+        self.cpu_budget.for_tier(*self.premium_tier.read().unwrap())
+    }
+
+    fn log_cpu_budget_exceeded(&self, what: String, budget: Duration) {
+        self.guild_logger.log(LogEntry::critical(
+            self.ctx.guild_id,
+            format!(
+                "{what} took longer than the cpu time budget of {}ms, shutting down the vm. Is a \
+                 script stuck in a loop?",
+                budget.as_millis()
+            ),
+        ));
+    }
+
+    // the snapshot is built separately by build.rs, running on a stale one breaks in subtle ways
+    // so the vm is not started at all instead
+    fn check_core_snapshot(&mut self) -> Result<(), String> {
+        let mut rt = self.isolate_cell.enter_isolate(&mut self.runtime);
+        let (version, op_count) = match crate::core_snapshot_version(&mut rt) {
+            Some(v) => v,
+            None => return Err("core snapshot has no version".to_string()),
+        };
+
+        match crate::expected_core_snapshot_version(&mut rt, op_count) {
+            Some(expected) if version == expected => Ok(()),
+            Some(expected) => Err(format!(
+                "core snapshot version is {version}, expected {expected}"
+            )),
+            None => Err(format!(
+                "core snapshot was made with {op_count} ops, but fewer are registered"
+            )),
+        }
     }
 
     fn get_property<'a>(
@@ -544,12 +961,17 @@ impl Vm {
     async fn run_until_completion(&mut self) {
         loop {
             let fut = RunUntilCompletion {
+                budget: self.current_cpu_budget(),
                 cell: &self.isolate_cell,
                 rt: &mut self.runtime,
+                watchdog: &self.cpu_watchdog,
             };
 
             if let Err(err) = fut.await {
                 self.log_guild_err(err);
+                if self.check_terminated() {
+                    return;
+                }
             } else {
                 return;
             }
@@ -562,37 +984,138 @@ impl Vm {
     ) {
         loop {
             let fut = CompleteModuleEval {
+                budget: self.current_cpu_budget(),
                 cell: &self.isolate_cell,
                 rt: &mut self.runtime,
+                watchdog: &self.cpu_watchdog,
                 rcv: &mut rcv,
             };
 
             match fut.await {
                 Err(err) => {
                     self.log_guild_err(err);
+                    if self.check_terminated() {
+                        break;
+                    }
                 }
                 Ok(_) => break,
             }
         }
     }
 
-    fn log_guild_err(&self, err: AnyError) {
+    fn log_guild_err(&mut self, err: AnyError) {
+        if err.is::<CpuBudgetExceeded>() {
+            self.log_cpu_budget_exceeded(
+                "Running async script code".to_string(),
+                self.current_cpu_budget(),
+            );
+            return;
+        }
+
+        if let Some(script_id) = error_script_id(&self.script_store, &err) {
+            self.record_error(script_id);
+            if self.error_breaker.record_error(script_id) {
+                self.log_script_handlers_disabled(script_id);
+            }
+        }
+
+        // emit the raw stack if source maps are turned off for this vm
+        let err = if self.source_maps {
+            source_map_error(&self.script_store, err)
+        } else {
+            err
+        };
+
         self.guild_logger.log(LogEntry::error(
+            self.ctx.guild_id,
+            format!("Script error occurred: {err}"),
+        ));
+    }
+
+    fn log_script_handlers_disabled(&self, script_id: u64) {
+        let name = if let Some(script) = self.script_store.borrow().get_script(script_id) {
+            script.script.name.clone()
+        } else {
+            return;
+        };
+
+        let config = self.error_breaker.config();
+        self.guild_logger.log(LogEntry::script_warning(
             self.ctx.guild_id,
             format!(
-                "Script error occurred: {}",
-                source_map_error(&self.script_store, err)
+                "This script errored {} times in the last {}s, its event handlers are disabled for \
+                 {}s. Further errors from handlers that were already running are still logged.",
+                config.threshold,
+                config.window.as_secs(),
+                config.cooldown.as_secs()
             ),
+            format!("guild_scripts/{name}.ts"),
+            None,
         ));
     }
 
-    async fn restart(&mut self, new_scripts: Vec<Script>) {
+    // counts the event as a run for the loaded scripts that have a handler for it, except for the
+    // ones the error breaker kept it from
+    fn record_dispatch(&mut self, name: &str, disabled_scripts: &[u64]) {
+        let now = chrono::Utc::now();
+        let borrow = self.script_store.borrow();
+        for script in &borrow.scripts {
+            if !matches!(script.state, ScriptLoadState::Loaded)
+                || !script.handled_events.contains(name)
+                || disabled_scripts.contains(&script.script.id)
+            {
+                continue;
+            }
+
+            let stats = self
+                .script_stats
+                .entry(script.script.id)
+                .or_insert_with(|| ScriptRuntimeStats::new(script.script.id));
+            stats.dispatches += 1;
+            stats.last_run_at = Some(now);
+        }
+        drop(borrow);
+
+        self.flush_script_stats(false);
+    }
+
+    fn record_error(&mut self, script_id: u64) {
+        let stats = self
+            .script_stats
+            .entry(script_id)
+            .or_insert_with(|| ScriptRuntimeStats::new(script_id));
+        stats.errors += 1;
+        stats.last_error_at = Some(chrono::Utc::now());
+    }
+
+    // sends the accumulated stats to the worker, unless it's been less than the flush interval
+    // since the last time and force is false
+    fn flush_script_stats(&mut self, force: bool) {
+        if self.script_stats.is_empty()
+            || (!force && self.script_stats_flushed_at.elapsed() < SCRIPT_STATS_FLUSH_INTERVAL)
+        {
+            return;
+        }
+
+        self.script_stats_flushed_at = Instant::now();
+        let stats = self.script_stats.drain().map(|(_, v)| v).collect();
+        let _ = self.tx.send((
+            self.ctx.guild_id,
+            self.ctx.role,
+            VmEvent::ScriptStats(stats),
+        ));
+    }
+
+    async fn restart(&mut self, new_scripts: Vec<Script>, reason: RestartReason) {
         self.guild_logger.log(LogEntry::info(
             self.ctx.guild_id,
             "restarting guild vm...".to_string(),
         ));
 
         self.stop_vm().await;
+        self.flush_script_stats(true);
+        self.error_breaker.reset();
+        self.reload_generations.clear();
 
         // create a new sandbox
         {
@@ -604,17 +1127,27 @@ impl Vm {
             self.compile_script(script.clone());
         }
 
+        self.last_restart = Some(RestartInfo::now(reason));
+
         let new_rt = Self::create_isolate(
             &self.extension_factory,
             self.module_manager.clone(),
             self.script_store.clone(),
             self.timeout_handle.clone(),
+            self.source_maps,
+            self.dispatch_ops.clone(),
+            self.last_restart.clone(),
         );
 
         self.runtime = new_rt;
         self.emit_isolate_handle();
 
         for script in new_scripts {
+            // no point in running the rest if one of them got the vm shut down
+            if self.check_terminated() {
+                break;
+            }
+
             self.run_script(script.id).await;
         }
 
@@ -625,6 +1158,23 @@ impl Vm {
     }
 }
 
+// the continuations of async handlers run while polling the event loop rather than in
+// dispatch_event, so each poll gets the cpu budget as well
+fn poll_event_loop_budgeted(
+    rt: &mut JsRuntime,
+    cx: &mut Context<'_>,
+    watchdog: &CpuWatchdog,
+    budget: Duration,
+) -> Poll<Result<(), AnyError>> {
+    let watchdog_guard = watchdog.arm(budget);
+    let res = rt.poll_event_loop(cx, false);
+    if watchdog_guard.timed_out() {
+        return Poll::Ready(Err(CpuBudgetExceeded.into()));
+    }
+
+    res
+}
+
 pub enum TickResult {
     VmError(AnyError),
     Completed,
@@ -637,6 +1187,8 @@ struct TickFuture<'a> {
     rt: &'a mut ManagedIsolate,
     cell: &'a IsolateCell,
     wakeup: &'a mut UnboundedReceiver<()>,
+    watchdog: &'a CpuWatchdog,
+    budget: Duration,
     completed: bool,
 }
 
@@ -657,12 +1209,13 @@ impl<'a> core::future::Future for TickFuture<'a> {
             return Poll::Ready(TickResult::Command(opt));
         }
 
+        let (watchdog, budget) = (self.watchdog, self.budget);
         let mut rt = self.cell.enter_isolate(self.rt);
 
         // if !self.completed{
         // }
 
-        match rt.poll_event_loop(cx, false) {
+        match poll_event_loop_budgeted(&mut rt, cx, watchdog, budget) {
             Poll::Pending => {
                 // let state_rc = rt.op_state();
                 // let op_state = state_rc.borrow();
@@ -690,6 +1243,8 @@ impl<'a> core::future::Future for TickFuture<'a> {
 struct RunUntilCompletion<'a> {
     rt: &'a mut ManagedIsolate,
     cell: &'a IsolateCell,
+    watchdog: &'a CpuWatchdog,
+    budget: Duration,
 }
 
 impl<'a> core::future::Future for RunUntilCompletion<'a> {
@@ -699,9 +1254,10 @@ impl<'a> core::future::Future for RunUntilCompletion<'a> {
         mut self: std::pin::Pin<&mut Self>,
         cx: &mut std::task::Context<'_>,
     ) -> std::task::Poll<Self::Output> {
+        let (watchdog, budget) = (self.watchdog, self.budget);
         let mut rt = self.cell.enter_isolate(self.rt);
 
-        match rt.poll_event_loop(cx, false) {
+        match poll_event_loop_budgeted(&mut rt, cx, watchdog, budget) {
             // Poll::Pending => {
             //     let state_rc = rt.op_state();
             //     let op_state = state_rc.borrow();
@@ -725,6 +1281,8 @@ impl<'a> core::future::Future for RunUntilCompletion<'a> {
 struct CompleteModuleEval<'a, 'b> {
     rt: &'a mut ManagedIsolate,
     cell: &'a IsolateCell,
+    watchdog: &'a CpuWatchdog,
+    budget: Duration,
     rcv: &'b mut futures::channel::oneshot::Receiver<Result<(), AnyError>>,
 }
 
@@ -744,9 +1302,10 @@ impl<'a, 'b> core::future::Future for CompleteModuleEval<'a, 'b> {
         }
 
         {
+            let (watchdog, budget) = (self.watchdog, self.budget);
             let mut rt = self.cell.enter_isolate(self.rt);
 
-            match rt.poll_event_loop(cx, false) {
+            match poll_event_loop_budgeted(&mut rt, cx, watchdog, budget) {
                 Poll::Pending => return Poll::Pending,
                 Poll::Ready(Err(e)) => return Poll::Ready(Err(e)),
                 Poll::Ready(_) => {}
@@ -780,6 +1339,9 @@ impl VmInterface for Vm {
             inner: Arc::new(StdRwLock::new(ShutdownHandleInner {
                 isolate_handle: None,
                 shutdown_reason: None,
+                deadline_extension: None,
+                budget_extension: None,
+                deadline_extended: false,
             })),
             wakeup: wakeup_tx,
         };
@@ -835,11 +1397,48 @@ impl ShutdownHandle for VmShutdownHandle {
         // trigger a shutdown check if we weren't in the js runtime
         self.wakeup.send(()).ok();
     }
+
+    fn take_deadline_extension(&self) -> Option<Duration> {
+        self.inner.write().unwrap().deadline_extension.take()
+    }
+
+    fn take_budget_extension(&self) -> Option<Duration> {
+        self.inner.write().unwrap().budget_extension.take()
+    }
+}
+
+impl VmShutdownHandle {
+    /// Gives the currently running event extra time before it's considered a runaway,
+    /// this can only be done once per event and returns false if it was already done
+    pub fn extend_deadline(&self, extra: Duration) -> bool {
+        let mut inner = self.inner.write().unwrap();
+        if inner.deadline_extended {
+            return false;
+        }
+
+        inner.deadline_extended = true;
+        inner.deadline_extension = Some(extra);
+        inner.budget_extension = Some(extra);
+        true
+    }
+
+    fn reset_deadline_extension(&self) {
+        let mut inner = self.inner.write().unwrap();
+        inner.deadline_extended = false;
+        inner.deadline_extension = None;
+        inner.budget_extension = None;
+    }
 }
 
 struct ShutdownHandleInner {
     shutdown_reason: Option<ShutdownReason>,
     isolate_handle: Option<IsolateHandle>,
+    // extra time granted past the runaway deadline, taken by the runaway checker when it's used
+    deadline_extension: Option<Duration>,
+    // the same extra time for the cpu budget watchdog, it's tracked separately as both need it
+    budget_extension: Option<Duration>,
+    // whether the current event already extended its deadline
+    deadline_extended: bool,
 }
 
 pub struct CreateRt {
@@ -850,6 +1449,26 @@ pub struct CreateRt {
     pub load_scripts: Vec<Script>,
     pub extension_factory: ExtensionFactory,
     pub extension_modules: Vec<ModuleEntry>,
+    /// Restricts which of the extension modules scripts can import, all of them are available if this is None
+    pub extension_module_access: Option<ModuleAccessCheck>,
+    /// Map error stack traces back to the original typescript source, turning this off
+    /// saves some cpu and memory when errors occur at the cost of less readable errors
+    pub source_maps: bool,
+    /// Why the previous vm for this guild was shut down, if it was
+    pub last_restart: Option<RestartInfo>,
+    /// How long a script that failed to load is skipped on restarts, unless its source changes
+    pub failed_script_retry_grace: Duration,
+    /// The scripts that failed to load in the previous vms for this guild
+    pub failed_scripts: FailedScripts,
+    /// When to disable the handlers of scripts that keep erroring, see [ErrorBreaker]
+    pub error_breaker: ErrorBreakerConfig,
+    /// How long to remember dispatched events to skip ones delivered again, see [DispatchDedup]
+    pub dispatch_dedup: DispatchDedupConfig,
+    /// How long a single event dispatch or script load can run before the vm is shut down,
+    /// see [CpuWatchdog]
+    pub cpu_budget: CpuBudgetConfig,
+    /// Picks the budget tier, can change while the vm is running
+    pub premium_tier: Arc<StdRwLock<Option<PremiumSlotTier>>>,
 }
 
 type ExtensionFactory = Box<dyn Fn() -> Vec<Extension> + Send>;
@@ -127,17 +127,31 @@ where
     async fn runaway_checker(handle: VmThreadHandle<T>) {
         let ping_interval = Duration::from_secs(10);
         loop {
-            let (send, rcv) = oneshot::channel();
+            let (send, mut rcv) = oneshot::channel();
             match handle.send_cmd.send(VmThreadCommand::Ping(send)) {
                 Ok(_) => {
                     let last_ping = Instant::now();
-                    match tokio::time::timeout(ping_interval, rcv).await {
+                    match tokio::time::timeout(ping_interval, &mut rcv).await {
                         Ok(_) => {
                             // sleep until the next ping
                             let remaining = ping_interval - last_ping.elapsed();
                             tokio::time::sleep(remaining).await;
                         }
                         Err(_) => {
+                            // the running vm may have been granted a bit of extra time for known slow work
+                            let extension = {
+                                let maybe_handle = handle.running_vm.read().unwrap();
+                                maybe_handle
+                                    .as_ref()
+                                    .and_then(|h| h.shutdown_handle.take_deadline_extension())
+                            };
+
+                            if let Some(extension) = extension {
+                                if tokio::time::timeout(extension, &mut rcv).await.is_ok() {
+                                    continue;
+                                }
+                            }
+
                             // we hit a timeout, meaning there's a runaway script
                             //
                             // note that this logic is currently very flawed,
@@ -300,6 +314,8 @@ pub enum ShutdownReason {
     Runaway,
     ThreadTermination,
     OutOfMemory,
+    // a single event dispatch or module evaluation ran past its cpu time budget
+    Timeout,
 }
 
 pub type VmCreateResult<T, U, V> = Result<CreateVmSuccess<T, U, V>, String>;
@@ -313,4 +329,14 @@ pub struct CreateVmSuccess<T, U, V> {
 pub trait ShutdownHandle {
     // shut down a runaway vm using the provided timeout handle
     fn shutdown_vm(&self, reason: ShutdownReason, force: bool);
+
+    /// takes the extra time the vm was granted past the runaway deadline, if any
+    fn take_deadline_extension(&self) -> Option<Duration> {
+        None
+    }
+
+    /// takes the extra time the vm was granted past its cpu time budget, if any
+    fn take_budget_extension(&self) -> Option<Duration> {
+        None
+    }
 }
@@ -1,5 +1,5 @@
 import { GuildMetaConfig } from ".";
-import { CreateScript, CurrentGuildsResponse, EmptyResponse, LoginResponse, Script, SessionMeta, UpdateScript, User } from "./api_models";
+import { CreateScript, CurrentGuildsResponse, EmptyResponse, LoginResponse, Script, ScriptLogLevel, ScriptRuntimeStats, ScriptValidationFailure, SessionMeta, UpdateGuildMetaConfig, UpdateScript, User } from "./api_models";
 
 /* eslint-disable @typescript-eslint/naming-convention */
 export class ApiClient {
@@ -117,6 +117,9 @@ export class ApiClient {
     async updatePremiumSlotGuild(slotId: string, guildId: string | null): Promise<ApiResult<PremiumSlot>> {
         return await this.post(`/api/premium_slots/${slotId}/update_guild`, { guild_id: guildId });
     }
+    async getPremiumSlotAttachableGuilds(slotId: string): Promise<ApiResult<AttachableGuild[]>> {
+        return await this.get(`/api/premium_slots/${slotId}/attachable_guilds`);
+    }
 
     async getAllScripts(guildId: string,): Promise<ApiResult<Script[]>> {
         return await this.get(`/api/guilds/${guildId}/scripts`);
@@ -130,6 +133,22 @@ export class ApiClient {
         return await this.patch(`/api/guilds/${guildId}/scripts/${id}`, data);
     }
 
+    async renameScript(guildId: string, id: number, name: string): Promise<ApiResult<Script>> {
+        return await this.post(`/api/guilds/${guildId}/scripts/${id}/rename`, { name: name });
+    }
+
+    async setScriptMinLogLevel(guildId: string, id: number, minLogLevel: ScriptLogLevel): Promise<ApiResult<Script>> {
+        return await this.put(`/api/guilds/${guildId}/scripts/${id}/min_log_level`, { min_log_level: minLogLevel });
+    }
+
+    async getScriptStats(guildId: string, id: number): Promise<ApiResult<ScriptRuntimeStats>> {
+        return await this.get(`/api/guilds/${guildId}/scripts/${id}/stats`);
+    }
+
+    async getFailingScripts(guildId: string): Promise<ApiResult<ScriptValidationFailure[]>> {
+        return await this.get(`/api/guilds/${guildId}/failing_scripts`);
+    }
+
     async delScript(guildId: string, id: number): Promise<ApiResult<EmptyResponse>> {
         return await this.delete(`/api/guilds/${guildId}/scripts/${id}`);
     }
@@ -142,6 +161,10 @@ export class ApiClient {
         return await this.get(`/api/guilds/${guildId}/settings`);
     }
 
+    async updateGuildMetaConfig(guildId: string, data: UpdateGuildMetaConfig): Promise<ApiResult<GuildMetaConfig>> {
+        return await this.patch(`/api/guilds/${guildId}/settings`, data);
+    }
+
     async getNews(): Promise<ApiResult<NewsItem[]>> {
         return await this.get(`/api/news`);
     }
@@ -149,6 +172,14 @@ export class ApiClient {
     async getGuildPremiumSlots(guildId: string): Promise<ApiResult<GuildPremiumSlot[]>> {
         return await this.get(`/api/guilds/${guildId}/premium_slots`);
     }
+
+    async getGuildLimits(guildId: string): Promise<ApiResult<GuildLimits>> {
+        return await this.get(`/api/guilds/${guildId}/limits`);
+    }
+
+    async sandboxRun(guildId: string, run: SandboxRunRequest): Promise<ApiResult<SandboxRunResponse>> {
+        return await this.post(`/api/guilds/${guildId}/sandbox_run`, run);
+    }
 }
 
 export type ApiResult<T> = T | ApiError;
@@ -208,11 +239,47 @@ export interface PremiumSlot {
     attached_guild_id: string | null,
 }
 
+export interface AttachableGuild {
+    guild: {
+        id: string,
+        name: string,
+        icon: string,
+        owner_id: string,
+        left_at: string | null,
+    },
+    current_slot: PremiumSlot | null,
+}
+
+export interface GuildLimits {
+    premium_tier: PremiumSlotTier | null,
+    script_source_max_len: number,
+}
+
+export interface SandboxRunRequest {
+    source: string,
+    event_name: string,
+    event: unknown,
+}
+
+export interface SandboxRunResponse {
+    logs: SandboxLogEntry[],
+    timed_out: boolean,
+    shutdown_reason: string | null,
+}
+
+export interface SandboxLogEntry {
+    guild_id: string,
+    message: string,
+    script_context: { filename: string, line_col: [number, number] | null } | null,
+    level: "Critical" | "Error" | "Warn" | "Info" | "ConsoleLog",
+}
+
 export type PremiumSlotState =
     "Active" |
     "Cancelling" |
     "Cancelled" |
-    "PaymentFailed";
+    "PaymentFailed" |
+    "Expired";
 
 export type PremiumSlotTier = "Lite" | "Premium";
 
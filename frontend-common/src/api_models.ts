@@ -51,6 +51,23 @@ export interface Script {
     original_source: string,
     compiled_js: string,
     enabled: boolean,
+    min_log_level: ScriptLogLevel,
+}
+
+export type ScriptLogLevel = "Inherit" | "ConsoleLog" | "Info" | "Warn" | "Error" | "Critical";
+
+export interface ScriptRuntimeStats {
+    script_id: number,
+    dispatches: number,
+    errors: number,
+    last_run_at: string | null,
+    last_error_at: string | null,
+}
+
+export interface ScriptValidationFailure {
+    script_id: number,
+    reason: string,
+    failed_at: string,
 }
 
 export interface CreateScript {
@@ -71,4 +88,10 @@ export interface EmptyResponse { }
 export interface GuildMetaConfig {
     guild_id: string,
     error_channel_id: string | null,
+    timezone: string | null,
+}
+
+export interface UpdateGuildMetaConfig {
+    /** IANA timezone name (e.g. "Europe/Oslo"), null resets it to UTC */
+    timezone: string | null,
 }
\ No newline at end of file